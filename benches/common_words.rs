@@ -0,0 +1,35 @@
+//! Lookups in the common-word lists: `cargo bench --bench common_words`. The built-in
+//! lists are folded into sets once, while a custom list with the same words is folded
+//! again on every call, as every list was before.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use passgen::{CommonWords, Password};
+use std::hint::black_box;
+
+const PASSWORDS: [&str; 4] = [
+    "letmein",
+    "Tr0ub4dor&3",
+    "correcthorsebattery",
+    "xQ9#vL2!pZ7@",
+];
+
+fn is_safe(c: &mut Criterion) {
+    let passwords: Vec<Password> = PASSWORDS.iter().map(|&p| Password::new(p)).collect();
+    let check = |common_words: &CommonWords| {
+        passwords
+            .iter()
+            .filter(|password| black_box(password).is_safe(common_words))
+            .count()
+    };
+
+    let mut group = c.benchmark_group("is_safe");
+    let built_in = CommonWords::English;
+    group.bench_function("built-in", |b| b.iter(|| check(&built_in)));
+    let words = built_in.words().iter().map(str::to_string).collect();
+    let custom = CommonWords::Custom(words);
+    group.bench_function("custom", |b| b.iter(|| check(&custom)));
+    group.finish();
+}
+
+criterion_group!(benches, is_safe);
+criterion_main!(benches);
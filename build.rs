@@ -0,0 +1,30 @@
+//! Compresses the embedded word lists and common-word corpora so they take up less of the
+//! binary; `src/passgen/resources.rs` inflates them on first use.
+
+use std::fs;
+use std::path::Path;
+
+const RESOURCE_DIRS: &[&str] = &["wordlist", "common"];
+
+fn main() {
+    let out_dir = std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR");
+    println!("cargo:rerun-if-changed=resources");
+
+    for dir in RESOURCE_DIRS {
+        let source_dir = Path::new("resources").join(dir);
+        let target_dir = Path::new(&out_dir).join(dir);
+        fs::create_dir_all(&target_dir).expect("create resource output directory");
+
+        for entry in fs::read_dir(&source_dir).expect("read resource directory") {
+            let path = entry.expect("read resource entry").path();
+            if path.extension().is_none_or(|ext| ext != "txt") {
+                continue;
+            }
+            let data = fs::read(&path).expect("read resource");
+            let compressed = miniz_oxide::deflate::compress_to_vec(&data, 10);
+            let mut name = path.file_name().expect("resource file name").to_owned();
+            name.push(".deflate");
+            fs::write(target_dir.join(name), compressed).expect("write compressed resource");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Password and passphrase generation with strength checking, as used by the `passgen`
+//! command-line tool.
+//!
+//! Passwords are drawn uniformly from an [`Alphabet`], passphrases from a [`WordList`], and
+//! any [`Password`] can be classified by entropy or checked against [`CommonWords`].
+//! Generation only accepts a [`rand::CryptoRng`], such as `rand::rng()` or one from an
+//! [`RngSource`]:
+//!
+//! ```
+//! use passgen::checker::Classification;
+//! use passgen::{Alphabet, CommonWords, Password, WordList, passphrase};
+//!
+//! let mut rng = rand::rng();
+//! let password = Password::generate(16, &Alphabet::Full, &mut rng).unwrap();
+//! assert_eq!(password.value.chars().count(), 16);
+//! assert_eq!(password.classify(&Alphabet::Full).unwrap(), Classification::VeryStrong);
+//!
+//! let phrase = passphrase::generate_passphrase(5, "-", &WordList::EffLarge, &mut rng).unwrap();
+//! assert_eq!(phrase.value.split('-').count(), 5);
+//!
+//! assert!(!Password::new("letmein").is_safe(&CommonWords::Passwords));
+//! ```
+//!
+//! The items re-exported here are the supported API. The `passgen` module holds the rest
+//! of the command-line tool and may change in any release.
+
+#[doc(hidden)]
+pub mod passgen;
+/// JavaScript bindings, for running the generators and checker in a web page.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crate::passgen::alphabet::Alphabet;
+pub use crate::passgen::commonwords::CommonWords;
+pub use crate::passgen::generate::Generator;
+pub use crate::passgen::mapped::MappedWords;
+pub use crate::passgen::password::Password;
+pub use crate::passgen::rng::RngSource;
+pub use crate::passgen::wordlist::{WordList, Words};
+
+/// Strength classification, entropy estimates and comparisons of passwords; see also
+/// [`Password::classify`] and [`Password::is_safe`].
+pub mod checker {
+    pub use crate::passgen::checker::{
+        AttackScenario, Classification, CrackTime, SafetyMatch, SafetyReport, StrengthReport,
+        WEAK_ENTROPY, compare, edit_distance, format_duration,
+    };
+    pub use crate::passgen::patterns::{Pattern, PatternMatch, PersonalContext};
+}
+
+/// Passphrases of words drawn from a [`WordList`].
+pub mod passphrase {
+    pub use crate::passgen::passphrase::generate_passphrase;
+}
@@ -1,12 +1,72 @@
-mod passgen;
+use ::passgen::passgen;
 
-use crate::passgen::alphabet::Alphabet;
-use crate::passgen::commonwords::CommonWords;
+use crate::passgen::alphabet::{self, Alphabet};
+use crate::passgen::breach::BreachList;
+use crate::passgen::bulk::Bulk;
+use crate::passgen::checker::{
+    Assessment, Classification, CrackTime, SafetyLevel, SafetyReport, Screen,
+};
+use crate::passgen::commonwords::{CommonWords, Language};
+use crate::passgen::config::{self, Config, Settings};
+use crate::passgen::confirm::WriteMode;
+#[cfg(unix)]
+use crate::passgen::daemon;
+use crate::passgen::decoy::{self, DecoyStyle};
+use crate::passgen::export::{Account, Credential, ExportFormat};
+use crate::passgen::generate::{EndClass, Ends, Generator, Repeats, Requirements};
+use crate::passgen::history::{self, History};
+use crate::passgen::hook::Hook;
+use crate::passgen::logging::{self, LogFormat};
+use crate::passgen::mapped::{FileFormat, MappedWords};
+#[cfg(unix)]
+use crate::passgen::metrics::Metrics;
+use crate::passgen::output::{self, Color, Destination, Format, Output, Secret};
+use crate::passgen::passphrase::{Capitalize, PadPosition, Padding, Transform};
 use crate::passgen::password::Password;
-use crate::passgen::wordlist::WordList;
-use crate::passgen::{commonwords, passphrase};
-use clap::{Parser, Subcommand};
-use log::debug;
+use crate::passgen::patterns::{PatternMatch, PersonalContext};
+use crate::passgen::policy::{self, Policy, PolicyFormat};
+use crate::passgen::pronounce::Pronounceable;
+use crate::passgen::recovery::{self, Sheet};
+use crate::passgen::rng::RngSource;
+use crate::passgen::ssh_key::{self, KeyType};
+use crate::passgen::store::Store;
+use crate::passgen::strengthen::{self, Method};
+use crate::passgen::template::Template;
+use crate::passgen::token::Encoding;
+use crate::passgen::unique::Batch;
+use crate::passgen::wordlist::{self, WordList};
+use crate::passgen::{
+    audit, breach, cache, checker, clipboard, collide, derive, dict, doctor, export, id, init,
+    interactive, mcp, native_host, passphrase, seed, selftest, token, update, variants,
+};
+#[cfg(feature = "serve")]
+use crate::passgen::{http, serve};
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rand::{CryptoRng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Read};
+#[cfg(feature = "serve")]
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::debug;
+
+// Bearer token for `serve` when no token file is given
+#[cfg(feature = "serve")]
+const SERVE_TOKEN_ENV: &str = "PASSGEN_SERVE_TOKEN";
+// Exit status of `check` when a password fails it; 1 stays for errors and 2 for usage
+const REJECTED_EXIT_CODE: i32 = 3;
+// Repeats are astronomically unlikely at sensible lengths, so this only trips on tiny ones
+const MAX_REISSUE_ATTEMPTS: usize = 100;
+
+// The config profile chosen with `--profile`, set once before running the command
+static PROFILE: OnceLock<String> = OnceLock::new();
+static HISTORY: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -15,6 +75,60 @@ struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count)]
     debug: u8,
 
+    /// Format of diagnostic messages on stderr
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Show what would be written to files or external stores without writing
+    #[arg(long, global = true, default_value_t = false)]
+    dry_run: bool,
+
+    /// Write to files and external stores without asking for confirmation
+    #[arg(short = 'y', long, global = true, default_value_t = false)]
+    yes: bool,
+
+    /// Output format for results
+    #[arg(long, global = true, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+
+    /// Write results to a file instead of stdout
+    #[arg(short, long, global = true, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Copy results to the clipboard instead of printing them
+    #[arg(
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "output"
+    )]
+    copy: bool,
+
+    /// Clear the clipboard this many seconds after copying, unless something else was
+    /// copied meanwhile
+    #[arg(long, global = true, value_name = "SECONDS")]
+    clear_after: Option<u64>,
+
+    /// Spell generated and checked passwords out in the NATO phonetic alphabet, e.g. for
+    /// reading them over the phone
+    #[arg(long, global = true, default_value_t = false)]
+    phonetic: bool,
+
+    /// Never color plain output; it is only colored on a terminal, and not when the
+    /// NO_COLOR environment variable is set
+    #[arg(long, global = true, default_value_t = false)]
+    no_color: bool,
+
+    /// Apply a named profile from the config file, e.g. `[profile.banking]`, over its
+    /// defaults; flags given on the command line still win
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// History file of issued secrets for --no-reissue to extend and `check` to warn
+    /// about, instead of `history` in the data directory
+    #[arg(long, global = true, value_name = "PATH")]
+    history: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -24,7 +138,7 @@ enum Commands {
     /// Generate a random password
     Password {
         /// Length of the generated password
-        #[arg(short, long, default_value_t = 12)]
+        #[arg(short, long, default_value_t = 12, value_parser = length_parser(config::MAX_LENGTH))]
         length: usize,
 
         /// Alphabet to use for password generation
@@ -39,17 +153,141 @@ enum Commands {
         #[arg(short, long, default_value_t = false)]
         strength: bool,
 
+        /// Format of the password, e.g. Cvcvnn-Cvcvnn!: c/C lower/upper case consonant,
+        /// v/V lower/upper case vowel, n digit, s special, a any; other characters are kept,
+        /// and a backslash keeps the next one
+        #[arg(
+            short,
+            long,
+            conflicts_with_all = ["length", "alphabet", "custom", "require_upper", "require_lower", "require_digit", "require_special"]
+        )]
+        pattern: Option<String>,
+
+        /// Use the shortest length reaching this many bits of entropy, and print the
+        /// entropy reached
+        #[arg(long, value_parser = checker::parse_bits, conflicts_with_all = ["length", "pattern"])]
+        bits: Option<f64>,
+
+        /// Build the password from syllables that can be read aloud, e.g. bakotinuzeb; the
+        /// strength shown is for the smaller space of such passwords
+        #[arg(long, conflicts_with_all = ["alphabet", "custom", "pattern", "require_upper", "require_lower", "require_digit", "require_special"])]
+        pronounceable: bool,
+
+        /// Leave out characters easily misread when copied by hand: 0 O 1 l I |
+        #[arg(long, conflicts_with_all = ["pattern", "pronounceable"])]
+        no_ambiguous: bool,
+
+        /// Leave out every look-alike character, e.g. also B and 8, S and 5, or i and j
+        #[arg(long, conflicts_with_all = ["pattern", "pronounceable"])]
+        no_similar: bool,
+
+        /// Require at least N upper case letters (1 if N is omitted)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        require_upper: Option<usize>,
+
+        /// Require at least N lower case letters (1 if N is omitted)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        require_lower: Option<usize>,
+
+        /// Require at least N digits (1 if N is omitted)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        require_digit: Option<usize>,
+
+        /// Require at least N special characters (1 if N is omitted)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        require_special: Option<usize>,
+
+        /// Use every character at most once, for systems that reject repeats; fails if
+        /// --length is more than the alphabet has
+        #[arg(long, conflicts_with_all = ["pattern", "pronounceable"])]
+        no_repeat: bool,
+
+        /// Never use the same character twice in a row
+        #[arg(long, conflicts_with_all = ["pattern", "pronounceable", "no_repeat"])]
+        no_adjacent_repeat: bool,
+
+        /// Start with a character of this class, e.g. a letter for systems that require one
+        /// first
+        #[arg(long, value_name = "CLASS", conflicts_with_all = ["pattern", "pronounceable"])]
+        start_with: Option<EndClass>,
+
+        /// End with a character of this class
+        #[arg(long, value_name = "CLASS", conflicts_with_all = ["pattern", "pronounceable"])]
+        end_with: Option<EndClass>,
+
+        /// Never end with a special character, the same as --end-with alphanumeric
+        #[arg(long, conflicts_with_all = ["pattern", "pronounceable", "end_with"])]
+        no_trailing_special: bool,
+
         /// Number of passwords to generate
         #[arg(short, long, default_value_t = 1)]
         count: usize,
+
+        /// Never repeat a secret within the batch, e.g. for recovery or voucher codes; fails
+        /// if --count is more than the number of possible passwords
+        #[arg(long)]
+        unique: bool,
+
+        /// Generate on N threads, or one per CPU for 0, e.g. to fill a large --count; seeded
+        /// output is the same for any N, though not the same as without --jobs
+        #[arg(long, value_name = "N", conflicts_with_all = ["store", "exec", "exec_fd", "no_reissue"])]
+        jobs: Option<usize>,
+
+        /// Save the result into a secret store instead of printing it, e.g. pass:web/github.com,
+        /// keyring:github.com/octocat, vault:secret/data/app/db, systemd-cred:db-password, or
+        /// keepassxc:vault.kdbx:web/github.com
+        #[arg(long, value_name = "STORE:NAME", conflicts_with = "count")]
+        store: Option<Store>,
+
+        /// Shell command to run afterwards with the secret in PASSGEN_SECRET and on stdin,
+        /// e.g. to upload or rotate it
+        #[arg(long, value_name = "COMMAND", conflicts_with = "count")]
+        exec: Option<String>,
+
+        /// Like --exec, but the secret is only readable from the inherited file descriptor
+        /// named in PASSGEN_SECRET_FD (Unix only)
+        #[arg(long, value_name = "COMMAND", conflicts_with_all = ["count", "exec"])]
+        exec_fd: Option<String>,
+
+        /// Never hand out a secret issued before: repeats are regenerated, and issued
+        /// secrets are remembered as salted hashes in a local history file
+        #[arg(long, visible_alias = "no-reuse", conflicts_with_all = ["seed_file", "seed"])]
+        no_reissue: bool,
+
+        /// Source of randomness; every source is cryptographically secure
+        #[arg(long, value_enum, default_value_t = RngSource::default(), conflicts_with_all = ["seed_file", "seed"])]
+        rng: RngSource,
+
+        /// Derive output deterministically from the contents of a keyfile
+        #[arg(long, value_name = "PATH")]
+        seed_file: Option<PathBuf>,
+
+        /// Label mixed into the keyfile derivation, e.g. a hostname
+        #[arg(long, requires = "seed_file")]
+        seed_label: Option<String>,
+
+        /// Seed the generator directly with up to 64 hex digits, zero-padded, so every run
+        /// gives the same output, e.g. for test fixtures; never for real secrets
+        #[arg(long, value_name = "HEX", value_parser = seed::parse_hex_seed, conflicts_with = "seed_file")]
+        seed: Option<[u8; 32]>,
+
+        /// Policy file, TOML or JSON, that generated passwords must comply with, instead of
+        /// the one at `passgen policy path`
+        #[arg(long, value_name = "PATH")]
+        policy: Option<PathBuf>,
     },
 
     /// Generate a passphrase from a word list
     Passphrase {
-        /// Length of the generated password
-        #[arg(short, long, default_value_t = 3)]
+        /// Number of words in the passphrase
+        #[arg(short, long, default_value_t = 3, value_parser = length_parser(config::MAX_WORDS))]
         length: usize,
 
+        /// Use the fewest words reaching this many bits of entropy, and print the entropy
+        /// reached
+        #[arg(long, value_parser = checker::parse_bits, conflicts_with = "length")]
+        bits: Option<f64>,
+
         /// Word list to use for password generation
         #[arg(short, long)]
         wordlist: Option<WordList>,
@@ -58,182 +296,3155 @@ enum Commands {
         #[arg(short = 'C', long = "custom", num_args = 1..)]
         custom: Option<Vec<String>>,
 
-        /// Custom separator for the passphrase
+        /// File of words to use, one per line or in EFF dice format; memory-mapped, so large
+        /// lists stay cheap
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["wordlist", "custom"])]
+        wordlist_file: Option<PathBuf>,
+
+        /// Only use words of at least this many characters
+        #[arg(long, value_name = "N")]
+        min_word_len: Option<usize>,
+
+        /// Only use words of at most this many characters
+        #[arg(long, value_name = "N")]
+        max_word_len: Option<usize>,
+
+        /// Only use words of letters alone with at most 3 syllables, and pick words with
+        /// double letters half as often
+        #[arg(long)]
+        easy_type: bool,
+
+        /// Keep passphrases to at most this many characters by only using words short enough
+        /// to fit; with --bits, more of them are used to make up the entropy
+        #[arg(long, value_name = "N")]
+        max_chars: Option<usize>,
+
+        /// Custom separator for the passphrase, or `random-digit`, `random-symbol` or
+        /// `random:CHARS`, e.g. `random:-_.,`, for a random one of those characters between
+        /// each pair of words. Any other separator is used as given, so `-_.,` on its own is
+        /// one four-character separator, not a set
         #[arg(short, long, default_value = "-")]
         separator: String,
 
         /// Number of passwords to generate
         #[arg(short, long, default_value_t = 1)]
         count: usize,
+
+        /// Never repeat a passphrase within the batch; fails if --count is more than the
+        /// number of possible passphrases
+        #[arg(long)]
+        unique: bool,
+
+        /// Print the entropy and strength of the passphrase, counting each word as one
+        /// choice from the word list
+        #[arg(long)]
+        strength: bool,
+
+        /// Capitalize the first letter of some words
+        #[arg(long, value_enum, value_name = "WHICH")]
+        capitalize: Option<Capitalize>,
+
+        /// Append a random digit to a random word
+        #[arg(long)]
+        add_digit: bool,
+
+        /// Append a random symbol to a random word
+        #[arg(long)]
+        add_symbol: bool,
+
+        /// Print the dice roll of each word under the passphrase, e.g. `52434 royal`, to
+        /// check against the published EFF lists
+        #[arg(long)]
+        show_rolls: bool,
+
+        /// Pad the passphrase with this many copies of one character, e.g.
+        /// correct-horse-battery!!!!!!!!
+        #[arg(long, value_name = "N")]
+        pad: Option<usize>,
+
+        /// Character to pad with; a random digit or symbol if not given
+        #[arg(long, value_name = "CHAR", requires = "pad")]
+        pad_char: Option<char>,
+
+        /// Where the padding goes
+        #[arg(
+            long,
+            value_enum,
+            value_name = "WHERE",
+            default_value_t,
+            requires = "pad"
+        )]
+        pad_position: PadPosition,
+
+        /// Pick the words by physical dice instead: read rolls such as `52434 11356` from
+        /// standard input, one roll per word, and look them up in the built-in list
+        #[arg(
+            long,
+            conflicts_with_all = ["length", "bits", "custom", "wordlist_file", "min_word_len", "max_word_len", "easy_type", "max_chars", "capitalize", "add_digit", "add_symbol", "pad", "count", "no_reissue", "rng", "seed_file", "seed"]
+        )]
+        dice: bool,
+
+        /// Save the result into a secret store instead of printing it, e.g. pass:web/github.com,
+        /// keyring:github.com/octocat, vault:secret/data/app/db, systemd-cred:db-password, or
+        /// keepassxc:vault.kdbx:web/github.com
+        #[arg(long, value_name = "STORE:NAME", conflicts_with = "count")]
+        store: Option<Store>,
+
+        /// Shell command to run afterwards with the secret in PASSGEN_SECRET and on stdin,
+        /// e.g. to upload or rotate it
+        #[arg(long, value_name = "COMMAND", conflicts_with = "count")]
+        exec: Option<String>,
+
+        /// Like --exec, but the secret is only readable from the inherited file descriptor
+        /// named in PASSGEN_SECRET_FD (Unix only)
+        #[arg(long, value_name = "COMMAND", conflicts_with_all = ["count", "exec"])]
+        exec_fd: Option<String>,
+
+        /// Never hand out a secret issued before: repeats are regenerated, and issued
+        /// secrets are remembered as salted hashes in a local history file
+        #[arg(long, visible_alias = "no-reuse", conflicts_with_all = ["seed_file", "seed"])]
+        no_reissue: bool,
+
+        /// Source of randomness; every source is cryptographically secure
+        #[arg(long, value_enum, default_value_t = RngSource::default(), conflicts_with_all = ["seed_file", "seed"])]
+        rng: RngSource,
+
+        /// Derive output deterministically from the contents of a keyfile
+        #[arg(long, value_name = "PATH")]
+        seed_file: Option<PathBuf>,
+
+        /// Label mixed into the keyfile derivation, e.g. a hostname
+        #[arg(long, requires = "seed_file")]
+        seed_label: Option<String>,
+
+        /// Seed the generator directly with up to 64 hex digits, zero-padded, so every run
+        /// gives the same output, e.g. for test fixtures; never for real secrets
+        #[arg(long, value_name = "HEX", value_parser = seed::parse_hex_seed, conflicts_with = "seed_file")]
+        seed: Option<[u8; 32]>,
+    },
+
+    /// Derive a site's password from a master passphrase instead of storing it: the same
+    /// passphrase, site and counter always give the same password. The passphrase is
+    /// prompted for, or read from the first line of standard input.
+    Derive {
+        /// Site the password is for, e.g. github.com; case is ignored
+        site: String,
+
+        /// Increase to change the site's password
+        #[arg(long, default_value_t = 1)]
+        counter: u32,
+
+        /// Length of the derived password
+        #[arg(short, long, default_value_t = 16, value_parser = length_parser(config::MAX_LENGTH))]
+        length: usize,
+
+        /// Alphabet of the derived password
+        #[arg(short, long)]
+        alphabet: Option<Alphabet>,
+
+        /// Custom alphabet of the derived password
+        #[arg(short = 'C', long = "custom")]
+        custom: Option<String>,
+
+        /// Argon2id memory cost in MiB; changing it changes every derived password
+        #[arg(long, value_name = "MIB", default_value_t = 64)]
+        memory: u32,
+
+        /// Argon2id passes; changing them changes every derived password
+        #[arg(long, default_value_t = 3)]
+        iterations: u32,
+
+        /// Print strength of the derived password
+        #[arg(short, long)]
+        strength: bool,
+    },
+
+    /// Generate API keys, session secrets and recovery codes from raw random bytes
+    Token {
+        /// Number of random bytes; the entropy is 8 bits per byte
+        #[arg(short, long, default_value_t = 32, value_parser = length_parser(config::MAX_LENGTH))]
+        bytes: usize,
+
+        /// Text encoding of the bytes (`--format` picks the output format)
+        #[arg(short, long, value_enum, default_value_t = Encoding::default())]
+        encoding: Encoding,
+
+        /// Number of tokens to generate
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
+
+        /// Print the entropy of each token
+        #[arg(short, long)]
+        strength: bool,
+
+        /// Source of randomness; every source is cryptographically secure
+        #[arg(long, value_enum, default_value_t = RngSource::default())]
+        rng: RngSource,
+    },
+
+    /// Generate identifiers: random or time-ordered UUIDs, or ULIDs
+    Uuid {
+        /// Kind of identifier
+        #[arg(short, long, value_enum, default_value_t = id::Kind::default())]
+        kind: id::Kind,
+
+        /// Number of identifiers to generate; time-ordered ones sort in the order generated
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
+    },
+
+    /// Generate one-time backup codes such as `7kqm-2xhv`, none repeated
+    RecoveryCodes {
+        /// Number of codes
+        #[arg(short, long, default_value_t = 10)]
+        count: usize,
+
+        /// Layout of each code: `X` is a letter or digit that cannot be misread, `#` a digit,
+        /// and anything else is kept
+        #[arg(short, long, default_value = "XXXX-XXXX")]
+        pattern: String,
+
+        /// Print the codes as a sheet to keep on paper instead
+        #[arg(long, value_enum, value_name = "LAYOUT")]
+        sheet: Option<Sheet>,
+
+        /// Heading of the sheet, e.g. the account the codes are for
+        #[arg(long, default_value = "Recovery codes", requires = "sheet")]
+        title: String,
+
+        /// Print the entropy of each code
+        #[arg(short, long, conflicts_with = "sheet")]
+        strength: bool,
+
+        /// Source of randomness; every source is cryptographically secure
+        #[arg(long, value_enum, default_value_t = RngSource::default())]
+        rng: RngSource,
+    },
+
+    /// Create an SSH key protected by a generated passphrase
+    SshKey {
+        /// Key algorithm
+        #[arg(short = 't', long = "type", value_enum, default_value_t = KeyType::default())]
+        key_type: KeyType,
+
+        /// Private key file; defaults to ~/.ssh/id_<type>
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+
+        /// Comment embedded in the public key, e.g. user@host
+        #[arg(short = 'C', long)]
+        comment: Option<String>,
+
+        /// Number of words in the passphrase
+        #[arg(short, long, default_value_t = 6, value_parser = length_parser(config::MAX_WORDS))]
+        length: usize,
+
+        /// Word list to use for the passphrase
+        #[arg(short, long)]
+        wordlist: Option<WordList>,
+
+        /// Separator between passphrase words
+        #[arg(short, long, default_value = "-")]
+        separator: String,
+
+        /// Save the passphrase into a secret store instead of printing it
+        #[arg(long, value_name = "STORE:NAME")]
+        store: Option<Store>,
+    },
+
+    /// Generate deliberately weak, plausible-looking passwords for honeypot accounts and
+    /// canary credentials; never use them to protect anything
+    Decoy {
+        /// Resemble a real password, prompted for without echo: each decoy has its length
+        /// and a character of the same class in each place, but is never the password itself
+        #[arg(long, conflicts_with = "style")]
+        resemble: bool,
+
+        /// Read the password to resemble from the first line of standard input instead
+        #[arg(long, requires = "resemble")]
+        stdin: bool,
+
+        /// Number of decoys to generate
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
+
+        /// How the decoys are built
+        #[arg(long, value_enum, default_value_t = DecoyStyle::default())]
+        style: DecoyStyle,
+    },
+
+    /// Generate credentials for a list of accounts in a password manager's import format
+    /// or as `chpasswd` or LDIF input
+    Export {
+        /// Format to write
+        #[arg(value_name = "FORMAT")]
+        target: ExportFormat,
+
+        /// File with one account title per line, optionally followed by a tab-separated
+        /// username and URL; chpasswd uses the username, or the title when there is none
+        #[arg(
+            short,
+            long,
+            value_name = "PATH",
+            visible_alias = "users-file",
+            required_unless_present = "site"
+        )]
+        names: Option<PathBuf>,
+
+        /// Title of a single account to export instead of a names file, e.g. a site name
+        #[arg(long, value_name = "TITLE", conflicts_with = "names")]
+        site: Option<String>,
+
+        /// Username of the --site account
+        #[arg(long, requires = "site")]
+        username: Option<String>,
+
+        /// URL of the --site account
+        #[arg(long, requires = "site")]
+        url: Option<String>,
+
+        /// Write salted SHA-512 crypt hashes instead of passwords (chpasswd only), for
+        /// `chpasswd -e` or cloud-init with `type: hash`
+        #[arg(long)]
+        hash: bool,
+
+        /// DN of each LDIF entry, with `{user}` replaced by the login name, e.g.
+        /// `uid={user},ou=people,dc=example,dc=com`
+        #[arg(long, value_name = "TEMPLATE")]
+        dn_template: Option<String>,
+
+        /// Length of each generated password
+        #[arg(short, long, default_value_t = 12, value_parser = length_parser(config::MAX_LENGTH))]
+        length: usize,
+
+        /// Alphabet to use for password generation
+        #[arg(short, long)]
+        alphabet: Option<Alphabet>,
+
+        /// Custom alphabet to use for password generation
+        #[arg(short = 'C', long = "custom")]
+        custom: Option<String>,
+
+        /// Generate passphrases with this many words instead of passwords
+        #[arg(long, value_parser = length_parser(config::MAX_WORDS))]
+        words: Option<usize>,
+
+        /// Word list to use for passphrases
+        #[arg(short, long, requires = "words")]
+        wordlist: Option<WordList>,
+
+        /// Separator between passphrase words
+        #[arg(short, long, default_value = "-", requires = "words")]
+        separator: String,
+
+        /// Group or folder the entries are placed in
+        #[arg(short, long, default_value = "PassGen")]
+        group: String,
+
+        /// Never hand out a secret issued before: repeats are regenerated, and issued
+        /// secrets are remembered as salted hashes in a local history file
+        #[arg(long, visible_alias = "no-reuse", conflicts_with_all = ["seed_file", "seed"])]
+        no_reissue: bool,
+
+        /// Source of randomness; every source is cryptographically secure
+        #[arg(long, value_enum, default_value_t = RngSource::default(), conflicts_with_all = ["seed_file", "seed"])]
+        rng: RngSource,
+
+        /// Derive output deterministically from the contents of a keyfile
+        #[arg(long, value_name = "PATH")]
+        seed_file: Option<PathBuf>,
+
+        /// Label mixed into the keyfile derivation, e.g. a hostname
+        #[arg(long, requires = "seed_file")]
+        seed_label: Option<String>,
+
+        /// Seed the generator directly with up to 64 hex digits, zero-padded, so every run
+        /// gives the same output, e.g. for test fixtures; never for real secrets
+        #[arg(long, value_name = "HEX", value_parser = seed::parse_hex_seed, conflicts_with = "seed_file")]
+        seed: Option<[u8; 32]>,
     },
 
     /// Check password strength
     Check {
-        /// Password to check for strength
-        password: String,
+        /// Password to check for strength; prompted for without echo when omitted, since
+        /// arguments end up in shell history and process listings
+        password: Option<String>,
+
+        /// Check each line of standard input, e.g. a list of passwords
+        #[arg(long, conflicts_with = "password")]
+        stdin: bool,
+
+        /// Breach corpus to look the password up in without loading it: an index from
+        /// `passgen breach index`, a sorted SHA-1 list such as Pwned Passwords, or plain
+        /// passwords one per line
+        #[arg(long, value_name = "PATH")]
+        breach_file: Option<PathBuf>,
 
         /// Custom alphabet to use for password strength calculation
         #[arg(short = 'C', long = "custom")]
         custom: Option<String>,
 
-        // Alphabet to use for password strength calculation
+        /// Alphabet to use for password strength calculation; inferred from the classes of
+        /// characters the password has if neither it nor --custom is given
         #[arg(short, long)]
         alphabet: Option<Alphabet>,
 
-        // Check safety against common words
-        #[arg(short, long, default_value_t = true)]
+        /// Check safety against common words; `--common false` skips it
+        #[arg(short, long, default_value_t = true, action = clap::ArgAction::Set)]
         common: bool,
 
+        /// Also flag passwords containing a common word of at least N characters anywhere,
+        /// not only those made up entirely of common words
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "4")]
+        contains: Option<usize>,
+
+        /// Also flag passwords a few edits from a common word, e.g. password1 or passw0rdz:
+        /// one edit for 5 to 8 characters, two for longer
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Something about the password's owner that guessers try first, e.g. name=Alice,
+        /// username, email, company or birth=1990; can be given multiple times
+        #[arg(long, value_name = "KEY=VALUE")]
+        context: Vec<String>,
+
         /// Word list to check for common word combinations
         #[arg(short, long, num_args = 1..)]
         wordlist: Option<Vec<String>>,
+
+        /// Languages whose common words to check against instead of all of them, e.g.
+        /// german,french
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            conflicts_with_all = ["wordlist", "common_file"]
+        )]
+        language: Vec<Language>,
+
+        /// File of common words or leaked passwords to check against instead, one per line
+        /// or in EFF dice format; memory-mapped, so large breach corpora stay cheap
+        #[arg(
+            long,
+            value_name = "PATH",
+            visible_alias = "wordlist-file",
+            conflicts_with = "wordlist"
+        )]
+        common_file: Option<PathBuf>,
+
+        /// Policy file, TOML or JSON, to validate against instead of the one at
+        /// `passgen policy path`
+        #[arg(long, value_name = "PATH")]
+        policy: Option<PathBuf>,
+
+        /// Also fail passwords classified below this, e.g. strong
+        #[arg(long, value_enum, value_name = "CLASS")]
+        min_class: Option<Classification>,
+
+        /// Print nothing, and only report through the exit status: 0 if every password
+        /// passed, 3 if any failed
+        #[arg(short, long)]
+        quiet: bool,
     },
-}
 
-fn generate_password(length: usize, alphabet: &Alphabet, strength: bool) {
-    let password = Password::generate(length, alphabet);
-    if strength {
-        let classification = password.classify(alphabet);
-        println!("{} [{:?}]", password.value, classification.unwrap());
-    } else {
-        println!("{}", password.value);
-    }
-}
+    /// Audit a file of passwords, e.g. an exported credential dump, and summarize their
+    /// strength and common-word findings without printing the passwords
+    Audit {
+        /// File with one password per line, or `-` for standard input
+        file: PathBuf,
 
-fn check_password_safety(password: &Password) -> Option<String> {
-    const SAFETY_CHECKS: &[(CommonWords, &str)] = &[
-        (CommonWords::Passwords, "common password"),
-        (CommonWords::English, "common English word"),
-        (CommonWords::MaleNames, "common male name"),
-        (CommonWords::FemaleNames, "common female name"),
-        (CommonWords::LastNames, "common last name"),
-        (CommonWords::All, "combination of common words"),
-    ];
+        /// Read the file as CSV with a header row naming a password column, labeling
+        /// entries by their title, name, username or URL
+        #[arg(long)]
+        csv: bool,
 
-    for (word_type, description) in SAFETY_CHECKS {
-        if !password.is_safe(word_type) {
-            return Some(format!(
-                "{} is not safe because it is a {}",
-                password.value, description
-            ));
-        }
-    }
-    None
-}
+        /// Number of weakest entries to list
+        #[arg(long, default_value_t = 5)]
+        weakest: usize,
+    },
 
-fn get_alphabet_from_args(alphabet: Option<Alphabet>, custom: Option<String>) -> Alphabet {
-    if let Some(custom_alphabet) = custom {
-        Alphabet::Custom(custom_alphabet)
-    } else {
-        alphabet.unwrap_or_default()
-    }
-}
+    /// Estimate the chance of duplicates in a batch of passwords, to size IDs and tokens
+    /// before generating millions of them
+    Collide {
+        /// Length of each password
+        #[arg(short, long, default_value_t = 12, value_parser = length_parser(config::MAX_LENGTH))]
+        length: usize,
 
-fn validate_alphabet_args(
-    alphabet: &Option<Alphabet>,
-    custom: &Option<String>,
-) -> Result<(), &'static str> {
-    if alphabet.is_some() && custom.is_some() {
-        Err("Cannot specify both alphabet and custom alphabet.")
-    } else {
-        Ok(())
-    }
-}
+        /// Alphabet the passwords are drawn from
+        #[arg(short, long)]
+        alphabet: Option<Alphabet>,
 
-fn main() {
-    debug!("starting run_bcl");
-    let cli = Cli::parse();
+        /// Custom alphabet the passwords are drawn from
+        #[arg(short = 'C', long = "custom")]
+        custom: Option<String>,
 
-    match cli.command {
-        Some(Commands::Password {
-            alphabet,
-            custom,
-            length,
-            strength,
-            count,
-        }) => {
-            if let Err(e) = validate_alphabet_args(&alphabet, &custom) {
-                eprintln!("Error: {}", e);
-                return;
-            }
+        /// Number of passwords in the batch, e.g. 1000000 or 1e6
+        #[arg(short, long, value_parser = collide::parse_count)]
+        count: f64,
+    },
 
-            let alphabet = get_alphabet_from_args(alphabet, custom);
+    /// Generate replacements for an existing secret that differ from it meaningfully,
+    /// not just by a changed digit or letter case
+    Variants {
+        /// Secret being rotated
+        base: String,
 
-            debug!(
-                "Generating {} passwords with length: {}, alphabet: {:?}",
-                count, length, &alphabet
-            );
+        /// Number of variants to generate
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
 
-            for _ in 0..count {
-                generate_password(length, &alphabet, strength);
-            }
-        }
+        /// Minimum number of character edits from the base and between variants
+        #[arg(long, default_value_t = 4)]
+        min_distance: usize,
+    },
 
-        Some(Commands::Passphrase {
-            length,
-            wordlist,
-            custom,
-            separator,
-            count,
-        }) => {
-            debug!(
-                "Generating {} passphrases with length: {}, separator: {}",
-                count, length, separator
-            );
+    /// Propose hardened variants of a password you want to keep memorable, appending
+    /// random words or symbols or inserting random characters until it is strong enough
+    Strengthen {
+        /// Password to harden
+        password: String,
 
-            let wordlist = if let Some(wl) = wordlist {
-                wl
-            } else if let Some(custom_words) = custom {
-                WordList::from_custom(custom_words)
-            } else {
-                WordList::default()
-            };
+        /// Classification every variant reaches
+        #[arg(long, value_enum, value_name = "CLASS", default_value = "strong")]
+        target: Classification,
 
-            for _ in 0..count {
-                let passphrase = passphrase::generate_passphrase(length, &separator, &wordlist);
-                println!("{}", passphrase.value);
-            }
-        }
+        /// Number of variants of each kind to propose
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
 
-        Some(Commands::Check {
-            password,
-            alphabet,
-            custom,
-            common,
-            wordlist,
-        }) => {
-            debug!("Checking password");
+        /// Word list appended words are drawn from
+        #[arg(short, long, value_enum, default_value_t)]
+        wordlist: WordList,
 
-            let alphabet = get_alphabet_from_args(alphabet, custom);
-            let password_obj = Password::new(&password);
-
-            if common {
-                if let Some(wl) = wordlist {
-                    let common_words = commonwords::CommonWords::Custom(wl);
-                    if !password_obj.is_safe(&common_words) {
-                        println!(
-                            "{} is not safe because it contains common words from the provided list",
-                            password_obj.value
-                        );
-                        return;
-                    }
-                } else if let Some(safety_message) = check_password_safety(&password_obj) {
-                    println!("{}", safety_message);
-                    return;
-                }
-            }
+        /// Separator between the password and appended words
+        #[arg(short, long, default_value = "-")]
+        separator: String,
+    },
 
-            match password_obj.classify(&alphabet) {
-                Ok(classification) => {
-                    println!("{} -> {:?}", password_obj.value, classification);
-                }
-                Err(e) => {
-                    eprintln!("Error classifying password: {}", e);
-                }
-            }
+    /// Verify the entropy source and embedded resources are healthy
+    Doctor,
+
+    /// Update passgen to the latest GitHub release
+    ///
+    /// The download is checked against the release's SHA256SUMS. That file comes from the same
+    /// release, so it catches corrupted downloads but not a tampered release.
+    SelfUpdate {
+        /// Only check whether a newer release exists
+        #[arg(long, default_value_t = false)]
+        check: bool,
+    },
+
+    /// Inspect the built-in word lists
+    Wordlist {
+        #[command(subcommand)]
+        command: WordlistCommands,
+    },
+
+    /// Inspect the built-in alphabets
+    Alphabet {
+        #[command(subcommand)]
+        command: AlphabetCommands,
+    },
+
+    /// Manage locally cached wordlists
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
+    /// Manage the downloaded common password list used by checks
+    Dict {
+        #[command(subcommand)]
+        command: DictCommands,
+    },
+
+    /// Create the config file by answering a few questions
+    Init,
+
+    /// Tweak the length, alphabet and word list with keys in a terminal UI, watching the
+    /// candidates and their entropy change, and copy the one you like
+    Interactive,
+
+    /// Serve generation and checks over a local HTTP JSON API
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+
+        /// File holding the bearer token clients must send; defaults to $PASSGEN_SERVE_TOKEN,
+        /// or a random token printed at startup
+        #[arg(long, value_name = "PATH")]
+        token_file: Option<PathBuf>,
+
+        /// Maximum requests per minute from each client address
+        #[arg(long, default_value_t = 60)]
+        rate_limit: u32,
+    },
+
+    /// Offer generation and checks as Model Context Protocol tools over stdio
+    Mcp,
+
+    /// Answer a browser extension over the native messaging protocol
+    NativeHost {
+        /// Arguments the browser passes, such as the calling extension's origin
+        #[arg(hide = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        browser_args: Vec<String>,
+    },
+
+    /// Serve generation and checks to local tools over a user-only Unix socket
+    #[cfg(unix)]
+    Daemon {
+        /// Socket path; defaults to passgen/daemon.sock in the runtime directory
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        /// Also serve Prometheus metrics over HTTP at this address, e.g. 127.0.0.1:9464
+        #[cfg(feature = "serve")]
+        #[arg(long, value_name = "ADDR")]
+        metrics_listen: Option<SocketAddr>,
+    },
+
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Prepare breach corpora for offline checks
+    Breach {
+        #[command(subcommand)]
+        command: BreachCommands,
+    },
+
+    /// Manage the password policy that generation and checks follow
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommands,
+    },
+
+    /// Clear the clipboard after a delay, run in the background by --clear-after
+    #[command(name = clipboard::CLEAR_COMMAND, hide = true)]
+    ClearClipboard {
+        #[arg(long)]
+        after: u64,
+    },
+
+    /// Run statistical tests on the random sampling code, with the configured alphabet and
+    /// word list unless others are given
+    Selftest {
+        /// Number of characters and words to sample
+        #[arg(short, long, default_value_t = 1_000_000)]
+        samples: usize,
+
+        /// Alphabet to sample characters from
+        #[arg(short, long)]
+        alphabet: Option<Alphabet>,
+
+        /// Custom alphabet to sample characters from
+        #[arg(short = 'C', long = "custom", conflicts_with = "alphabet")]
+        custom: Option<String>,
+
+        /// Word list to sample words from
+        #[arg(short, long)]
+        wordlist: Option<WordList>,
+
+        /// File of words to sample from, one per line or in EFF dice format
+        #[arg(long, value_name = "PATH", conflicts_with = "wordlist")]
+        wordlist_file: Option<PathBuf>,
+
+        /// Source of randomness to test
+        #[arg(long, value_enum, default_value_t = RngSource::default())]
+        rng: RngSource,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// List cached files and their sizes
+    List,
+
+    /// Delete all cached files
+    Clean,
+
+    /// Print the cache directory
+    Path,
+}
+
+#[derive(Subcommand)]
+enum DictCommands {
+    /// Download a common password list that checks prefer over the embedded one
+    #[command(group(clap::ArgGroup::new("checksum").required(true)))]
+    Update {
+        /// HTTPS URL of a list with one password per line
+        #[arg(long, default_value = dict::SECLISTS_TOP_1M)]
+        url: String,
+
+        /// Expected SHA-256 digest of the list
+        #[arg(long, value_name = "HEX", group = "checksum")]
+        sha256: Option<String>,
+
+        /// HTTPS URL of a sha256sum-style listing that includes the list's file name
+        #[arg(long, value_name = "URL", group = "checksum")]
+        checksums: Option<String>,
+    },
+
+    /// Print where the downloaded list is stored
+    Path,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Validate the config file and report problems with line numbers
+    Check {
+        /// Config file to check instead of the default location
+        file: Option<PathBuf>,
+    },
+
+    /// Print the config file location
+    Path,
+
+    /// Print the settings in effect: the config file's defaults with the `--profile` chosen
+    /// laid over them
+    Show,
+}
+
+#[derive(Subcommand)]
+enum BreachCommands {
+    /// Build a sorted-hash index of a plain breach corpus, one password per line, for
+    /// `passgen check --breach-file`; lookups then take a few reads instead of a full scan
+    Index {
+        /// Corpus of breached passwords
+        corpus: PathBuf,
+
+        /// Index file to write
+        index: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyCommands {
+    /// Convert a policy exported from another system into the policy file
+    Import {
+        /// Format of the exported policy
+        #[arg(value_name = "FORMAT")]
+        kind: PolicyFormat,
+
+        /// Exported policy, e.g. the output of `secedit /export /cfg policy.inf`
+        export: PathBuf,
+    },
+
+    /// Print the policy file location
+    Path,
+
+    /// Print the policy in effect, or the one in a given file
+    Show {
+        /// Policy file, TOML or JSON
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlphabetCommands {
+    /// List all alphabets, including custom ones from the config file, with their size and
+    /// entropy per character
+    List,
+
+    /// Show the characters of a single alphabet
+    Show {
+        /// Alphabet to show, built in or from the config file's `[alphabets]` table
+        alphabet: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WordlistCommands {
+    /// List the built-in word lists, and the config file's word list file, with their size
+    /// and entropy per word
+    List,
+
+    /// Show the size, entropy per word, word lengths and format of a word list
+    Info {
+        /// Built-in word list to show
+        #[arg(required_unless_present = "file")]
+        wordlist: Option<WordList>,
+
+        /// Word list file to show instead
+        #[arg(long, value_name = "PATH", conflicts_with = "wordlist")]
+        file: Option<PathBuf>,
+    },
+
+    /// Print a built-in word list, one word per line
+    Export {
+        /// Word list to export
+        wordlist: WordList,
+
+        /// Prefix each word with its diceware index
+        #[arg(short, long, default_value_t = false)]
+        indices: bool,
+    },
+}
+
+// Structured formats always carry the details of a generated secret; plain output only
+// shows those asked for
+#[derive(Default, Serialize)]
+struct Generated {
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classification: Option<Classification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entropy: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alphabet_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wordlist_size: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rolls: Vec<DiceRoll>,
+    // Length in characters, for secrets generated to fit a limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chars: Option<usize>,
+    #[serde(skip)]
+    show_classification: bool,
+    #[serde(skip)]
+    show_entropy: bool,
+}
+
+impl Secret for Generated {
+    fn secret(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if let Some(classification) = self
+            .classification
+            .as_ref()
+            .filter(|_| self.show_classification)
+        {
+            write!(f, " [{:?}]", classification)?;
+        }
+        if let Some(entropy) = self.entropy.filter(|_| self.show_entropy) {
+            write!(f, " ({:.1} bits)", entropy)?;
+        }
+        if let Some(chars) = self.chars {
+            write!(f, " ({} characters)", chars)?;
+        }
+        for roll in &self.rolls {
+            write!(f, "\n  {} {}", roll.roll, roll.word)?;
+        }
+        Ok(())
+    }
+}
+
+// A word of a passphrase with the dice index that picks it in the published list
+#[derive(Serialize)]
+struct DiceRoll {
+    roll: &'static str,
+    word: String,
+}
+
+#[derive(Serialize)]
+struct Decoy {
+    value: String,
+    style: DecoyStyle,
+    insecure: bool,
+}
+
+impl fmt::Display for Decoy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [insecure decoy]", self.value)
+    }
+}
+
+#[derive(Serialize)]
+struct Variant {
+    value: String,
+    distance: usize,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[derive(Serialize)]
+struct Strengthened {
+    value: String,
+    method: Method,
+    entropy: f64,
+    gain: f64,
+    classification: Classification,
+}
+
+impl fmt::Display for Strengthened {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t+{:.1} bits, {} -> {:?}",
+            self.value, self.gain, self.method, self.classification
+        )
+    }
+}
+
+// How a password found in a `--wordlist` or `--common-file` list is described
+const PROVIDED_WORDS: &str = "combination of words from the provided list";
+
+// Passwords that failed `check`, reported through its exit status
+#[derive(Debug, Default)]
+struct Rejected {
+    checked: usize,
+    failed: usize,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.checked {
+            1 => write!(f, "The password failed the check"),
+            checked => write!(
+                f,
+                "{} of {} passwords failed the check",
+                self.failed, checked
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+// A result of `check --stdin` in structured formats, for auditing large corpora: the
+// password only as its line number, since even a hash of a weak password gives it away,
+// and the same fields in every record so CSV columns line up
+#[derive(Serialize)]
+struct CheckLine {
+    line: usize,
+    safe: bool,
+    classification: Option<Classification>,
+    entropy: f64,
+    findings: Vec<String>,
+}
+
+impl fmt::Display for CheckLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.classification {
+            Some(classification) => write!(f, "line {}: {:?}", self.line, classification)?,
+            None => write!(f, "line {}: not safe", self.line)?,
+        }
+        if !self.findings.is_empty() {
+            write!(f, " ({})", self.findings.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    password: String,
+    safe: bool,
+    #[serde(flatten)]
+    safety: SafetyReport,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    violations: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classification: Option<Classification>,
+    entropy: f64,
+    raw_entropy: f64,
+    alphabet_size: usize,
+    // Character classes the alphabet was inferred from, without one given
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    detected_classes: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    patterns: Vec<PatternMatch>,
+    crack_times: Vec<CrackTime>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<String>,
+    // Plain output of many results keeps to a line each
+    #[serde(skip)]
+    show_details: bool,
+    #[serde(skip)]
+    color: bool,
+}
+
+// Entropy that fills the bar of a checked password, and the marks it has
+const ENTROPY_BAR_BITS: f64 = 100.0;
+const ENTROPY_BAR_WIDTH: usize = 20;
+
+fn classification_color(classification: Classification) -> Color {
+    match classification {
+        Classification::Weak => Color::Red,
+        Classification::Medium => Color::Yellow,
+        Classification::Strong | Classification::VeryStrong => Color::Green,
+    }
+}
+
+impl Secret for CheckResult {
+    fn secret(&self) -> &str {
+        &self.password
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.safety.finding, &self.classification) {
+            (Some(_), _) => write!(
+                f,
+                "{} is {} because it is a {}",
+                self.password,
+                Color::Red.paint("not safe", self.color),
+                self.safety
+            )?,
+            _ if !self.violations.is_empty() => write!(
+                f,
+                "{} {}: {}",
+                self.password,
+                Color::Red.paint("does not meet the password policy", self.color),
+                self.violations.join("; ")
+            )?,
+            (None, Some(classification)) => {
+                let color = classification_color(*classification);
+                write!(
+                    f,
+                    "{} -> {}",
+                    self.password,
+                    color.paint(classification, self.color)
+                )?;
+                let chars: Vec<char> = self.password.chars().collect();
+                let found: Vec<String> = self
+                    .patterns
+                    .iter()
+                    .map(|found| {
+                        let text: String = chars[found.start..found.start + found.length]
+                            .iter()
+                            .collect();
+                        format!("{} `{}`", found.pattern, text)
+                    })
+                    .collect();
+                if !found.is_empty() {
+                    write!(f, " ({})", found.join(", "))?;
+                }
+                if self.show_details {
+                    let bar =
+                        output::entropy_bar(self.entropy, ENTROPY_BAR_BITS, ENTROPY_BAR_WIDTH);
+                    write!(
+                        f,
+                        "\n{:<19}{} {:.1} bits",
+                        "entropy:",
+                        color.paint(bar, self.color),
+                        self.entropy
+                    )?;
+                    if !self.detected_classes.is_empty() {
+                        write!(
+                            f,
+                            "\n{:<19}{} ({} characters)",
+                            "character classes:",
+                            self.detected_classes.join(", "),
+                            self.alphabet_size
+                        )?;
+                    }
+                    write!(f, "\ntime to crack:")?;
+                    let scenarios: Vec<String> = self
+                        .crack_times
+                        .iter()
+                        .map(|time| format!("{}:", time.scenario))
+                        .collect();
+                    let width = scenarios.iter().map(String::len).max().unwrap_or(0);
+                    for (scenario, time) in scenarios.iter().zip(&self.crack_times) {
+                        write!(f, "\n  {:<width$} {}", scenario, time.duration)?;
+                    }
+                }
+            }
+            (None, None) => write!(f, "{}", self.password)?,
+        }
+        if self.show_details && !self.suggestions.is_empty() {
+            write!(f, "\nsuggestions:")?;
+            for suggestion in &self.suggestions {
+                write!(f, "\n  {}", suggestion)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct WordlistEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<&'static str>,
+    word: String,
+}
+
+impl fmt::Display for WordlistEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{}\t{}", index, self.word),
+            None => write!(f, "{}", self.word),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WordlistInfo {
+    name: String,
+    #[serde(flatten)]
+    stats: wordlist::Stats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<FileFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dice: Option<usize>,
+}
+
+impl WordlistInfo {
+    fn new(wordlist: &WordList) -> Self {
+        Self {
+            name: wordlist.name(),
+            stats: wordlist.stats(),
+            format: wordlist.format(),
+            dice: wordlist.dice(),
+        }
+    }
+}
+
+impl fmt::Display for WordlistInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stats = &self.stats;
+        if f.alternate() {
+            writeln!(f, "name: {}", self.name)?;
+            writeln!(f, "words: {}", stats.words)?;
+            writeln!(f, "bits per word: {:.2}", stats.bits_per_word)?;
+            write!(
+                f,
+                "word length: {} to {}, {:.1} on average",
+                stats.min_length, stats.max_length, stats.average_length
+            )?;
+            match (self.format, self.dice) {
+                (Some(format), Some(dice)) => write!(f, "\nformat: {}, {} dice", format, dice),
+                (Some(format), None) => write!(f, "\nformat: {}", format),
+                _ => Ok(()),
+            }
+        } else {
+            write!(
+                f,
+                "{:<14} {:>5} words  {:.2} bits/word  {:.1} chars on average, at most {}",
+                self.name, stats.words, stats.bits_per_word, stats.average_length, stats.max_length
+            )
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AlphabetInfo {
+    name: String,
+    characters: String,
+    size: usize,
+    bits_per_char: f64,
+}
+
+impl AlphabetInfo {
+    fn new(alphabet: &Alphabet) -> Self {
+        Self::named(alphabet.name(), alphabet)
+    }
+
+    fn named(name: String, alphabet: &Alphabet) -> Self {
+        Self {
+            name,
+            characters: alphabet.as_str().to_string(),
+            size: alphabet.len(),
+            bits_per_char: alphabet.bits_per_char(),
+        }
+    }
+}
+
+impl fmt::Display for AlphabetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "name: {}", self.name)?;
+            writeln!(f, "characters: {}", self.characters)?;
+            writeln!(f, "size: {}", self.size)?;
+            write!(f, "bits per character: {:.2}", self.bits_per_char)
+        } else {
+            write!(
+                f,
+                "{:<14} {:>3} chars  {:.2} bits/char  {}",
+                self.name, self.size, self.bits_per_char, self.characters
+            )
+        }
+    }
+}
+
+// Renders the wrapped record in its detailed (alternate) plain form
+#[derive(Serialize)]
+#[serde(transparent)]
+struct Detailed<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Detailed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+#[derive(Serialize)]
+struct CacheCleaned {
+    removed: usize,
+    path: PathBuf,
+}
+
+impl fmt::Display for CacheCleaned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Removed {} cached files from {}",
+            self.removed,
+            self.path.display()
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct Stored {
+    store: String,
+}
+
+impl fmt::Display for Stored {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Saved to {}", self.store)
+    }
+}
+
+#[derive(Serialize)]
+struct SshKeyCreated {
+    path: PathBuf,
+    public_key: PathBuf,
+    passphrase: String,
+}
+
+impl fmt::Display for SshKeyCreated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.passphrase)
+    }
+}
+
+#[derive(Serialize)]
+struct BearerToken {
+    token: String,
+}
+
+impl fmt::Display for BearerToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bearer token: {}", self.token)
+    }
+}
+
+#[derive(Serialize)]
+struct Location {
+    path: PathBuf,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+// Accepts lengths from 1 to `max`, so empty and runaway secrets fail before generation
+fn length_parser(max: i64) -> clap::builder::RangedI64ValueParser<usize> {
+    clap::builder::RangedI64ValueParser::new().range(1..=max)
+}
+
+// The config file, or an empty one when there is none, with the settings of the chosen
+// profile laid over its defaults
+fn settings() -> anyhow::Result<(Config, Settings)> {
+    let config = Config::load()?.unwrap_or_default();
+    let settings = config
+        .resolve(PROFILE.get().map(String::as_str))
+        .map_err(anyhow::Error::msg)?;
+    Ok((config, settings))
+}
+
+// The configured `min_entropy`, or the weak classification when none is configured
+fn min_entropy() -> anyhow::Result<f64> {
+    Ok(settings()?.1.min_entropy.unwrap_or(checker::WEAK_ENTROPY))
+}
+
+// Fills the generation options left at their built-in defaults from the config file's
+// settings, and returns whether those ask for results on the clipboard. Options that
+// conflict with ones given, e.g. a configured length with --pattern, are left alone.
+fn apply_settings(command: &mut Commands, given: &clap::ArgMatches) -> anyhow::Result<bool> {
+    let explicit = |id: &str| given.value_source(id) == Some(ValueSource::CommandLine);
+    match command {
+        Commands::Password {
+            length,
+            alphabet,
+            custom,
+            pattern,
+            bits,
+            pronounceable,
+            count,
+            store,
+            exec,
+            exec_fd,
+            ..
+        } => {
+            let (config, settings) = settings()?;
+            let shaped = pattern.is_some() || *pronounceable;
+            if let Some(configured) = settings.length
+                && !explicit("length")
+                && pattern.is_none()
+                && bits.is_none()
+            {
+                *length = configured;
+            }
+            if alphabet.is_none() && custom.is_none() && !shaped {
+                if let Some(chars) = settings.custom {
+                    *custom = Some(chars);
+                } else if let Some(name) = &settings.alphabet {
+                    let configured = config.alphabet(name).ok_or_else(|| {
+                        anyhow::anyhow!("Unknown alphabet `{}` in the config file", name)
+                    })?;
+                    *alphabet = Some(configured);
+                }
+            }
+            if let Some(configured) = settings.count
+                && !explicit("count")
+                && store.is_none()
+                && exec.is_none()
+                && exec_fd.is_none()
+            {
+                *count = configured;
+            }
+            Ok(settings.copy == Some(true) && store.is_none())
+        }
+        Commands::Passphrase {
+            length,
+            bits,
+            wordlist,
+            custom,
+            wordlist_file,
+            separator,
+            dice,
+            count,
+            store,
+            exec,
+            exec_fd,
+            ..
+        } => {
+            let (_, settings) = settings()?;
+            if let Some(configured) = settings.words
+                && !explicit("length")
+                && bits.is_none()
+                && !*dice
+            {
+                *length = configured;
+            }
+            if wordlist.is_none() && custom.is_none() && wordlist_file.is_none() {
+                if let Some(path) = &settings.wordlist_file
+                    && !*dice
+                {
+                    *wordlist_file = Some(config::resolve_path(path));
+                } else if let Some(name) = &settings.wordlist {
+                    let configured = WordList::from_str(name, true).map_err(|_| {
+                        anyhow::anyhow!("Unknown wordlist `{}` in the config file", name)
+                    })?;
+                    *wordlist = Some(configured);
+                }
+            }
+            if let Some(configured) = settings.separator
+                && !explicit("separator")
+            {
+                *separator = configured;
+            }
+            if let Some(configured) = settings.count
+                && !explicit("count")
+                && !*dice
+                && store.is_none()
+                && exec.is_none()
+                && exec_fd.is_none()
+            {
+                *count = configured;
+            }
+            Ok(settings.copy == Some(true) && store.is_none())
+        }
+        Commands::Selftest {
+            alphabet,
+            custom,
+            wordlist,
+            wordlist_file,
+            ..
+        } => {
+            let (config, settings) = settings()?;
+            if alphabet.is_none() && custom.is_none() {
+                if let Some(chars) = settings.custom {
+                    *custom = Some(chars);
+                } else if let Some(name) = &settings.alphabet {
+                    let configured = config.alphabet(name).ok_or_else(|| {
+                        anyhow::anyhow!("Unknown alphabet `{}` in the config file", name)
+                    })?;
+                    *alphabet = Some(configured);
+                }
+            }
+            if wordlist.is_none() && wordlist_file.is_none() {
+                if let Some(path) = &settings.wordlist_file {
+                    *wordlist_file = Some(config::resolve_path(path));
+                } else if let Some(name) = &settings.wordlist {
+                    let configured = WordList::from_str(name, true).map_err(|_| {
+                        anyhow::anyhow!("Unknown wordlist `{}` in the config file", name)
+                    })?;
+                    *wordlist = Some(configured);
+                }
+            }
+            Ok(false)
+        }
+        _ => Ok(false),
+    }
+}
+
+// Warns when `count` picks from `choices` fall below the minimum entropy
+fn warn_if_short(flag: &str, count: usize, choices: usize) -> anyhow::Result<()> {
+    if let Some(warning) = checker::length_warning(flag, count, choices, min_entropy()?) {
+        tracing::warn!("{}", warning);
+    }
+    Ok(())
+}
+
+// Warns when secrets generated as `option` requests fall below the minimum entropy
+fn warn_if_weak(option: &str, bits: f64) -> anyhow::Result<()> {
+    if let Some(warning) = checker::entropy_warning(option, bits, min_entropy()?) {
+        tracing::warn!("{}", warning);
+    }
+    Ok(())
+}
+
+// Generates into `generated`, reusing its buffer across a batch
+fn generate_password(
+    generator: &Generator,
+    length: usize,
+    policy: Option<&Policy>,
+    rng: &mut dyn CryptoRng,
+    generated: &mut Generated,
+) -> anyhow::Result<()> {
+    match policy {
+        Some(policy) => policy
+            .generate(length, generator, rng, &mut generated.value)
+            .map_err(anyhow::Error::msg)?,
+        None => generator.generate_into(length, rng, &mut generated.value),
+    }
+    Ok(())
+}
+
+// The shortest password length reaching `bits` of entropy from `alphabet` with
+// `requirements`, `ends` and `repeats`, or from pronounceable syllables
+fn length_for_bits(
+    bits: f64,
+    alphabet: &Alphabet,
+    requirements: &Requirements,
+    ends: Ends,
+    repeats: Repeats,
+    pronounceable: bool,
+) -> anyhow::Result<usize> {
+    let entropy = |length| {
+        if pronounceable {
+            Pronounceable::new(length).map(|generator| generator.entropy())
+        } else {
+            Generator::new(alphabet)
+                .and_then(|generator| generator.require(requirements, length))
+                .and_then(|generator| generator.require_ends(ends, length))
+                .and_then(|generator| generator.restrict_repeats(repeats, length))
+                .map(|generator| generator.entropy(length))
+        }
+    };
+    // Requirements, ends, repeat restrictions and syllables only take entropy away from
+    // uniformly chosen characters, so no shorter length can reach the target
+    let choices = if pronounceable {
+        Alphabet::LowerCase.len()
+    } else {
+        alphabet.len()
+    };
+    let shortest = checker::length_for_entropy(bits, choices).ok_or_else(|| {
+        anyhow::anyhow!(
+            "the {} alphabet needs at least two characters to add entropy",
+            alphabet.name()
+        )
+    })?;
+    let max = config::MAX_LENGTH as usize;
+    (shortest..=max)
+        .find(|&length| entropy(length).is_ok_and(|entropy| entropy >= bits - 1e-9))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no password of up to {} characters reaches {} bits",
+                max,
+                bits
+            )
+        })
+}
+
+// Like `generate_password`, for generators other than an alphabet: `draw` generates a
+// password into a buffer
+fn generate_with<R: CryptoRng + ?Sized>(
+    draw: impl Fn(&mut R, &mut String),
+    policy: Option<&Policy>,
+    rng: &mut R,
+    generated: &mut Generated,
+) -> anyhow::Result<()> {
+    match policy {
+        Some(policy) => policy
+            .sample(rng, &mut generated.value, &draw)
+            .map_err(anyhow::Error::msg)?,
+        None => draw(rng, &mut generated.value),
+    }
+    Ok(())
+}
+
+// Asks before replacing an existing secret, or before any write in a dry run
+fn approve_store(store: &Store, write_mode: &WriteMode) -> anyhow::Result<bool> {
+    let exists = store.exists()?;
+    let action = if exists {
+        format!("overwrite {}", store)
+    } else {
+        format!("save a new secret to {}", store)
+    };
+    Ok(!(write_mode.dry_run || exists) || write_mode.approve(&action)?)
+}
+
+// Saves or prints a single generated secret, then hands it to the `--exec` command
+fn deliver(
+    generated: Generated,
+    store: Option<&Store>,
+    hook: Option<&Hook>,
+    out: &mut Output,
+    write_mode: &WriteMode,
+) -> anyhow::Result<()> {
+    match store {
+        Some(store) => {
+            if !approve_store(store, write_mode)? {
+                return Ok(());
+            }
+            save_secret(store, &generated.value, out)?;
+        }
+        None => out.emit_secret(&generated)?,
+    }
+    if let Some(hook) = hook {
+        let command = hook.command();
+        if write_mode.dry_run && !write_mode.approve(&format!("run `{}`", command))? {
+            return Ok(());
+        }
+        debug!("Running hook `{}`", command);
+        hook.run(&generated.value)
+            .with_context(|| format!("Hook `{}` failed", command))?;
+    }
+    Ok(())
+}
+
+// The history file given with `--history`, or the one in the data directory
+fn history_path() -> anyhow::Result<PathBuf> {
+    HISTORY
+        .get()
+        .cloned()
+        .or_else(history::history_path)
+        .ok_or_else(|| anyhow::anyhow!("No data directory for the history of issued secrets"))
+}
+
+// Opens the history of issued secrets when `--no-reissue` asks for it
+fn open_history(no_reissue: bool) -> anyhow::Result<Option<History>> {
+    if !no_reissue {
+        return Ok(None);
+    }
+    let path = history_path()?;
+    History::open(&path)
+        .map(Some)
+        .with_context(|| format!("Cannot read history {}", path.display()))
+}
+
+// Calls `generate` until it produces a secret `history` has not issued before
+fn generate_unissued(
+    history: Option<&mut History>,
+    generated: &mut Generated,
+    mut generate: impl FnMut(&mut Generated) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(history) = history else {
+        return generate(generated);
+    };
+    for _ in 0..MAX_REISSUE_ATTEMPTS {
+        generate(generated)?;
+        if history.issue(&generated.value) {
+            return Ok(());
+        }
+        debug!("Discarding a secret that was issued before");
+    }
+    anyhow::bail!(
+        "Every one of {} attempts repeated a secret issued before; generate longer secrets",
+        MAX_REISSUE_ATTEMPTS
+    )
+}
+
+// Calls `generate` until it produces a secret not yet in `batch`
+fn generate_unique(
+    batch: Option<&mut Batch>,
+    generated: &mut Generated,
+    mut generate: impl FnMut(&mut Generated) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(batch) = batch else {
+        return generate(generated);
+    };
+    loop {
+        generate(generated)?;
+        if batch.insert(&generated.value).map_err(anyhow::Error::msg)? {
+            return Ok(());
+        }
+        debug!("Discarding a secret already in the batch");
+    }
+}
+
+fn save_history(history: Option<History>, write_mode: &WriteMode) -> anyhow::Result<()> {
+    let Some(mut history) = history else {
+        return Ok(());
+    };
+    if write_mode.dry_run {
+        write_mode.approve(&format!(
+            "record the issued secrets in {}",
+            history.path().display()
+        ))?;
+        return Ok(());
+    }
+    history
+        .save()
+        .with_context(|| format!("Cannot write history {}", history.path().display()))
+}
+
+fn save_secret(store: &Store, secret: &str, out: &mut Output) -> anyhow::Result<()> {
+    debug!("Saving generated secret to {}", store);
+    store.save(secret)?;
+    out.emit(&Stored {
+        store: store.to_string(),
+    })?;
+    Ok(())
+}
+
+fn wordlist_error(path: &Path) -> String {
+    format!("Cannot read wordlist file {}", path.display())
+}
+
+// The policy in `path` if given, else the policy file if one exists
+fn load_policy(path: Option<&Path>) -> anyhow::Result<Option<Policy>> {
+    match path {
+        Some(path) => Policy::from_file(path).map(Some),
+        None => Policy::load(),
+    }
+}
+
+fn rng_from_args(
+    source: RngSource,
+    seed: Option<[u8; 32]>,
+    seed_file: Option<&Path>,
+    seed_label: Option<&str>,
+) -> anyhow::Result<Box<dyn CryptoRng>> {
+    match (seed, seed_file) {
+        (Some(seed), _) => Ok(Box::new(ChaCha20Rng::from_seed(seed))),
+        (None, Some(path)) => Ok(Box::new(seed::rng_from_seed_file(path, seed_label)?)),
+        (None, None) => Ok(source.rng()),
+    }
+}
+
+fn get_alphabet_from_args(alphabet: Option<Alphabet>, custom: Option<String>) -> Alphabet {
+    if let Some(custom_alphabet) = custom {
+        Alphabet::Custom(custom_alphabet)
+    } else {
+        alphabet.unwrap_or_default()
+    }
+}
+
+fn validate_alphabet_args(
+    alphabet: &Option<Alphabet>,
+    custom: &Option<String>,
+) -> Result<(), &'static str> {
+    if alphabet.is_some() && custom.is_some() {
+        Err("Cannot specify both alphabet and custom alphabet.")
+    } else {
+        Ok(())
+    }
+}
+
+fn destination_from_args(
+    output: Option<PathBuf>,
+    copy: bool,
+    clear_after: Option<u64>,
+) -> Destination {
+    if copy {
+        Destination::Clipboard(clear_after.map(Duration::from_secs))
+    } else if let Some(path) = output {
+        Destination::File(path)
+    } else {
+        Destination::Stdout
+    }
+}
+
+fn run(command: Commands, out: &mut Output, write_mode: &WriteMode) -> anyhow::Result<()> {
+    match command {
+        Commands::Password {
+            alphabet,
+            custom,
+            length,
+            strength,
+            pattern,
+            bits,
+            pronounceable,
+            no_ambiguous,
+            no_similar,
+            require_upper,
+            require_lower,
+            require_digit,
+            require_special,
+            no_repeat,
+            no_adjacent_repeat,
+            start_with,
+            end_with,
+            no_trailing_special,
+            count,
+            unique,
+            jobs,
+            store,
+            exec,
+            exec_fd,
+            no_reissue,
+            rng,
+            seed_file,
+            seed_label,
+            seed,
+            policy,
+        } => {
+            validate_alphabet_args(&alphabet, &custom).map_err(anyhow::Error::msg)?;
+
+            let mut rng = rng_from_args(rng, seed, seed_file.as_deref(), seed_label.as_deref())?;
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            let alphabet = if no_similar {
+                alphabet.without(alphabet::SIMILAR)
+            } else if no_ambiguous {
+                alphabet.without(alphabet::AMBIGUOUS)
+            } else {
+                alphabet
+            };
+            let policy = load_policy(policy.as_deref())?;
+            let template = pattern
+                .as_deref()
+                .map(Template::parse)
+                .transpose()
+                .map_err(anyhow::Error::msg)?;
+            let requirements = Requirements {
+                upper: require_upper.unwrap_or(0),
+                lower: require_lower.unwrap_or(0),
+                digit: require_digit.unwrap_or(0),
+                special: require_special.unwrap_or(0),
+            };
+            let repeats = if no_repeat {
+                Repeats::Never
+            } else if no_adjacent_repeat {
+                Repeats::NotAdjacent
+            } else {
+                Repeats::Allowed
+            };
+            let ends = Ends {
+                start: start_with,
+                end: match no_trailing_special {
+                    true => Some(EndClass::Alphanumeric),
+                    false => end_with,
+                },
+            };
+            let length = match bits {
+                Some(bits) => {
+                    length_for_bits(bits, &alphabet, &requirements, ends, repeats, pronounceable)?
+                }
+                None => length,
+            };
+            let length = match &policy {
+                Some(policy) if template.is_none() && policy.length(length) > length => {
+                    out.note(format_args!(
+                        "Using length {} required by the password policy",
+                        policy.length(length)
+                    ))?;
+                    policy.length(length)
+                }
+                _ => length,
+            };
+
+            debug!(
+                "Generating {} passwords with length: {}, alphabet: {:?}",
+                count, length, &alphabet
+            );
+
+            let generator = Generator::new(&alphabet)
+                .and_then(|generator| generator.require(&requirements, length))
+                .and_then(|generator| generator.require_ends(ends, length))
+                .and_then(|generator| generator.restrict_repeats(repeats, length))
+                .map_err(anyhow::Error::msg)?;
+            let pronounceable = pronounceable
+                .then(|| Pronounceable::new(length))
+                .transpose()
+                .map_err(anyhow::Error::msg)?;
+            if let (Some(template), Some(pattern)) = (&template, &pattern) {
+                warn_if_weak(&format!("--pattern {}", pattern), template.entropy())?;
+            } else if let Some(pronounceable) = &pronounceable {
+                let option = format!("--pronounceable --length {}", length);
+                warn_if_weak(&option, pronounceable.entropy())?;
+            } else if repeats != Repeats::Allowed || !ends.is_empty() {
+                let restrictions = [
+                    (repeats == Repeats::Never, "--no-repeat"),
+                    (repeats == Repeats::NotAdjacent, "--no-adjacent-repeat"),
+                    (start_with.is_some(), "--start-with"),
+                    (end_with.is_some(), "--end-with"),
+                    (no_trailing_special, "--no-trailing-special"),
+                ];
+                let flags: Vec<&str> = restrictions
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, flag)| *flag)
+                    .collect();
+                let option = format!("{} --length {}", flags.join(" "), length);
+                warn_if_weak(&option, generator.entropy(length))?;
+            } else {
+                warn_if_short("--length", length, alphabet.len())?;
+            }
+            let entropy = match (&template, &pronounceable) {
+                (Some(template), _) => template.entropy(),
+                (_, Some(pronounceable)) => pronounceable.entropy(),
+                _ => generator.entropy(length),
+            };
+            let mut history = open_history(no_reissue)?;
+            let mut batch = unique
+                .then(|| Batch::new(count, entropy))
+                .transpose()
+                .map_err(anyhow::Error::msg)?;
+            let mut generated = Generated {
+                classification: Some(Classification::from_entropy(entropy)),
+                entropy: Some(entropy),
+                alphabet_size: (template.is_none() && pronounceable.is_none())
+                    .then(|| alphabet.len()),
+                show_classification: strength,
+                show_entropy: bits.is_some(),
+                ..Generated::default()
+            };
+            if let Some(jobs) = jobs {
+                let policy = policy.as_ref();
+                let draw_one =
+                    |rng: &mut ChaCha20Rng, buf: &mut String| match (&template, &pronounceable) {
+                        (Some(template), _) => template.generate_into(rng, buf),
+                        (_, Some(pronounceable)) => pronounceable.generate_into(rng, buf),
+                        _ => generator.generate_into(length, rng, buf),
+                    };
+                let shaped = template.is_some() || pronounceable.is_some();
+                let draw = |rng: &mut ChaCha20Rng, buf: &mut String| match policy {
+                    Some(policy) if shaped => policy.sample(rng, buf, draw_one),
+                    Some(policy) => policy.generate(length, &generator, rng, buf),
+                    None => {
+                        draw_one(rng, buf);
+                        Ok(())
+                    }
+                };
+                let bulk = Bulk::new(jobs).map_err(anyhow::Error::msg)?;
+                let mut emitted = 0;
+                // Rounds after the first replace the duplicates --unique dropped
+                while emitted < count {
+                    bulk.generate(count - emitted, &mut rng, draw, |value| {
+                        if let Some(batch) = &mut batch
+                            && !batch.insert(value).map_err(anyhow::Error::msg)?
+                        {
+                            return Ok(());
+                        }
+                        emitted += 1;
+                        value.clone_into(&mut generated.value);
+                        Ok(out.emit_secret(&generated)?)
+                    })?;
+                }
+                return Ok(());
+            }
+            let mut generate = |generated: &mut Generated| match (&template, &pronounceable) {
+                (Some(template), _) => generate_with(
+                    |rng, buf| template.generate_into(rng, buf),
+                    policy.as_ref(),
+                    &mut rng,
+                    generated,
+                ),
+                (_, Some(pronounceable)) => generate_with(
+                    |rng, buf| pronounceable.generate_into(rng, buf),
+                    policy.as_ref(),
+                    &mut rng,
+                    generated,
+                ),
+                _ => generate_password(&generator, length, policy.as_ref(), &mut rng, generated),
+            };
+            let hook = Hook::from_args(exec, exec_fd);
+            if store.is_some() || hook.is_some() {
+                generate_unissued(history.as_mut(), &mut generated, generate)?;
+                deliver(generated, store.as_ref(), hook.as_ref(), out, write_mode)?;
+                return save_history(history, write_mode);
+            }
+            for _ in 0..count {
+                generate_unissued(history.as_mut(), &mut generated, |generated| {
+                    generate_unique(batch.as_mut(), generated, &mut generate)
+                })?;
+                out.emit_secret(&generated)?;
+            }
+            save_history(history, write_mode)?;
+        }
+
+        Commands::Passphrase {
+            length,
+            bits,
+            wordlist,
+            custom,
+            wordlist_file,
+            min_word_len,
+            max_word_len,
+            easy_type,
+            max_chars,
+            separator,
+            strength,
+            capitalize,
+            add_digit,
+            add_symbol,
+            show_rolls,
+            pad,
+            pad_char,
+            pad_position,
+            dice,
+            count,
+            unique,
+            store,
+            exec,
+            exec_fd,
+            no_reissue,
+            rng,
+            seed_file,
+            seed_label,
+            seed,
+        } => {
+            debug!(
+                "Generating {} passphrases with length: {}, separator: {}",
+                count, length, separator
+            );
+
+            let wordlist = if let Some(wl) = wordlist {
+                wl
+            } else if let Some(path) = wordlist_file {
+                WordList::from_file(&path).with_context(|| wordlist_error(&path))?
+            } else if let Some(custom_words) = custom {
+                WordList::from_custom(custom_words)
+            } else {
+                WordList::default()
+            };
+            let wordlist = if min_word_len.is_some() || max_word_len.is_some() {
+                let lengths = min_word_len.unwrap_or(1)..=max_word_len.unwrap_or(usize::MAX);
+                let filtered = wordlist.with_word_lengths(lengths.clone());
+                if filtered.words().is_empty() {
+                    anyhow::bail!(
+                        "The word list has no words of {} to {} characters",
+                        lengths.start(),
+                        lengths.end()
+                    );
+                }
+                filtered
+            } else {
+                wordlist
+            };
+            let wordlist = match easy_type {
+                true => {
+                    let easy = wordlist.easy_to_type(wordlist::EASY_SYLLABLES);
+                    if easy.words().is_empty() {
+                        anyhow::bail!("The word list has no words that are easy to type");
+                    }
+                    easy
+                }
+                false => wordlist,
+            };
+
+            let separators = passphrase::separator_set(&separator).map_err(anyhow::Error::msg)?;
+
+            let rolls = match show_rolls {
+                true => Some(wordlist.rolls().ok_or_else(|| {
+                    anyhow::anyhow!("--show-rolls needs one of the built-in EFF word lists")
+                })?),
+                false => None,
+            };
+            let dice_rolls = |words: &[&str]| -> Vec<DiceRoll> {
+                let Some(rolls) = rolls else {
+                    return Vec::new();
+                };
+                words
+                    .iter()
+                    .map(|&word| DiceRoll {
+                        roll: rolls[word],
+                        word: word.to_string(),
+                    })
+                    .collect()
+            };
+
+            if dice {
+                if separators.is_some() {
+                    anyhow::bail!("--dice needs a separator used as given, not a random one");
+                }
+                if io::stdin().is_terminal() {
+                    out.note("Enter dice rolls, one group per word, then end with Ctrl-D:")?;
+                    out.flush()?;
+                }
+                let mut rolls = String::new();
+                io::stdin()
+                    .read_to_string(&mut rolls)
+                    .context("Cannot read dice rolls")?;
+                let words =
+                    passphrase::dice_words(&rolls, &wordlist).map_err(anyhow::Error::msg)?;
+                let entropy = passphrase::entropy(words.len(), &wordlist, &Transform::default());
+                warn_if_weak(&format!("--dice with {} rolls", words.len()), entropy)?;
+                let generated = Generated {
+                    value: words.join(&separator),
+                    rolls: dice_rolls(&words),
+                    classification: Some(Classification::from_entropy(entropy)),
+                    entropy: Some(entropy),
+                    wordlist_size: Some(wordlist.words().len()),
+                    show_classification: strength,
+                    show_entropy: strength,
+                    ..Generated::default()
+                };
+                return deliver(
+                    generated,
+                    store.as_ref(),
+                    Hook::from_args(exec, exec_fd).as_ref(),
+                    out,
+                    write_mode,
+                );
+            }
+
+            let transform = Transform {
+                capitalize,
+                add_digit,
+                add_symbol,
+                pad: pad.map(|length| Padding {
+                    length,
+                    char: pad_char,
+                    position: pad_position,
+                }),
+                easy_type,
+                separators,
+            };
+            let length = match bits {
+                Some(bits) => {
+                    let words = passphrase::words_for_entropy(bits, &wordlist, &transform)
+                        .map_err(anyhow::Error::msg)?;
+                    if words > config::MAX_WORDS as usize {
+                        anyhow::bail!(
+                            "{} bits would take {} words, more than the maximum of {}",
+                            bits,
+                            words,
+                            config::MAX_WORDS
+                        );
+                    }
+                    words
+                }
+                None => length,
+            };
+            // Sites capping the length get only words short enough for any passphrase to fit,
+            // with as many more of them as --bits then takes
+            let (length, wordlist) = match max_chars {
+                Some(max) if bits.is_some() => {
+                    let bits = bits.expect("--bits is given");
+                    (length..=config::MAX_WORDS as usize)
+                        .find_map(|words| {
+                            let fitting =
+                                passphrase::fit_chars(max, words, &separator, &wordlist, &transform)
+                                    .ok()?;
+                            (passphrase::entropy(words, &fitting, &transform) >= bits - 1e-9)
+                                .then_some((words, fitting))
+                        })
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "No passphrase of at most {} characters reaches {} bits with this word list",
+                                max,
+                                bits
+                            )
+                        })?
+                }
+                Some(max) => {
+                    let fitting =
+                        passphrase::fit_chars(max, length, &separator, &wordlist, &transform)
+                            .map_err(anyhow::Error::msg)?;
+                    (length, fitting)
+                }
+                None => (length, wordlist),
+            };
+            let entropy = passphrase::entropy(length, &wordlist, &transform);
+            warn_if_weak(&format!("--length {}", length), entropy)?;
+            let mut rng = rng_from_args(rng, seed, seed_file.as_deref(), seed_label.as_deref())?;
+
+            let mut history = open_history(no_reissue)?;
+            let mut batch = unique
+                .then(|| Batch::new(count, entropy))
+                .transpose()
+                .map_err(anyhow::Error::msg)?;
+            let mut generated = Generated {
+                classification: Some(Classification::from_entropy(entropy)),
+                entropy: Some(entropy),
+                wordlist_size: Some(wordlist.words().len()),
+                show_classification: strength,
+                show_entropy: strength || bits.is_some(),
+                ..Generated::default()
+            };
+            let mut generate = |generated: &mut Generated| {
+                let (passphrase, words) = passphrase::generate_words(
+                    length, &separator, &wordlist, &transform, &mut *rng,
+                )
+                .map_err(anyhow::Error::msg)?;
+                generated.value = passphrase.value.into_owned();
+                generated.rolls = dice_rolls(&words);
+                generated.chars = max_chars.map(|_| generated.value.chars().count());
+                Ok(())
+            };
+            let hook = Hook::from_args(exec, exec_fd);
+            if store.is_some() || hook.is_some() {
+                generate_unissued(history.as_mut(), &mut generated, generate)?;
+                deliver(generated, store.as_ref(), hook.as_ref(), out, write_mode)?;
+                return save_history(history, write_mode);
+            }
+            for _ in 0..count {
+                generate_unissued(history.as_mut(), &mut generated, |generated| {
+                    generate_unique(batch.as_mut(), generated, &mut generate)
+                })?;
+                out.emit_secret(&generated)?;
+            }
+            save_history(history, write_mode)?;
+        }
+
+        Commands::Derive {
+            site,
+            counter,
+            length,
+            alphabet,
+            custom,
+            memory,
+            iterations,
+            strength,
+        } => {
+            validate_alphabet_args(&alphabet, &custom).map_err(anyhow::Error::msg)?;
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            let generator = Generator::new(&alphabet).map_err(anyhow::Error::msg)?;
+            warn_if_short("--length", length, alphabet.len())?;
+
+            let master = if io::stdin().is_terminal() {
+                rpassword::prompt_password("Master passphrase: ")
+                    .context("Cannot read master passphrase")?
+            } else {
+                let mut line = String::new();
+                io::stdin()
+                    .lock()
+                    .read_line(&mut line)
+                    .context("Cannot read standard input")?;
+                line.trim_end_matches(['\r', '\n']).to_string()
+            };
+            let cost = derive::Cost {
+                memory_kib: memory.saturating_mul(1024),
+                iterations,
+            };
+            debug!("Deriving a password for {} with {:?}", site, cost);
+            let mut rng =
+                derive::site_rng(&master, &site, counter, cost).map_err(anyhow::Error::msg)?;
+
+            let mut value = String::new();
+            generator.generate_into(length, &mut rng, &mut value);
+            let entropy = Password::new(&value).entropy(alphabet.len());
+            out.emit_secret(&Generated {
+                value,
+                classification: Some(Classification::from_entropy(entropy)),
+                entropy: Some(entropy),
+                alphabet_size: Some(alphabet.len()),
+                show_classification: strength,
+                ..Generated::default()
+            })?;
+        }
+        Commands::Token {
+            bytes,
+            encoding,
+            count,
+            strength,
+            rng,
+        } => {
+            debug!(
+                "Generating {} {:?} tokens of {} bytes",
+                count, encoding, bytes
+            );
+
+            let mut rng = rng.rng();
+            let entropy = bytes as f64 * 8.0;
+            warn_if_weak(&format!("--bytes {}", bytes), entropy)?;
+            for _ in 0..count {
+                out.emit_secret(&Generated {
+                    value: token::generate(bytes, encoding, &mut rng),
+                    classification: Some(Classification::from_entropy(entropy)),
+                    entropy: Some(entropy),
+                    show_classification: strength,
+                    show_entropy: strength,
+                    ..Generated::default()
+                })?;
+            }
+        }
+        Commands::Uuid { kind, count } => {
+            debug!("Generating {} {:?} identifiers", count, kind);
+
+            let mut rng = rand::rng();
+            let mut ids = id::Ids::new(kind);
+            for _ in 0..count {
+                out.emit_secret(&Generated {
+                    value: ids.generate(&mut rng),
+                    entropy: Some(kind.entropy()),
+                    ..Generated::default()
+                })?;
+            }
+        }
+        Commands::RecoveryCodes {
+            count,
+            pattern,
+            sheet,
+            title,
+            strength,
+            rng,
+        } => {
+            debug!("Generating {} recovery codes of {}", count, pattern);
+
+            let template = recovery::template(&pattern).map_err(anyhow::Error::msg)?;
+            let entropy = template.entropy();
+            warn_if_weak(&format!("--pattern {}", pattern), entropy)?;
+            let codes =
+                recovery::generate(&template, count, &mut rng.rng()).map_err(anyhow::Error::msg)?;
+            if let Some(layout) = sheet {
+                out.write_document(&recovery::sheet(&codes, &title, layout))?;
+                return Ok(());
+            }
+            for value in codes {
+                out.emit_secret(&Generated {
+                    value,
+                    classification: Some(Classification::from_entropy(entropy)),
+                    entropy: Some(entropy),
+                    show_classification: strength,
+                    show_entropy: strength,
+                    ..Generated::default()
+                })?;
+            }
+        }
+        Commands::SshKey {
+            key_type,
+            out: path,
+            comment,
+            length,
+            wordlist,
+            separator,
+            store,
+        } => {
+            let path = path
+                .or_else(|| ssh_key::default_path(key_type))
+                .ok_or_else(|| anyhow::anyhow!("No home directory; pass --out"))?;
+            let public = ssh_key::public_key_path(&path);
+
+            // Settle every confirmation first so no key is left behind with a passphrase
+            // that was never saved
+            let exists = path.exists() || public.exists();
+            let action = if exists {
+                format!("overwrite {}", path.display())
+            } else {
+                format!("create {}", path.display())
+            };
+            if (write_mode.dry_run || exists) && !write_mode.approve(&action)? {
+                return Ok(());
+            }
+            if let Some(store) = &store
+                && !approve_store(store, write_mode)?
+            {
+                return Ok(());
+            }
+
+            let wordlist = wordlist.unwrap_or_default();
+            warn_if_short("--length", length, wordlist.words().len())?;
+            let passphrase =
+                passphrase::generate_passphrase(length, &separator, &wordlist, &mut rand::rng())
+                    .map_err(anyhow::Error::msg)?;
+            // Saved or printed before the key exists, so no key is ever protected by a
+            // passphrase that was lost
+            match &store {
+                Some(store) => save_secret(store, &passphrase.value, out)?,
+                None => out.emit(&SshKeyCreated {
+                    path: path.clone(),
+                    public_key: public.clone(),
+                    passphrase: passphrase.value.to_string(),
+                })?,
+            }
+            out.flush()?;
+            debug!("Creating {:?} key at {}", key_type, path.display());
+            ssh_key::generate(key_type, &path, &passphrase.value, comment.as_deref())
+                .with_context(|| format!("Cannot create {}", path.display()))?;
+            out.note(format_args!(
+                "Created {} and {}",
+                path.display(),
+                public.display()
+            ))?;
+        }
+
+        Commands::Decoy {
+            resemble,
+            stdin,
+            count,
+            style,
+        } => {
+            debug!("Generating {} {:?} decoys", count, style);
+            // Never an argument, which would end up in shell history and process listings
+            let password = match (resemble, stdin) {
+                (false, _) => None,
+                (true, true) => {
+                    let mut line = String::new();
+                    io::stdin()
+                        .read_line(&mut line)
+                        .context("Cannot read standard input")?;
+                    Some(line.trim_end_matches(['\n', '\r']).to_string())
+                }
+                (true, false) if io::stdin().is_terminal() => Some(
+                    rpassword::prompt_password("Password to resemble: ")
+                        .context("Cannot read password")?,
+                ),
+                (true, false) => anyhow::bail!(
+                    "No password to resemble; pass --stdin to read it from standard input"
+                ),
+            };
+            let mut rng = rand::rng();
+            for _ in 0..count {
+                let decoy = match &password {
+                    Some(password) => Decoy {
+                        value: decoy::resembling(password, &mut rng).map_err(anyhow::Error::msg)?,
+                        style: DecoyStyle::Resembling,
+                        insecure: true,
+                    },
+                    None => Decoy {
+                        value: decoy::generate(style, &mut rng),
+                        style,
+                        insecure: true,
+                    },
+                };
+                out.emit(&decoy)?;
+            }
+        }
+
+        Commands::Export {
+            target,
+            names,
+            site,
+            username,
+            url,
+            hash,
+            dn_template,
+            length,
+            alphabet,
+            custom,
+            words,
+            wordlist,
+            separator,
+            group,
+            no_reissue,
+            rng,
+            seed_file,
+            seed_label,
+            seed,
+        } => {
+            validate_alphabet_args(&alphabet, &custom).map_err(anyhow::Error::msg)?;
+
+            let accounts = match (names, site) {
+                (Some(names), _) => {
+                    let source = std::fs::read_to_string(&names)
+                        .with_context(|| format!("Cannot read names file {}", names.display()))?;
+                    export::parse_names(&source)
+                }
+                (None, Some(title)) => vec![Account {
+                    title,
+                    username: username.unwrap_or_default(),
+                    url: url.unwrap_or_default(),
+                }],
+                (None, None) => unreachable!("clap requires --names or --site"),
+            };
+            debug!("Exporting {} accounts as {:?}", accounts.len(), target);
+
+            let mut rng = rng_from_args(rng, seed, seed_file.as_deref(), seed_label.as_deref())?;
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            let wordlist = wordlist.unwrap_or_default();
+            let generator = Generator::new(&alphabet).map_err(anyhow::Error::msg)?;
+            match words {
+                Some(words) => warn_if_short("--words", words, wordlist.words().len())?,
+                None => warn_if_short("--length", length, alphabet.len())?,
+            }
+
+            let mut history = open_history(no_reissue)?;
+            let mut generate = |generated: &mut Generated| {
+                let password = match words {
+                    Some(words) => {
+                        passphrase::generate_passphrase(words, &separator, &wordlist, &mut *rng)
+                            .map_err(anyhow::Error::msg)?
+                    }
+                    None => generator.generate(length, &mut *rng),
+                };
+                generated.value = password.value.into_owned();
+                Ok(())
+            };
+            let credentials = accounts
+                .into_iter()
+                .map(|account| {
+                    let mut generated = Generated::default();
+                    generate_unissued(history.as_mut(), &mut generated, &mut generate)?;
+                    Ok(Credential {
+                        account,
+                        password: generated.value,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let options = export::Options {
+                group,
+                hash,
+                dn_template,
+            };
+            let document =
+                export::render(target, &options, &credentials).map_err(anyhow::Error::msg)?;
+            out.write_document(&document)?;
+            save_history(history, write_mode)?;
+        }
+
+        Commands::Check {
+            password,
+            stdin,
+            breach_file,
+            alphabet,
+            custom,
+            common,
+            contains,
+            fuzzy,
+            context,
+            wordlist,
+            language,
+            common_file,
+            policy,
+            min_class,
+            quiet,
+        } => {
+            debug!("Checking password");
+
+            // Without an alphabet given, it is inferred from the classes of characters the
+            // password has, and characters beyond them widen the search to their script
+            // instead of failing the check
+            let given_alphabet = alphabet.is_some() || custom.is_some();
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            let context = PersonalContext::parse(&context).map_err(anyhow::Error::msg)?;
+            let mut lists: Vec<(CommonWords, &str)> = if !common {
+                Vec::new()
+            } else if let Some(wl) = wordlist {
+                vec![(CommonWords::Custom(wl), PROVIDED_WORDS)]
+            } else if let Some(path) = common_file {
+                let words = CommonWords::from_file(&path).with_context(|| wordlist_error(&path))?;
+                vec![(words, PROVIDED_WORDS)]
+            } else if !language.is_empty() {
+                checker::safety_checks(&language)
+            } else {
+                checker::SAFETY_CHECKS.into()
+            };
+            dict::prefer_downloaded(&mut lists);
+            let level = contains.map_or(SafetyLevel::Combination, SafetyLevel::Contains);
+            let screen = Screen::new(&lists).with_level(level).with_fuzzy(fuzzy);
+            let breach = breach_file
+                .map(|path| {
+                    BreachList::open(&path)
+                        .with_context(|| format!("Cannot read breach file {}", path.display()))
+                })
+                .transpose()?;
+            let policy = load_policy(policy.as_deref())?;
+            // Only a history that was opted into by issuing secrets with it is consulted
+            let issued = match history_path() {
+                Ok(path) if path.exists() => Some(
+                    History::open(&path)
+                        .with_context(|| format!("Cannot read history {}", path.display()))?,
+                ),
+                _ => None,
+            };
+            let mut rejected = Rejected::default();
+            // Structured results of many lines leave the passwords out
+            let by_line = stdin && out.format() != Format::Plain;
+            let color = out.color();
+            let mut check = |password: &str, line: usize| -> anyhow::Result<()> {
+                let password_obj = Password::new(password);
+                let safety = match &breach {
+                    Some(breach) if breach.contains(password) => SafetyReport {
+                        finding: Some("breached password".into()),
+                        matches: Vec::new(),
+                    },
+                    _ if issued
+                        .as_ref()
+                        .is_some_and(|issued| issued.contains(password)) =>
+                    {
+                        SafetyReport {
+                            finding: Some("password you have used before".into()),
+                            matches: Vec::new(),
+                        }
+                    }
+                    _ => screen.report(&password_obj),
+                };
+                let Assessment {
+                    safety,
+                    strength,
+                    classification,
+                } = Assessment::of(
+                    &password_obj,
+                    safety,
+                    given_alphabet.then_some(&alphabet),
+                    &context,
+                )?;
+                let detected_classes = match given_alphabet {
+                    true => Vec::new(),
+                    false => Alphabet::detect_classes(password),
+                };
+                let violations = policy
+                    .as_ref()
+                    .map(|policy| policy.violations(password))
+                    .unwrap_or_default();
+                let safe = safety.is_safe() && violations.is_empty();
+                let strong_enough =
+                    min_class.is_none_or(|min| classification.is_some_and(|class| class >= min));
+                rejected.checked += 1;
+                if !safe || !strong_enough {
+                    rejected.failed += 1;
+                }
+                if quiet {
+                    return Ok(());
+                }
+                if by_line {
+                    let findings = safety
+                        .finding
+                        .is_some()
+                        .then(|| safety.to_string())
+                        .into_iter()
+                        .chain(violations)
+                        .collect();
+                    out.emit(&CheckLine {
+                        line,
+                        safe,
+                        classification,
+                        entropy: strength.entropy,
+                        findings,
+                    })?;
+                    return Ok(());
+                }
+                let target = min_class.unwrap_or(Classification::Strong);
+                let suggestions =
+                    strength.suggestions(password, &screen.words(&password_obj), target);
+                out.emit_secret(&CheckResult {
+                    crack_times: strength.crack_times(),
+                    suggestions,
+                    show_details: !stdin,
+                    color,
+                    entropy: strength.entropy,
+                    raw_entropy: strength.raw_entropy,
+                    alphabet_size: strength.alphabet_size,
+                    detected_classes,
+                    patterns: strength.patterns,
+                    password: password.to_string(),
+                    safe,
+                    safety,
+                    violations,
+                    classification,
+                })?;
+                Ok(())
+            };
+
+            match password {
+                Some(password) => check(&password, 1)?,
+                None if stdin => {
+                    // One line at a time into the same buffer, so corpora of millions of
+                    // lines stream through
+                    let mut input = io::stdin().lock();
+                    let mut buf = String::new();
+                    for line in 1.. {
+                        buf.clear();
+                        if input
+                            .read_line(&mut buf)
+                            .context("Cannot read standard input")?
+                            == 0
+                        {
+                            break;
+                        }
+                        let password = buf.trim_end_matches(['\n', '\r']);
+                        if !password.is_empty() {
+                            check(password, line)?;
+                        }
+                    }
+                }
+                None if io::stdin().is_terminal() => {
+                    let password = rpassword::prompt_password("Password to check: ")
+                        .context("Cannot read password")?;
+                    check(&password, 1)?;
+                }
+                None => anyhow::bail!(
+                    "No password to check; pass --stdin to check the lines of standard input"
+                ),
+            }
+            if rejected.failed > 0 {
+                if !quiet {
+                    out.note(&rejected)?;
+                }
+                return Err(rejected.into());
+            }
+        }
+        Commands::Audit { file, csv, weakest } => {
+            let source = if file == Path::new("-") {
+                io::read_to_string(io::stdin()).context("Cannot read standard input")?
+            } else {
+                std::fs::read_to_string(&file)
+                    .with_context(|| format!("Cannot read {}", file.display()))?
+            };
+            let entries = if csv {
+                audit::parse_csv(&source)
+                    .map_err(anyhow::Error::msg)
+                    .with_context(|| format!("Cannot parse {}", file.display()))?
+            } else {
+                audit::parse_lines(&source)
+            };
+            debug!("Auditing {} passwords", entries.len());
+
+            let mut lists = checker::SAFETY_CHECKS;
+            dict::prefer_downloaded(&mut lists);
+            out.emit(&audit::Report::audit(
+                &entries,
+                &Screen::new(&lists),
+                weakest,
+            ))?;
+        }
+        Commands::Collide {
+            length,
+            alphabet,
+            custom,
+            count,
+        } => {
+            validate_alphabet_args(&alphabet, &custom).map_err(anyhow::Error::msg)?;
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            out.emit(&collide::Collision::estimate(length, alphabet.len(), count))?;
+        }
+        Commands::Variants {
+            base,
+            count,
+            min_distance,
+        } => {
+            debug!(
+                "Generating {} variants at least {} edits apart",
+                count, min_distance
+            );
+            let variants = variants::generate(&base, count, min_distance, &mut rand::rng())
+                .map_err(anyhow::Error::msg)?;
+            for value in variants {
+                out.emit(&Variant {
+                    distance: checker::edit_distance(&base, &value),
+                    value,
+                })?;
+            }
+        }
+        Commands::Strengthen {
+            password,
+            target,
+            count,
+            wordlist,
+            separator,
+        } => {
+            debug!("Strengthening a password to {:?}", target);
+            let mut rng = rand::rng();
+            for method in Method::ALL {
+                for _ in 0..count {
+                    let Some(hardened) = strengthen::strengthen(
+                        &password, method, target, &wordlist, &separator, &mut rng,
+                    )
+                    .map_err(anyhow::Error::msg)?
+                    else {
+                        out.note(format_args!(
+                            "The password is already {:?} or better",
+                            target
+                        ))?;
+                        return Ok(());
+                    };
+                    out.emit(&Strengthened {
+                        classification: Classification::from_entropy(hardened.entropy),
+                        value: hardened.value,
+                        method: hardened.method,
+                        entropy: hardened.entropy,
+                        gain: hardened.gain,
+                    })?;
+                }
+            }
+        }
+        Commands::Doctor => {
+            debug!("Running health checks");
+
+            let checks = doctor::run_checks();
+            for check in &checks {
+                out.emit(check)?;
+            }
+
+            let failed = checks
+                .iter()
+                .filter(|c| c.status == doctor::Status::Fail)
+                .count();
+            if failed > 0 {
+                anyhow::bail!("{} health checks failed", failed);
+            }
+        }
+        Commands::SelfUpdate { check } => {
+            debug!("Checking for updates");
+
+            out.emit(&update::self_update(check, write_mode)?)?;
+        }
+        Commands::Wordlist { command } => match command {
+            WordlistCommands::List => {
+                for wordlist in WordList::value_variants() {
+                    out.emit(&WordlistInfo::new(wordlist))?;
+                }
+                if let Some(path) = settings()?.1.wordlist_file {
+                    let path = config::resolve_path(&path);
+                    let wordlist =
+                        WordList::from_file(&path).with_context(|| wordlist_error(&path))?;
+                    out.emit(&WordlistInfo::new(&wordlist))?;
+                }
+            }
+            WordlistCommands::Info { wordlist, file } => {
+                let wordlist = match (wordlist, file) {
+                    (_, Some(path)) => {
+                        WordList::from_file(&path).with_context(|| wordlist_error(&path))?
+                    }
+                    (wordlist, None) => wordlist.unwrap_or_default(),
+                };
+                out.emit(&Detailed(WordlistInfo::new(&wordlist)))?;
+            }
+            WordlistCommands::Export { wordlist, indices } => {
+                debug!("Exporting wordlist {:?}", wordlist);
+
+                if indices {
+                    for (index, word) in wordlist.dice_entries().unwrap_or_default() {
+                        out.emit(&WordlistEntry {
+                            index: Some(index),
+                            word: word.to_string(),
+                        })?;
+                    }
+                } else {
+                    for word in wordlist.words().iter() {
+                        out.emit(&WordlistEntry {
+                            index: None,
+                            word: word.to_string(),
+                        })?;
+                    }
+                }
+            }
+        },
+        Commands::Alphabet { command } => match command {
+            AlphabetCommands::List => {
+                for alphabet in Alphabet::value_variants() {
+                    out.emit(&AlphabetInfo::new(alphabet))?;
+                }
+                if let Some(config) = Config::load()? {
+                    for (name, alphabet) in config.custom_alphabets() {
+                        out.emit(&AlphabetInfo::named(name.to_string(), &alphabet))?;
+                    }
+                }
+            }
+            AlphabetCommands::Show { alphabet: name } => {
+                let config = Config::load()?.unwrap_or_default();
+                let alphabet = config
+                    .alphabet(&name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown alphabet `{}`", name))?;
+                let info = match alphabet {
+                    Alphabet::Custom(_) => AlphabetInfo::named(name, &alphabet),
+                    _ => AlphabetInfo::new(&alphabet),
+                };
+                out.emit(&Detailed(info))?;
+            }
+        },
+        Commands::Cache { command } => {
+            let dir = cache::cache_dir()
+                .ok_or_else(|| anyhow::anyhow!("No cache directory available on this platform"))?;
+
+            match command {
+                CacheCommands::List => {
+                    let entries = cache::list_entries(&dir)?;
+                    let total: u64 = entries.iter().map(|e| e.size).sum();
+                    for entry in &entries {
+                        out.emit(entry)?;
+                    }
+                    out.note(format_args!("{:>10}  total", cache::format_size(total)))?;
+                }
+                CacheCommands::Clean => {
+                    let entries = cache::list_entries(&dir)?;
+                    let action = format!(
+                        "delete {} cached files from {}",
+                        entries.len(),
+                        dir.display()
+                    );
+                    if !entries.is_empty() && write_mode.approve(&action)? {
+                        let removed = cache::clean(&dir)?;
+                        out.emit(&CacheCleaned { removed, path: dir })?;
+                    }
+                }
+                CacheCommands::Path => {
+                    out.emit(&Location { path: dir })?;
+                }
+            }
+        }
+        Commands::Dict { command } => {
+            let path = dict::passwords_path()
+                .ok_or_else(|| anyhow::anyhow!("No data directory available on this platform"))?;
+
+            match command {
+                DictCommands::Update {
+                    url,
+                    sha256,
+                    checksums,
+                } => {
+                    let checksum = match (sha256, checksums) {
+                        (Some(digest), _) => dict::Checksum::Digest(digest),
+                        (None, Some(listing)) => dict::Checksum::Listing(listing),
+                        (None, None) => unreachable!("clap requires one checksum option"),
+                    };
+                    let action = if path.exists() {
+                        format!("replace {} with {}", path.display(), url)
+                    } else {
+                        format!("download {} to {}", url, path.display())
+                    };
+                    if (write_mode.dry_run || path.exists()) && !write_mode.approve(&action)? {
+                        return Ok(());
+                    }
+
+                    debug!("Updating common passwords from {}", url);
+                    out.emit(&dict::update(&url, &checksum, &path)?)?;
+                }
+                DictCommands::Path => {
+                    out.emit(&Location { path })?;
+                }
+            }
+        }
+        Commands::Interactive => {
+            if !io::stdout().is_terminal() {
+                anyhow::bail!("passgen interactive needs a terminal");
+            }
+            let (config, settings) = settings()?;
+            let alphabet = match (settings.custom, &settings.alphabet) {
+                (Some(chars), _) => Alphabet::Custom(chars),
+                (None, Some(name)) => config.alphabet(name).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown alphabet `{}` in the config file", name)
+                })?,
+                (None, None) => Alphabet::default(),
+            };
+            let wordlist = match (&settings.wordlist_file, &settings.wordlist) {
+                (Some(path), _) => {
+                    let path = config::resolve_path(path);
+                    WordList::from_file(&path).with_context(|| wordlist_error(&path))?
+                }
+                (None, Some(name)) => WordList::from_str(name, true).map_err(|_| {
+                    anyhow::anyhow!("Unknown wordlist `{}` in the config file", name)
+                })?,
+                (None, None) => WordList::default(),
+            };
+            let mut app = interactive::App::new(
+                settings.length.unwrap_or(12),
+                alphabet,
+                settings.words.unwrap_or(3),
+                wordlist,
+                settings.separator.unwrap_or_else(|| "-".to_string()),
+                rand::rng(),
+            );
+            interactive::run(&mut app).context("Cannot run the terminal UI")?;
+        }
+        Commands::Init => {
+            let path = config::config_path()
+                .ok_or_else(|| anyhow::anyhow!("No config directory available on this platform"))?;
+
+            // Ask before the questions so an existing config is not replaced by accident
+            let action = if path.exists() {
+                format!("overwrite {}", path.display())
+            } else {
+                format!("write config to {}", path.display())
+            };
+            if (write_mode.dry_run || path.exists()) && !write_mode.approve(&action)? {
+                return Ok(());
+            }
+
+            let config = init::run_wizard(&mut std::io::stdin().lock(), &mut std::io::stderr())?;
+            config.save(&path)?;
+            out.emit(&Location { path })?;
+        }
+        #[cfg(feature = "serve")]
+        Commands::Serve {
+            listen,
+            token_file,
+            rate_limit,
+        } => {
+            let token = match token_file {
+                Some(path) => std::fs::read_to_string(&path)
+                    .with_context(|| format!("Cannot read token file {}", path.display()))?
+                    .trim()
+                    .to_string(),
+                None => match std::env::var(SERVE_TOKEN_ENV) {
+                    Ok(token) => token,
+                    Err(_) => {
+                        let token = serve::random_token();
+                        out.emit(&BearerToken {
+                            token: token.clone(),
+                        })?;
+                        token
+                    }
+                },
+            };
+            if token.is_empty() {
+                anyhow::bail!("The bearer token must not be empty");
+            }
+            out.note(format_args!("Listening on http://{}", listen))?;
+            out.flush()?;
+            http::serve(listen, serve::Api::new(token, rate_limit))?;
+        }
+        Commands::Mcp => {
+            debug!("Serving MCP over stdio");
+
+            // The protocol owns stdout, so this bypasses the output layer
+            mcp::run(std::io::stdin().lock(), std::io::stdout().lock())?;
+        }
+        Commands::NativeHost { browser_args } => {
+            debug!("Serving native messaging for {:?}", browser_args);
+
+            // Like MCP, the protocol owns stdout
+            native_host::run(std::io::stdin().lock(), std::io::stdout().lock())?;
+        }
+        #[cfg(unix)]
+        Commands::Daemon {
+            socket,
+            #[cfg(feature = "serve")]
+            metrics_listen,
+        } => {
+            let socket = socket
+                .or_else(daemon::default_socket_path)
+                .ok_or_else(|| anyhow::anyhow!("No runtime directory; pass --socket"))?;
+            let metrics = Arc::new(Metrics::default());
+            #[cfg(feature = "serve")]
+            if let Some(listen) = metrics_listen {
+                let metrics = Arc::clone(&metrics);
+                std::thread::spawn(move || {
+                    if let Err(e) = http::serve_metrics(listen, metrics) {
+                        tracing::warn!("Metrics endpoint stopped: {:#}", e);
+                    }
+                });
+                out.note(format_args!("Metrics on http://{}/metrics", listen))?;
+            }
+            out.note(format_args!("Listening on {}", socket.display()))?;
+            out.flush()?;
+            daemon::run(&socket, metrics)?;
+        }
+        Commands::Config { command } => match command {
+            ConfigCommands::Check { file } => {
+                let path = file.or_else(config::config_path).ok_or_else(|| {
+                    anyhow::anyhow!("No config directory available on this platform")
+                })?;
+                if !path.exists() {
+                    out.note(format_args!(
+                        "No config file at {}; built-in defaults are used",
+                        path.display()
+                    ))?;
+                    return Ok(());
+                }
+                debug!("Checking config file {}", path.display());
+
+                let source = std::fs::read_to_string(&path)?;
+                let diagnostics = config::validate(&source, path.parent());
+                for diagnostic in &diagnostics {
+                    out.emit(diagnostic)?;
+                }
+
+                let errors = diagnostics
+                    .iter()
+                    .filter(|d| d.severity == config::Severity::Error)
+                    .count();
+                if errors > 0 {
+                    anyhow::bail!("{} has {} errors", path.display(), errors);
+                }
+                out.note(format_args!("{} is valid", path.display()))?;
+            }
+            ConfigCommands::Path => {
+                let path = config::config_path().ok_or_else(|| {
+                    anyhow::anyhow!("No config directory available on this platform")
+                })?;
+                out.emit(&Location { path })?;
+            }
+            ConfigCommands::Show => out.emit(&settings()?.1)?,
+        },
+        Commands::Breach { command } => match command {
+            BreachCommands::Index { corpus, index } => {
+                let words = MappedWords::open(&corpus)
+                    .with_context(|| format!("Cannot read corpus {}", corpus.display()))?;
+                let action = if index.exists() {
+                    format!("overwrite {}", index.display())
+                } else {
+                    format!("write breach index to {}", index.display())
+                };
+                if (write_mode.dry_run || index.exists()) && !write_mode.approve(&action)? {
+                    return Ok(());
+                }
+                let file = std::fs::File::create(&index)
+                    .with_context(|| format!("Cannot create {}", index.display()))?;
+                let count = breach::write_index(&words, &mut io::BufWriter::new(file))
+                    .with_context(|| format!("Cannot write {}", index.display()))?;
+                out.note(format_args!("Indexed {} distinct passwords", count))?;
+                out.emit(&Location { path: index })?;
+            }
+        },
+        Commands::Policy { command } => match command {
+            PolicyCommands::Import { kind, export } => {
+                let path = policy::policy_path().ok_or_else(|| {
+                    anyhow::anyhow!("No config directory available on this platform")
+                })?;
+                let bytes = std::fs::read(&export)
+                    .with_context(|| format!("Cannot read {}", export.display()))?;
+                let text = policy::decode_text(&bytes)
+                    .map_err(anyhow::Error::msg)
+                    .with_context(|| format!("Cannot decode {}", export.display()))?;
+                let imported = Policy::import(kind, &text)
+                    .map_err(anyhow::Error::msg)
+                    .with_context(|| format!("Cannot import {}", export.display()))?;
+                debug!("Imported policy {:?}", imported);
+
+                let action = if path.exists() {
+                    format!("overwrite {}", path.display())
+                } else {
+                    format!("write policy to {}", path.display())
+                };
+                if (write_mode.dry_run || path.exists()) && !write_mode.approve(&action)? {
+                    return Ok(());
+                }
+                imported.save(&path)?;
+                out.emit(&Location { path })?;
+            }
+            PolicyCommands::Path => {
+                let path = policy::policy_path().ok_or_else(|| {
+                    anyhow::anyhow!("No config directory available on this platform")
+                })?;
+                out.emit(&Location { path })?;
+            }
+            PolicyCommands::Show { file } => match load_policy(file.as_deref())? {
+                Some(policy) => out.emit(&policy)?,
+                None => out.note("No password policy is set")?,
+            },
+        },
+        Commands::ClearClipboard { after } => {
+            clipboard::wait_and_clear(Duration::from_secs(after))?;
+        }
+        Commands::Selftest {
+            samples,
+            alphabet,
+            custom,
+            wordlist,
+            wordlist_file,
+            rng,
+        } => {
+            debug!("Running self-test with {} samples", samples);
+
+            let alphabet = get_alphabet_from_args(alphabet, custom);
+            let wordlist = match (wordlist, wordlist_file) {
+                (Some(wordlist), _) => wordlist,
+                (None, Some(path)) => {
+                    WordList::from_file(&path).with_context(|| wordlist_error(&path))?
+                }
+                (None, None) => WordList::default(),
+            };
+            let results = selftest::run(&alphabet, &wordlist, samples, &mut rng.rng())
+                .map_err(anyhow::Error::msg)?;
+            for result in &results {
+                out.emit(result)?;
+            }
+
+            let failed = results.iter().filter(|r| !r.passed).count();
+            if failed > 0 {
+                anyhow::bail!("{} of {} self-tests failed", failed, results.len());
+            }
+            out.note(format_args!("All {} self-tests passed", results.len()))?;
+        }
+    }
+    Ok(())
+}
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+}
+
+fn main() {
+    // Run as ssh-keygen's askpass helper by `ssh-key`: answer its prompt and nothing else
+    if let Some(passphrase) = ssh_key::askpass_passphrase() {
+        println!("{}", passphrase.to_string_lossy());
+        return;
+    }
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(cli.debug, cli.log_format);
+    debug!(version = env!("CARGO_PKG_VERSION"), "starting passgen");
+    let write_mode = WriteMode::new(cli.dry_run, cli.yes);
+
+    if let Some(profile) = cli.profile {
+        PROFILE.set(profile).expect("the profile is set once");
+    }
+    if let Some(history) = cli.history {
+        HISTORY.set(history).expect("the history is set once");
+    }
+    let Some(mut command) = cli.command else {
+        let shown =
+            Output::new(cli.format, Destination::Stdout, &write_mode).and_then(|mut out| {
+                out.note("No command provided. Use --help for more information.")?;
+                if config::config_path().is_some_and(|path| !path.exists()) {
+                    out.note("No config file yet; run `passgen init` to choose your defaults.")?;
+                }
+                out.finish()
+            });
+        if let Err(e) = shown {
+            output::report_error(cli.format, &e);
+            std::process::exit(1);
+        }
+        return;
+    };
+
+    let given = matches
+        .subcommand()
+        .map(|(_, given)| given)
+        .expect("a command was given");
+    let result = apply_settings(&mut command, given)
+        .and_then(|configured_copy| {
+            // These speak their own protocol or draw on stdout, so results never reach the
+            // output layer
+            if matches!(
+                command,
+                Commands::Mcp | Commands::NativeHost { .. } | Commands::Interactive
+            ) && (cli.output.is_some() || cli.copy || cli.format != Format::Plain)
+            {
+                anyhow::bail!("--output, --copy and --format do not apply to this command");
+            }
+            let copy = cli.copy || (configured_copy && cli.output.is_none());
+            if cli.clear_after.is_some() && !copy {
+                anyhow::bail!("--clear-after only applies with --copy");
+            }
+            let destination = destination_from_args(cli.output, copy, cli.clear_after);
+            Output::new(cli.format, destination, &write_mode)
+                .map(|out| out.with_phonetic(cli.phonetic).with_color(!cli.no_color))
+                .map_err(anyhow::Error::from)
+        })
+        .and_then(|mut out| {
+            let result = run(command, &mut out, &write_mode);
+            // Deliver whatever was produced before a failure, then report the failure
+            let finished = out.finish().map_err(anyhow::Error::from);
+            result.and(finished)
+        });
+
+    if let Err(e) = result {
+        if is_broken_pipe(&e) {
+            return;
         }
-        None => {
-            eprintln!("No command provided. Use --help for more information.");
+        // Already reported through the output layer
+        if e.downcast_ref::<Rejected>().is_some() {
+            std::process::exit(REJECTED_EXIT_CODE);
         }
+        output::report_error(cli.format, format_args!("{:#}", e));
+        std::process::exit(1);
     }
 }
@@ -1,12 +1,17 @@
 use clap::ValueEnum;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Clone, ValueEnum)]
+/// Characters passwords are drawn from.
+#[derive(Debug, Clone, Default, ValueEnum)]
 pub enum Alphabet {
+    #[default]
     Full,
     LowerCase,
     UpperCase,
     Digits,
     SpecialChars,
+    /// Exactly the characters of the string
     #[clap(skip)]
     Custom(String),
 }
@@ -16,14 +21,75 @@ const LOWER_CASE: &str = "abcdefghijklmnopqrstuvwxyz";
 const UPPER_CASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const DIGITS: &str = "0123456789";
 const SPECIAL_CHARS: &str = "!@#$%^&*";
+// Every printable ASCII character besides letters and digits, space included
+const SYMBOLS: &str = " !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
 
-impl Default for Alphabet {
-    fn default() -> Self {
-        Alphabet::Full
-    }
-}
+// Classes of printable ASCII characters a password to audit is detected to use, with the
+// characters an attacker guessing by class tries for each
+const CLASSES: [(&str, &str); 4] = [
+    ("lower-case", LOWER_CASE),
+    ("upper-case", UPPER_CASE),
+    ("digits", DIGITS),
+    ("symbols", SYMBOLS),
+];
+
+// Scripts a password may be typed in, with the characters an attacker would try for a
+// character of each: the letters in everyday use, or the whole block for ideographs
+const SCRIPTS: &[(RangeInclusive<char>, usize)] = &[
+    // Accented Latin letters of Latin-1 and Latin Extended-A
+    ('\u{c0}'..='\u{17f}', 128),
+    ('\u{370}'..='\u{3ff}', 49),        // Greek
+    ('\u{400}'..='\u{4ff}', 66),        // Cyrillic
+    ('\u{590}'..='\u{5ff}', 27),        // Hebrew
+    ('\u{600}'..='\u{6ff}', 36),        // Arabic
+    ('\u{900}'..='\u{97f}', 64),        // Devanagari
+    ('\u{e00}'..='\u{e7f}', 70),        // Thai
+    ('\u{3040}'..='\u{309f}', 86),      // Hiragana
+    ('\u{30a0}'..='\u{30ff}', 90),      // Katakana
+    ('\u{4e00}'..='\u{9fff}', 20_992),  // CJK Unified Ideographs
+    ('\u{ac00}'..='\u{d7a3}', 11_172),  // Hangul syllables
+    ('\u{1f300}'..='\u{1faff}', 1_500), // Emoji and pictographs
+];
+
+/// Characters most often misread when a password is copied by hand.
+pub const AMBIGUOUS: &str = "0O1lI|";
+/// [`AMBIGUOUS`] and every other look-alike in the built-in alphabets, as pwgen's `-B`
+/// leaves out, e.g. `B` and `8` or `S` and `5`.
+pub const SIMILAR: &str = "0OoQD1lIij|!B8G6S5Z2";
 
 impl Alphabet {
+    /// The classes of printable ASCII characters `password` has any of, for passwords not
+    /// drawn from a known alphabet: all lower-case letters for one of them, all symbols
+    /// for one symbol, and so on. Characters outside them count through
+    /// [`Alphabet::search_space`].
+    pub fn detect(password: &str) -> Alphabet {
+        Alphabet::Custom(
+            CLASSES
+                .iter()
+                .filter(|(_, chars)| password.chars().any(|c| chars.contains(c)))
+                .map(|(_, chars)| *chars)
+                .collect(),
+        )
+    }
+
+    /// The characters of the class [`Alphabet::detect`] puts `c` in, e.g. the digits for
+    /// `7`; `None` for characters outside printable ASCII.
+    pub fn class_of(c: char) -> Option<&'static str> {
+        CLASSES
+            .iter()
+            .find(|(_, chars)| chars.contains(c))
+            .map(|(_, chars)| *chars)
+    }
+
+    /// Names of the classes [`Alphabet::detect`] finds in `password`.
+    pub fn detect_classes(password: &str) -> Vec<&'static str> {
+        CLASSES
+            .iter()
+            .filter(|(_, chars)| password.chars().any(|c| chars.contains(c)))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             Alphabet::Full => FULL,
@@ -39,7 +105,126 @@ impl Alphabet {
         self.as_str().contains(c)
     }
 
+    /// The distinct characters in order; a custom alphabet may list one twice, which
+    /// would otherwise make it more likely.
+    pub fn chars(&self) -> Vec<char> {
+        let mut seen = HashSet::new();
+        self.as_str().chars().filter(|&c| seen.insert(c)).collect()
+    }
+
+    /// Number of distinct characters, which for custom alphabets may take several bytes
+    /// each.
     pub fn len(&self) -> usize {
-        self.as_str().len()
+        self.chars().len()
+    }
+
+    /// Characters an attacker must try at each position of `password`: those of this
+    /// alphabet, plus the whole script of any character of the password outside it, e.g.
+    /// all CJK ideographs for one of them, or the character alone for no known script.
+    pub fn search_space(&self, password: &str) -> usize {
+        let mut scripts = HashSet::new();
+        let mut others = HashSet::new();
+        for c in password.chars().filter(|&c| !self.contains(c)) {
+            match SCRIPTS.iter().position(|(range, _)| range.contains(&c)) {
+                Some(script) => scripts.insert(script),
+                None => others.insert(c),
+            };
+        }
+        self.len() + scripts.iter().map(|&i| SCRIPTS[i].1).sum::<usize>() + others.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    /// Name as accepted on the command line, `custom` for custom alphabets.
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_else(|| "custom".to_string())
+    }
+
+    /// The characters of this alphabet except those in `chars`, as a custom alphabet.
+    pub fn without(&self, chars: &str) -> Alphabet {
+        Alphabet::Custom(
+            self.as_str()
+                .chars()
+                .filter(|&c| !chars.contains(c))
+                .collect(),
+        )
+    }
+
+    /// Entropy contributed by each uniformly chosen character.
+    pub fn bits_per_char(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        (self.len() as f64).log2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Alphabet::Full.name(), "full");
+        assert_eq!(Alphabet::SpecialChars.name(), "special-chars");
+        assert_eq!(Alphabet::Custom("ab".to_string()).name(), "custom");
+    }
+
+    #[test]
+    fn test_bits_per_char() {
+        assert!((Alphabet::LowerCase.bits_per_char() - 4.70).abs() < 0.01);
+        assert_eq!(Alphabet::Digits.len(), 10);
+        assert_eq!(Alphabet::Custom("01".to_string()).bits_per_char(), 1.0);
+        assert_eq!(Alphabet::Custom("".to_string()).bits_per_char(), 0.0);
+    }
+
+    #[test]
+    fn test_without() {
+        let digits = Alphabet::Digits.without(AMBIGUOUS);
+        assert_eq!(digits.as_str(), "23456789");
+        assert_eq!(digits.name(), "custom");
+        assert_eq!(Alphabet::Full.without(AMBIGUOUS).len(), 72 - 5);
+        let similar = Alphabet::Full.without(SIMILAR);
+        assert!(!SIMILAR.chars().any(|c| similar.contains(c)));
+        // All but `|` are in the full alphabet
+        assert_eq!(similar.len(), 72 - (SIMILAR.len() - 1));
+    }
+
+    #[test]
+    fn test_unicode() {
+        let emoji = Alphabet::Custom("🔑🔒🔓🔑".to_string());
+        assert_eq!(emoji.len(), 3);
+        assert_eq!(emoji.chars(), ['🔑', '🔒', '🔓']);
+        assert_eq!(Alphabet::Custom("密码安全".to_string()).len(), 4);
+    }
+
+    #[test]
+    fn test_search_space() {
+        let lower = Alphabet::LowerCase;
+        assert_eq!(lower.search_space("hunter"), 26);
+        // Both characters are CJK, counted as one script
+        assert_eq!(lower.search_space("abc密码"), 26 + 20_992);
+        assert_eq!(lower.search_space("пароль"), 26 + 66);
+        // No script listed, so each counts alone
+        assert_eq!(lower.search_space("a\u{2603}\u{2603}\u{2602}"), 26 + 2);
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Alphabet::detect("hunter").len(), 26);
+        assert_eq!(Alphabet::detect("Hunter2").len(), 62);
+        assert_eq!(Alphabet::detect("hunter 2.").len(), 26 + 10 + 33);
+        assert_eq!(
+            Alphabet::detect_classes("Hunter2!"),
+            ["lower-case", "upper-case", "digits", "symbols"]
+        );
+        // Only the script of characters outside printable ASCII
+        let detected = Alphabet::detect("пароль1");
+        assert_eq!(detected.search_space("пароль1"), 10 + 66);
+        assert!(Alphabet::detect_classes("密码").is_empty());
     }
 }
@@ -0,0 +1,282 @@
+use crate::passgen::checker::{Classification, Screen};
+use crate::passgen::password::{Password, Redacted};
+use crate::passgen::variants;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+
+// Header names of the password and label columns, as password managers export them
+const PASSWORD_COLUMNS: &[&str] = &["password", "login_password"];
+const LABEL_COLUMNS: &[&str] = &[
+    "title",
+    "name",
+    "label",
+    "account",
+    "username",
+    "login_username",
+    "url",
+    "login_uri",
+];
+
+/// A password to audit, with what identifies it in the report.
+#[derive(Clone, PartialEq)]
+pub struct Entry {
+    pub label: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("label", &self.label)
+            .field("password", &Redacted(&self.password))
+            .finish()
+    }
+}
+
+/// Reads one password per line, labeled by line number. Blank lines are skipped.
+pub fn parse_lines(source: &str) -> Vec<Entry> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| Entry {
+            label: format!("line {}", i + 1),
+            password: line.to_string(),
+        })
+        .collect()
+}
+
+/// Reads a CSV export with a header row naming a `password` column, labeling entries by
+/// their title, name, username or URL column, whichever comes first, or else by row.
+pub fn parse_csv(source: &str) -> Result<Vec<Entry>, String> {
+    let mut records = csv_records(source).into_iter();
+    let header = records.next().ok_or("the CSV file is empty")?;
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|cell| names.contains(&cell.trim().to_lowercase().as_str()))
+    };
+    let password = column(PASSWORD_COLUMNS).ok_or("the CSV file has no password column")?;
+    let label = LABEL_COLUMNS.iter().find_map(|name| column(&[name]));
+
+    Ok(records
+        .enumerate()
+        .filter_map(|(i, record)| {
+            let password = record.get(password).filter(|value| !value.is_empty())?;
+            let label = label
+                .and_then(|label| record.get(label))
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("row {}", i + 1));
+            Some(Entry {
+                label,
+                password: password.clone(),
+            })
+        })
+        .collect())
+}
+
+// Splits CSV text into records, with quoted cells that may hold commas, doubled quotes and
+// line breaks
+fn csv_records(source: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut cell));
+                records.push(std::mem::take(&mut record));
+            }
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push(record);
+    }
+    records.retain(|record| record.iter().any(|cell| !cell.is_empty()));
+    records
+}
+
+/// Number of entries in each strength classification.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Counts {
+    pub weak: usize,
+    pub medium: usize,
+    pub strong: usize,
+    pub very_strong: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Unsafe {
+    pub label: String,
+    pub finding: Cow<'static, str>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Weak {
+    pub label: String,
+    pub entropy: f64,
+    pub classification: Classification,
+}
+
+/// Summary of a password audit. Passwords themselves are left out, so the report can be
+/// shared.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub entries: usize,
+    pub classifications: Counts,
+    pub unsafe_entries: Vec<Unsafe>,
+    /// The entries of least entropy, weakest first
+    pub weakest: Vec<Weak>,
+}
+
+impl Report {
+    /// Classifies each entry by its entropy over the character classes it uses, checks it
+    /// against the lists of `screen`, and keeps the `weakest` entries of least entropy.
+    pub fn audit(entries: &[Entry], screen: &Screen, weakest: usize) -> Self {
+        let mut classifications = Counts::default();
+        let mut unsafe_entries = Vec::new();
+        let mut scored = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let password = Password::new(entry.password.as_str());
+            // An attacker guessing by character class searches only the classes used
+            let strength = password.strength(variants::search_space(&entry.password));
+            let (entropy, classification) = (strength.entropy, strength.classification);
+            match classification {
+                Classification::Weak => classifications.weak += 1,
+                Classification::Medium => classifications.medium += 1,
+                Classification::Strong => classifications.strong += 1,
+                Classification::VeryStrong => classifications.very_strong += 1,
+            }
+            if let Some(finding) = screen.finding(&password) {
+                unsafe_entries.push(Unsafe {
+                    label: entry.label.clone(),
+                    finding,
+                });
+            }
+            scored.push((entry, entropy, classification));
+        }
+
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.truncate(weakest);
+        Self {
+            entries: entries.len(),
+            classifications,
+            unsafe_entries,
+            weakest: scored
+                .into_iter()
+                .map(|(entry, entropy, classification)| Weak {
+                    label: entry.label.clone(),
+                    entropy,
+                    classification,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "entries: {}", self.entries)?;
+        let counts = &self.classifications;
+        writeln!(f, "weak: {}", counts.weak)?;
+        writeln!(f, "medium: {}", counts.medium)?;
+        writeln!(f, "strong: {}", counts.strong)?;
+        writeln!(f, "very strong: {}", counts.very_strong)?;
+        write!(f, "unsafe: {}", self.unsafe_entries.len())?;
+        for entry in &self.unsafe_entries {
+            write!(f, "\n  {}: {}", entry.label, entry.finding)?;
+        }
+        if !self.weakest.is_empty() {
+            write!(f, "\nweakest:")?;
+        }
+        for entry in &self.weakest {
+            write!(
+                f,
+                "\n  {}: {:.1} bits ({:?})",
+                entry.label, entry.entropy, entry.classification
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passgen::commonwords::CommonWords;
+
+    #[test]
+    fn test_parse_lines() {
+        let entries = parse_lines("hunter2\n\nXk9#mP2$vL\n");
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    label: "line 1".to_string(),
+                    password: "hunter2".to_string()
+                },
+                Entry {
+                    label: "line 3".to_string(),
+                    password: "Xk9#mP2$vL".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let source = "Group,Title,Username,Password,URL,Notes\r\n\
+                      Work,GitHub,octocat,\"pa,ss\"\"word\",https://github.com,\"two\nlines\"\r\n\
+                      Work,,admin,letmein,,\r\n\
+                      Work,Empty,nobody,,,\r\n";
+        let entries = parse_csv(source).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label, "GitHub");
+        assert_eq!(entries[0].password, "pa,ss\"word");
+        // No title, so the row number stands in
+        assert_eq!(entries[1].label, "row 2");
+
+        assert!(parse_csv("name,url\nx,y\n").is_err());
+        assert!(parse_csv("").is_err());
+    }
+
+    #[test]
+    fn test_audit() {
+        let entries = parse_lines("letmein\nXk9#mP2$vL\nabc\nqzvkmwtrplx");
+        let lists = [(CommonWords::Passwords, "common password")];
+        let report = Report::audit(&entries, &Screen::new(&lists), 2);
+        assert_eq!(report.entries, 4);
+        assert_eq!(
+            report.classifications,
+            Counts {
+                weak: 1,
+                medium: 1,
+                strong: 1,
+                very_strong: 1
+            }
+        );
+        assert_eq!(
+            report.unsafe_entries,
+            vec![Unsafe {
+                label: "line 1".to_string(),
+                finding: "common password".into()
+            }]
+        );
+        let weakest: Vec<&str> = report.weakest.iter().map(|w| w.label.as_str()).collect();
+        assert_eq!(weakest, ["line 3", "line 1"]);
+        assert!(!report.to_string().contains("letmein"));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::passgen::hash::hex;
+use crate::passgen::mapped::MappedWords;
+use memmap2::Mmap;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// First bytes of an index written by [`write_index`]; sorted SHA-1 digests follow.
+pub const MAGIC: &[u8] = b"passgen breach index 1\n";
+const DIGEST_LEN: usize = 20;
+const HEX_LEN: usize = 2 * DIGEST_LEN;
+
+/// A breach corpus to look passwords up in without reading it into memory. Three kinds of
+/// file are understood:
+///
+/// - an index built by [`write_index`], searched by binary search over its sorted digests
+/// - a list of SHA-1 hashes in ascending order, one per line and optionally followed by
+///   `:count`, as Have I Been Pwned's downloadable Pwned Passwords are; also binary searched
+/// - any other file is taken as one plain password per line and scanned through
+pub enum BreachList {
+    Index(Mmap),
+    Hashes(Mmap),
+    Plain(MappedWords),
+}
+
+impl BreachList {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: as for `MappedWords`, the map is only read
+        let map = unsafe { Mmap::map(&file)? };
+        if let Some(digests) = map.strip_prefix(MAGIC) {
+            if digests.len() % DIGEST_LEN != 0 {
+                return Err(io::Error::other("the breach index is truncated"));
+            }
+            return Ok(BreachList::Index(map));
+        }
+        let first_line = map.split(|&b| b == b'\n').next().unwrap_or_default();
+        if is_hash_line(first_line) {
+            return Ok(BreachList::Hashes(map));
+        }
+        Ok(BreachList::Plain(MappedWords::open(path)?))
+    }
+
+    /// Whether the password appears in the corpus, compared exactly.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub fn contains(&self, password: &str) -> bool {
+        let digest = Sha1::digest(password.as_bytes());
+        match self {
+            BreachList::Index(map) => search_digests(&map[MAGIC.len()..], &digest),
+            BreachList::Hashes(map) => search_lines(map, hex(&digest).to_uppercase().as_bytes()),
+            BreachList::Plain(words) => words.iter().any(|word| word == password),
+        }
+    }
+}
+
+/// The SHA-1 digest of `password` in upper-case hex, as Pwned Passwords lists it.
+pub fn sha1_hex(password: &str) -> String {
+    hex(&Sha1::digest(password.as_bytes())).to_uppercase()
+}
+
+fn is_hash_line(line: &[u8]) -> bool {
+    line.len() >= HEX_LEN
+        && line[..HEX_LEN].iter().all(u8::is_ascii_hexdigit)
+        && matches!(line.get(HEX_LEN), None | Some(b':' | b'\r'))
+}
+
+fn search_digests(digests: &[u8], target: &[u8]) -> bool {
+    let (mut low, mut high) = (0, digests.len() / DIGEST_LEN);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let digest = &digests[middle * DIGEST_LEN..(middle + 1) * DIGEST_LEN];
+        match digest.cmp(target) {
+            Ordering::Equal => return true,
+            Ordering::Less => low = middle + 1,
+            Ordering::Greater => high = middle,
+        }
+    }
+    false
+}
+
+// Binary search over the lines of a sorted hash list, by byte offset so that no line index
+// is needed
+fn search_lines(data: &[u8], target: &[u8]) -> bool {
+    let (mut low, mut high) = (0, data.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let start = data[..middle]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+        let end = data[middle..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(data.len(), |newline| middle + newline);
+        let line = &data[start..end];
+        let hash = &line[..line.len().min(HEX_LEN)];
+        match compare_hex(hash, target) {
+            Ordering::Equal => return true,
+            Ordering::Less => low = end + 1,
+            Ordering::Greater => high = start,
+        }
+    }
+    false
+}
+
+fn compare_hex(hash: &[u8], target: &[u8]) -> Ordering {
+    hash.iter()
+        .map(u8::to_ascii_uppercase)
+        .cmp(target.iter().copied())
+}
+
+/// Writes an index of the passwords in `corpus` for [`BreachList`], returning the number of
+/// distinct passwords. The digests are sorted in memory, 20 bytes per password.
+pub fn write_index(corpus: &MappedWords, out: &mut impl Write) -> io::Result<usize> {
+    let mut digests: Vec<[u8; DIGEST_LEN]> = corpus
+        .iter()
+        .map(|password| Sha1::digest(password.as_bytes()).into())
+        .collect();
+    digests.sort_unstable();
+    digests.dedup();
+    out.write_all(MAGIC)?;
+    for digest in &digests {
+        out.write_all(digest)?;
+    }
+    out.flush()?;
+    Ok(digests.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("passgen-{}-{}", name, std::process::id()))
+    }
+
+    const CORPUS: &[u8] = b"123456\npassword\nletmein\npassword\ncorrect horse\n";
+
+    fn assert_finds_corpus(list: &BreachList) {
+        for password in ["123456", "password", "letmein", "correct horse"] {
+            assert!(list.contains(password), "{}", password);
+        }
+        for password in ["Password", "hunter2", "", "letmein "] {
+            assert!(!list.contains(password), "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_plain() {
+        let path = temp_path("breach-plain");
+        std::fs::write(&path, CORPUS).unwrap();
+        let list = BreachList::open(&path).unwrap();
+        assert!(matches!(list, BreachList::Plain(_)));
+        assert_finds_corpus(&list);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_index() {
+        let corpus_path = temp_path("breach-corpus");
+        let index_path = temp_path("breach-index");
+        std::fs::write(&corpus_path, CORPUS).unwrap();
+        let corpus = MappedWords::open(&corpus_path).unwrap();
+        let mut index = Vec::new();
+        assert_eq!(write_index(&corpus, &mut index).unwrap(), 4);
+        std::fs::write(&index_path, &index).unwrap();
+
+        let list = BreachList::open(&index_path).unwrap();
+        assert!(matches!(list, BreachList::Index(_)));
+        assert_finds_corpus(&list);
+
+        std::fs::write(&index_path, &index[..index.len() - 1]).unwrap();
+        assert!(BreachList::open(&index_path).is_err());
+        std::fs::remove_file(&corpus_path).unwrap();
+        std::fs::remove_file(&index_path).unwrap();
+    }
+
+    #[test]
+    fn test_sha1_hex() {
+        assert_eq!(
+            sha1_hex("password"),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn test_sorted_hashes() {
+        let mut lines: Vec<String> = ["123456", "password", "letmein", "correct horse"]
+            .iter()
+            .enumerate()
+            .map(|(i, password)| {
+                let hash = sha1_hex(password);
+                format!("{}:{}\r\n", hash, i + 1)
+            })
+            .collect();
+        // Unrelated hashes around them, as in a full list
+        lines.extend((0..200).map(|i| format!("{:040X}:1\r\n", i * 7919)));
+        lines.sort();
+        let path = temp_path("breach-hashes");
+        std::fs::write(&path, lines.concat()).unwrap();
+
+        let list = BreachList::open(&path).unwrap();
+        assert!(matches!(list, BreachList::Hashes(_)));
+        assert_finds_corpus(&list);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use rand::{CryptoRng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+
+// Secrets per task. Each task draws from its own RNG, seeded in order from the caller's,
+// so a seeded run gives the same output whatever the number of jobs.
+const CHUNK: usize = 1024;
+// Tasks per thread in flight at once, which bounds the secrets held in memory
+const TASKS_PER_JOB: usize = 4;
+
+/// Generates secrets on a pool of threads, for batches of millions.
+pub struct Bulk {
+    pool: rayon::ThreadPool,
+}
+
+impl Bulk {
+    /// A pool of `jobs` threads, or one per CPU for 0.
+    pub fn new(jobs: usize) -> Result<Self, String> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .map_err(|e| format!("cannot start {} threads: {}", jobs, e))?;
+        Ok(Self { pool })
+    }
+
+    /// Draws `count` secrets with `draw`, handing each to `emit` in order.
+    pub fn generate<R, D, E>(
+        &self,
+        count: usize,
+        rng: &mut R,
+        draw: D,
+        mut emit: E,
+    ) -> anyhow::Result<()>
+    where
+        R: CryptoRng + ?Sized,
+        D: Fn(&mut ChaCha20Rng, &mut String) -> Result<(), String> + Sync,
+        E: FnMut(&str) -> anyhow::Result<()>,
+    {
+        let round = self.pool.current_num_threads() * TASKS_PER_JOB * CHUNK;
+        let mut remaining = count;
+        while remaining > 0 {
+            let batch = remaining.min(round);
+            let tasks: Vec<(ChaCha20Rng, usize)> = (0..batch)
+                .step_by(CHUNK)
+                .map(|start| {
+                    let mut seed = [0u8; 32];
+                    rng.fill_bytes(&mut seed);
+                    (ChaCha20Rng::from_seed(seed), CHUNK.min(batch - start))
+                })
+                .collect();
+            let chunks: Vec<Result<Vec<String>, String>> = self.pool.install(|| {
+                tasks
+                    .into_par_iter()
+                    .map(|(mut rng, size)| {
+                        (0..size)
+                            .map(|_| {
+                                let mut secret = String::new();
+                                draw(&mut rng, &mut secret).map(|()| secret)
+                            })
+                            .collect()
+                    })
+                    .collect()
+            });
+            for chunk in chunks {
+                for secret in chunk.map_err(anyhow::Error::msg)? {
+                    emit(&secret)?;
+                }
+            }
+            remaining -= batch;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn run(jobs: usize, count: usize) -> Vec<String> {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        let mut secrets = Vec::new();
+        let draw = |rng: &mut ChaCha20Rng, buf: &mut String| {
+            buf.push_str(&rng.random::<u64>().to_string());
+            Ok(())
+        };
+        Bulk::new(jobs)
+            .unwrap()
+            .generate(count, &mut rng, draw, |secret| {
+                secrets.push(secret.to_string());
+                Ok(())
+            })
+            .unwrap();
+        secrets
+    }
+
+    #[test]
+    fn test_same_output_for_any_jobs() {
+        let count = 3 * 4 * CHUNK + 17;
+        let one = run(1, count);
+        assert_eq!(one.len(), count);
+        assert_eq!(one, run(3, count));
+        let mut distinct = one.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), count);
+        assert!(run(2, 0).is_empty());
+    }
+
+    #[test]
+    fn test_draw_errors_stop_generation() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        let mut emitted = 0;
+        let result = Bulk::new(2).unwrap().generate(
+            10,
+            &mut rng,
+            |_, _| Err("no compliant password".to_string()),
+            |_| {
+                emitted += 1;
+                Ok(())
+            },
+        );
+        assert_eq!(result.unwrap_err().to_string(), "no compliant password");
+        assert_eq!(emitted, 0);
+    }
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Overrides the platform cache location, mainly for tests and portable installs
+const CACHE_DIR_ENV: &str = "PASSGEN_CACHE_DIR";
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CacheEntry {
+    pub name: String,
+    pub size: u64,
+}
+
+impl fmt::Display for CacheEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>10}  {}", format_size(self.size), self.name)
+    }
+}
+
+/// Directory holding downloaded wordlists, e.g. `~/.cache/passgen` on Linux.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(CACHE_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::cache_dir().map(|dir| dir.join("passgen"))
+}
+
+/// Lists cached files sorted by name. A missing directory is an empty cache.
+pub fn list_entries(dir: &Path) -> io::Result<Vec<CacheEntry>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            entries.push(CacheEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: metadata.len(),
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Removes every cached file and returns how many were deleted.
+pub fn clean(dir: &Path) -> io::Result<usize> {
+    let entries = list_entries(dir)?;
+    for entry in &entries {
+        fs::remove_file(dir.join(&entry.name))?;
+    }
+    Ok(entries.len())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("passgen-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_missing_dir_is_empty() {
+        let dir = temp_cache("missing");
+        assert!(list_entries(&dir).unwrap().is_empty());
+        assert_eq!(clean(&dir).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_list_and_clean() {
+        let dir = temp_cache("list");
+        fs::create_dir_all(dir.join("subdir")).unwrap();
+        fs::write(dir.join("b.txt"), "hello").unwrap();
+        fs::write(dir.join("a.txt"), "hi").unwrap();
+
+        let entries = list_entries(&dir).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                CacheEntry {
+                    name: "a.txt".to_string(),
+                    size: 2
+                },
+                CacheEntry {
+                    name: "b.txt".to_string(),
+                    size: 5
+                },
+            ]
+        );
+
+        assert_eq!(clean(&dir).unwrap(), 2);
+        assert!(list_entries(&dir).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -1,9 +1,16 @@
 use crate::passgen::alphabet::Alphabet;
-use crate::passgen::commonwords::CommonWords;
+use crate::passgen::commonwords::{CommonWords, Language, WordSet};
 use crate::passgen::password::Password;
+use crate::passgen::patterns::{self, PatternMatch, PersonalContext};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+/// Strength of a password by its entropy: below 28 bits is weak, below 40 medium, below 60
+/// strong. Classifications order from weakest to strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ValueEnum)]
 pub enum Classification {
     Weak,
     Medium,
@@ -11,91 +18,971 @@ pub enum Classification {
     VeryStrong,
 }
 
+/// How strong a password is, and why: its entropy before and after discounting the
+/// patterns found in it.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StrengthReport {
+    /// Length in characters
+    pub length: usize,
+    pub alphabet_size: usize,
+    /// Entropy as if every character were chosen independently from the alphabet
+    pub raw_entropy: f64,
+    /// Entropy with each pattern counted as one guessable choice
+    pub entropy: f64,
+    pub patterns: Vec<PatternMatch>,
+    pub classification: Classification,
+}
+
+impl StrengthReport {
+    /// Average time to guess the password in each [`AttackScenario`], trying half of the
+    /// `2^entropy` candidates before finding it.
+    pub fn crack_times(&self) -> Vec<CrackTime> {
+        let guesses = (self.entropy - 1.0).exp2().max(1.0);
+        AttackScenario::ALL
+            .into_iter()
+            .map(|scenario| {
+                let seconds = guesses / scenario.guesses_per_second();
+                CrackTime {
+                    scenario,
+                    seconds,
+                    duration: format_duration(seconds),
+                }
+            })
+            .collect()
+    }
+
+    /// What would bring `password`, measured in this report, up to `target`, most specific
+    /// first: the common `words` found in it, e.g. by [`Screen::words`], and its patterns to
+    /// avoid, then how many characters to add, then the character class that mixed in
+    /// would add the most bits. Empty when there is nothing to improve.
+    pub fn suggestions(
+        &self,
+        password: &str,
+        words: &[String],
+        target: Classification,
+    ) -> Vec<String> {
+        let mut suggestions: Vec<String> = words
+            .iter()
+            .map(|word| format!("avoid the dictionary word `{}`", word))
+            .collect();
+        let chars: Vec<char> = password.chars().collect();
+        for found in &self.patterns {
+            let text: String = chars[found.start..found.start + found.length]
+                .iter()
+                .collect();
+            suggestions.push(format!("avoid the {} `{}`", found.pattern, text));
+        }
+
+        let missing = target.min_entropy() - self.entropy;
+        if missing <= 0.0 || self.alphabet_size == 0 {
+            return suggestions;
+        }
+        let per_char = (self.alphabet_size as f64).log2();
+        if per_char > 0.0 {
+            let more = (missing / per_char).ceil() as usize;
+            suggestions.push(format!(
+                "add {} more character{} to reach {:?}",
+                more,
+                if more == 1 { "" } else { "s" },
+                target
+            ));
+        }
+
+        // Classes are measured against those the password uses, as an attacker who
+        // guesses them would; characters in patterns gain nothing
+        let in_patterns: usize = self.patterns.iter().map(|found| found.length).sum();
+        let free = self.length.saturating_sub(in_patterns) as f64;
+        let used: usize = CHARACTER_CLASSES
+            .iter()
+            .filter(|(is_in, _, _)| chars.iter().any(|&c| is_in(c)))
+            .map(|(_, size, _)| size)
+            .sum();
+        let best = CHARACTER_CLASSES
+            .iter()
+            .filter(|(is_in, _, _)| !chars.iter().any(|&c| is_in(c)))
+            .map(|(_, size, name)| {
+                let gain = free * ((used + size) as f64 / used.max(1) as f64).log2();
+                (gain, name)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((gain, name)) = best
+            && gain >= 1.0
+        {
+            suggestions.push(format!("mixing in {} would add ~{:.0} bits", name, gain));
+        }
+        suggestions
+    }
+}
+
+type CharTest = fn(char) -> bool;
+
+// Character classes suggestions mix in, with the characters an attacker tries for each
+const CHARACTER_CLASSES: [(CharTest, usize, &str); 4] = [
+    (|c| c.is_ascii_lowercase(), 26, "lower-case letters"),
+    (|c| c.is_ascii_uppercase(), 26, "upper-case letters"),
+    (|c| c.is_ascii_digit(), 10, "digits"),
+    (|c| c.is_ascii_punctuation(), 32, "symbols"),
+];
+
+/// How fast an attacker can try passwords.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttackScenario {
+    /// Against a login that limits attempts, 100 per hour
+    OnlineThrottled,
+    /// Against a login without limits, 10 per second
+    OnlineUnthrottled,
+    /// Against leaked hashes of a slow function such as bcrypt, 10 thousand per second
+    OfflineSlowHash,
+    /// Against leaked hashes of a fast function such as SHA-1, 10 billion per second
+    OfflineFastHash,
+}
+
+impl AttackScenario {
+    pub const ALL: [AttackScenario; 4] = [
+        AttackScenario::OnlineThrottled,
+        AttackScenario::OnlineUnthrottled,
+        AttackScenario::OfflineSlowHash,
+        AttackScenario::OfflineFastHash,
+    ];
+
+    pub fn guesses_per_second(self) -> f64 {
+        match self {
+            AttackScenario::OnlineThrottled => 100.0 / 3600.0,
+            AttackScenario::OnlineUnthrottled => 10.0,
+            AttackScenario::OfflineSlowHash => 1e4,
+            AttackScenario::OfflineFastHash => 1e10,
+        }
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Classification::Weak => "weak",
+            Classification::Medium => "medium",
+            Classification::Strong => "strong",
+            Classification::VeryStrong => "very strong",
+        })
+    }
+}
+
+impl fmt::Display for AttackScenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AttackScenario::OnlineThrottled => "online, throttled (100/hour)",
+            AttackScenario::OnlineUnthrottled => "online, unthrottled (10/second)",
+            AttackScenario::OfflineSlowHash => "offline, slow hash (10k/second)",
+            AttackScenario::OfflineFastHash => "offline, fast hash (10B/second)",
+        })
+    }
+}
+
+/// Estimated time to crack a password in one scenario.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrackTime {
+    pub scenario: AttackScenario,
+    pub seconds: f64,
+    /// `seconds` for people, e.g. `3 hours` or `centuries`
+    pub duration: String,
+}
+
+/// Rounds a number of seconds to the largest whole unit, e.g. `3 hours`, from `less than a
+/// second` up to `centuries`.
+pub fn format_duration(seconds: f64) -> String {
+    const UNITS: [(&str, f64); 6] = [
+        ("year", 365.25 * 86_400.0),
+        ("month", 365.25 * 86_400.0 / 12.0),
+        ("day", 86_400.0),
+        ("hour", 3_600.0),
+        ("minute", 60.0),
+        ("second", 1.0),
+    ];
+    if seconds < 1.0 {
+        return "less than a second".to_string();
+    }
+    if seconds >= 100.0 * UNITS[0].1 {
+        return "centuries".to_string();
+    }
+    let (unit, size) = UNITS
+        .into_iter()
+        .find(|(_, size)| seconds >= *size)
+        .expect("seconds is at least one");
+    match (seconds / size).floor() as u64 {
+        1 => format!("1 {}", unit),
+        count => format!("{} {}s", count, unit),
+    }
+}
+
+/// Entropy in bits below which a password is classified weak.
+pub const WEAK_ENTROPY: f64 = 28.0;
+
+impl Classification {
+    /// Least entropy in bits classified as this, e.g. 40 for [`Classification::Strong`].
+    pub fn min_entropy(self) -> f64 {
+        match self {
+            Classification::Weak => 0.0,
+            Classification::Medium => WEAK_ENTROPY,
+            Classification::Strong => 40.0,
+            Classification::VeryStrong => 60.0,
+        }
+    }
+
+    pub fn from_entropy(entropy: f64) -> Self {
+        if entropy < WEAK_ENTROPY {
+            Classification::Weak
+        } else if entropy < 40.0 {
+            Classification::Medium
+        } else if entropy < 60.0 {
+            Classification::Strong
+        } else {
+            Classification::VeryStrong
+        }
+    }
+}
+
+/// Entropy in bits of a passphrase of `word_count` words drawn uniformly from a list of
+/// `wordlist_size` words. Each word is one choice, so counting its characters as if they
+/// were chosen one by one would overstate the entropy several times over.
+pub fn passphrase_entropy(word_count: usize, wordlist_size: usize) -> f64 {
+    match wordlist_size {
+        0 => 0.0,
+        words => word_count as f64 * (words as f64).log2(),
+    }
+}
+
+/// How strictly passwords are checked against common words.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SafetyLevel {
+    /// Passwords that are a common word or made up entirely of them are unsafe
+    #[default]
+    Combination,
+    /// Passwords containing a common word of at least this many characters anywhere, e.g.
+    /// `mypassword123`, are unsafe too
+    Contains(usize),
+}
+
+/// Shortest word [`SafetyLevel::Contains`] looks for unless told otherwise; shorter words
+/// turn up in too many strong passwords by chance.
+pub const MIN_CONTAINED_LENGTH: usize = 4;
+
+/// A word of a password found in a common-word list, case folded, with how that list is
+/// described, e.g. `common password`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SafetyMatch {
+    pub category: &'static str,
+    pub word: String,
+}
+
+/// Why a password is unsafe, if it is, and the common words making it up; see
+/// [`Screen::report`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SafetyReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finding: Option<Cow<'static, str>>,
+    /// Each word under the first list it is in, in the order they appear
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<SafetyMatch>,
+}
+
+impl SafetyReport {
+    pub fn is_safe(&self) -> bool {
+        self.finding.is_none()
+    }
+}
+
+impl fmt::Display for SafetyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(finding) = &self.finding else {
+            return f.write_str("no common words");
+        };
+        f.write_str(finding)?;
+        // A password that is a single common word has nothing more to name
+        if let [found] = self.matches.as_slice()
+            && found.category == finding
+        {
+            return Ok(());
+        }
+        for (i, found) in self.matches.iter().enumerate() {
+            let joiner = match i {
+                0 => ", matching ",
+                i if i + 1 == self.matches.len() => " and ",
+                _ => ", ",
+            };
+            write!(f, "{}{} `{}`", joiner, found.category, found.word)?;
+        }
+        Ok(())
+    }
+}
+
+/// What `passgen check` makes of a password: why it is unsafe, if it is, and how strong
+/// it is. The local protocols and the WebAssembly build judge passwords the same way.
+#[derive(Debug)]
+pub struct Assessment {
+    pub safety: SafetyReport,
+    pub strength: StrengthReport,
+    /// `None` for an unsafe password
+    pub classification: Option<Classification>,
+}
+
+impl Assessment {
+    /// Like [`Screen::assess`] with the safety of the password already known, e.g. from a
+    /// breach list.
+    pub fn of(
+        password: &Password,
+        safety: SafetyReport,
+        alphabet: Option<&Alphabet>,
+        context: &PersonalContext,
+    ) -> Result<Self, anyhow::Error> {
+        let size = match alphabet {
+            Some(alphabet) => alphabet.len(),
+            None => Alphabet::detect(&password.value).search_space(&password.value),
+        };
+        let strength = password.strength_in(size, context);
+        let classification = match (&safety.finding, alphabet) {
+            (Some(_), _) => None,
+            (None, Some(alphabet)) => Some(password.classify_in(alphabet, context)?),
+            (None, None) => Some(strength.classification),
+        };
+        Ok(Self {
+            safety,
+            strength,
+            classification,
+        })
+    }
+}
+
+// How a password falls into a word list
+enum Match {
+    Whole,
+    Contained,
+}
+
+/// The built-in lists [`Password::safety_finding`] checks, with how a password found in
+/// each is described.
+pub const SAFETY_CHECKS: [(CommonWords, &str); 9] = [
+    (CommonWords::Passwords, "common password"),
+    (CommonWords::English, "common English word"),
+    (CommonWords::German, "common German word"),
+    (CommonWords::French, "common French word"),
+    (CommonWords::Spanish, "common Spanish word"),
+    (CommonWords::MaleNames, "common male name"),
+    (CommonWords::FemaleNames, "common female name"),
+    (CommonWords::LastNames, "common last name"),
+    (CommonWords::All, "combination of common words"),
+];
+
+/// Like [`SAFETY_CHECKS`] with the common words of `languages` alone. Combinations are
+/// then only looked for within each list, since [`CommonWords::All`] has every language.
+pub fn safety_checks(languages: &[Language]) -> Vec<(CommonWords, &'static str)> {
+    SAFETY_CHECKS
+        .into_iter()
+        .filter(|(words, _)| match words {
+            CommonWords::All => false,
+            CommonWords::English
+            | CommonWords::German
+            | CommonWords::French
+            | CommonWords::Spanish => languages.iter().any(|language| {
+                std::mem::discriminant(&language.common_words()) == std::mem::discriminant(words)
+            }),
+            _ => true,
+        })
+        .collect()
+}
+
 impl<'a> Password<'a> {
+    /// Entropy in bits of a password drawn uniformly from an alphabet of `alphabet` characters.
     pub fn entropy(&self, alphabet: usize) -> f64 {
-        let length = self.value.len() as f64;
+        let length = self.value.chars().count() as f64;
         if length == 0.0 || alphabet == 0 {
             return 0.0;
         }
-        let entropy = length * (alphabet as f64).log2();
-        entropy
+        length * (alphabet as f64).log2()
     }
 
+    /// Classifies the password as drawn from `alphabet`, failing if it has other characters.
     pub fn classify(&self, alphabet: &Alphabet) -> Result<Classification, anyhow::Error> {
+        self.classify_in(alphabet, &PersonalContext::default())
+    }
+
+    /// Like [`Password::classify`], also discounting what `context` reveals about the
+    /// password's owner.
+    pub fn classify_in(
+        &self,
+        alphabet: &Alphabet,
+        context: &PersonalContext,
+    ) -> Result<Classification, anyhow::Error> {
         if !self.value.chars().all(|c| alphabet.contains(c)) {
             return Err(anyhow::anyhow!(
                 "Password contains characters not in the specified alphabet"
             ));
         }
 
-        let alphabet = alphabet.len();
+        Ok(self.strength_in(alphabet.len(), context).classification)
+    }
 
-        let entropy = self.entropy(alphabet);
-        if entropy < 28.0 {
-            Ok(Classification::Weak)
-        } else if entropy < 40.0 {
-            Ok(Classification::Medium)
-        } else if entropy < 60.0 {
-            Ok(Classification::Strong)
-        } else {
-            Ok(Classification::VeryStrong)
+    /// Measures the password as drawn from an alphabet of `alphabet` characters, counting
+    /// repeats, sequences and keyboard walks as the few guesses they take rather than by
+    /// their length.
+    pub fn strength(&self, alphabet: usize) -> StrengthReport {
+        self.strength_in(alphabet, &PersonalContext::default())
+    }
+
+    /// Like [`Password::strength`], also counting names, birth years and the like from
+    /// `context` as guesses.
+    pub fn strength_in(&self, alphabet: usize, context: &PersonalContext) -> StrengthReport {
+        let raw_entropy = self.entropy(alphabet);
+        let length = self.value.chars().count();
+        let per_char = match alphabet {
+            0 => 0.0,
+            size => (size as f64).log2(),
+        };
+        let patterns = patterns::find_all(&self.value, context);
+        let in_patterns: usize = patterns.iter().map(|found| found.length).sum();
+        let entropy = patterns
+            .iter()
+            .map(|found| found.entropy(per_char))
+            .sum::<f64>()
+            + (length - in_patterns) as f64 * per_char;
+        let entropy = entropy.min(raw_entropy);
+        StrengthReport {
+            length,
+            alphabet_size: alphabet,
+            raw_entropy,
+            entropy,
+            patterns,
+            classification: Classification::from_entropy(entropy),
         }
     }
 
-    // Assumes words are lowercase and checks if the password can be formed by concatenating words from the provided list
-    fn is_combination_of_word_set(&self, word_set: &HashSet<&str>) -> bool {
-        let password = self.value.to_lowercase();
+    /// Classifies the password as a passphrase of words from a list of `wordlist_size`
+    /// words joined by `separator`.
+    pub fn classify_passphrase(&self, separator: &str, wordlist_size: usize) -> Classification {
+        let word_count = match separator {
+            "" => 1,
+            separator => self.value.split(separator).count(),
+        };
+        Classification::from_entropy(passphrase_entropy(word_count, wordlist_size))
+    }
 
-        let mut dp = vec![false; password.len() + 1];
+    // Checks if the password can be formed by concatenating words from the provided list,
+    // which must already be case folded
+    fn is_combination_of_word_set(&self, word_set: &WordSet) -> bool {
+        let password = fold_case(&self.value);
+        // Candidate words start and end on character boundaries only
+        let boundaries: Vec<usize> = password
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([password.len()])
+            .collect();
+
+        let mut dp = vec![false; boundaries.len()];
         dp[0] = true; // Empty string can always be formed
-        for i in 1..=password.len() {
+        for i in 1..boundaries.len() {
             for j in 0..i {
-                if dp[j] && word_set.contains(&password[j..i]) {
+                if dp[j] && word_set.contains(&password[boundaries[j]..boundaries[i]]) {
                     dp[i] = true;
                     break;
                 }
             }
         }
-        dp[password.len()]
+        dp[boundaries.len() - 1]
+    }
+
+    // Checks if the password has a word of at least `min_length` characters from the
+    // provided list, which must already be case folded, anywhere in it
+    fn contains_word_of_set(&self, word_set: &WordSet, min_length: usize) -> bool {
+        let password = fold_case(&self.value);
+        let boundaries: Vec<usize> = password
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([password.len()])
+            .collect();
+        (0..boundaries.len()).any(|i| {
+            (i + min_length.max(1)..boundaries.len())
+                .any(|j| word_set.contains(&password[boundaries[i]..boundaries[j]]))
+        })
     }
 
     #[allow(dead_code)]
     fn is_combination_of_words(&self, words: &[&str]) -> bool {
-        let word_set = words.iter().cloned().collect::<HashSet<_>>();
+        let word_set = words.iter().map(|w| fold_case(w)).collect::<HashSet<_>>();
         self.is_combination_of_word_set(&word_set)
     }
 
+    /// Describes the first common-word list the password falls into, if any.
+    pub fn safety_finding(&self) -> Option<Cow<'static, str>> {
+        let checks = SAFETY_CHECKS;
+        Screen::new(&checks).finding(self)
+    }
+
+    /// Every built-in list the password falls into, and the common words found in it.
+    pub fn safety_report(&self) -> SafetyReport {
+        let checks = SAFETY_CHECKS;
+        Screen::new(&checks).report(self)
+    }
+
+    /// Whether the password is neither empty, one of `common_words`, nor a combination of
+    /// them, ignoring case; [`Password::safety_report`] tells which words matched.
     pub fn is_safe(&self, common_words: &CommonWords) -> bool {
+        self.is_safe_at(common_words, SafetyLevel::default())
+    }
+
+    /// Whether the password is safe against `common_words` at the given strictness.
+    pub fn is_safe_at(&self, common_words: &CommonWords, level: SafetyLevel) -> bool {
+        self.match_in(&common_words.word_set(), level).is_none()
+    }
+
+    fn match_in(&self, word_set: &WordSet, level: SafetyLevel) -> Option<Match> {
         // If the password is empty, it's considered not safe
         if self.value.is_empty() {
-            return false;
+            return Some(Match::Whole);
         }
 
-        let word_set = common_words.words().iter().cloned().collect::<HashSet<_>>();
-        let lowercase_password = self.value.to_lowercase();
+        let folded_password = fold_case(&self.value);
 
         // Check if the password is a common word
-        if word_set.contains(lowercase_password.as_str()) {
-            return false;
+        if word_set.contains(folded_password.as_ref()) {
+            return Some(Match::Whole);
         }
 
         // Check if the password is a combination of common words
-        if self.is_combination_of_word_set(&word_set) {
-            return false;
+        if self.is_combination_of_word_set(word_set) {
+            return Some(Match::Whole);
+        }
+
+        // Check if the password merely contains a common word, e.g. "mypassword123"
+        match level {
+            SafetyLevel::Contains(min_length)
+                if self.contains_word_of_set(word_set, min_length) =>
+            {
+                Some(Match::Contained)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Words by their length in characters, each with its `char_mask`
+type ByLength = Vec<Vec<(u64, Box<[char]>)>>;
+
+/// Common-word lists folded once, for checking many passwords against them; see
+/// [`Password::safety_finding`].
+pub struct Screen<'a> {
+    lists: Vec<(Cow<'a, WordSet<'a>>, &'static str)>,
+    level: SafetyLevel,
+    // For each list, the words no earlier list has; empty unless near misses are looked for
+    near: Vec<ByLength>,
+}
+
+impl<'a> Screen<'a> {
+    /// Lists to check in order, each with how a password found in it is described.
+    pub fn new(lists: &'a [(CommonWords, &'static str)]) -> Self {
+        Self {
+            lists: lists
+                .iter()
+                .map(|(words, description)| (words.word_set(), *description))
+                .collect(),
+            level: SafetyLevel::default(),
+            near: Vec::new(),
+        }
+    }
+
+    /// Checks at the given strictness instead of the default.
+    pub fn with_level(mut self, level: SafetyLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Also flags passwords a few edits from a common word, e.g. `passw0rdz`: one edit for
+    /// 5 to 8 characters and two for longer, counting a swap of neighbouring characters as
+    /// one; see [`damerau_distance`].
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.near.clear();
+        if !fuzzy {
+            return self;
+        }
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (word_set, _) in &self.lists {
+            let mut by_length: ByLength = Vec::new();
+            for word in word_set.iter().filter(|word| seen.insert(word)) {
+                let chars: Box<[char]> = word.chars().collect();
+                if by_length.len() <= chars.len() {
+                    by_length.resize(chars.len() + 1, Vec::new());
+                }
+                by_length[chars.len()].push((char_mask(&chars), chars));
+            }
+            self.near.push(by_length);
+        }
+        self
+    }
+
+    /// Common words of at least [`MIN_CONTAINED_LENGTH`] characters, or the length checked
+    /// for, in the password: the longest at each position, without overlaps and case
+    /// folded, e.g. `dragon` in `Dragon2024!`.
+    pub fn words(&self, password: &Password) -> Vec<String> {
+        let min_length = match self.level {
+            SafetyLevel::Contains(min_length) => min_length.max(1),
+            SafetyLevel::Combination => MIN_CONTAINED_LENGTH,
+        };
+        let password = fold_case(&password.value);
+        let boundaries: Vec<usize> = password
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([password.len()])
+            .collect();
+        let mut words: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < boundaries.len() {
+            let end = (i + min_length..boundaries.len()).rev().find(|&j| {
+                let word = &password[boundaries[i]..boundaries[j]];
+                self.lists
+                    .iter()
+                    .any(|(word_set, _)| word_set.contains(word))
+            });
+            match end {
+                Some(j) => {
+                    let word = &password[boundaries[i]..boundaries[j]];
+                    if !words.iter().any(|w| w == word) {
+                        words.push(word.to_string());
+                    }
+                    i = j;
+                }
+                None => i += 1,
+            }
+        }
+        words
+    }
+
+    /// Describes the first list the password falls into, if any, e.g. `common password` or
+    /// `password containing a common password`; failing that, whether it is only dates
+    /// and common words, e.g. `summer2024`.
+    pub fn finding(&self, password: &Password) -> Option<Cow<'static, str>> {
+        self.lists
+            .iter()
+            .find_map(
+                |(word_set, description)| match password.match_in(word_set, self.level)? {
+                    Match::Whole => Some(Cow::Borrowed(*description)),
+                    Match::Contained => {
+                        Some(Cow::Owned(format!("password containing a {}", description)))
+                    }
+                },
+            )
+            .or_else(|| self.date_finding(password).map(Cow::Borrowed))
+            .or_else(|| self.near_finding(password).map(Cow::Owned))
+    }
+
+    /// Like [`Screen::finding`], also naming the common words of an unsafe password: the
+    /// fewest making it up, e.g. `dragon` and `mary` in `DragonMary`, or else those
+    /// [`Screen::words`] finds in it.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub fn report(&self, password: &Password) -> SafetyReport {
+        let Some(finding) = self.finding(password) else {
+            return SafetyReport::default();
+        };
+        let mut words = self
+            .segments(&fold_case(&password.value))
+            .unwrap_or_else(|| self.words(password));
+        let mut seen = HashSet::new();
+        words.retain(|word| seen.insert(word.clone()));
+        let matches = words
+            .into_iter()
+            .filter_map(|word| {
+                let (_, category) = self
+                    .lists
+                    .iter()
+                    .find(|(word_set, _)| word_set.contains(word.as_str()))?;
+                Some(SafetyMatch { category, word })
+            })
+            .collect();
+        SafetyReport {
+            finding: Some(finding),
+            matches,
+        }
+    }
+
+    /// Judges `password` as `passgen check` does: over `alphabet`, failing if the password
+    /// has other characters, or else over the classes of characters it has (see
+    /// [`Alphabet::detect`]), counting what `context` reveals about its owner as guesses.
+    pub fn assess(
+        &self,
+        password: &Password,
+        alphabet: Option<&Alphabet>,
+        context: &PersonalContext,
+    ) -> Result<Assessment, anyhow::Error> {
+        Assessment::of(password, self.report(password), alphabet, context)
+    }
+
+    // The folded password split into the fewest words of any of the lists, if it is made
+    // up of them
+    fn segments(&self, password: &str) -> Option<Vec<String>> {
+        let boundaries: Vec<usize> = password
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([password.len()])
+            .collect();
+        // For each boundary, the fewest words up to it and where the last of them starts
+        let mut fewest: Vec<Option<(usize, usize)>> = vec![None; boundaries.len()];
+        fewest[0] = Some((0, 0));
+        for i in 1..boundaries.len() {
+            fewest[i] = (0..i)
+                .filter_map(|j| {
+                    let (count, _) = fewest[j]?;
+                    let word = &password[boundaries[j]..boundaries[i]];
+                    self.lists
+                        .iter()
+                        .any(|(word_set, _)| word_set.contains(word))
+                        .then_some((count + 1, j))
+                })
+                .min();
+        }
+        let mut words = Vec::new();
+        let mut i = boundaries.len() - 1;
+        while i > 0 {
+            let (_, j) = fewest[i]?;
+            words.push(password[boundaries[j]..boundaries[i]].to_string());
+            i = j;
+        }
+        words.reverse();
+        Some(words)
+    }
+
+    // The closest word of the first list with one within the edits allowed for the
+    // password's length
+    fn near_finding(&self, password: &Password) -> Option<String> {
+        let password: Vec<char> = fold_case(&password.value).chars().collect();
+        let max = match password.len() {
+            0..=4 => return None,
+            5..=8 => 1,
+            _ => 2,
+        };
+        let lengths = password.len() - max..=password.len() + max;
+        let mask = char_mask(&password);
+        self.near
+            .iter()
+            .zip(&self.lists)
+            .find_map(|(by_length, (_, description))| {
+                let (_, word) = lengths
+                    .clone()
+                    .filter_map(|length| by_length.get(length))
+                    .flatten()
+                    // Each edit but a swap adds at most one character the other lacks
+                    .filter(|(word_mask, _)| (mask & !word_mask).count_ones() as usize <= max)
+                    .filter_map(|(_, word)| Some((damerau_distance(&password, word, max)?, word)))
+                    // The closest, whatever order the list's set iterates in
+                    .min()?;
+                let word: String = word.iter().collect();
+                Some(format!("close variant of a {}, `{}`", description, word))
+            })
+    }
+
+    // Whether the password is dates alone, or dates and common words of any of the lists,
+    // allowing for one symbol between them, e.g. `01011990` or `Summer2024!`
+    fn date_finding(&self, password: &Password) -> Option<&'static str> {
+        let dates = patterns::find_dates(&password.value);
+        if dates.is_empty() {
+            return None;
+        }
+        let chars: Vec<char> = password.value.chars().collect();
+        let mut rest: Vec<String> = Vec::new();
+        let mut start = 0;
+        for date in &dates {
+            rest.push(chars[start..date.start].iter().collect());
+            start = date.start + date.length;
+        }
+        rest.push(chars[start..].iter().collect());
+        let mut words = false;
+        for text in rest.iter().filter(|text| !text.is_empty()) {
+            let symbol = text.chars().count() == 1 && !text.chars().all(char::is_alphanumeric);
+            if symbol {
+                continue;
+            }
+            let word = Password::new(text.as_str());
+            let common = self.lists.iter().any(|(word_set, _)| {
+                matches!(
+                    word.match_in(word_set, SafetyLevel::Combination),
+                    Some(Match::Whole)
+                )
+            });
+            if !common {
+                return None;
+            }
+            words = true;
+        }
+        Some(match words {
+            true => "combination of common words and a date",
+            false => "date",
+        })
+    }
+}
+
+// The characters of `chars` as bits of their code points modulo 64; characters sharing a bit
+// only make two masks look more alike
+fn char_mask(chars: &[char]) -> u64 {
+    chars.iter().fold(0, |mask, &c| mask | 1 << (c as u32 % 64))
+}
+
+/// Folds case for dictionary comparisons with full Unicode case folding, so that e.g. `ß`
+/// matches `ss` and final `ς` matches `σ`, independent of any locale. Both Turkish `İ` and
+/// dotless `ı` fold to a plain `i` as well, so Turkish spellings of a word are caught too.
+pub(crate) fn fold_case(text: &str) -> Cow<'_, str> {
+    // Most list entries are lowercase ASCII already
+    if !text
+        .bytes()
+        .any(|b| b.is_ascii_uppercase() || !b.is_ascii())
+    {
+        return Cow::Borrowed(text);
+    }
+    let folded = caseless::default_case_fold_str(text);
+    let mut result = String::with_capacity(folded.len());
+    for c in folded.chars() {
+        match c {
+            // `İ` folds to `i` followed by a combining dot above
+            '\u{307}' if result.ends_with('i') => {}
+            'ı' => result.push('i'),
+            c => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Number of single-character insertions, deletions, substitutions and swaps of neighbouring
+/// characters turning `a` into `b`, editing no part twice (the optimal string alignment
+/// distance), or `None` if more than `max`. Only cells within `max` of the diagonal are
+/// computed, so comparing against a whole word list stays fast.
+pub fn damerau_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let over = max + 1;
+    let mut two_back = vec![over; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).map(|j| j.min(over)).collect();
+    let mut current = vec![over; b.len() + 1];
+    for i in 1..=a.len() {
+        current.fill(over);
+        current[0] = i.min(over);
+        let mut row_min = current[0];
+        for j in i.saturating_sub(max).max(1)..=(i + max).min(b.len()) {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (previous[j - 1] + cost)
+                .min(previous[j] + 1)
+                .min(current[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(two_back[j - 2] + 1);
+            }
+            current[j] = distance.min(over);
+            row_min = row_min.min(current[j]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut two_back, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+/// Number of single-character insertions, deletions and substitutions turning `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
         }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+// Letters, digits and other characters as `a`, `9` and `!`, e.g. `Summer2024!` as `aaaaaa9999!`
+fn structure(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphabetic() {
+                'a'
+            } else if c.is_numeric() {
+                '9'
+            } else {
+                '!'
+            }
+        })
+        .collect()
+}
+
+/// Ways in which `new` is only a trivial mutation of `old`, e.g. `Summer2024!` of
+/// `summer2023!`; empty when it is a meaningful change. Case is ignored throughout.
+pub fn compare(old: &str, new: &str, min_distance: usize) -> Vec<String> {
+    let (old, new) = (fold_case(old), fold_case(new));
+    let mut findings = Vec::new();
+
+    let distance = edit_distance(&old, &new);
+    if distance < min_distance {
+        findings.push(format!(
+            "differs by only {} of {} required edits",
+            distance, min_distance
+        ));
+    }
+
+    let letters = |text: &str| {
+        text.chars()
+            .filter(|c| c.is_alphabetic())
+            .collect::<String>()
+    };
+    let old_letters = letters(&old);
+    if !old_letters.is_empty() && old_letters == letters(&new) {
+        findings.push("keeps the same letters".to_string());
+    }
 
-        // Check if the password contains any of the common words as substrings.
-        // This is a simple check and might not be what is desired for all cases.
-        // For example, "mypassword" would be unsafe if "password" is a common word.
-        // The current logic in `is_combination_of_word_set` already handles substrings
-        // that form the whole password. This check is for partial containment.
-        // A more robust implementation might be needed depending on desired behavior.
-        // For now, the combination check is the primary logic.
+    // A secret of one kind of character always keeps its structure, which says nothing
+    let old_structure = structure(&old);
+    let mixed = old_structure.chars().any(|c| !old_structure.starts_with(c));
+    if mixed && old_structure == structure(&new) {
+        findings.push("keeps the same arrangement of letters, digits and symbols".to_string());
+    }
+    findings
+}
+
+/// Describes how far a secret of `count` uniform picks from `choices` options, as requested
+/// with `flag`, falls short of `minimum` bits; `None` when it reaches them.
+pub fn length_warning(flag: &str, count: usize, choices: usize, minimum: f64) -> Option<String> {
+    let bits = count as f64 * (choices as f64).log2();
+    entropy_warning(&format!("{} {}", flag, count), bits, minimum)
+}
+
+/// The fewest uniform picks from `choices` options that reach `bits` of entropy; `None`
+/// when there are too few options for any number of picks to add entropy.
+pub fn length_for_entropy(bits: f64, choices: usize) -> Option<usize> {
+    if choices < 2 {
+        return None;
+    }
+    // Allow for rounding, so exact multiples such as 128 bits of hex need no extra pick
+    let picks = (bits / (choices as f64).log2() - 1e-9).ceil();
+    Some((picks as usize).max(1))
+}
 
-        true // If no checks failed, the password is safe
+/// Parses a target entropy in bits, which must be positive.
+pub fn parse_bits(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(bits) if bits.is_finite() && bits > 0.0 => Ok(bits),
+        _ => Err(format!("`{}` is not a positive number of bits", s)),
     }
 }
 
+/// Describes how far a secret generated as `option` requests, e.g. `--pattern nnnn`, falls
+/// short of `minimum` bits; `None` when it reaches them.
+pub fn entropy_warning(option: &str, bits: f64, minimum: f64) -> Option<String> {
+    (bits < minimum).then(|| {
+        format!(
+            "{} gives only {:.1} bits of entropy, below the minimum of {}",
+            option, bits, minimum
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +992,39 @@ mod tests {
         let password = Password::new("password");
         // rewrite above with an epsilon comparison
         assert!((password.entropy(26) - 37.6).abs() < 0.01);
+        // Characters, not bytes
+        assert!((Password::new("ééé").entropy(2) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strength() {
+        let report = Password::new("aaaaaaaaaaaa").strength(26);
+        assert_eq!(report.length, 12);
+        assert!((report.raw_entropy - 56.4).abs() < 0.1);
+        // The first character and the length of the run
+        assert!((report.entropy - (26f64.log2() + 12f64.log2())).abs() < 1e-9);
+        assert_eq!(report.classification, Classification::Weak);
+        assert_eq!(report.patterns.len(), 1);
+
+        let walk = Password::new("qwertyuiop").strength(26);
+        assert_eq!(walk.patterns[0].pattern, patterns::Pattern::KeyboardWalk);
+        assert_eq!(walk.classification, Classification::Weak);
+
+        let random = Password::new("kq7#Zm2!pX").strength(72);
+        assert!(random.patterns.is_empty());
+        assert_eq!(random.entropy, random.raw_entropy);
+        assert_eq!(random.classification, Classification::VeryStrong);
+
+        assert_eq!(Password::new("aaa").strength(1).entropy, 0.0);
+
+        let context = PersonalContext::parse(&["name=Alice", "birth=1990"]).unwrap();
+        let personal = Password::new("Al1ce1990!kq").strength_in(72, &context);
+        assert_eq!(personal.patterns.len(), 2);
+        assert_eq!(personal.classification, Classification::Weak);
+        // Without the context, the year is still a date
+        let unrelated = Password::new("Al1ce1990!kq").strength(72);
+        assert_eq!(unrelated.patterns[0].pattern, patterns::Pattern::Date);
+        assert_eq!(unrelated.classification, Classification::Strong);
     }
 
     #[test]
@@ -119,11 +1039,17 @@ mod tests {
         );
 
         // Test Medium classification (28 <= entropy < 40)
-        let medium_password = Password::new("abcdef"); // 6 chars, lowercase only: ~28.2 entropy
+        let medium_password = Password::new("kdmwfz"); // 6 chars, lowercase only: ~28.2 entropy
         assert_eq!(
             medium_password.classify(&Alphabet::LowerCase).unwrap(),
             Classification::Medium
         );
+        // A sequence of the same length takes far fewer guesses
+        let sequence_password = Password::new("abcdef");
+        assert_eq!(
+            sequence_password.classify(&Alphabet::LowerCase).unwrap(),
+            Classification::Weak
+        );
 
         // Test Strong classification (40 <= entropy < 60)
         let strong_password = Password::new("password"); // 8 chars, lowercase only: ~37.6 entropy
@@ -484,4 +1410,384 @@ mod tests {
         let upper_combo = Password::new("AI");
         assert!(!upper_combo.is_safe(&single_custom));
     }
+
+    #[test]
+    fn test_fold_case() {
+        assert!(matches!(fold_case("password"), Cow::Borrowed("password")));
+        assert_eq!(fold_case("PassWord"), "password");
+        assert_eq!(fold_case("STRASSE"), fold_case("Straße"));
+        assert_eq!(fold_case("ΟΔΟΣ"), fold_case("οδος"));
+        assert_eq!(fold_case("İSTANBUL"), "istanbul");
+        assert_eq!(fold_case("ıstanbul"), "istanbul");
+        assert_eq!(fold_case("DİYARBAKIR"), "diyarbakir");
+    }
+
+    #[test]
+    fn test_screen() {
+        let lists = [
+            (CommonWords::Custom(vec!["dragon".to_string()]), "dragon"),
+            (
+                CommonWords::Custom(vec!["blue".to_string(), "sky".to_string()]),
+                "sky",
+            ),
+        ];
+        let screen = Screen::new(&lists);
+        assert_eq!(
+            screen.finding(&Password::new("Dragon")).as_deref(),
+            Some("dragon")
+        );
+        assert_eq!(
+            screen.finding(&Password::new("blueSKY")).as_deref(),
+            Some("sky")
+        );
+        assert_eq!(screen.finding(&Password::new("dragonfly")), None);
+        assert_eq!(Screen::new(&[]).finding(&Password::new("sky")), None);
+    }
+
+    #[test]
+    fn test_safety_report() {
+        let lists = [
+            (
+                CommonWords::Custom(vec!["dragon".to_string(), "drag".to_string()]),
+                "common password",
+            ),
+            (
+                CommonWords::Custom(vec!["mary".to_string(), "on".to_string()]),
+                "female name",
+            ),
+            (
+                CommonWords::Custom(vec![
+                    "dragon".to_string(),
+                    "mary".to_string(),
+                    "on".to_string(),
+                ]),
+                "combination of common words",
+            ),
+        ];
+        let screen = Screen::new(&lists);
+        let report = screen.report(&Password::new("DragonMary"));
+        assert!(!report.is_safe());
+        assert_eq!(
+            report.to_string(),
+            "combination of common words, matching common password `dragon` and female name \
+             `mary`"
+        );
+
+        let report = screen.report(&Password::new("maryDRAGONmary"));
+        let words: Vec<&str> = report.matches.iter().map(|m| m.word.as_str()).collect();
+        assert_eq!(words, ["mary", "dragon"]);
+
+        let screen = Screen::new(&lists).with_level(SafetyLevel::Contains(4));
+        let report = screen.report(&Password::new("xdragon7"));
+        assert_eq!(
+            report.finding.as_deref(),
+            Some("password containing a common password")
+        );
+        assert_eq!(report.matches.len(), 1);
+
+        let report = screen.report(&Password::new("kq7#Zm2!"));
+        assert!(report.is_safe());
+        assert!(report.matches.is_empty());
+        assert_eq!(report.to_string(), "no common words");
+    }
+
+    #[test]
+    fn test_is_safe_special_casing() {
+        let words = ["straße", "istanbul", "οδος", "iç"]
+            .map(String::from)
+            .to_vec();
+        let custom_words = CommonWords::Custom(words);
+
+        for password in [
+            "STRASSE",
+            "Strasse",
+            "İstanbul",
+            "ISTANBUL",
+            "ıstanbul",
+            "ΟΔΟΣ",
+        ] {
+            assert!(
+                !Password::new(password).is_safe(&custom_words),
+                "{}",
+                password
+            );
+        }
+        // Combinations across multi-byte characters
+        assert!(!Password::new("İÇSTRASSE").is_safe(&custom_words));
+        assert!(!Password::new("ıçοδος").is_safe(&custom_words));
+        assert!(Password::new("ıçx").is_safe(&custom_words));
+        assert!(Password::new("ßüß").is_safe(&custom_words));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("straße", "strase"), 1);
+    }
+
+    #[test]
+    fn test_damerau_distance() {
+        let distance = |a: &str, b: &str, max| {
+            let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+            damerau_distance(&a, &b, max)
+        };
+        assert_eq!(distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(distance("kitten", "sitting", 2), None);
+        // A swap is one edit, unlike for `edit_distance`
+        assert_eq!(distance("pasword", "paswrod", 2), Some(1));
+        assert_eq!(distance("passw0rdz", "password", 2), Some(2));
+        assert_eq!(distance("abc", "abc", 0), Some(0));
+        assert_eq!(distance("", "ab", 2), Some(2));
+        assert_eq!(distance("abcdef", "ab", 2), None);
+    }
+
+    #[test]
+    fn test_fuzzy_findings() {
+        let lists = [
+            (
+                CommonWords::Custom(vec!["password".to_string()]),
+                "common password",
+            ),
+            (
+                CommonWords::Custom(vec!["password".to_string(), "dragon".to_string()]),
+                "common word",
+            ),
+        ];
+        let screen = Screen::new(&lists).with_fuzzy(true);
+        let finding = |password| screen.finding(&Password::new(password));
+        assert_eq!(
+            finding("password1").unwrap(),
+            "close variant of a common password, `password`"
+        );
+        assert_eq!(
+            finding("Passw0rdz").unwrap(),
+            "close variant of a common password, `password`"
+        );
+        assert_eq!(
+            finding("dargon").unwrap(),
+            "close variant of a common word, `dragon`"
+        );
+        // Two edits are too many for six characters, and short ones are not looked at
+        assert!(finding("dargn!").is_none());
+        assert!(finding("drgn").is_none());
+        assert!(
+            Screen::new(&lists)
+                .finding(&Password::new("password1"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            compare("summer2023!", "Summer2024!", 4),
+            [
+                "differs by only 1 of 4 required edits",
+                "keeps the same letters",
+                "keeps the same arrangement of letters, digits and symbols",
+            ]
+        );
+        assert_eq!(
+            compare("summer2023!", "winter2023?", 4),
+            ["keeps the same arrangement of letters, digits and symbols"]
+        );
+        assert!(compare("Hs0F!ya#9jBH", "k2@Lq9zPw!xe", 4).is_empty());
+        // Without digits or symbols, the structure cannot change
+        assert!(compare("abcdefgh", "qrstuvwx", 4).is_empty());
+    }
+
+    #[test]
+    fn test_length_warning() {
+        // 4 characters drawn from all 94 printable ASCII characters
+        assert_eq!(
+            length_warning("--length", 4, 94, WEAK_ENTROPY).unwrap(),
+            "--length 4 gives only 26.2 bits of entropy, below the minimum of 28"
+        );
+        assert_eq!(length_warning("--length", 5, 94, WEAK_ENTROPY), None);
+        // The default 3 words of the EFF large list
+        assert_eq!(length_warning("--length", 3, 7776, WEAK_ENTROPY), None);
+        assert!(length_warning("--length", 3, 7776, 60.0).is_some());
+    }
+
+    #[test]
+    fn test_length_for_entropy() {
+        assert_eq!(length_for_entropy(80.0, 72), Some(13));
+        assert_eq!(length_for_entropy(128.0, 16), Some(32));
+        assert_eq!(length_for_entropy(77.0, 7776), Some(6));
+        assert_eq!(length_for_entropy(0.5, 72), Some(1));
+        assert_eq!(length_for_entropy(80.0, 1), None);
+    }
+
+    #[test]
+    fn test_parse_bits() {
+        assert_eq!(parse_bits("80"), Ok(80.0));
+        assert_eq!(parse_bits("77.5"), Ok(77.5));
+        assert!(parse_bits("0").is_err());
+        assert!(parse_bits("-3").is_err());
+        assert!(parse_bits("inf").is_err());
+    }
+
+    #[test]
+    fn test_passphrase_entropy() {
+        assert!((passphrase_entropy(6, 7776) - 77.55).abs() < 0.01);
+        assert_eq!(passphrase_entropy(6, 0), 0.0);
+        assert_eq!(passphrase_entropy(0, 7776), 0.0);
+
+        // Character entropy would call four words of 30 letters very strong
+        let phrase = Password::new("correct-horse-battery-staple");
+        assert_eq!(
+            phrase.classify_passphrase("-", 2048),
+            Classification::Strong
+        );
+        assert_eq!(phrase.classify_passphrase("-", 16), Classification::Weak);
+        assert_eq!(phrase.classify_passphrase(" ", 7776), Classification::Weak);
+    }
+
+    #[test]
+    fn test_contains() {
+        let passwords = CommonWords::Passwords;
+        let password = Password::new("mypassword123");
+        assert!(password.is_safe(&passwords));
+        assert!(!password.is_safe_at(&passwords, SafetyLevel::Contains(MIN_CONTAINED_LENGTH)));
+        // "pass" is a common password too, but shorter than asked for
+        let short = Password::new("xpassx");
+        assert!(!short.is_safe_at(&passwords, SafetyLevel::Contains(4)));
+        assert!(short.is_safe_at(&passwords, SafetyLevel::Contains(5)));
+        assert!(Password::new("Xk9#mP2$vL").is_safe_at(&passwords, SafetyLevel::Contains(4)));
+
+        let lists = [(CommonWords::Passwords, "common password")];
+        let screen = Screen::new(&lists).with_level(SafetyLevel::Contains(4));
+        assert_eq!(
+            screen.finding(&password).as_deref(),
+            Some("password containing a common password")
+        );
+        assert_eq!(
+            screen.finding(&Password::new("password")).as_deref(),
+            Some("common password")
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.2), "less than a second");
+        assert_eq!(format_duration(1.0), "1 second");
+        assert_eq!(format_duration(59.9), "59 seconds");
+        assert_eq!(format_duration(7_200.0), "2 hours");
+        assert_eq!(format_duration(86_400.0 * 45.0), "1 month");
+        assert_eq!(format_duration(86_400.0 * 365.25 * 99.0), "99 years");
+        assert_eq!(format_duration(1e300), "centuries");
+        assert_eq!(format_duration(f64::INFINITY), "centuries");
+    }
+
+    #[test]
+    fn test_date_findings() {
+        let lists = [(
+            CommonWords::Custom(vec!["summer".to_string()]),
+            "common word",
+        )];
+        let screen = Screen::new(&lists);
+        let finding = |password| screen.finding(&Password::new(password));
+        assert_eq!(finding("01011990").unwrap(), "date");
+        assert_eq!(finding("jan1990").unwrap(), "date");
+        assert_eq!(
+            finding("Summer2024!").unwrap(),
+            "combination of common words and a date"
+        );
+        assert_eq!(
+            finding("2024summer1990").unwrap(),
+            "combination of common words and a date"
+        );
+        assert!(finding("winter2024").is_none());
+        assert!(finding("kq7#1990pX").is_none());
+        assert!(finding("summer").is_some());
+    }
+
+    #[test]
+    fn test_other_languages() {
+        let screen = Screen::new(&SAFETY_CHECKS);
+        let finding = |password| screen.finding(&Password::new(password));
+        assert_eq!(finding("sommerhaus").unwrap(), "common German word");
+        // Spelled without the umlaut or accent, as often typed
+        assert_eq!(finding("Muenchen").unwrap(), "common German word");
+        assert_eq!(finding("motdepasse").unwrap(), "common French word");
+        assert_eq!(finding("contraseña").unwrap(), "common Spanish word");
+        assert!(
+            CommonWords::All
+                .words()
+                .iter()
+                .any(|word| word == "fußball")
+        );
+
+        let german = safety_checks(&[Language::German]);
+        let descriptions: Vec<&str> = german.iter().map(|(_, d)| *d).collect();
+        assert!(descriptions.contains(&"common German word"));
+        assert!(!descriptions.contains(&"common English word"));
+        assert!(!descriptions.contains(&"combination of common words"));
+        let screen = Screen::new(&german);
+        assert!(screen.finding(&Password::new("motdepasse")).is_none());
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let lists = [(
+            CommonWords::Custom(vec!["dragon".to_string()]),
+            "common word",
+        )];
+        let screen = Screen::new(&lists);
+        let password = Password::new("Dragon12");
+        let words = screen.words(&password);
+        assert_eq!(words, ["dragon"]);
+        // 8 characters of 62 are about 47.6 bits, 12.4 short of very strong
+        let report = password.strength(62);
+        assert_eq!(
+            report.suggestions("Dragon12", &words, Classification::VeryStrong),
+            [
+                "avoid the dictionary word `dragon`",
+                "add 3 more characters to reach VeryStrong",
+                "mixing in symbols would add ~5 bits",
+            ]
+        );
+        assert!(
+            report
+                .suggestions("Dragon12", &[], Classification::Medium)
+                .iter()
+                .all(|s| s.starts_with("avoid"))
+        );
+
+        let random = Password::new("kq7#Zm2!pX").strength(72);
+        assert!(
+            random
+                .suggestions("kq7#Zm2!pX", &[], Classification::VeryStrong)
+                .is_empty()
+        );
+        assert!(screen.words(&Password::new("drag0n")).is_empty());
+    }
+
+    #[test]
+    fn test_crack_times() {
+        // 2^20 candidates, half of them tried on average
+        let report = Password::new("aaaa").strength(32);
+        let report = StrengthReport {
+            entropy: 21.0,
+            ..report
+        };
+        let times = report.crack_times();
+        assert_eq!(times.len(), 4);
+        assert_eq!(times[0].scenario, AttackScenario::OnlineThrottled);
+        assert!((times[0].seconds - 1_048_576.0 * 36.0).abs() < 1e-3);
+        assert_eq!(times[0].duration, "1 year");
+        assert_eq!(times[1].duration, "1 day");
+        assert_eq!(times[2].duration, "1 minute");
+        assert_eq!(times[3].duration, "less than a second");
+
+        let strong = Password::new("kq7#Zm2!pXr4Tw9&").strength(72);
+        assert!(
+            strong
+                .crack_times()
+                .iter()
+                .all(|t| t.duration == "centuries")
+        );
+    }
 }
@@ -0,0 +1,120 @@
+use crate::passgen::hash::sha256_hex;
+use crate::passgen::which::on_path;
+use std::io::{self, BufRead, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+// Hidden command that `clear_after` runs in the background
+pub const CLEAR_COMMAND: &str = "clear-clipboard";
+
+// Clipboard helpers in order of preference; the first one found on PATH is used
+#[cfg(target_os = "macos")]
+const BACKENDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const BACKENDS: &[&[&str]] = &[&["clip.exe"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const BACKENDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+// Helpers that print the clipboard, to check it still holds what was copied before clearing
+#[cfg(target_os = "macos")]
+const PASTE_BACKENDS: &[&[&str]] = &[&["pbpaste"]];
+#[cfg(windows)]
+const PASTE_BACKENDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE_BACKENDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// Command line of the clipboard helper that will be used, if any is installed.
+pub fn backend() -> Option<&'static [&'static str]> {
+    BACKENDS.iter().copied().find(|command| on_path(command[0]))
+}
+
+pub fn copy(text: &str) -> io::Result<()> {
+    let command = backend().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No clipboard helper found; install one of: {}",
+                BACKENDS.iter().map(|c| c[0]).collect::<Vec<_>>().join(", ")
+            ),
+        )
+    })?;
+
+    let mut child = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            command[0], status
+        )));
+    }
+    Ok(())
+}
+
+fn paste() -> io::Result<String> {
+    let command = PASTE_BACKENDS
+        .iter()
+        .find(|command| on_path(command[0]))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no clipboard reader found"))?;
+    let output = Command::new(command[0])
+        .args(&command[1..])
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            command[0], output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Clears the clipboard after `delay` from a background copy of this program, so the
+/// command can exit meanwhile. Only a hash of `text` is handed over, on a pipe, so the
+/// clipboard is left alone if something else was copied in the meantime.
+pub fn clear_after(text: &str, delay: Duration) -> io::Result<()> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .args([CLEAR_COMMAND, "--after", &delay.as_secs().to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    writeln!(stdin, "{}", sha256_hex(text.as_bytes()))
+}
+
+/// The background half of [`clear_after`]: reads the hash from stdin, waits, and clears
+/// the clipboard if it still holds the copied text or cannot be read to tell.
+pub fn wait_and_clear(delay: Duration) -> io::Result<()> {
+    let mut digest = String::new();
+    io::stdin().lock().read_line(&mut digest)?;
+    std::thread::sleep(delay);
+    let unchanged = match paste() {
+        Ok(current) => {
+            let current = current.trim_end_matches(['\r', '\n']);
+            sha256_hex(current.as_bytes()) == digest.trim()
+        }
+        Err(_) => true,
+    };
+    if unchanged {
+        copy("")?;
+    }
+    Ok(())
+}
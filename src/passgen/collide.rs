@@ -0,0 +1,134 @@
+use crate::passgen::config;
+use serde::Serialize;
+use std::fmt;
+
+/// Probability of a duplicate the suggested length stays below.
+pub const TARGET_PROBABILITY: f64 = 1e-6;
+// Longer secrets are refused, so no suggestion goes beyond them
+const MAX_LENGTH: usize = config::MAX_LENGTH as usize;
+
+/// Birthday-bound estimate of duplicates among a batch of uniformly generated secrets.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Collision {
+    pub length: usize,
+    pub alphabet_size: usize,
+    pub count: f64,
+    pub bits: f64,
+    pub probability: f64,
+    pub expected_duplicates: f64,
+    /// Shortest length keeping the probability below [`TARGET_PROBABILITY`], if any
+    pub safe_length: Option<usize>,
+}
+
+impl Collision {
+    pub fn estimate(length: usize, alphabet_size: usize, count: f64) -> Self {
+        let bits = length as f64 * (alphabet_size as f64).log2();
+        let expected = expected_duplicates(bits, count);
+        Self {
+            length,
+            alphabet_size,
+            count,
+            bits,
+            probability: probability(expected),
+            expected_duplicates: expected,
+            safe_length: (1..=MAX_LENGTH).find(|&length| {
+                let bits = length as f64 * (alphabet_size as f64).log2();
+                probability(expected_duplicates(bits, count)) < TARGET_PROBABILITY
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "space: {} characters of {} ({:.1} bits)",
+            self.length, self.alphabet_size, self.bits
+        )?;
+        writeln!(f, "secrets: {:.0}", self.count)?;
+        writeln!(
+            f,
+            "probability of a duplicate: {}",
+            format_probability(self.probability)
+        )?;
+        writeln!(
+            f,
+            "expected duplicates: {}",
+            format_probability(self.expected_duplicates)
+        )?;
+        match self.safe_length {
+            Some(length) => write!(f, "length for under one in a million: {}", length),
+            None => write!(
+                f,
+                "length for under one in a million: none up to {}",
+                MAX_LENGTH
+            ),
+        }
+    }
+}
+
+// Pairs of secrets divided by the number of possible secrets, in log space so that huge
+// spaces neither overflow nor round the result to zero
+fn expected_duplicates(bits: f64, count: f64) -> f64 {
+    if count < 2.0 {
+        return 0.0;
+    }
+    let pairs = count.log2() + (count - 1.0).log2() - 1.0;
+    (pairs - bits).exp2()
+}
+
+fn probability(expected_duplicates: f64) -> f64 {
+    -(-expected_duplicates).exp_m1()
+}
+
+fn format_probability(p: f64) -> String {
+    if p == 0.0 || p >= 0.001 {
+        format!("{:.4}", p)
+    } else {
+        format!("{:.2e}", p)
+    }
+}
+
+/// Parses a batch size such as `1000000` or `1e6`.
+pub fn parse_count(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(count) if count.is_finite() && count >= 1.0 && count.fract() == 0.0 => Ok(count),
+        _ => Err(format!("`{}` is not a whole number of at least 1", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_birthday_bound() {
+        // 23 people sharing one of 365 birthdays
+        let expected = expected_duplicates(365f64.log2(), 23.0);
+        assert!((probability(expected) - 0.5).abs() < 0.02);
+
+        // A million 10-character hex IDs: about 36% chance of a duplicate
+        let collision = Collision::estimate(10, 16, 1e6);
+        assert!((collision.probability - 0.36).abs() < 0.01);
+        assert_eq!(collision.safe_length, Some(15));
+    }
+
+    #[test]
+    fn test_tiny_probabilities_do_not_vanish() {
+        let collision = Collision::estimate(64, 72, 1e9);
+        assert!(collision.probability > 0.0);
+        assert!(collision.probability < 1e-100);
+        assert_eq!(Collision::estimate(12, 72, 1.0).probability, 0.0);
+        assert_eq!(Collision::estimate(12, 1, 2.0).safe_length, None);
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("1e6"), Ok(1e6));
+        assert_eq!(parse_count("250"), Ok(250.0));
+        assert!(parse_count("0").is_err());
+        assert!(parse_count("1.5").is_err());
+        assert!(parse_count("inf").is_err());
+    }
+}
@@ -1,80 +1,183 @@
+use crate::passgen::checker;
+use crate::passgen::mapped::MappedWords;
+use crate::passgen::resources::{Embedded, embedded};
+use crate::passgen::wordlist::Words;
+use clap::ValueEnum;
+use std::borrow::Cow;
 use std::collections::HashSet;
-use std::sync::OnceLock;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-#[derive(Debug, Clone)]
+/// Lists of common passwords, words and names that [`Password::is_safe`] checks against;
+/// `All` combines the built-in ones.
+///
+/// [`Password::is_safe`]: crate::Password::is_safe
+#[derive(Debug, Clone, Default)]
 pub enum CommonWords {
     Passwords,
     English,
+    German,
+    French,
+    Spanish,
     MaleNames,
     FemaleNames,
     LastNames,
+    #[default]
     All,
     Custom(Vec<String>),
+    /// A breach corpus or other list read from a file
+    File(Arc<MappedWords>),
 }
 
-const COMMON_ENGLISH: &str = include_str!("../../resources/common/english.txt");
-const COMMON_PASSWORDS: &str = include_str!("../../resources/common/passwords.txt");
-const COMMON_MALE_NAMES: &str = include_str!("../../resources/common/male_names.txt");
+/// Words of a list folded as dictionary lookups compare them, see [`CommonWords::word_set`].
+pub type WordSet<'a> = HashSet<Cow<'a, str>>;
 
-const COMMON_FEMALE_NAMES: &str = include_str!("../../resources/common/female_names.txt");
-const COMMON_LAST_NAMES: &str = include_str!("../../resources/common/last_names.txt");
-
-// Static caches for lazy loading
-static COMMON_ENGLISH_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static COMMON_PASSWORDS_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static COMMON_MALE_NAMES_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static COMMON_FEMALE_NAMES_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static COMMON_LAST_NAMES_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static COMMON_ALL_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-
-fn get_common_english() -> &'static [&'static str] {
-    COMMON_ENGLISH_CACHE.get_or_init(|| COMMON_ENGLISH.lines().collect())
+/// A common-word list built into the binary, split into words and folded into a set on
+/// first use. Adding a list takes its file under `resources/common`, a static here and a
+/// `CommonWords` variant.
+struct Builtin {
+    name: &'static str,
+    // `None` for the union of every other list
+    source: Option<Embedded>,
+    words: OnceLock<Vec<&'static str>>,
+    set: OnceLock<WordSet<'static>>,
 }
 
-fn get_common_passwords() -> &'static [&'static str] {
-    COMMON_PASSWORDS_CACHE.get_or_init(|| COMMON_PASSWORDS.lines().collect())
+macro_rules! builtin {
+    ($name:literal, $path:literal) => {
+        Builtin {
+            name: $name,
+            source: Some(embedded!($path)),
+            words: OnceLock::new(),
+            set: OnceLock::new(),
+        }
+    };
 }
 
-fn get_common_male_names() -> &'static [&'static str] {
-    COMMON_MALE_NAMES_CACHE.get_or_init(|| COMMON_MALE_NAMES.lines().collect())
-}
+static PASSWORDS: Builtin = builtin!("passwords", "common/passwords.txt");
+static ENGLISH: Builtin = builtin!("english", "common/english.txt");
+static GERMAN: Builtin = builtin!("german", "common/german.txt");
+static FRENCH: Builtin = builtin!("french", "common/french.txt");
+static SPANISH: Builtin = builtin!("spanish", "common/spanish.txt");
+static MALE_NAMES: Builtin = builtin!("male-names", "common/male_names.txt");
+static FEMALE_NAMES: Builtin = builtin!("female-names", "common/female_names.txt");
+static LAST_NAMES: Builtin = builtin!("last-names", "common/last_names.txt");
 
-fn get_common_female_names() -> &'static [&'static str] {
-    COMMON_FEMALE_NAMES_CACHE.get_or_init(|| COMMON_FEMALE_NAMES.lines().collect())
-}
+// Every built-in list, which `All` combines
+static BUILTINS: [&Builtin; 8] = [
+    &PASSWORDS,
+    &ENGLISH,
+    &GERMAN,
+    &FRENCH,
+    &SPANISH,
+    &MALE_NAMES,
+    &FEMALE_NAMES,
+    &LAST_NAMES,
+];
 
-fn get_common_last_names() -> &'static [&'static str] {
-    COMMON_LAST_NAMES_CACHE.get_or_init(|| COMMON_LAST_NAMES.lines().collect())
-}
+static ALL: Builtin = Builtin {
+    name: "all",
+    source: None,
+    words: OnceLock::new(),
+    set: OnceLock::new(),
+};
 
-fn get_common_all() -> &'static [&'static str] {
-    COMMON_ALL_CACHE.get_or_init(|| {
-        let mut all_words = HashSet::new();
-        all_words.extend(get_common_passwords().iter());
-        all_words.extend(get_common_english().iter());
-        all_words.extend(get_common_male_names().iter());
-        all_words.extend(get_common_female_names().iter());
-        all_words.extend(get_common_last_names().iter());
-        all_words.into_iter().collect()
-    })
-}
+impl Builtin {
+    fn text(&'static self) -> Option<&'static str> {
+        self.source.as_ref().map(Embedded::text)
+    }
 
-impl Default for CommonWords {
-    fn default() -> Self {
-        CommonWords::All
+    fn words(&'static self) -> &'static [&'static str] {
+        self.words.get_or_init(|| {
+            let _span = tracing::debug_span!("load_common_words", list = self.name).entered();
+            match self.text() {
+                Some(text) => text.lines().collect(),
+                None => {
+                    let mut all_words = HashSet::new();
+                    for list in BUILTINS {
+                        all_words.extend(list.words().iter());
+                    }
+                    all_words.into_iter().collect()
+                }
+            }
+        })
+    }
+
+    // The words case-folded, as lookups compare them
+    fn set(&'static self) -> &'static WordSet<'static> {
+        self.set.get_or_init(|| {
+            let _span = tracing::debug_span!("fold_common_words", list = self.name).entered();
+            self.words()
+                .iter()
+                .map(|word| checker::fold_case(word))
+                .collect()
+        })
     }
 }
 
 impl CommonWords {
-    pub fn words(&self) -> Vec<&str> {
+    /// A list memory-mapped from a file of one word per line, or of EFF dice lines such as
+    /// `11111<TAB>abacus`.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Ok(CommonWords::File(Arc::new(MappedWords::open(path)?)))
+    }
+
+    // The embedded list behind a single built-in variant
+    fn builtin(&self) -> Option<&'static Builtin> {
+        match self {
+            CommonWords::Passwords => Some(&PASSWORDS),
+            CommonWords::English => Some(&ENGLISH),
+            CommonWords::German => Some(&GERMAN),
+            CommonWords::French => Some(&FRENCH),
+            CommonWords::Spanish => Some(&SPANISH),
+            CommonWords::MaleNames => Some(&MALE_NAMES),
+            CommonWords::FemaleNames => Some(&FEMALE_NAMES),
+            CommonWords::LastNames => Some(&LAST_NAMES),
+            CommonWords::All => Some(&ALL),
+            CommonWords::Custom(_) | CommonWords::File(_) => None,
+        }
+    }
+
+    /// Raw embedded text of a single built-in list, `None` for `All` and custom lists.
+    pub fn source(&self) -> Option<&'static str> {
+        self.builtin().and_then(Builtin::text)
+    }
+
+    pub fn words(&self) -> Words<'_> {
+        match self {
+            CommonWords::Custom(custom) => Words::Custom(custom),
+            CommonWords::File(words) => Words::Mapped(words),
+            _ => Words::Embedded(self.builtin().expect("other lists are built in").words()),
+        }
+    }
+
+    /// The words case-folded into a set for lookups. The built-in lists are folded once per
+    /// process and borrowed from then on; custom lists and files are folded on every call.
+    pub fn word_set(&self) -> Cow<'_, WordSet<'_>> {
+        match self.builtin() {
+            Some(builtin) => Cow::Borrowed(builtin.set()),
+            None => Cow::Owned(self.words().iter().map(checker::fold_case).collect()),
+        }
+    }
+}
+
+/// Languages with a built-in list of common words.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl Language {
+    pub fn common_words(self) -> CommonWords {
         match self {
-            CommonWords::Passwords => get_common_passwords().to_vec(),
-            CommonWords::English => get_common_english().to_vec(),
-            CommonWords::MaleNames => get_common_male_names().to_vec(),
-            CommonWords::FemaleNames => get_common_female_names().to_vec(),
-            CommonWords::LastNames => get_common_last_names().to_vec(),
-            CommonWords::All => get_common_all().to_vec(),
-            CommonWords::Custom(custom) => custom.iter().map(|s| s.as_str()).collect(),
+            Language::English => CommonWords::English,
+            Language::German => CommonWords::German,
+            Language::French => CommonWords::French,
+            Language::Spanish => CommonWords::Spanish,
         }
     }
 }
@@ -0,0 +1,651 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::wordlist::WordList;
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+// Overrides the platform config location, mainly for tests and portable installs
+const CONFIG_FILE_ENV: &str = "PASSGEN_CONFIG";
+
+pub const MAX_LENGTH: i64 = 4096;
+pub const MAX_WORDS: i64 = 64;
+const MAX_ENTROPY: f64 = 1024.0;
+
+/// Defaults applied to generation, either globally or as a named profile.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub length: Option<usize>,
+    pub alphabet: Option<String>,
+    pub custom: Option<String>,
+    pub words: Option<usize>,
+    pub wordlist: Option<String>,
+    pub wordlist_file: Option<PathBuf>,
+    pub separator: Option<String>,
+    pub count: Option<usize>,
+    pub min_entropy: Option<f64>,
+    pub copy: Option<bool>,
+}
+
+impl Settings {
+    /// These settings, with those left unset taken from `base`. Setting either of
+    /// `alphabet` and `custom`, or either of `wordlist` and `wordlist_file`, replaces both.
+    pub fn or(self, base: Settings) -> Settings {
+        let alphabet_set = self.alphabet.is_some() || self.custom.is_some();
+        let wordlist_set = self.wordlist.is_some() || self.wordlist_file.is_some();
+        Settings {
+            length: self.length.or(base.length),
+            alphabet: if alphabet_set {
+                self.alphabet
+            } else {
+                base.alphabet
+            },
+            custom: if alphabet_set {
+                self.custom
+            } else {
+                base.custom
+            },
+            words: self.words.or(base.words),
+            wordlist: if wordlist_set {
+                self.wordlist
+            } else {
+                base.wordlist
+            },
+            wordlist_file: if wordlist_set {
+                self.wordlist_file
+            } else {
+                base.wordlist_file
+            },
+            separator: self.separator.or(base.separator),
+            count: self.count.or(base.count),
+            min_entropy: self.min_entropy.or(base.min_entropy),
+            copy: self.copy.or(base.copy),
+        }
+    }
+
+    // Holds the numbers to the limits of the flags they stand in for
+    fn check_ranges(&self, section: &str) -> anyhow::Result<()> {
+        let limits = [
+            ("length", self.length, MAX_LENGTH),
+            ("words", self.words, MAX_WORDS),
+            ("count", self.count, i64::MAX),
+        ];
+        for (name, value, max) in limits {
+            if let Some(n) = value
+                && !i64::try_from(n).is_ok_and(|n| (1..=max).contains(&n))
+            {
+                anyhow::bail!("{}: `{}` {} is not in 1..={}", section, name, n, max);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let toml = toml::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(toml.trim_end())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Settings,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub alphabets: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Settings>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "line {}: {}: {}", self.line, severity, self.message)
+    }
+}
+
+/// Location of the config file, e.g. `~/.config/passgen/config.toml` on Linux.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_FILE_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join("passgen").join("config.toml"))
+}
+
+impl Config {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let config: Config = toml::from_str(source)?;
+        config.defaults.check_ranges("defaults")?;
+        for (name, settings) in &config.profile {
+            settings.check_ranges(&format!("profile.{}", name))?;
+        }
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Writes the config file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        std::fs::write(path, self.to_toml()?)
+            .with_context(|| format!("Cannot write config file {}", path.display()))
+    }
+
+    /// Loads the config file if one exists.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let Some(path) = config_path() else {
+            return Ok(None);
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read config file {}", path.display()))?;
+        Self::parse(&source)
+            .map(Some)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// The `[defaults]` with the settings of `profile`, if given, laid over them.
+    pub fn resolve(&self, profile: Option<&str>) -> Result<Settings, String> {
+        let Some(name) = profile else {
+            return Ok(self.defaults.clone());
+        };
+        match self.profile.get(name) {
+            Some(settings) => Ok(settings.clone().or(self.defaults.clone())),
+            None if self.profile.is_empty() => {
+                Err(format!("no profile `{}`; the config file has none", name))
+            }
+            None => Err(format!(
+                "no profile `{}`; the config file has {}",
+                name,
+                self.profile.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    /// The built-in alphabet called `name`, or one declared in the `[alphabets]` table.
+    pub fn alphabet(&self, name: &str) -> Option<Alphabet> {
+        Alphabet::from_str(name, true).ok().or_else(|| {
+            self.alphabets
+                .get(name)
+                .map(|chars| Alphabet::Custom(chars.clone()))
+        })
+    }
+
+    /// Custom alphabets declared in the `[alphabets]` table.
+    pub fn custom_alphabets(&self) -> impl Iterator<Item = (&str, Alphabet)> {
+        self.alphabets
+            .iter()
+            .map(|(name, chars)| (name.as_str(), Alphabet::Custom(chars.clone())))
+    }
+}
+
+/// Resolves a path given in the config file: `~/` starts from the home directory, and
+/// relative paths from the config file's directory.
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let path = match path.to_str() {
+        Some(path) => expand_home(path),
+        None => path.to_path_buf(),
+    };
+    match config_path().as_deref().and_then(Path::parent) {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+struct Validator<'a> {
+    source: &'a str,
+    base_dir: Option<&'a Path>,
+    alphabets: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn line(&self, span: Range<usize>) -> usize {
+        self.source[..span.start.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+
+    fn report(&mut self, span: Range<usize>, severity: Severity, message: String) {
+        let line = self.line(span);
+        self.diagnostics.push(Diagnostic {
+            line,
+            severity,
+            message,
+        });
+    }
+
+    fn error(&mut self, span: Range<usize>, message: String) {
+        self.report(span, Severity::Error, message);
+    }
+
+    fn root(&mut self, table: &DeTable) {
+        // Alphabets are collected first so settings may refer to them regardless of order
+        if let Some(alphabets) = table.get("alphabets") {
+            self.alphabets(alphabets);
+        }
+        for (key, value) in table.iter() {
+            match key.get_ref().as_ref() {
+                "defaults" => self.settings("defaults", value),
+                "alphabets" => {}
+                "profile" => self.profiles(value),
+                other => self.error(key.span(), format!("unknown top-level key `{}`", other)),
+            }
+        }
+    }
+
+    fn table<'v>(&mut self, name: &str, value: &'v Spanned<DeValue>) -> Option<&'v DeTable<'v>> {
+        match value.get_ref() {
+            DeValue::Table(table) => Some(table),
+            _ => {
+                self.error(value.span(), format!("`{}` must be a table", name));
+                None
+            }
+        }
+    }
+
+    fn alphabets(&mut self, value: &Spanned<DeValue>) {
+        let Some(table) = self.table("alphabets", value) else {
+            return;
+        };
+        for (key, chars) in table.iter() {
+            let name = key.get_ref().to_string();
+            match chars.get_ref() {
+                DeValue::String(s) if !s.is_empty() => {
+                    if Alphabet::from_str(&name, true).is_ok() {
+                        self.error(
+                            key.span(),
+                            format!("alphabet `{}` shadows a built-in alphabet", name),
+                        );
+                    }
+                    self.alphabets.push(name);
+                }
+                DeValue::String(_) => {
+                    self.error(chars.span(), format!("alphabet `{}` is empty", name))
+                }
+                _ => self.error(
+                    chars.span(),
+                    format!("alphabet `{}` must be a string of characters", name),
+                ),
+            }
+        }
+    }
+
+    fn profiles(&mut self, value: &Spanned<DeValue>) {
+        let Some(table) = self.table("profile", value) else {
+            return;
+        };
+        for (key, profile) in table.iter() {
+            self.settings(&format!("profile.{}", key.get_ref()), profile);
+        }
+    }
+
+    fn settings(&mut self, section: &str, value: &Spanned<DeValue>) {
+        let Some(table) = self.table(section, value) else {
+            return;
+        };
+
+        for (key, value) in table.iter() {
+            let span = value.span();
+            match (key.get_ref().as_ref(), value.get_ref()) {
+                ("length", v) => self.integer(section, "length", span, v, 1, MAX_LENGTH),
+                ("words", v) => self.integer(section, "words", span, v, 1, MAX_WORDS),
+                ("count", v) => self.integer(section, "count", span, v, 1, i64::MAX),
+                ("min_entropy", v) => self.min_entropy(section, span, v),
+                ("alphabet", DeValue::String(name)) => {
+                    if Alphabet::from_str(name, true).is_err()
+                        && !self.alphabets.iter().any(|a| a == name.as_ref())
+                    {
+                        self.error(span, format!("{}: unknown alphabet `{}`", section, name));
+                    }
+                }
+                ("custom", DeValue::String(chars)) => {
+                    if chars.is_empty() {
+                        self.error(span, format!("{}: `custom` alphabet is empty", section));
+                    }
+                }
+                ("wordlist", DeValue::String(name)) => {
+                    if WordList::from_str(name, true).is_err() {
+                        self.error(span, format!("{}: unknown wordlist `{}`", section, name));
+                    }
+                }
+                ("wordlist_file", DeValue::String(path)) => {
+                    let path = expand_home(path);
+                    let path = match self.base_dir {
+                        Some(base) if path.is_relative() => base.join(path),
+                        _ => path,
+                    };
+                    if !path.is_file() {
+                        self.error(
+                            span,
+                            format!("{}: wordlist file {} not found", section, path.display()),
+                        );
+                    }
+                }
+                ("separator", DeValue::String(_)) | ("copy", DeValue::Boolean(_)) => {}
+                (
+                    name @ ("alphabet" | "custom" | "wordlist" | "wordlist_file" | "separator"),
+                    _,
+                ) => self.error(span, format!("{}: `{}` must be a string", section, name)),
+                ("copy", _) => {
+                    self.error(span, format!("{}: `copy` must be true or false", section))
+                }
+                (other, _) => {
+                    self.error(key.span(), format!("{}: unknown key `{}`", section, other))
+                }
+            }
+        }
+
+        self.conflicts(section, table);
+    }
+
+    fn integer(
+        &mut self,
+        section: &str,
+        name: &str,
+        span: Range<usize>,
+        value: &DeValue,
+        min: i64,
+        max: i64,
+    ) {
+        let parsed = match value {
+            DeValue::Integer(i) => i64::from_str_radix(i.as_str(), i.radix()).ok(),
+            _ => None,
+        };
+        match parsed {
+            Some(n) if (min..=max).contains(&n) => {}
+            Some(n) => self.error(
+                span,
+                format!(
+                    "{}: `{}` must be between {} and {}, found {}",
+                    section, name, min, max, n
+                ),
+            ),
+            None => self.error(span, format!("{}: `{}` must be an integer", section, name)),
+        }
+    }
+
+    fn min_entropy(&mut self, section: &str, span: Range<usize>, value: &DeValue) {
+        match number(value) {
+            Some(bits) if bits > 0.0 && bits <= MAX_ENTROPY => {}
+            Some(bits) => self.error(
+                span,
+                format!(
+                    "{}: `min_entropy` must be above 0 and at most {} bits, found {}",
+                    section, MAX_ENTROPY, bits
+                ),
+            ),
+            None => self.error(span, format!("{}: `min_entropy` must be a number", section)),
+        }
+    }
+
+    fn conflicts(&mut self, section: &str, table: &DeTable) {
+        if let (Some(_), Some(custom)) = (table.get("alphabet"), table.get("custom")) {
+            self.error(
+                custom.span(),
+                format!("{}: `alphabet` and `custom` cannot both be set", section),
+            );
+        }
+
+        // A minimum entropy the configured password shape can never reach
+        let length = table.get("length").and_then(|v| number(v.get_ref()));
+        let alphabet_bits = match table.get("alphabet").map(|v| v.get_ref()) {
+            Some(DeValue::String(name)) => Alphabet::from_str(name, true)
+                .ok()
+                .map(|a| a.bits_per_char()),
+            None => Some(Alphabet::default().bits_per_char()),
+            _ => None,
+        };
+        if let (Some(min_entropy), Some(length), Some(bits)) =
+            (table.get("min_entropy"), length, alphabet_bits)
+            && let Some(required) = number(min_entropy.get_ref())
+            && length * bits < required
+        {
+            self.report(
+                min_entropy.span(),
+                Severity::Warning,
+                format!(
+                    "{}: `length` {} only reaches {:.1} bits, below `min_entropy` {}",
+                    section,
+                    length,
+                    length * bits,
+                    required
+                ),
+            );
+        }
+    }
+}
+
+fn number(value: &DeValue) -> Option<f64> {
+    match value {
+        DeValue::Integer(i) => i64::from_str_radix(i.as_str(), i.radix())
+            .ok()
+            .map(|n| n as f64),
+        DeValue::Float(f) => f.as_str().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Checks a config file's contents, reporting every problem with its line number.
+/// Relative `wordlist_file` paths are resolved against `base_dir`.
+pub fn validate(source: &str, base_dir: Option<&Path>) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        source,
+        base_dir,
+        alphabets: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    match DeTable::parse(source) {
+        Ok(table) => validator.root(table.get_ref()),
+        Err(e) => validator.error(e.span().unwrap_or(0..0), e.message().to_string()),
+    }
+
+    validator.diagnostics.sort_by_key(|d| d.line);
+    validator.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        validate(source, None)
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let source = r#"
+[defaults]
+length = 16
+alphabet = "full"
+words = 4
+wordlist = "eff-short1"
+separator = "."
+copy = true
+
+[alphabets]
+hex = "0123456789abcdef"
+
+[profile.banking]
+length = 24
+min_entropy = 100
+
+[profile.pin]
+alphabet = "hex"
+"#;
+        assert!(messages(source).is_empty(), "{:?}", messages(source));
+
+        let config = Config::parse(source).unwrap();
+        assert_eq!(config.defaults.length, Some(16));
+        assert_eq!(config.profile["banking"].length, Some(24));
+        assert_eq!(config.alphabets["hex"], "0123456789abcdef");
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = "[defaults]\nlenght = 16\n\n[colors]\nred = 1\n";
+        assert_eq!(
+            messages(source),
+            vec![
+                "line 2: error: defaults: unknown key `lenght`",
+                "line 4: error: unknown top-level key `colors`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let source = "[defaults]\nlength = 0\nwords = \"four\"\nmin_entropy = -5\nalphabet = \"greek\"\nwordlist = \"klingon\"\n";
+        assert_eq!(
+            messages(source),
+            vec![
+                "line 2: error: defaults: `length` must be between 1 and 4096, found 0",
+                "line 3: error: defaults: `words` must be an integer",
+                "line 4: error: defaults: `min_entropy` must be above 0 and at most 1024 bits, found -5",
+                "line 5: error: defaults: unknown alphabet `greek`",
+                "line 6: error: defaults: unknown wordlist `klingon`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_wordlist_file() {
+        let source = "[defaults]\nwordlist_file = \"/nonexistent/words.txt\"\n";
+        assert_eq!(
+            messages(source),
+            vec!["line 2: error: defaults: wordlist file /nonexistent/words.txt not found"]
+        );
+    }
+
+    #[test]
+    fn test_conflicting_profile() {
+        let source = "[profile.work]\nalphabet = \"digits\"\ncustom = \"abc\"\nlength = 4\nmin_entropy = 40\n";
+        assert_eq!(
+            messages(source),
+            vec![
+                "line 3: error: profile.work: `alphabet` and `custom` cannot both be set",
+                "line 5: warning: profile.work: `length` 4 only reaches 13.3 bits, below `min_entropy` 40",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let diagnostics = validate("[defaults]\nlength = \n", None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_alphabet_shadowing_builtin() {
+        let source = "[alphabets]\ndigits = \"01\"\n";
+        assert_eq!(
+            messages(source),
+            vec!["line 2: error: alphabet `digits` shadows a built-in alphabet"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_profile() {
+        let config = Config::parse(
+            r#"
+[defaults]
+length = 16
+alphabet = "full"
+separator = "."
+
+[alphabets]
+hex = "0123456789abcdef"
+
+[profile.banking]
+length = 24
+custom = "abc123"
+
+[profile.pin]
+alphabet = "hex"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.resolve(None).unwrap(), config.defaults);
+
+        let banking = config.resolve(Some("banking")).unwrap();
+        assert_eq!(banking.length, Some(24));
+        assert_eq!(banking.separator.as_deref(), Some("."));
+        // The profile's custom alphabet replaces the default named one
+        assert_eq!(banking.alphabet, None);
+        assert_eq!(banking.custom.as_deref(), Some("abc123"));
+
+        let pin = config.resolve(Some("pin")).unwrap();
+        assert_eq!(pin.length, Some(16));
+        assert!(matches!(
+            config.alphabet(pin.alphabet.as_deref().unwrap()),
+            Some(Alphabet::Custom(chars)) if chars == "0123456789abcdef"
+        ));
+        assert!(matches!(config.alphabet("digits"), Some(Alphabet::Digits)));
+        assert!(config.alphabet("octal").is_none());
+
+        assert_eq!(
+            config.resolve(Some("work")).unwrap_err(),
+            "no profile `work`; the config file has banking, pin"
+        );
+        assert!(Config::default().resolve(Some("work")).is_err());
+    }
+
+    #[test]
+    fn test_parse_checks_limits() {
+        let error = Config::parse("[defaults]\nlength = 100000000\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "defaults: `length` 100000000 is not in 1..=4096"
+        );
+        let error = Config::parse("[profile.pin]\nwords = 0\n").unwrap_err();
+        assert_eq!(error.to_string(), "profile.pin: `words` 0 is not in 1..=64");
+        assert!(Config::parse("[defaults]\nlength = 4096\ncount = 3\n").is_ok());
+        assert!(Config::parse("[profile.work]\nlenght = 24\n").is_err());
+        assert!(Config::parse("[colors]\nred = 1\n").is_err());
+    }
+}
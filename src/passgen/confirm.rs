@@ -0,0 +1,89 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// How writes to files and external stores are gated: dry-run, prompted, or pre-approved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteMode {
+    pub dry_run: bool,
+    pub assume_yes: bool,
+}
+
+impl WriteMode {
+    pub fn new(dry_run: bool, assume_yes: bool) -> Self {
+        Self {
+            dry_run,
+            assume_yes,
+        }
+    }
+
+    /// Announces a pending write (e.g. "overwrite pass entry web/github.com") and returns whether
+    /// it should go ahead. Prompts on a terminal; refuses without `--yes` otherwise.
+    pub fn approve(&self, action: &str) -> io::Result<bool> {
+        if !self.dry_run && !self.assume_yes && !io::stdin().is_terminal() {
+            return Err(io::Error::other(format!(
+                "Refusing to {} without confirmation; pass --yes to proceed",
+                action
+            )));
+        }
+        self.approve_with(action, &mut io::stdin().lock(), &mut io::stderr())
+    }
+
+    fn approve_with(
+        &self,
+        action: &str,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> io::Result<bool> {
+        if self.dry_run {
+            writeln!(output, "Dry run: would {}", action)?;
+            return Ok(false);
+        }
+        if self.assume_yes {
+            return Ok(true);
+        }
+
+        write!(output, "About to {}. Continue? [y/N] ", action)?;
+        output.flush()?;
+        let mut answer = String::new();
+        input.read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approve(mode: WriteMode, input: &str) -> (bool, String) {
+        let mut output = Vec::new();
+        let approved = mode
+            .approve_with("write file", &mut input.as_bytes(), &mut output)
+            .unwrap();
+        (approved, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_dry_run_never_approves() {
+        let (approved, output) = approve(WriteMode::new(true, true), "y\n");
+        assert!(!approved);
+        assert_eq!(output, "Dry run: would write file\n");
+    }
+
+    #[test]
+    fn test_assume_yes_skips_prompt() {
+        let (approved, output) = approve(WriteMode::new(false, true), "");
+        assert!(approved);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_answers() {
+        assert!(approve(WriteMode::default(), "y\n").0);
+        assert!(approve(WriteMode::default(), "YES\n").0);
+        assert!(!approve(WriteMode::default(), "n\n").0);
+        assert!(!approve(WriteMode::default(), "\n").0);
+        assert!(!approve(WriteMode::default(), "").0);
+
+        let (_, output) = approve(WriteMode::default(), "n\n");
+        assert_eq!(output, "About to write file. Continue? [y/N] ");
+    }
+}
@@ -0,0 +1,237 @@
+use crate::passgen::commonwords::CommonWords;
+use crate::passgen::metrics::Metrics;
+use crate::passgen::serve;
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::fs::{self, DirBuilder};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+// Frames larger than this are refused rather than allocated
+const MAX_FRAME: u32 = 64 * 1024;
+
+/// Default socket location, e.g. `$XDG_RUNTIME_DIR/passgen/daemon.sock`.
+pub fn default_socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("passgen").join("daemon.sock"))
+}
+
+/// Reads one frame: a big-endian `u32` length followed by that many bytes of JSON.
+/// Returns `None` when the peer closed the connection between frames.
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(header);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "frame of {} bytes exceeds the {} byte limit",
+                len, MAX_FRAME
+            ),
+        ));
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Answers a request like `{"method": "generate", "params": {"length": 20}}`.
+pub fn handle_request(payload: &[u8], metrics: &Metrics) -> Value {
+    let request: Value = match serde_json::from_slice(payload) {
+        Ok(request) => request,
+        Err(e) => return json!({ "error": format!("invalid request: {}", e) }),
+    };
+    let params = match &request["params"] {
+        Value::Null => json!({}),
+        params => params.clone(),
+    };
+    let started = Instant::now();
+    let method = request["method"].as_str();
+    let result = match method {
+        Some(method) => serve::call(method, params),
+        None => Err("missing method".to_string()),
+    };
+    metrics.observe(
+        "daemon",
+        method.unwrap_or_default(),
+        result.as_ref().ok(),
+        started.elapsed(),
+    );
+    match result {
+        Ok(result) => json!({ "result": result }),
+        Err(message) => json!({ "error": message }),
+    }
+}
+
+fn handle_connection(mut stream: UnixStream, metrics: &Metrics) -> io::Result<()> {
+    while let Some(payload) = read_frame(&mut stream)? {
+        let reply = handle_request(&payload, metrics);
+        debug!(error = reply.get("error").is_some(), "daemon request");
+        write_frame(&mut stream, reply.to_string().as_bytes())?;
+    }
+    Ok(())
+}
+
+// Binds inside a directory only the current user can enter, so the socket is never
+// reachable by others even before its own permissions are tightened.
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    if path.exists() {
+        // A socket nobody answers on is left over from a previous run
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", path.display()),
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Serves requests on the Unix socket at `path` until the process is stopped.
+pub fn run(path: &Path, metrics: Arc<Metrics>) -> io::Result<()> {
+    let listener = bind(path)?;
+
+    // Load the embedded lists up front so no request pays for parsing them
+    for wordlist in WordList::value_variants() {
+        wordlist.words();
+    }
+    CommonWords::All.words();
+    info!(socket = %path.display(), "daemon listening");
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let metrics = Arc::clone(&metrics);
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &metrics) {
+                        warn!("connection failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("failed to accept connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(stream: &mut UnixStream, request: Value) -> Value {
+        write_frame(stream, request.to_string().as_bytes()).unwrap();
+        let reply = read_frame(stream).unwrap().unwrap();
+        serde_json::from_slice(&reply).unwrap()
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"{}").unwrap();
+        assert_eq!(buffer, b"\0\0\0\x02{}");
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(b"{}".to_vec()));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_oversized_frame_is_refused() {
+        let mut reader: &[u8] = &(MAX_FRAME + 1).to_be_bytes();
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let metrics = Metrics::default();
+        let reply = handle_request(
+            br#"{"method": "generate", "params": {"length": 30}}"#,
+            &metrics,
+        );
+        assert_eq!(reply["result"]["passwords"][0].as_str().unwrap().len(), 30);
+
+        let reply = handle_request(
+            br#"{"method": "check", "params": {"password": "password"}}"#,
+            &metrics,
+        );
+        assert_eq!(reply["result"]["safe"], false);
+
+        assert!(handle_request(br#"{"method": "nope"}"#, &metrics)["error"].is_string());
+        assert!(handle_request(b"not json", &metrics)["error"].is_string());
+
+        let text = metrics.render();
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"daemon\",method=\"generate\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"daemon\",method=\"unknown\",outcome=\"error\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_connection_serves_several_requests() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let handle = std::thread::spawn(move || handle_connection(server, &Metrics::default()));
+
+        let reply = request(&mut client, json!({"method": "passphrase"}));
+        assert_eq!(
+            reply["result"]["passphrases"][0]
+                .as_str()
+                .unwrap()
+                .split('-')
+                .count(),
+            3
+        );
+        let reply = request(&mut client, json!({"method": "generate"}));
+        assert!(reply["result"]["passwords"].is_array());
+
+        drop(client);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_socket_is_user_only() {
+        let dir = std::env::temp_dir().join(format!("passgen-daemon-test-{}", std::process::id()));
+        let path = dir.join("sub").join("daemon.sock");
+
+        let listener = bind(&path).unwrap();
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(path.parent().unwrap()), 0o700);
+
+        // A live daemon is not replaced, a stale socket is
+        assert!(bind(&path).is_err());
+        drop(listener);
+        assert!(bind(&path).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::commonwords::CommonWords;
+use clap::ValueEnum;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Serialize;
+
+// The lists are sorted by frequency, and decoys should be the obvious guesses
+const TOP_NAMES: usize = 300;
+const TOP_WORDS: usize = 3000;
+// Birth years and recent years, as people put them in passwords
+const YEARS: std::ops::RangeInclusive<u32> = 1960..=2025;
+
+const KEYBOARD_WALKS: &[&str] = &[
+    "qwerty",
+    "qwertyuiop",
+    "qwer1234",
+    "asdf",
+    "asdfgh",
+    "asdfghjkl",
+    "zxcvbnm",
+    "qazwsx",
+    "1qaz2wsx",
+    "zaq12wsx",
+    "1q2w3e4r",
+    "1q2w3e",
+    "q1w2e3r4",
+    "123qwe",
+    "azerty",
+    "qwertz",
+];
+const SUFFIXES: &[&str] = &["", "1", "12", "123", "1234", "!", "!!", "01", "007", "69"];
+
+/// How decoy passwords are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecoyStyle {
+    /// Names, words and years the way people combine them, e.g. Jessica1987 or summer2024!
+    #[default]
+    Human,
+    /// Keyboard walks with common suffixes, e.g. qwerty123 or 1qaz2wsx
+    Keyboard,
+    /// Entries of the common password list, e.g. letmein
+    Leaked,
+    /// The length and character classes of a given password, see [`resembling`]
+    #[value(skip)]
+    Resembling,
+}
+
+/// A deliberately weak but plausible password, for honeypot accounts and canary
+/// credentials that attackers should find and try.
+pub fn generate<R: Rng + ?Sized>(style: DecoyStyle, rng: &mut R) -> String {
+    match style {
+        DecoyStyle::Human => human(rng),
+        DecoyStyle::Keyboard => format!(
+            "{}{}",
+            KEYBOARD_WALKS.choose(rng).expect("walks are not empty"),
+            SUFFIXES.choose(rng).expect("suffixes are not empty")
+        ),
+        DecoyStyle::Leaked => CommonWords::Passwords
+            .words()
+            .choose(rng)
+            .unwrap_or("password")
+            .to_string(),
+        DecoyStyle::Resembling => unreachable!("resembling decoys need a password"),
+    }
+}
+
+/// A decoy of the same length as `password` with a character of the same class in each
+/// place, e.g. `Wbk5#tqm` for `Pass1!ab`, so a honeytoken passes for the real one.
+/// Characters outside printable ASCII are kept. Never gives `password` itself, and fails
+/// when there is nothing to change.
+pub fn resembling<R: Rng + ?Sized>(password: &str, rng: &mut R) -> Result<String, String> {
+    if !password.chars().any(|c| Alphabet::class_of(c).is_some()) {
+        return Err("the password has no ASCII letters, digits or symbols to vary".to_string());
+    }
+    loop {
+        let decoy: String = password
+            .chars()
+            .map(|c| match Alphabet::class_of(c) {
+                Some(class) => {
+                    let class = class.as_bytes();
+                    class[rng.random_range(0..class.len())] as char
+                }
+                None => c,
+            })
+            .collect();
+        if decoy != password {
+            return Ok(decoy);
+        }
+    }
+}
+
+fn human<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let year = rng.random_range(YEARS);
+    match rng.random_range(0..5) {
+        0 => format!("{}{}", capitalize(&name(rng)), year),
+        1 => format!("{}{:02}", name(rng), year % 100),
+        2 => format!("{}{}", word(rng), rng.random_range(1..100)),
+        3 => format!("{}{}!", capitalize(&word(rng)), year),
+        // A bare name is rarely accepted, so always add a suffix
+        _ => format!(
+            "{}{}",
+            name(rng),
+            SUFFIXES[1..].choose(rng).expect("suffixes are not empty")
+        ),
+    }
+}
+
+fn name<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let list = if rng.random_bool(0.5) {
+        CommonWords::MaleNames
+    } else {
+        CommonWords::FemaleNames
+    };
+    common(&list, TOP_NAMES, rng)
+}
+
+fn word<R: Rng + ?Sized>(rng: &mut R) -> String {
+    // The English list also ranks numbers and fragments; keep plain words
+    loop {
+        let word = common(&CommonWords::English, TOP_WORDS, rng);
+        if word.len() >= 4 && word.bytes().all(|b| b.is_ascii_lowercase()) {
+            return word;
+        }
+    }
+}
+
+// One of the `top` most frequent entries of a built-in list
+fn common<R: Rng + ?Sized>(list: &CommonWords, top: usize, rng: &mut R) -> String {
+    let words = list.words();
+    let top = words.len().min(top);
+    words.get(rng.random_range(0..top)).to_string()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_human() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        for _ in 0..200 {
+            let decoy = generate(DecoyStyle::Human, &mut rng);
+            assert!(decoy.len() >= 4, "{}", decoy);
+            assert!(
+                decoy.starts_with(|c: char| c.is_ascii_alphabetic()),
+                "{}",
+                decoy
+            );
+            assert!(decoy.is_ascii(), "{}", decoy);
+        }
+    }
+
+    #[test]
+    fn test_keyboard() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        for _ in 0..50 {
+            let decoy = generate(DecoyStyle::Keyboard, &mut rng);
+            assert!(
+                KEYBOARD_WALKS.iter().any(|walk| decoy.starts_with(walk)),
+                "{}",
+                decoy
+            );
+        }
+    }
+
+    #[test]
+    fn test_leaked_are_common_passwords() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        let common = CommonWords::Passwords;
+        let words: Vec<&str> = common.words().iter().collect();
+        for _ in 0..20 {
+            let decoy = generate(DecoyStyle::Leaked, &mut rng);
+            assert!(words.contains(&decoy.as_str()), "{}", decoy);
+        }
+    }
+
+    #[test]
+    fn test_resembling() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        let password = "Pass1!ab émigré";
+        for _ in 0..100 {
+            let decoy = resembling(password, &mut rng).unwrap();
+            assert_ne!(decoy, password);
+            assert_eq!(decoy.chars().count(), password.chars().count());
+            for (d, p) in decoy.chars().zip(password.chars()) {
+                assert_eq!(Alphabet::class_of(d), Alphabet::class_of(p), "{}", decoy);
+                if Alphabet::class_of(p).is_none() {
+                    assert_eq!(d, p);
+                }
+            }
+        }
+        // Only one other choice, which it must find
+        assert_eq!(resembling("7", &mut rng).map(|d| d != "7"), Ok(true));
+        assert!(resembling("", &mut rng).is_err());
+        assert!(resembling("密码", &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_capitalize() {
+        assert_eq!(capitalize("jessica"), "Jessica");
+        assert_eq!(capitalize(""), "");
+    }
+}
@@ -0,0 +1,183 @@
+use crate::passgen::commonwords::CommonWords;
+use crate::passgen::hash::sha256_hex;
+use crate::passgen::update::{find_checksum, verify_checksum};
+use anyhow::Context;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// SecLists' top million passwords from its 10 million password list.
+pub const SECLISTS_TOP_1M: &str = "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Passwords/Common-Credentials/10-million-password-list-top-1000000.txt";
+
+// Overrides the platform data location, mainly for tests and portable installs
+const DATA_DIR_ENV: &str = "PASSGEN_DATA_DIR";
+const PASSWORDS_FILE: &str = "common-passwords.txt";
+// The top million list is about 8 MiB; anything far larger is not a password list
+const MAX_DICT_SIZE: u64 = 64 * 1024 * 1024;
+// Very short entries would make almost any password look like a combination of them
+const MIN_WORD_LENGTH: usize = 4;
+
+/// How the downloaded list is verified.
+pub enum Checksum {
+    /// The expected SHA-256 digest in hex
+    Digest(String),
+    /// URL of a `sha256sum`-style listing naming the list's file
+    Listing(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct DictUpdate {
+    pub path: PathBuf,
+    pub words: usize,
+    pub sha256: String,
+}
+
+impl fmt::Display for DictUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Saved {} common passwords to {}",
+            self.words,
+            self.path.display()
+        )
+    }
+}
+
+/// Directory holding downloaded data, e.g. `~/.local/share/passgen` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::data_dir().map(|dir| dir.join("passgen"))
+}
+
+/// Where `dict update` stores the common password list that `check` prefers.
+pub fn passwords_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(PASSWORDS_FILE))
+}
+
+fn get(url: &str) -> anyhow::Result<ureq::http::Response<ureq::Body>> {
+    if !url.starts_with("https://") {
+        anyhow::bail!(
+            "Refusing to download {} over an unencrypted connection",
+            url
+        );
+    }
+    ureq::get(url)
+        .header("User-Agent", concat!("passgen/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .with_context(|| format!("Request to {} failed", url))
+}
+
+fn expected_digest(url: &str, checksum: &Checksum) -> anyhow::Result<String> {
+    match checksum {
+        Checksum::Digest(digest) => Ok(digest.clone()),
+        Checksum::Listing(listing_url) => {
+            let listing = get(listing_url)?.body_mut().read_to_string()?;
+            let name = url.rsplit('/').next().unwrap_or(url);
+            find_checksum(&listing, name)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("{} does not list {}", listing_url, name))
+        }
+    }
+}
+
+/// Downloads a password list, verifies it, and atomically replaces the file at `path`.
+pub fn update(url: &str, checksum: &Checksum, path: &Path) -> anyhow::Result<DictUpdate> {
+    let expected = expected_digest(url, checksum)?;
+    let data = get(url)?
+        .body_mut()
+        .with_config()
+        .limit(MAX_DICT_SIZE)
+        .read_to_vec()
+        .with_context(|| format!("Cannot download {}", url))?;
+    verify_checksum(&data, &expected)?;
+
+    let text = String::from_utf8_lossy(&data);
+    let words = parse(&text).count();
+    if words == 0 {
+        anyhow::bail!("{} contains no usable passwords", url);
+    }
+
+    let dir = path
+        .parent()
+        .context("The password list path has no parent directory")?;
+    fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    // Stage next to the target so a failed write never leaves a truncated list behind
+    let staged = path.with_extension("txt.new");
+    fs::write(&staged, &data).with_context(|| format!("Cannot write {}", staged.display()))?;
+    fs::rename(&staged, path)?;
+
+    Ok(DictUpdate {
+        path: path.to_path_buf(),
+        words,
+        sha256: sha256_hex(&data),
+    })
+}
+
+fn parse(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|word| word.chars().count() >= MIN_WORD_LENGTH)
+}
+
+/// Puts the common password list `dict update` stored, if any, in place of the embedded
+/// one in `lists`, such as [`checker::SAFETY_CHECKS`].
+///
+/// [`checker::SAFETY_CHECKS`]: crate::passgen::checker::SAFETY_CHECKS
+pub fn prefer_downloaded(lists: &mut [(CommonWords, &'static str)]) {
+    let Some(downloaded) = load_passwords() else {
+        return;
+    };
+    let downloaded = CommonWords::Custom(downloaded.lines().map(String::from).collect());
+    for (words, _) in lists {
+        if matches!(words, CommonWords::Passwords) {
+            *words = downloaded.clone();
+        }
+    }
+}
+
+/// The downloaded common password list, lowercased one per line, if `dict update` has
+/// stored one.
+pub fn load_passwords() -> Option<String> {
+    let path = passwords_path()?;
+    if !path.exists() {
+        return None;
+    }
+    match fs::read(&path) {
+        Ok(data) => {
+            let text = String::from_utf8_lossy(&data).to_lowercase();
+            Some(parse(&text).collect::<Vec<_>>().join("\n"))
+        }
+        Err(e) => {
+            warn!(
+                "Cannot read {}, using the embedded list: {}",
+                path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_short_entries() {
+        let words: Vec<&str> = parse("123456\r\n  password \n\nabc\nqwerty\n").collect();
+        assert_eq!(words, vec!["123456", "password", "qwerty"]);
+    }
+
+    #[test]
+    fn test_only_https() {
+        let checksum = Checksum::Digest("00".to_string());
+        let path = std::env::temp_dir().join("passgen-dict-test.txt");
+        let error = update("http://example.com/list.txt", &checksum, &path).unwrap_err();
+        assert!(error.to_string().contains("unencrypted"));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,338 @@
+use crate::passgen::cache;
+use crate::passgen::clipboard;
+use crate::passgen::commonwords::CommonWords;
+use crate::passgen::config::{self, Severity};
+use crate::passgen::hash::sha256_hex;
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use rand::TryRngCore;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        write!(f, "{:<5} {}: {}", status, self.name, self.detail)
+    }
+}
+
+// Expected word counts and SHA-256 digests of the embedded resources
+const EMBEDDED_WORDLISTS: &[(WordList, usize, &str)] = &[
+    (
+        WordList::EffLarge,
+        7776,
+        "addd35536511597a02fa0a9ff1e5284677b8883b83e986e43f15a3db996b903e",
+    ),
+    (
+        WordList::EffShort1,
+        1296,
+        "8f5ca830b8bffb6fe39c9736c024a00a6a6411adb3f83a9be8bfeeb6e067ae69",
+    ),
+    (
+        WordList::EffShort2,
+        1296,
+        "22b45c52e0bd0bbf03aa522240b111eb4c7c0c1d86c4e518e1be2a7eb2a625e4",
+    ),
+    (
+        WordList::DeDiceware,
+        1296,
+        "2c70948bf2222cc97dd3e5de0e42a3395a5d1a348788858bab36069a4a7b16c7",
+    ),
+    (
+        WordList::EsDiceware,
+        1296,
+        "2c0656f4b6a793a612c9978b3679e3b1d75bbb2c9edc3b6c9a672368b16c4ffd",
+    ),
+    (
+        WordList::FrDiceware,
+        1296,
+        "853c252327aa5d9adcffe7855d9230843f01873450b89896b001d74bb7370134",
+    ),
+    (
+        WordList::ItDiceware,
+        1296,
+        "d0ab5f10171051457b8a5141a56c57b6e87c1852873aff729d09dfaf97c9bb14",
+    ),
+];
+
+const EMBEDDED_COMMON_WORDS: &[(CommonWords, &str, &str)] = &[
+    (
+        CommonWords::Passwords,
+        "passwords",
+        "ca95cf2ddfa58f476cb1e39224c72eb304119997062eb8f84774b4819371ecfc",
+    ),
+    (
+        CommonWords::English,
+        "english",
+        "77af32bdfdb5953f07652ab6ba81205dda9eb3f4790ae0034c2be79b913e63db",
+    ),
+    (
+        CommonWords::German,
+        "german",
+        "0af5e60d34a1b3e596a6be7cc323b85db6df22021bfb9839670e60659c25b3cd",
+    ),
+    (
+        CommonWords::French,
+        "french",
+        "2869ce64114587f0f71ca92b99d965f9ce1d8eb938ce65c6d226c0812a870df2",
+    ),
+    (
+        CommonWords::Spanish,
+        "spanish",
+        "8646afefc33d521ee277175239b885553f5bda2b7b360e0742a160ebaea154ba",
+    ),
+    (
+        CommonWords::MaleNames,
+        "male-names",
+        "8a4d5904f8d7024ad84a34e70e2ca5689e6b4cfef8602d7fba83aaccee0fc2fb",
+    ),
+    (
+        CommonWords::FemaleNames,
+        "female-names",
+        "03918cf774c45b338d3feda5111eb2dedeec21a94ace24ea39d6635b3d7920d2",
+    ),
+    (
+        CommonWords::LastNames,
+        "last-names",
+        "6a7f3df8c4d6e9232038b2220c0f63edf126c62dcb4be42a9a4351d2d789dddc",
+    ),
+];
+
+/// Runs every health check and returns the results in display order.
+pub fn run_checks() -> Vec<Check> {
+    let mut checks = vec![check_entropy_source()];
+    for (wordlist, count, digest) in EMBEDDED_WORDLISTS {
+        checks.push(check_wordlist(wordlist, *count, digest));
+    }
+    for (common_words, name, digest) in EMBEDDED_COMMON_WORDS {
+        checks.push(check_common_words(common_words, name, digest));
+    }
+    checks.push(check_config());
+    checks.push(check_cache());
+    checks.push(check_clipboard());
+    checks.push(check_keyring());
+    checks
+}
+
+fn check_config() -> Check {
+    const NAME: &str = "config";
+    let Some(path) = config::config_path() else {
+        return Check::new(NAME, Status::Warn, "no config directory on this platform");
+    };
+    if !path.exists() {
+        return Check::new(NAME, Status::Ok, "no config file, using defaults");
+    }
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => return Check::new(NAME, Status::Fail, format!("{}: {}", path.display(), e)),
+    };
+
+    let diagnostics = config::validate(&source, path.parent());
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let status = match (errors, diagnostics.len()) {
+        (0, 0) => Status::Ok,
+        (0, _) => Status::Warn,
+        _ => Status::Fail,
+    };
+    let detail = if diagnostics.is_empty() {
+        format!("{} is valid", path.display())
+    } else {
+        format!(
+            "{} has {} problems, run `passgen config check`",
+            path.display(),
+            diagnostics.len()
+        )
+    };
+    Check::new(NAME, status, detail)
+}
+
+fn check_clipboard() -> Check {
+    const NAME: &str = "clipboard";
+    match clipboard::backend() {
+        Some(command) => Check::new(NAME, Status::Ok, format!("using {}", command[0])),
+        None => Check::new(
+            NAME,
+            Status::Warn,
+            "no clipboard helper found, --copy unavailable",
+        ),
+    }
+}
+
+fn check_keyring() -> Check {
+    const NAME: &str = "keyring";
+    // Looking up an entry that is never written reaches the platform store without
+    // changing it
+    let entry = match keyring::Entry::new("passgen", "doctor") {
+        Ok(entry) => entry,
+        Err(e) => return Check::new(NAME, Status::Fail, format!("cannot open an entry: {}", e)),
+    };
+    match entry.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => {
+            Check::new(NAME, Status::Ok, "platform credential store available")
+        }
+        Err(e) => Check::new(
+            NAME,
+            Status::Warn,
+            format!("{}, keyring: stores unavailable", e),
+        ),
+    }
+}
+
+fn check_cache() -> Check {
+    const NAME: &str = "cache";
+    let Some(dir) = cache::cache_dir() else {
+        return Check::new(NAME, Status::Fail, "no cache directory for this platform");
+    };
+
+    match cache::list_entries(&dir) {
+        Ok(entries) => Check::new(
+            NAME,
+            Status::Ok,
+            format!("{} files in {}", entries.len(), dir.display()),
+        ),
+        Err(e) => Check::new(
+            NAME,
+            Status::Fail,
+            format!("cannot read {}: {}", dir.display(), e),
+        ),
+    }
+}
+
+fn check_entropy_source() -> Check {
+    const NAME: &str = "entropy source";
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+
+    if let Err(e) = OsRng
+        .try_fill_bytes(&mut first)
+        .and_then(|_| OsRng.try_fill_bytes(&mut second))
+    {
+        return Check::new(NAME, Status::Fail, format!("OS RNG unavailable: {}", e));
+    }
+
+    if first == second || first.iter().all(|&b| b == first[0]) {
+        return Check::new(NAME, Status::Fail, "OS RNG returned repeating output");
+    }
+
+    Check::new(NAME, Status::Ok, "OS RNG is readable")
+}
+
+fn check_wordlist(wordlist: &WordList, expected_count: usize, expected_digest: &str) -> Check {
+    let name = format!(
+        "wordlist {}",
+        wordlist
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    );
+    let Some(source) = wordlist.source() else {
+        return Check::new(name, Status::Fail, "no embedded source");
+    };
+
+    let count = wordlist.words().len();
+    check_resource(name, source, count, Some(expected_count), expected_digest)
+}
+
+fn check_common_words(common_words: &CommonWords, name: &str, expected_digest: &str) -> Check {
+    let name = format!("common words {}", name);
+    let Some(source) = common_words.source() else {
+        return Check::new(name, Status::Fail, "no embedded source");
+    };
+
+    let count = common_words.words().len();
+    check_resource(name, source, count, None, expected_digest)
+}
+
+fn check_resource(
+    name: String,
+    source: &str,
+    count: usize,
+    expected_count: Option<usize>,
+    expected_digest: &str,
+) -> Check {
+    if count == 0 {
+        return Check::new(name, Status::Fail, "parsed to zero words");
+    }
+    if let Some(expected) = expected_count
+        && count != expected
+    {
+        return Check::new(
+            name,
+            Status::Fail,
+            format!("parsed {} words, expected {}", count, expected),
+        );
+    }
+    if sha256_hex(source.as_bytes()) != expected_digest {
+        return Check::new(name, Status::Fail, "checksum does not match");
+    }
+    Check::new(
+        name,
+        Status::Ok,
+        format!("{} words, checksum matches", count),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_resources_pass() {
+        let checks = run_checks();
+        assert_eq!(
+            checks.len(),
+            5 + EMBEDDED_WORDLISTS.len() + EMBEDDED_COMMON_WORDS.len()
+        );
+        for check in checks {
+            // The clipboard, keyring and config file depend on the host, not on passgen
+            if !["clipboard", "keyring", "config"].contains(&check.name.as_str()) {
+                assert_eq!(check.status, Status::Ok, "{}: {}", check.name, check.detail);
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_mismatch_fails() {
+        let check = check_resource("test".to_string(), "a\nb", 2, Some(3), "");
+        assert_eq!(check.status, Status::Fail);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_fails() {
+        let check = check_resource("test".to_string(), "a\nb", 2, Some(2), "00");
+        assert_eq!(check.status, Status::Fail);
+    }
+}
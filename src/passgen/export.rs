@@ -0,0 +1,477 @@
+use crate::passgen::hash::sha256_hex;
+use crate::passgen::output::csv_escape;
+use crate::passgen::password::Redacted;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+
+/// Formats batch-generated credentials can be written as: password manager imports and
+/// bulk provisioning input for systems.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// CSV importable into KeePass and KeePassXC
+    KeepassCsv,
+    /// Bitwarden's unencrypted JSON import
+    BitwardenJson,
+    /// CSV importable into 1Password
+    OnepasswordCsv,
+    /// `user:password` lines for chpasswd and cloud-init
+    Chpasswd,
+    /// LDIF modifications replacing each user's hashed userPassword, for ldapmodify
+    Ldif,
+}
+
+/// Settings that only some formats use.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Group, folder, or tag the entries are placed in
+    pub group: String,
+    /// Write SHA-512 crypt hashes instead of plain passwords
+    pub hash: bool,
+    /// LDAP DN of each entry, with `{user}` standing for the login name
+    pub dn_template: Option<String>,
+}
+
+/// One account to create, read from a line of the names file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub title: String,
+    pub username: String,
+    pub url: String,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Credential {
+    pub account: Account,
+    pub password: String,
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credential")
+            .field("account", &self.account)
+            .field("password", &Redacted(&self.password))
+            .finish()
+    }
+}
+
+/// Parses a names file: one title per line, optionally followed by a tab-separated
+/// username and URL. Blank lines and lines starting with `#` are skipped.
+pub fn parse_names(source: &str) -> Vec<Account> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split('\t').map(str::trim);
+            Account {
+                title: fields.next().unwrap_or_default().to_string(),
+                username: fields.next().unwrap_or_default().to_string(),
+                url: fields.next().unwrap_or_default().to_string(),
+            }
+        })
+        .collect()
+}
+
+pub fn render(
+    format: ExportFormat,
+    options: &Options,
+    credentials: &[Credential],
+) -> Result<String, String> {
+    if options.hash && format != ExportFormat::Chpasswd {
+        return Err("hashed passwords are only supported for chpasswd".to_string());
+    }
+    if options.dn_template.is_some() != (format == ExportFormat::Ldif) {
+        return Err("a DN template is required for ldif and only used by it".to_string());
+    }
+    let group = options.group.as_str();
+    match format {
+        ExportFormat::KeepassCsv => Ok(keepass_csv(group, credentials)),
+        ExportFormat::BitwardenJson => Ok(bitwarden_json(group, credentials)),
+        ExportFormat::OnepasswordCsv => Ok(onepassword_csv(group, credentials)),
+        ExportFormat::Chpasswd => chpasswd(credentials, options.hash),
+        ExportFormat::Ldif => ldif(
+            options.dn_template.as_deref().unwrap_or_default(),
+            credentials,
+        ),
+    }
+}
+
+/// The login name for system formats: the username column, or the title when it is empty.
+fn login(account: &Account) -> &str {
+    match account.username.as_str() {
+        "" => &account.title,
+        username => username,
+    }
+}
+
+/// A salted SHA-512 crypt hash (`$6$...`), the default for `/etc/shadow` on Linux.
+pub fn sha512_crypt(password: &str) -> Result<String, String> {
+    pwhash::sha512_crypt::hash(password).map_err(|e| e.to_string())
+}
+
+// One `user:password` line per account, the input of `chpasswd` (`chpasswd -e` when hashed)
+fn chpasswd(credentials: &[Credential], hash: bool) -> Result<String, String> {
+    let mut lines = String::new();
+    for credential in credentials {
+        let user = login(&credential.account);
+        if user.contains([':', '\n']) {
+            return Err(format!("user name `{}` cannot contain `:`", user));
+        }
+        // Passphrase separators could otherwise break the line format
+        if credential.password.contains(['\n', '\r']) {
+            return Err(format!("the password for `{}` contains a line break", user));
+        }
+        let password = if hash {
+            sha512_crypt(&credential.password)?
+        } else {
+            credential.password.clone()
+        };
+        lines.push_str(&format!("{}:{}\n", user, password));
+    }
+    Ok(lines)
+}
+
+// Escapes a value placed in a DN attribute, following RFC 4514
+fn escape_dn_value(value: &str) -> String {
+    let last = value.chars().count().saturating_sub(1);
+    let mut escaped = String::new();
+    for (i, c) in value.chars().enumerate() {
+        let leading = i == 0 && matches!(c, ' ' | '#');
+        let trailing = i == last && c == ' ';
+        if leading || trailing || matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// An `attr: value` line, base64-encoded (`attr:: ...`) when the value is not a safe string
+fn ldif_line(attribute: &str, value: &str) -> String {
+    let safe = value
+        .chars()
+        .all(|c| c.is_ascii() && !matches!(c, '\0' | '\n' | '\r'))
+        && !value.starts_with([' ', ':', '<'])
+        && !value.ends_with(' ');
+    if safe {
+        format!("{}: {}\n", attribute, value)
+    } else {
+        format!("{}:: {}\n", attribute, BASE64.encode(value))
+    }
+}
+
+// One modify record per account replacing its userPassword with a `{CRYPT}` SHA-512 hash,
+// which OpenLDAP and 389 Directory Server verify natively
+fn ldif(dn_template: &str, credentials: &[Credential]) -> Result<String, String> {
+    if !dn_template.contains("{user}") {
+        return Err(format!(
+            "DN template `{}` does not contain `{{user}}`",
+            dn_template
+        ));
+    }
+    let mut records = Vec::new();
+    for credential in credentials {
+        let dn = dn_template.replace("{user}", &escape_dn_value(login(&credential.account)));
+        let hash = sha512_crypt(&credential.password)?;
+        records.push(format!(
+            "{}changetype: modify\nreplace: userPassword\n{}-\n",
+            ldif_line("dn", &dn),
+            ldif_line("userPassword", &format!("{{CRYPT}}{}", hash))
+        ));
+    }
+    Ok(records.join("\n"))
+}
+
+#[derive(Serialize)]
+struct BitwardenExport<'a> {
+    encrypted: bool,
+    folders: Vec<BitwardenFolder<'a>>,
+    items: Vec<BitwardenItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct BitwardenFolder<'a> {
+    id: &'a str,
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem<'a> {
+    folder_id: &'a str,
+    // 1 is a login item
+    #[serde(rename = "type")]
+    kind: u8,
+    name: &'a str,
+    favorite: bool,
+    login: BitwardenLogin<'a>,
+}
+
+#[derive(Serialize)]
+struct BitwardenLogin<'a> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    uris: Vec<BitwardenUri<'a>>,
+    username: Option<&'a str>,
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct BitwardenUri<'a> {
+    uri: &'a str,
+}
+
+// Folder ids only link items to folders within the file; derive one from the name
+fn folder_id(name: &str) -> String {
+    let hex = sha256_hex(name.as_bytes());
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    (!s.is_empty()).then_some(s)
+}
+
+fn bitwarden_json(group: &str, credentials: &[Credential]) -> String {
+    let id = folder_id(group);
+    let export = BitwardenExport {
+        encrypted: false,
+        folders: vec![BitwardenFolder {
+            id: &id,
+            name: group,
+        }],
+        items: credentials
+            .iter()
+            .map(|credential| BitwardenItem {
+                folder_id: &id,
+                kind: 1,
+                name: &credential.account.title,
+                favorite: false,
+                login: BitwardenLogin {
+                    uris: non_empty(&credential.account.url)
+                        .map(|uri| BitwardenUri { uri })
+                        .into_iter()
+                        .collect(),
+                    username: non_empty(&credential.account.username),
+                    password: &credential.password,
+                },
+            })
+            .collect(),
+    };
+    let mut json = serde_json::to_string_pretty(&export).expect("export serializes");
+    json.push('\n');
+    json
+}
+
+fn csv_document<'a>(header: &str, rows: impl Iterator<Item = [&'a str; 6]>) -> String {
+    let mut csv = format!("{}\n", header);
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| csv_escape(cell)).collect();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// Same column layout KeePassXC exports, so its CSV import maps the columns automatically
+fn keepass_csv(group: &str, credentials: &[Credential]) -> String {
+    csv_document(
+        "Group,Title,Username,Password,URL,Notes",
+        credentials.iter().map(|credential| {
+            [
+                group,
+                &credential.account.title,
+                &credential.account.username,
+                &credential.password,
+                &credential.account.url,
+                "",
+            ]
+        }),
+    )
+}
+
+// Column names 1Password's CSV import recognises; the group becomes a tag
+fn onepassword_csv(group: &str, credentials: &[Credential]) -> String {
+    csv_document(
+        "Title,Website,Username,Password,Notes,Tags",
+        credentials.iter().map(|credential| {
+            [
+                &credential.account.title,
+                &credential.account.url,
+                &credential.account.username,
+                &credential.password,
+                "",
+                group,
+            ]
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> Vec<Credential> {
+        parse_names("# accounts\nGitHub\tdewaka\thttps://github.com\n\nBank, Inc.\n")
+            .into_iter()
+            .zip(["s3cr\"t", "pa,ss"])
+            .map(|(account, password)| Credential {
+                account,
+                password: password.to_string(),
+            })
+            .collect()
+    }
+
+    fn options() -> Options {
+        Options {
+            group: "PassGen".to_string(),
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_names() {
+        let accounts = parse_names("# comment\n\nGitHub\tdewaka\thttps://github.com\nBank\n");
+        assert_eq!(
+            accounts,
+            vec![
+                Account {
+                    title: "GitHub".to_string(),
+                    username: "dewaka".to_string(),
+                    url: "https://github.com".to_string(),
+                },
+                Account {
+                    title: "Bank".to_string(),
+                    username: String::new(),
+                    url: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keepass_csv() {
+        assert_eq!(
+            render(ExportFormat::KeepassCsv, &options(), &credentials()).unwrap(),
+            "Group,Title,Username,Password,URL,Notes\n\
+             PassGen,GitHub,dewaka,\"s3cr\"\"t\",https://github.com,\n\
+             PassGen,\"Bank, Inc.\",,\"pa,ss\",,\n"
+        );
+    }
+
+    #[test]
+    fn test_onepassword_csv() {
+        assert_eq!(
+            render(ExportFormat::OnepasswordCsv, &options(), &credentials()).unwrap(),
+            "Title,Website,Username,Password,Notes,Tags\n\
+             GitHub,https://github.com,dewaka,\"s3cr\"\"t\",,PassGen\n\
+             \"Bank, Inc.\",,,\"pa,ss\",,PassGen\n"
+        );
+    }
+
+    #[test]
+    fn test_bitwarden_json() {
+        let json = render(ExportFormat::BitwardenJson, &options(), &credentials()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["encrypted"], false);
+        let folder_id = &value["folders"][0]["id"];
+        assert_eq!(value["folders"][0]["name"], "PassGen");
+
+        let items = value["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["type"], 1);
+        assert_eq!(&items[0]["folderId"], folder_id);
+        assert_eq!(items[0]["name"], "GitHub");
+        assert_eq!(items[0]["login"]["username"], "dewaka");
+        assert_eq!(items[0]["login"]["password"], "s3cr\"t");
+        assert_eq!(items[0]["login"]["uris"][0]["uri"], "https://github.com");
+
+        assert_eq!(items[1]["name"], "Bank, Inc.");
+        assert!(items[1]["login"]["username"].is_null());
+        assert!(items[1]["login"].get("uris").is_none());
+    }
+
+    #[test]
+    fn test_folder_id_is_stable() {
+        assert_eq!(folder_id("Work"), folder_id("Work"));
+        assert_ne!(folder_id("Work"), folder_id("Home"));
+        assert_eq!(folder_id("Work").len(), 36);
+    }
+
+    #[test]
+    fn test_chpasswd() {
+        let credentials = credentials();
+        assert_eq!(
+            render(ExportFormat::Chpasswd, &options(), &credentials).unwrap(),
+            "dewaka:s3cr\"t\nBank, Inc.:pa,ss\n"
+        );
+
+        let hashed = Options {
+            hash: true,
+            ..options()
+        };
+        let lines = render(ExportFormat::Chpasswd, &hashed, &credentials).unwrap();
+        let (user, hash) = lines.lines().next().unwrap().split_once(':').unwrap();
+        assert_eq!(user, "dewaka");
+        assert!(hash.starts_with("$6$"));
+        assert!(pwhash::sha512_crypt::verify("s3cr\"t", hash));
+
+        assert!(render(ExportFormat::KeepassCsv, &hashed, &credentials).is_err());
+    }
+
+    #[test]
+    fn test_chpasswd_rejects_unsafe_users() {
+        let credentials = vec![Credential {
+            account: parse_names("root:x\n").remove(0),
+            password: "pw".to_string(),
+        }];
+        assert!(render(ExportFormat::Chpasswd, &options(), &credentials).is_err());
+    }
+
+    #[test]
+    fn test_ldif() {
+        let options = Options {
+            dn_template: Some("uid={user},ou=people,dc=example,dc=com".to_string()),
+            ..options()
+        };
+        let ldif = render(ExportFormat::Ldif, &options, &credentials()).unwrap();
+        let records: Vec<&str> = ldif.split("\n\n").collect();
+        assert_eq!(records.len(), 2);
+
+        let lines: Vec<&str> = records[0].lines().collect();
+        assert_eq!(lines[0], "dn: uid=dewaka,ou=people,dc=example,dc=com");
+        assert_eq!(lines[1..3], ["changetype: modify", "replace: userPassword"]);
+        let hash = lines[3].strip_prefix("userPassword: {CRYPT}").unwrap();
+        assert!(pwhash::sha512_crypt::verify("s3cr\"t", hash));
+        assert_eq!(lines[4], "-");
+
+        assert!(records[1].starts_with("dn: uid=Bank\\, Inc.,ou=people"));
+    }
+
+    #[test]
+    fn test_ldif_requires_template() {
+        assert!(render(ExportFormat::Ldif, &options(), &credentials()).is_err());
+        let options = Options {
+            dn_template: Some("ou=people,dc=example,dc=com".to_string()),
+            ..options()
+        };
+        assert!(render(ExportFormat::Ldif, &options, &credentials()).is_err());
+        assert!(render(ExportFormat::Chpasswd, &options, &credentials()).is_err());
+    }
+
+    #[test]
+    fn test_ldif_encoding() {
+        assert_eq!(escape_dn_value(" #a+b "), "\\ #a\\+b\\ ");
+        assert_eq!(ldif_line("dn", "uid=a"), "dn: uid=a\n");
+        assert_eq!(ldif_line("dn", "uid=jörg"), "dn:: dWlkPWrDtnJn\n");
+    }
+}
@@ -1,30 +1,644 @@
 use crate::passgen::alphabet::Alphabet;
 use crate::passgen::password::Password;
-use rand::Rng;
+use clap::ValueEnum;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{CryptoRng, Rng};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-impl<'a> Password<'a> {
-    pub fn generate(len: usize, alphabet: &Alphabet) -> Password<'static> {
-        let mut rng = rand::rng();
-        let alphabet_str = alphabet.as_str();
-        let chars: Vec<char> = alphabet_str.chars().collect();
+/// Character classes that generated passwords can be required to contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CharClass {
+    Upper,
+    Lower,
+    Digit,
+    /// Everything else, e.g. `!@#$`
+    Special,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Upper,
+        CharClass::Lower,
+        CharClass::Digit,
+        CharClass::Special,
+    ];
+
+    pub fn of(c: char) -> Self {
+        if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else {
+            CharClass::Special
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CharClass::Upper => "upper case",
+            CharClass::Lower => "lower case",
+            CharClass::Digit => "digit",
+            CharClass::Special => "special",
+        }
+    }
+}
+
+/// Minimum numbers of characters of each class in a password.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Requirements {
+    pub upper: usize,
+    pub lower: usize,
+    pub digit: usize,
+    pub special: usize,
+}
+
+impl Requirements {
+    pub fn min(&self, class: CharClass) -> usize {
+        match class {
+            CharClass::Upper => self.upper,
+            CharClass::Lower => self.lower,
+            CharClass::Digit => self.digit,
+            CharClass::Special => self.special,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Characters a password can be required to start or end with.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum EndClass {
+    Letter,
+    Upper,
+    Lower,
+    Digit,
+    Alphanumeric,
+    Special,
+}
+
+impl EndClass {
+    pub fn allows(self, class: CharClass) -> bool {
+        match self {
+            EndClass::Letter => matches!(class, CharClass::Upper | CharClass::Lower),
+            EndClass::Upper => class == CharClass::Upper,
+            EndClass::Lower => class == CharClass::Lower,
+            EndClass::Digit => class == CharClass::Digit,
+            EndClass::Alphanumeric => class != CharClass::Special,
+            EndClass::Special => class == CharClass::Special,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EndClass::Letter => "letter",
+            EndClass::Upper => "upper case",
+            EndClass::Lower => "lower case",
+            EndClass::Digit => "digit",
+            EndClass::Alphanumeric => "alphanumeric",
+            EndClass::Special => "special",
+        }
+    }
+}
+
+/// Classes the first and last characters of a password must be of, e.g. a letter first
+/// and no special character last.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Ends {
+    pub start: Option<EndClass>,
+    pub end: Option<EndClass>,
+}
+
+impl Ends {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How often a character may recur in a generated password, for systems that reject
+/// repeats.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Repeats {
+    #[default]
+    Allowed,
+    /// Never the same character twice in a row
+    NotAdjacent,
+    /// Every character distinct, so at most as many as the alphabet has
+    Never,
+}
+
+const NOT_ADJACENT_CONSTRAINED: &str =
+    "characters not repeated in a row cannot be combined with required classes or ends";
+
+/// Draws passwords from one alphabet. Its characters are collected once, so batches reuse
+/// them instead of collecting them again for every password.
+pub struct Generator<'a> {
+    alphabet: &'a Alphabet,
+    chars: Vec<char>,
+    constraint: Option<Constraint>,
+    repeats: Repeats,
+}
+
+// Class requirements and ends for one length, with the characters of each class and the
+// number of satisfying passwords precomputed
+struct Constraint {
+    length: usize,
+    // Only `Allowed` and `Never`; requirements do not combine with `NotAdjacent`
+    repeats: Repeats,
+    // Characters and minimum count of every class in the alphabet
+    classes: Vec<(Vec<char>, usize)>,
+    ends: Ends,
+    // One for every pair of classes the ends can take, or a single one without ends
+    cases: Vec<Case>,
+    // Natural log of the number of satisfying passwords, over all cases
+    total: f64,
+    // `ln_factorial[n]`: natural log of `n!`
+    ln_factorial: Vec<f64>,
+}
+
+// Passwords whose first and last characters are of given classes, leaving the characters
+// between them to meet what remains of the minimums
+struct Case {
+    // Indexes into the classes of the first and last characters, if constrained
+    first: Option<usize>,
+    last: Option<usize>,
+    // Characters of each class taken by the ends
+    reserved: Vec<usize>,
+    // Characters between the ends
+    middle: usize,
+    // Natural log of the number of passwords in the case
+    total: f64,
+    // `ways[i][n]`: natural log of the number of `n`-character middles over classes `i..`
+    // that meet their minimums
+    ways: Vec<Vec<f64>>,
+}
+
+impl<'a> Generator<'a> {
+    /// Fails for an empty alphabet, which could only produce empty passwords.
+    pub fn new(alphabet: &'a Alphabet) -> Result<Self, String> {
+        let chars = alphabet.chars();
         if chars.is_empty() {
-            return Password {
-                value: Cow::Borrowed(""),
-            };
+            return Err(format!("the {} alphabet is empty", alphabet.name()));
         }
-        let password: String = (0..len)
-            .map(|_| {
-                let idx = rng.random_range(0..chars.len());
-                chars[idx]
-            })
-            .collect();
+        Ok(Self {
+            alphabet,
+            chars,
+            constraint: None,
+            repeats: Repeats::Allowed,
+        })
+    }
+
+    /// Restricts passwords of `length` characters to those meeting `requirements`, still
+    /// choosing uniformly among all of them. Fails when the alphabet lacks a required
+    /// class or the requirements do not fit in `length`.
+    pub fn require(mut self, requirements: &Requirements, length: usize) -> Result<Self, String> {
+        if requirements.is_empty() {
+            return Ok(self);
+        }
+        let classes = self.classes(requirements)?;
+        let required: usize = classes.iter().map(|(_, min)| min).sum();
+        if required > length {
+            return Err(format!(
+                "{} required characters cannot fit in {} characters",
+                required, length
+            ));
+        }
+        let ends = self.constraint.take().map_or(Ends::default(), |c| c.ends);
+        self.constrain(classes, ends, length)
+    }
+
+    /// Restricts passwords of `length` characters to those starting and ending with
+    /// characters of the classes `ends` names, still choosing uniformly among all of them.
+    /// Fails when the alphabet has no such characters, or for characters not repeated in a
+    /// row.
+    pub fn require_ends(mut self, ends: Ends, length: usize) -> Result<Self, String> {
+        if ends.is_empty() {
+            return Ok(self);
+        }
+        for (end, position) in [(ends.start, "start"), (ends.end, "end")] {
+            if let Some(end) = end
+                && !self.chars.iter().any(|&c| end.allows(CharClass::of(c)))
+            {
+                return Err(format!(
+                    "the {} alphabet has no {} characters to {} with",
+                    self.alphabet.name(),
+                    end.name(),
+                    position
+                ));
+            }
+        }
+        if self.repeats == Repeats::NotAdjacent {
+            return Err(NOT_ADJACENT_CONSTRAINED.to_string());
+        }
+        let classes = match self.constraint.take() {
+            Some(constraint) => constraint.classes,
+            None => self.classes(&Requirements::default())?,
+        };
+        self.constrain(classes, ends, length)
+    }
+
+    // The characters of each class the alphabet has, with their minimum count
+    fn classes(&self, requirements: &Requirements) -> Result<Vec<(Vec<char>, usize)>, String> {
+        let mut classes = Vec::new();
+        for class in CharClass::ALL {
+            let chars: Vec<char> = self
+                .chars
+                .iter()
+                .copied()
+                .filter(|&c| CharClass::of(c) == class)
+                .collect();
+            let min = requirements.min(class);
+            if chars.is_empty() && min > 0 {
+                return Err(format!(
+                    "the {} alphabet has no {} characters",
+                    self.alphabet.name(),
+                    class.name()
+                ));
+            }
+            if !chars.is_empty() {
+                classes.push((chars, min));
+            }
+        }
+        Ok(classes)
+    }
+
+    // Replaces the constraint, failing if no password meets it
+    fn constrain(
+        mut self,
+        classes: Vec<(Vec<char>, usize)>,
+        ends: Ends,
+        length: usize,
+    ) -> Result<Self, String> {
+        let constraint = Constraint::new(classes, ends, length, self.repeats);
+        if constraint.total == f64::NEG_INFINITY {
+            return Err(match self.repeats {
+                Repeats::Never => format!(
+                    "the {} alphabet has too few characters of a required class for {} \
+                     distinct ones",
+                    self.alphabet.name(),
+                    length
+                ),
+                _ => format!(
+                    "no password of {} characters starts and ends as required and has the \
+                     required classes",
+                    length
+                ),
+            });
+        }
+        self.constraint = Some(constraint);
+        Ok(self)
+    }
+
+    /// Restricts passwords of `length` characters to those repeating characters no more
+    /// than `repeats` allows, still choosing uniformly among all of them. Fails when the
+    /// alphabet is too small for that, or for characters not repeated in a row along with
+    /// [`Generator::require`] or [`Generator::require_ends`].
+    pub fn restrict_repeats(mut self, repeats: Repeats, length: usize) -> Result<Self, String> {
+        let size = self.chars.len();
+        match repeats {
+            Repeats::Allowed => {}
+            Repeats::NotAdjacent if self.constraint.is_some() => {
+                return Err(NOT_ADJACENT_CONSTRAINED.to_string());
+            }
+            Repeats::NotAdjacent if size < 2 && length > 1 => {
+                return Err(format!(
+                    "the {} alphabet needs two characters to avoid repeating one",
+                    self.alphabet.name()
+                ));
+            }
+            Repeats::Never if size < length => {
+                return Err(format!(
+                    "the {} alphabet has only {} characters, too few for {} distinct ones",
+                    self.alphabet.name(),
+                    size,
+                    length
+                ));
+            }
+            Repeats::NotAdjacent | Repeats::Never => {}
+        }
+        self.repeats = repeats;
+        match self.constraint.take() {
+            Some(constraint) => self.constrain(constraint.classes, constraint.ends, length),
+            None => Ok(self),
+        }
+    }
+
+    pub fn alphabet(&self) -> &'a Alphabet {
+        self.alphabet
+    }
+
+    /// Entropy in bits of a password of `len` characters, less than `len` uniform characters
+    /// when the generator has requirements.
+    ///
+    /// # Panics
+    ///
+    /// If the generator was restricted with [`Generator::require`] for another length.
+    pub fn entropy(&self, len: usize) -> f64 {
+        match &self.constraint {
+            Some(constraint) => {
+                assert_eq!(
+                    len, constraint.length,
+                    "generator is restricted to another length"
+                );
+                constraint.total / std::f64::consts::LN_2
+            }
+            None => {
+                let size = self.chars.len();
+                match self.repeats {
+                    Repeats::Allowed => len as f64 * (size as f64).log2(),
+                    Repeats::NotAdjacent if len == 0 => 0.0,
+                    Repeats::NotAdjacent => {
+                        (size as f64).log2() + (len - 1) as f64 * ((size - 1) as f64).log2()
+                    }
+                    Repeats::Never => (0..len).map(|i| ((size - i) as f64).log2()).sum(),
+                }
+            }
+        }
+    }
+
+    /// Replaces the contents of `buf` with a password of `len` characters, reusing its
+    /// allocation.
+    ///
+    /// # Panics
+    ///
+    /// If the generator was restricted with [`Generator::require`] for another length, or
+    /// `len` is more than distinct characters [`Repeats::Never`] leaves.
+    #[tracing::instrument(level = "debug", skip_all, fields(len = len, alphabet_size = self.chars.len()))]
+    pub fn generate_into<R: CryptoRng + ?Sized>(&self, len: usize, rng: &mut R, buf: &mut String) {
+        buf.clear();
+        match &self.constraint {
+            Some(constraint) => {
+                assert_eq!(
+                    len, constraint.length,
+                    "generator is restricted to another length"
+                );
+                constraint.generate_into(rng, buf);
+            }
+            None => match self.repeats {
+                Repeats::Allowed => buf.extend(
+                    (0..len).map(|_| *self.chars.choose(rng).expect("alphabet is not empty")),
+                ),
+                Repeats::NotAdjacent => {
+                    let mut previous: Option<usize> = None;
+                    for _ in 0..len {
+                        // Any character but the previous one, by skipping over it
+                        let index = match previous {
+                            None => rng.random_range(0..self.chars.len()),
+                            Some(previous) => {
+                                let index = rng.random_range(0..self.chars.len() - 1);
+                                index + usize::from(index >= previous)
+                            }
+                        };
+                        buf.push(self.chars[index]);
+                        previous = Some(index);
+                    }
+                }
+                Repeats::Never => {
+                    assert!(len <= self.chars.len(), "too few characters to not repeat");
+                    let mut chars = self.chars.clone();
+                    let (chosen, _) = chars.partial_shuffle(rng, len);
+                    buf.extend(chosen.iter());
+                }
+            },
+        }
+    }
+
+    pub fn generate<R: CryptoRng + ?Sized>(&self, len: usize, rng: &mut R) -> Password<'static> {
+        let mut password = String::with_capacity(len);
+        self.generate_into(len, rng, &mut password);
         Password {
             value: Cow::Owned(password),
         }
     }
 }
 
+impl Constraint {
+    fn new(classes: Vec<(Vec<char>, usize)>, ends: Ends, length: usize, repeats: Repeats) -> Self {
+        let mut ln_factorial = vec![0.0; length + 1];
+        for n in 1..=length {
+            ln_factorial[n] = ln_factorial[n - 1] + (n as f64).ln();
+        }
+        let mut constraint = Self {
+            length,
+            repeats,
+            classes,
+            ends,
+            cases: Vec::new(),
+            total: f64::NEG_INFINITY,
+            ln_factorial,
+        };
+
+        let class_of = |i: usize| CharClass::of(constraint.classes[i].0[0]);
+        // The classes each end can take, or `None` for an end left free
+        let allowed = |end: Option<EndClass>| -> Vec<Option<usize>> {
+            match end {
+                None => vec![None],
+                Some(end) => (0..constraint.classes.len())
+                    .filter(|&i| end.allows(class_of(i)))
+                    .map(Some)
+                    .collect(),
+            }
+        };
+        let pairs: Vec<(Option<usize>, Option<usize>)> = match length {
+            0 => vec![(None, None)],
+            // The only character is both first and last
+            1 if !ends.is_empty() => (0..constraint.classes.len())
+                .filter(|&i| {
+                    [ends.start, ends.end]
+                        .into_iter()
+                        .flatten()
+                        .all(|end| end.allows(class_of(i)))
+                })
+                .map(|i| (Some(i), None))
+                .collect(),
+            _ => allowed(ends.start)
+                .into_iter()
+                .flat_map(|first| allowed(ends.end).into_iter().map(move |last| (first, last)))
+                .collect(),
+        };
+        let cases: Vec<Case> = pairs
+            .into_iter()
+            .map(|(first, last)| constraint.case(first, last))
+            .collect();
+        constraint.total = ln_sum(&cases.iter().map(|case| case.total).collect::<Vec<_>>());
+        constraint.cases = cases;
+        constraint
+    }
+
+    // Counts the passwords with the first and last characters of the given classes
+    fn case(&self, first: Option<usize>, last: Option<usize>) -> Case {
+        let mut reserved = vec![0; self.classes.len()];
+        for i in [first, last].into_iter().flatten() {
+            reserved[i] += 1;
+        }
+        let middle = self.length - reserved.iter().sum::<usize>();
+        let mut case = Case {
+            first,
+            last,
+            reserved,
+            middle,
+            total: 0.0,
+            ways: Vec::new(),
+        };
+        // With no classes left only the empty string remains
+        let mut ways = vec![vec![f64::NEG_INFINITY; middle + 1]; self.classes.len() + 1];
+        ways[self.classes.len()][0] = 0.0;
+        for i in (0..self.classes.len()).rev() {
+            for n in 0..=middle {
+                let terms: Vec<f64> = (self.min(&case, i)..=n)
+                    .map(|k| self.ln_term(&case, i, n, k, &ways[i + 1]))
+                    .collect();
+                ways[i][n] = ln_sum(&terms);
+            }
+        }
+        // The characters at the ends, of which there are size^r, or size! / (size - r)!
+        // when distinct
+        let ends: f64 = self
+            .classes
+            .iter()
+            .zip(&case.reserved)
+            .map(|((class, _), &r)| ln_choices(class.len(), r, self.repeats))
+            .sum();
+        case.total = ends + ways[0][middle];
+        case.ways = ways;
+        case
+    }
+
+    // The minimum of class `i` left for the middle of `case`
+    fn min(&self, case: &Case, i: usize) -> usize {
+        self.classes[i].1.saturating_sub(case.reserved[i])
+    }
+
+    // Log of the number of middles of `n` characters of `case` with exactly `k` from class
+    // `i` and the rest from the classes after it: C(n, k) * size^k * ways[i + 1][n - k], or
+    // with size! / (size - k)! for the `k` of the class's characters when they are distinct
+    // and none left for the ends
+    fn ln_term(&self, case: &Case, i: usize, n: usize, k: usize, rest: &[f64]) -> f64 {
+        let binomial = self.ln_factorial[n] - self.ln_factorial[k] - self.ln_factorial[n - k];
+        let size = match self.repeats {
+            Repeats::Never => match self.classes[i].0.len().checked_sub(case.reserved[i]) {
+                Some(size) => size,
+                None => return f64::NEG_INFINITY,
+            },
+            _ => self.classes[i].0.len(),
+        };
+        binomial + ln_choices(size, k, self.repeats) + rest[n - k]
+    }
+
+    // Picks the classes of the ends, then how many characters of each class the middle gets,
+    // weighted by how many passwords have those, then fills in uniformly chosen characters
+    // and shuffles the middle, which makes every satisfying password equally likely
+    fn generate_into<R: CryptoRng + ?Sized>(&self, rng: &mut R, buf: &mut String) {
+        let case = match self.cases.as_slice() {
+            [case] => case,
+            cases => {
+                let mut target: f64 = rng.random();
+                let mut chosen = None;
+                for case in cases {
+                    let p = (case.total - self.total).exp();
+                    if target < p {
+                        chosen = Some(case);
+                        break;
+                    }
+                    target -= p;
+                }
+                // Rounding can leave `target` above every case; the last possible one then
+                chosen.unwrap_or_else(|| {
+                    cases
+                        .iter()
+                        .rfind(|case| case.total > f64::NEG_INFINITY)
+                        .expect("some case is possible")
+                })
+            }
+        };
+        let mut chars = Vec::with_capacity(case.middle);
+        let (mut first, mut last) = (None, None);
+        let mut remaining = case.middle;
+        for (i, (class, _)) in self.classes.iter().enumerate() {
+            let total = case.ways[i][remaining];
+            let mut target: f64 = rng.random();
+            let mut chosen = None;
+            let min = self.min(case, i);
+            // Rounding can leave `target` above every count; the largest possible one then
+            let mut possible = min;
+            for k in min..=remaining {
+                let p = (self.ln_term(case, i, remaining, k, &case.ways[i + 1]) - total).exp();
+                if p > 0.0 {
+                    possible = k;
+                }
+                if target < p {
+                    chosen = Some(k);
+                    break;
+                }
+                target -= p;
+            }
+            let mut count = chosen.unwrap_or(possible);
+            // and the last class takes whatever is left
+            if i == self.classes.len() - 1 {
+                count = remaining;
+            }
+            let picked: Vec<char> = match self.repeats {
+                Repeats::Never => {
+                    let mut class = class.clone();
+                    let reserved = count + case.reserved[i];
+                    class.partial_shuffle(rng, reserved).0.to_vec()
+                }
+                _ => (0..count + case.reserved[i])
+                    .map(|_| *class.choose(rng).expect("classes are not empty"))
+                    .collect(),
+            };
+            let mut picked = picked.into_iter();
+            if case.first == Some(i) {
+                first = picked.next();
+            }
+            if case.last == Some(i) {
+                last = picked.next();
+            }
+            chars.extend(picked);
+            remaining -= count;
+        }
+        chars.shuffle(rng);
+        buf.extend(first);
+        buf.extend(chars);
+        buf.extend(last);
+    }
+}
+
+// Log of the ways to pick `k` characters of `size`, any or only distinct ones
+fn ln_choices(size: usize, k: usize, repeats: Repeats) -> f64 {
+    match repeats {
+        Repeats::Never if k > size => f64::NEG_INFINITY,
+        Repeats::Never => (0..k).map(|j| ((size - j) as f64).ln()).sum(),
+        _ => k as f64 * (size as f64).ln(),
+    }
+}
+
+/// `ln(sum(exp(terms)))`, without overflowing for counts kept as logarithms.
+pub fn ln_sum(terms: &[f64]) -> f64 {
+    let max = terms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + terms.iter().map(|t| (t - max).exp()).sum::<f64>().ln()
+}
+
+impl<'a> Password<'a> {
+    /// A password of `len` characters drawn uniformly from `alphabet`; use a [`Generator`]
+    /// for batches. Fails for an empty alphabet.
+    pub fn generate<R: CryptoRng + ?Sized>(
+        len: usize,
+        alphabet: &Alphabet,
+        rng: &mut R,
+    ) -> Result<Password<'static>, String> {
+        Ok(Generator::new(alphabet)?.generate(len, rng))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,7 +647,7 @@ mod tests {
     #[test]
     fn test_generate() {
         let alphabet = Alphabet::Full;
-        let password = Password::generate(12, &alphabet);
+        let password = Password::generate(12, &alphabet, &mut rand::rng()).unwrap();
         assert_eq!(password.value.len(), 12);
         for c in password.value.chars() {
             assert!(alphabet.contains(c));
@@ -43,7 +657,414 @@ mod tests {
     #[test]
     fn test_generate_empty() {
         let alphabet = Custom("abc".to_string());
-        let password = Password::generate(0, &alphabet);
+        let password = Password::generate(0, &alphabet, &mut rand::rng()).unwrap();
         assert_eq!(password.value.len(), 0);
     }
+
+    #[test]
+    fn test_generate_empty_alphabet_fails() {
+        let alphabet = Custom(String::new());
+        let error = Password::generate(12, &alphabet, &mut rand::rng()).unwrap_err();
+        assert_eq!(error, "the custom alphabet is empty");
+        assert!(Generator::new(&alphabet).is_err());
+    }
+
+    #[test]
+    fn test_generator_matches_password_generate() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let alphabet = Alphabet::Full;
+        let generator = Generator::new(&alphabet).unwrap();
+        let mut rng = ChaCha20Rng::from_seed([7; 32]);
+        let mut buf = String::from("previous contents");
+        generator.generate_into(16, &mut rng, &mut buf);
+        assert_eq!(buf, "Hs0F!ya#9jBHrWDE");
+        generator.generate_into(4, &mut rng, &mut buf);
+        assert_eq!(buf.chars().count(), 4);
+    }
+
+    #[test]
+    fn test_generate_is_uniform() {
+        use crate::passgen::selftest::{chi_square, chi_square_critical};
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let alphabet = Custom("abcdefghij".to_string());
+        let password =
+            Password::generate(100_000, &alphabet, &mut ChaCha20Rng::from_seed([3; 32])).unwrap();
+        let mut counts = [0u64; 10];
+        for c in password.value.chars() {
+            counts[(c as u8 - b'a') as usize] += 1;
+        }
+        assert!(chi_square(&counts) < chi_square_critical(counts.len() - 1));
+    }
+
+    #[test]
+    fn test_generate_seeded_is_reproducible() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let alphabet = Alphabet::Full;
+        let first =
+            Password::generate(16, &alphabet, &mut ChaCha20Rng::from_seed([7; 32])).unwrap();
+        let second =
+            Password::generate(16, &alphabet, &mut ChaCha20Rng::from_seed([7; 32])).unwrap();
+        assert_eq!(first, second);
+        // Keyfile-derived passwords must not change between releases
+        assert_eq!(first.value, "Hs0F!ya#9jBHrWDE");
+    }
+
+    #[test]
+    fn test_requirements_are_met() {
+        let alphabet = Alphabet::Full;
+        let requirements = Requirements {
+            upper: 2,
+            digit: 3,
+            special: 1,
+            ..Requirements::default()
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 8)
+            .unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..500 {
+            let password = generator.generate(8, &mut rng);
+            let count = |class| {
+                password
+                    .value
+                    .chars()
+                    .filter(|&c| CharClass::of(c) == class)
+                    .count()
+            };
+            assert_eq!(password.value.chars().count(), 8);
+            assert!(count(CharClass::Upper) >= 2, "{}", password.value);
+            assert!(count(CharClass::Digit) >= 3, "{}", password.value);
+            assert!(count(CharClass::Special) >= 1, "{}", password.value);
+        }
+    }
+
+    #[test]
+    fn test_requirements_are_uniform() {
+        use crate::passgen::selftest::{chi_square, chi_square_critical};
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        // Of the 27 strings over "aA1" of length 3, the 6 permutations of "aA1" are the only
+        // ones with a character of every class
+        let alphabet = Custom("aA1".to_string());
+        let requirements = Requirements {
+            upper: 1,
+            lower: 1,
+            digit: 1,
+            special: 0,
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 3)
+            .unwrap();
+        let mut rng = ChaCha20Rng::from_seed([4; 32]);
+        let mut counts = std::collections::BTreeMap::new();
+        for _ in 0..6000 {
+            *counts
+                .entry(generator.generate(3, &mut rng).value.into_owned())
+                .or_insert(0u64) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        let counts: Vec<u64> = counts.into_values().collect();
+        assert!(chi_square(&counts) < chi_square_critical(counts.len() - 1));
+
+        // "ab1" at length 3 with a digit: 19 valid strings, with 1, 2 or 3 digits in
+        // proportion 12:6:1
+        let alphabet = Custom("ab1".to_string());
+        let requirements = Requirements {
+            digit: 1,
+            ..Requirements::default()
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 3)
+            .unwrap();
+        let mut counts = std::collections::BTreeMap::new();
+        for _ in 0..19_000 {
+            *counts
+                .entry(generator.generate(3, &mut rng).value.into_owned())
+                .or_insert(0u64) += 1;
+        }
+        assert_eq!(counts.len(), 19);
+        let counts: Vec<u64> = counts.into_values().collect();
+        assert!(chi_square(&counts) < chi_square_critical(counts.len() - 1));
+    }
+
+    #[test]
+    fn test_entropy_with_requirements() {
+        let alphabet = Custom("ab1".to_string());
+        let generator = Generator::new(&alphabet).unwrap();
+        assert!((generator.entropy(3) - 27f64.log2()).abs() < 1e-9);
+        let requirements = Requirements {
+            digit: 1,
+            ..Requirements::default()
+        };
+        let generator = generator.require(&requirements, 3).unwrap();
+        assert!((generator.entropy(3) - 19f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_impossible_requirements() {
+        let alphabet = Alphabet::LowerCase;
+        let generator = Generator::new(&alphabet).unwrap();
+        let requirements = Requirements {
+            digit: 1,
+            ..Requirements::default()
+        };
+        assert_eq!(
+            generator.require(&requirements, 12).err().unwrap(),
+            "the lower-case alphabet has no digit characters"
+        );
+
+        let alphabet = Alphabet::Full;
+        let requirements = Requirements {
+            upper: 3,
+            digit: 3,
+            ..Requirements::default()
+        };
+        let generator = Generator::new(&alphabet).unwrap();
+        assert!(generator.require(&requirements, 5).is_err());
+    }
+
+    #[test]
+    fn test_restricted_repeats() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let mut rng = ChaCha20Rng::from_seed([9; 32]);
+        let alphabet = Alphabet::Digits;
+        let distinct = Generator::new(&alphabet)
+            .unwrap()
+            .restrict_repeats(Repeats::Never, 10)
+            .unwrap();
+        let not_adjacent = Generator::new(&alphabet)
+            .unwrap()
+            .restrict_repeats(Repeats::NotAdjacent, 10)
+            .unwrap();
+        for _ in 0..100 {
+            let mut chars: Vec<char> = distinct.generate(10, &mut rng).value.chars().collect();
+            chars.sort();
+            chars.dedup();
+            assert_eq!(chars.len(), 10);
+            let password = not_adjacent.generate(10, &mut rng);
+            let chars: Vec<char> = password.value.chars().collect();
+            assert!(chars.windows(2).all(|w| w[0] != w[1]), "{}", password.value);
+        }
+
+        // 10! orderings, and 10 * 9^9 strings without a repeat in a row
+        let factorial: f64 = (1..=10).map(|n| n as f64).product();
+        assert!((distinct.entropy(10) - factorial.log2()).abs() < 1e-9);
+        let expected = 10f64.log2() + 9.0 * 9f64.log2();
+        assert!((not_adjacent.entropy(10) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_restricted_repeats_with_requirements() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        // Distinct characters of "ab12" at length 3 with two digits: "12" or "21" and one
+        // letter, at three positions, so 12 passwords
+        let alphabet = Custom("ab12".to_string());
+        let requirements = Requirements {
+            digit: 2,
+            ..Requirements::default()
+        };
+        // Either order of restrictions
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .restrict_repeats(Repeats::Never, 3)
+            .unwrap()
+            .require(&requirements, 3)
+            .unwrap();
+        assert!((generator.entropy(3) - 12f64.log2()).abs() < 1e-9);
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 3)
+            .unwrap()
+            .restrict_repeats(Repeats::Never, 3)
+            .unwrap();
+        assert!((generator.entropy(3) - 12f64.log2()).abs() < 1e-9);
+
+        let mut rng = ChaCha20Rng::from_seed([9; 32]);
+        let mut counts = std::collections::BTreeMap::new();
+        for _ in 0..12_000 {
+            *counts
+                .entry(generator.generate(3, &mut rng).value.into_owned())
+                .or_insert(0u64) += 1;
+        }
+        assert_eq!(counts.len(), 12);
+        let counts: Vec<u64> = counts.into_values().collect();
+        assert!(
+            crate::passgen::selftest::chi_square(&counts)
+                < crate::passgen::selftest::chi_square_critical(counts.len() - 1)
+        );
+    }
+
+    #[test]
+    fn test_impossible_repeats() {
+        let alphabet = Alphabet::Digits;
+        let generator = Generator::new(&alphabet).unwrap();
+        assert_eq!(
+            generator
+                .restrict_repeats(Repeats::Never, 11)
+                .err()
+                .unwrap(),
+            "the digits alphabet has only 10 characters, too few for 11 distinct ones"
+        );
+
+        let alphabet = Custom("a".to_string());
+        let generator = Generator::new(&alphabet).unwrap();
+        assert!(generator.restrict_repeats(Repeats::NotAdjacent, 2).is_err());
+
+        // Only two digits to meet a minimum of three
+        let alphabet = Custom("abc12".to_string());
+        let requirements = Requirements {
+            digit: 3,
+            ..Requirements::default()
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 4)
+            .unwrap();
+        assert!(generator.restrict_repeats(Repeats::Never, 4).is_err());
+
+        let alphabet = Alphabet::Full;
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require(&requirements, 8)
+            .unwrap();
+        assert!(generator.restrict_repeats(Repeats::NotAdjacent, 8).is_err());
+    }
+
+    #[test]
+    fn test_ends_are_uniform() {
+        use crate::passgen::selftest::{chi_square, chi_square_critical};
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        // Over "aA1!" at length 3 with a digit, starting with a letter and not ending with a
+        // special character: 2 * 4 * 3 strings, less the 2 * 3 * 2 without a digit
+        let alphabet = Custom("aA1!".to_string());
+        let requirements = Requirements {
+            digit: 1,
+            ..Requirements::default()
+        };
+        let ends = Ends {
+            start: Some(EndClass::Letter),
+            end: Some(EndClass::Alphanumeric),
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require_ends(ends, 3)
+            .unwrap()
+            .require(&requirements, 3)
+            .unwrap();
+        assert!((generator.entropy(3) - 12f64.log2()).abs() < 1e-9);
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let mut counts = std::collections::BTreeMap::new();
+        for _ in 0..12_000 {
+            let password = generator.generate(3, &mut rng).value.into_owned();
+            assert!(password.starts_with(['a', 'A']), "{}", password);
+            assert!(!password.ends_with('!'), "{}", password);
+            assert!(password.contains('1'), "{}", password);
+            *counts.entry(password).or_insert(0u64) += 1;
+        }
+        assert_eq!(counts.len(), 12);
+        let counts: Vec<u64> = counts.into_values().collect();
+        assert!(chi_square(&counts) < chi_square_critical(counts.len() - 1));
+
+        // Distinct characters: "a" or "A", then the other three in any order but "!" last
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .restrict_repeats(Repeats::Never, 4)
+            .unwrap()
+            .require_ends(ends, 4)
+            .unwrap();
+        assert!((generator.entropy(4) - 8f64.log2()).abs() < 1e-9);
+        for _ in 0..100 {
+            let password = generator.generate(4, &mut rng).value.into_owned();
+            assert!(password.starts_with(['a', 'A']) && !password.ends_with('!'));
+        }
+    }
+
+    #[test]
+    fn test_single_character_ends() {
+        let alphabet = Custom("aA1!".to_string());
+        let ends = Ends {
+            start: Some(EndClass::Letter),
+            end: Some(EndClass::Upper),
+        };
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .require_ends(ends, 1)
+            .unwrap();
+        assert_eq!(generator.entropy(1), 0.0);
+        assert_eq!(generator.generate(1, &mut rand::rng()).value, "A");
+    }
+
+    #[test]
+    fn test_impossible_ends() {
+        let alphabet = Alphabet::Digits;
+        let ends = Ends {
+            start: Some(EndClass::Letter),
+            end: None,
+        };
+        assert_eq!(
+            Generator::new(&alphabet)
+                .unwrap()
+                .require_ends(ends, 8)
+                .err()
+                .unwrap(),
+            "the digits alphabet has no letter characters to start with"
+        );
+
+        let alphabet = Alphabet::Full;
+        let ends = Ends {
+            start: Some(EndClass::Digit),
+            end: Some(EndClass::Special),
+        };
+        assert!(
+            Generator::new(&alphabet)
+                .unwrap()
+                .require_ends(ends, 1)
+                .is_err()
+        );
+        assert!(
+            Generator::new(&alphabet)
+                .unwrap()
+                .restrict_repeats(Repeats::NotAdjacent, 8)
+                .unwrap()
+                .require_ends(ends, 8)
+                .is_err()
+        );
+        // Upper case at both ends takes two distinct upper-case letters
+        let ends = Ends {
+            start: Some(EndClass::Upper),
+            end: Some(EndClass::Upper),
+        };
+        let alphabet = Custom("ABa".to_string());
+        let generator = Generator::new(&alphabet)
+            .unwrap()
+            .restrict_repeats(Repeats::Never, 3)
+            .unwrap()
+            .require_ends(ends, 3)
+            .unwrap();
+        assert!((generator.entropy(3) - 1.0).abs() < 1e-9);
+        assert!(
+            Generator::new(&Custom("Aab".to_string()))
+                .unwrap()
+                .restrict_repeats(Repeats::Never, 3)
+                .unwrap()
+                .require_ends(ends, 3)
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,215 @@
+use crate::passgen::dict;
+use crate::passgen::hash::hex;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE: &str = "history";
+const SALT_PREFIX: &str = "salt ";
+
+/// Where `--no-reissue` remembers issued secrets, next to the downloaded common passwords.
+pub fn history_path() -> Option<PathBuf> {
+    dict::data_dir().map(|dir| dir.join(HISTORY_FILE))
+}
+
+/// Salted SHA-256 digests of previously issued secrets, so that a secret is never handed
+/// out twice. The file starts with a `salt <hex>` line, chosen randomly when it is created,
+/// followed by one hex digest per line; the secrets themselves are never stored.
+///
+/// The digests are fast to compute, so they only protect secrets with enough entropy to
+/// resist guessing, which is what PassGen generates.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    salt: Vec<u8>,
+    digests: HashSet<String>,
+    // Issued since the file was read, appended by `save`
+    pending: Vec<String>,
+    exists: bool,
+}
+
+impl History {
+    /// Reads the history at `path`; a missing file is an empty history with a fresh salt.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path: path.to_path_buf(),
+                    salt: rand::random::<[u8; 32]>().to_vec(),
+                    digests: HashSet::new(),
+                    pending: Vec::new(),
+                    exists: false,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = source.lines();
+        let salt = lines
+            .next()
+            .and_then(|line| line.strip_prefix(SALT_PREFIX))
+            .and_then(decode_hex)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "missing or invalid salt line")
+            })?;
+        let digests = lines
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            salt,
+            digests,
+            pending: Vec::new(),
+            exists: true,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn digest(&self, secret: &str) -> String {
+        hex(&Sha256::new()
+            .chain_update(&self.salt)
+            .chain_update(secret.as_bytes())
+            .finalize())
+    }
+
+    /// Whether `secret` was issued before.
+    pub fn contains(&self, secret: &str) -> bool {
+        self.digests.contains(&self.digest(secret))
+    }
+
+    /// Records `secret` as issued unless it was issued before, returning whether it is new.
+    /// Nothing is written until [`History::save`].
+    pub fn issue(&mut self, secret: &str) -> bool {
+        let digest = self.digest(secret);
+        if !self.digests.insert(digest.clone()) {
+            return false;
+        }
+        self.pending.push(digest);
+        true
+    }
+
+    /// Appends the secrets issued since the history was read, creating the file readable
+    /// only by its owner if needed.
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)?;
+
+        let mut text = String::new();
+        if !self.exists {
+            text.push_str(SALT_PREFIX);
+            text.push_str(&hex(&self.salt));
+            text.push('\n');
+        }
+        for digest in &self.pending {
+            text.push_str(digest);
+            text.push('\n');
+        }
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+
+        self.pending.clear();
+        self.exists = true;
+        Ok(())
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.is_empty() || !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_history(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("passgen-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("history")
+    }
+
+    #[test]
+    fn test_issue_and_reload() {
+        let path = temp_history("reload");
+        let mut history = History::open(&path).unwrap();
+        assert!(history.digests.is_empty());
+        assert!(!history.contains("s3cret"));
+        assert!(history.issue("s3cret"));
+        assert!(history.contains("s3cret"));
+        assert!(!history.issue("s3cret"));
+        assert!(history.issue("other"));
+        assert_eq!(history.pending.len(), 2);
+        history.save().unwrap();
+
+        let mut history = History::open(&path).unwrap();
+        assert_eq!(history.digests.len(), 2);
+        assert!(!history.issue("s3cret"));
+        assert!(!history.issue("other"));
+        assert!(history.issue("third"));
+        history.save().unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("s3cret"));
+        assert_eq!(contents.lines().count(), 4);
+        assert_eq!(History::open(&path).unwrap().digests.len(), 3);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_salts_differ() {
+        let first = History::open(&temp_history("salt-a")).unwrap();
+        let second = History::open(&temp_history("salt-b")).unwrap();
+        assert_ne!(first.digest("s3cret"), second.digest("s3cret"));
+    }
+
+    #[test]
+    fn test_invalid_salt() {
+        let path = temp_history("invalid");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "0123\n").unwrap();
+        let error = History::open(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_history("private");
+        let mut history = History::open(&path).unwrap();
+        history.issue("s3cret");
+        history.save().unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
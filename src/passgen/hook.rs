@@ -0,0 +1,160 @@
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+
+/// Environment variable holding the secret for `--exec` commands.
+pub const SECRET_ENV: &str = "PASSGEN_SECRET";
+/// Environment variable holding the descriptor number `--exec-fd` commands read from.
+pub const SECRET_FD_ENV: &str = "PASSGEN_SECRET_FD";
+
+/// A command to run with a generated secret, and how the secret reaches it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hook {
+    /// `--exec`: the secret is in `PASSGEN_SECRET` and on stdin
+    Exec(String),
+    /// `--exec-fd`: the secret is read from the descriptor in `PASSGEN_SECRET_FD`
+    ExecFd(String),
+}
+
+impl Hook {
+    pub fn from_args(exec: Option<String>, exec_fd: Option<String>) -> Option<Self> {
+        exec.map(Hook::Exec).or(exec_fd.map(Hook::ExecFd))
+    }
+
+    pub fn command(&self) -> &str {
+        match self {
+            Hook::Exec(command) | Hook::ExecFd(command) => command,
+        }
+    }
+
+    pub fn run(&self, secret: &str) -> io::Result<()> {
+        match self {
+            Hook::Exec(command) => run(command, secret),
+            Hook::ExecFd(command) => run_with_fd(command, secret),
+        }
+    }
+}
+
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    }
+    #[cfg(not(windows))]
+    {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Runs `command` through the shell with the secret in `PASSGEN_SECRET` and on stdin
+/// (without a trailing newline), never on its command line. The command's output goes
+/// to stderr so stdout keeps only PassGen's results.
+pub fn run(command: &str, secret: &str) -> io::Result<()> {
+    let mut child = shell(command)
+        .env(SECRET_ENV, secret)
+        .stdin(Stdio::piped())
+        .stdout(io::stderr())
+        .spawn()?;
+    let stdin = child.stdin.take().expect("stdin is piped");
+    hand_over(stdin, secret)?;
+    wait(child)
+}
+
+/// Runs `command` through the shell with the secret readable, without a trailing
+/// newline, from an inherited pipe whose descriptor number is in `PASSGEN_SECRET_FD`.
+/// Unlike [`run`], the secret appears in neither the environment nor stdin, which
+/// other processes of the same user could inspect or which the command may pass on.
+#[cfg(unix)]
+pub fn run_with_fd(command: &str, secret: &str) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::process::CommandExt;
+
+    // Both ends are close-on-exec; only the read end is made inheritable in the child
+    let (reader, writer) = io::pipe()?;
+    let fd = reader.as_raw_fd();
+    let mut shell = shell(command);
+    shell
+        .env(SECRET_FD_ENV, fd.to_string())
+        .stdin(Stdio::null())
+        .stdout(io::stderr());
+    // SAFETY: fcntl is async-signal-safe and the closure allocates nothing
+    unsafe {
+        shell.pre_exec(move || {
+            let flags = libc::fcntl(fd, libc::F_GETFD);
+            if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = shell.spawn()?;
+    // The command must see end of file once the secret is read, so keep no read end here
+    drop(reader);
+    hand_over(writer, secret)?;
+    wait(child)
+}
+
+#[cfg(not(unix))]
+pub fn run_with_fd(_command: &str, _secret: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "passing the secret through a file descriptor requires Unix",
+    ))
+}
+
+// Writes the secret and closes the pipe. Commands that ignore it may exit without
+// reading, which is not an error.
+fn hand_over(mut pipe: impl Write, secret: &str) -> io::Result<()> {
+    match pipe.write_all(secret.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn wait(mut child: Child) -> io::Result<()> {
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("exited with {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_in_env_and_stdin() {
+        run(
+            r#"[ "$PASSGEN_SECRET" = s3cret ] && [ "$(cat)" = s3cret ]"#,
+            "s3cret",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_ignores_unread_stdin() {
+        // Larger than a pipe buffer, so the write fails once `true` exits
+        run("true", &"x".repeat(100_000)).unwrap();
+    }
+
+    #[test]
+    fn test_failure() {
+        let error = run("exit 3", "s3cret").unwrap_err();
+        assert!(error.to_string().contains("exited with"));
+        assert!(!error.to_string().contains("s3cret"));
+    }
+
+    #[test]
+    fn test_secret_from_fd() {
+        run_with_fd(
+            r#"[ -z "$PASSGEN_SECRET" ] && [ "$(cat <&"$PASSGEN_SECRET_FD")" = s3cret ]"#,
+            "s3cret",
+        )
+        .unwrap();
+        run_with_fd("true", &"x".repeat(100_000)).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use crate::passgen::metrics::Metrics;
+use crate::passgen::serve::{Api, Reply};
+use std::io::{Cursor, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+// Request bodies are tiny JSON objects; anything larger is rejected unread
+const MAX_BODY: u64 = 64 * 1024;
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn respond(mut request: Request, api: &mut Api) -> std::io::Result<()> {
+    let client = request
+        .remote_addr()
+        .map(SocketAddr::ip)
+        .unwrap_or(IpAddr::from([0, 0, 0, 0]));
+    let method = request.method().clone();
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    // Metrics are counters only, so scrapers need neither the token nor a rate limit
+    if method == Method::Get && path == "/metrics" {
+        return request.respond(metrics_response(api.metrics()));
+    }
+
+    let mut body = String::new();
+    let too_large = request
+        .body_length()
+        .is_some_and(|len| len as u64 > MAX_BODY);
+    let reply = if too_large {
+        Reply::error(413, "request body too large")
+    } else if request
+        .as_reader()
+        .take(MAX_BODY)
+        .read_to_string(&mut body)
+        .is_err()
+    {
+        Reply::error(400, "request body must be UTF-8")
+    } else {
+        api.handle(
+            method.as_str(),
+            &path,
+            header(&request, "Authorization"),
+            client,
+            &body,
+            Instant::now(),
+        )
+    };
+
+    // Only the route and status are logged; bodies carry secrets
+    if reply.status < 400 {
+        info!(%client, %method, path, status = reply.status, "request");
+    } else {
+        warn!(%client, %method, path, status = reply.status, "request");
+    }
+
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    let cache_control =
+        Header::from_bytes("Cache-Control", "no-store").expect("static header is valid");
+    request.respond(
+        Response::from_string(reply.body.to_string())
+            .with_status_code(reply.status)
+            .with_header(content_type)
+            .with_header(cache_control),
+    )
+}
+
+/// Serves the API on `listen` until the process is stopped.
+pub fn serve(listen: SocketAddr, mut api: Api) -> anyhow::Result<()> {
+    let server =
+        Server::http(listen).map_err(|e| anyhow::anyhow!("Cannot listen on {}: {}", listen, e))?;
+    info!(%listen, "serving");
+
+    for request in server.incoming_requests() {
+        if let Err(e) = respond(request, &mut api) {
+            warn!("failed to send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// The metrics in the Prometheus text format
+fn metrics_response(metrics: &Metrics) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+        .expect("static header is valid");
+    Response::from_string(metrics.render()).with_header(content_type)
+}
+
+/// Serves `GET /metrics` on `listen` until the process is stopped, for modes whose
+/// own transport is not HTTP.
+pub fn serve_metrics(listen: SocketAddr, metrics: Arc<Metrics>) -> anyhow::Result<()> {
+    let server =
+        Server::http(listen).map_err(|e| anyhow::anyhow!("Cannot listen on {}: {}", listen, e))?;
+    info!(%listen, "serving metrics");
+
+    for request in server.incoming_requests() {
+        let response = if request.url() == "/metrics" {
+            metrics_response(&metrics)
+        } else {
+            Response::from_string("not found").with_status_code(404)
+        };
+        if let Err(e) = request.respond(response) {
+            warn!("failed to send response: {}", e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use clap::ValueEnum;
+use rand::{CryptoRng, Rng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Crockford's base32, as ULIDs use: no I, L, O or U
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const RANDOM_BITS: u32 = 80;
+
+/// Kind of identifier, with the bits of each that are random.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Kind {
+    /// Random UUID (RFC 9562 version 4), 122 random bits
+    #[default]
+    V4,
+    /// Time-ordered UUID (RFC 9562 version 7), a millisecond timestamp and 74 random bits
+    V7,
+    /// ULID: a millisecond timestamp and 80 random bits in 26 characters of Crockford's
+    /// base32
+    Ulid,
+}
+
+impl Kind {
+    /// Bits of a fresh identifier that are random; a time-ordered one generated in the
+    /// same millisecond as the last adds one to it instead.
+    pub fn entropy(self) -> f64 {
+        match self {
+            Kind::V4 => 122.0,
+            Kind::V7 => 74.0,
+            Kind::Ulid => f64::from(RANDOM_BITS),
+        }
+    }
+}
+
+/// Generates identifiers of one kind. Time-ordered ones sort in the order generated, as
+/// RFC 9562 and the ULID spec recommend: within a millisecond the random part counts up
+/// from the first identifier's.
+pub struct Ids {
+    kind: Kind,
+    last: Option<(u64, u128)>,
+}
+
+impl Ids {
+    pub fn new(kind: Kind) -> Self {
+        Self { kind, last: None }
+    }
+
+    pub fn generate<R: CryptoRng + ?Sized>(&mut self, rng: &mut R) -> String {
+        self.generate_at(unix_millis(), rng)
+    }
+
+    fn generate_at<R: CryptoRng + ?Sized>(&mut self, millis: u64, rng: &mut R) -> String {
+        let bits: u128 = rng.random();
+        match self.kind {
+            Kind::V4 => format_uuid(with_version(bits, 4)),
+            Kind::V7 => {
+                let (millis, random) = self.next_random(millis, bits >> 54);
+                let value = u128::from(millis) << 80 | spread_v7(random);
+                format_uuid(with_version(value, 7))
+            }
+            Kind::Ulid => {
+                let (millis, random) = self.next_random(millis, bits >> (128 - RANDOM_BITS));
+                crockford(u128::from(millis) << RANDOM_BITS | random)
+            }
+        }
+    }
+
+    // The timestamp and random part to use at `millis`: a fresh random part in a new
+    // millisecond, else the last identifier's plus one, keeping its timestamp in case the
+    // clock stepped back
+    fn next_random(&mut self, millis: u64, fresh: u128) -> (u64, u128) {
+        let (millis, random) = match self.last {
+            Some((last_millis, last_random)) if millis <= last_millis => {
+                (last_millis, last_random + 1)
+            }
+            _ => (millis, fresh),
+        };
+        self.last = Some((millis, random));
+        (millis, random)
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+// Places 74 random bits around the version and variant fields of a version 7 UUID
+fn spread_v7(random: u128) -> u128 {
+    let rand_a = (random >> 62) & 0xfff;
+    let rand_b = random & ((1 << 62) - 1);
+    rand_a << 64 | rand_b
+}
+
+// Sets the version nibble and the RFC 9562 variant bits
+fn with_version(bits: u128, version: u128) -> u128 {
+    let cleared = bits & !(0xf << 76) & !(0b11 << 62);
+    cleared | version << 76 | 0b10 << 62
+}
+
+fn format_uuid(value: u128) -> String {
+    let hex = format!("{:032x}", value);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn crockford(value: u128) -> String {
+    (0..26)
+        .rev()
+        .map(|i| CROCKFORD[((value >> (5 * i)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_v4() {
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let mut ids = Ids::new(Kind::V4);
+        for _ in 0..100 {
+            let id = ids.generate(&mut rng);
+            assert_eq!(id.len(), 36);
+            assert_eq!(&id[14..15], "4", "{}", id);
+            assert!("89ab".contains(&id[19..20]), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_v7_is_ordered() {
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let mut ids = Ids::new(Kind::V7);
+        let millis = 0x0189_4a2b_3c4d;
+        let generated: Vec<String> = (0..50)
+            .map(|i| ids.generate_at(millis + i / 10, &mut rng))
+            .collect();
+        for id in &generated {
+            assert!(id.starts_with("01894a2b-3c"), "{}", id);
+            assert_eq!(&id[14..15], "7", "{}", id);
+            assert!("89ab".contains(&id[19..20]), "{}", id);
+        }
+        assert!(generated.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_ulid() {
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let mut ids = Ids::new(Kind::Ulid);
+        // The spec's example timestamp, 2016-07-30
+        let first = ids.generate_at(1_469_918_176_385, &mut rng);
+        assert_eq!(first.len(), 26);
+        assert!(first.starts_with("01ARYZ6S41"), "{}", first);
+        assert!(first.chars().all(|c| CROCKFORD.contains(&(c as u8))));
+        // A clock step back still sorts after
+        let second = ids.generate_at(1_469_918_176_000, &mut rng);
+        assert!(second > first, "{} {}", first, second);
+        assert_eq!(crockford(u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    }
+}
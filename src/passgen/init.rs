@@ -0,0 +1,183 @@
+use crate::passgen::config::{self, Config, Settings};
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+// Defaults offered by the wizard, matching the command-line defaults
+const DEFAULT_LENGTH: usize = 12;
+const DEFAULT_WORDS: usize = 3;
+const DEFAULT_SEPARATOR: &str = "-";
+
+/// Asks for answers on `output`, reading them line by line from `input`.
+/// An empty answer (or end of input) keeps the suggested default.
+struct Prompter<'a, I: BufRead, O: Write> {
+    input: &'a mut I,
+    output: &'a mut O,
+}
+
+impl<I: BufRead, O: Write> Prompter<'_, I, O> {
+    fn answer(&mut self, question: &str, default: &str) -> io::Result<String> {
+        write!(self.output, "{} [{}]: ", question, default)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            writeln!(self.output)?;
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        Ok(if line.trim().is_empty() {
+            default.to_string()
+        } else {
+            line.to_string()
+        })
+    }
+
+    fn ask<T>(&mut self, question: &str, default: T, valid: impl Fn(&T) -> bool) -> io::Result<T>
+    where
+        T: FromStr + ToString,
+    {
+        let default = default.to_string();
+        loop {
+            let answer = self.answer(question, &default)?;
+            match answer.trim().parse::<T>() {
+                Ok(value) if valid(&value) => return Ok(value),
+                _ => writeln!(self.output, "  `{}` is not a valid answer", answer.trim())?,
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> io::Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let answer = self.answer(question, hint)?;
+            match answer.trim().to_lowercase().as_str() {
+                "y/n" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                other => writeln!(self.output, "  `{}` is not a valid answer", other)?,
+            }
+        }
+    }
+}
+
+fn wordlist_names() -> Vec<String> {
+    WordList::value_variants()
+        .iter()
+        .filter_map(|w| w.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+/// Walks through the preferred defaults and returns a config holding them.
+pub fn run_wizard(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<Config> {
+    let mut prompter = Prompter { input, output };
+    writeln!(
+        prompter.output,
+        "Set your preferred defaults; press Enter to keep the suggestion."
+    )?;
+
+    // The limits of the --length flags of `password` and `passphrase`
+    let length = prompter.ask(
+        &format!("Password length (1-{})", config::MAX_LENGTH),
+        DEFAULT_LENGTH,
+        |n| (1..=config::MAX_LENGTH as usize).contains(n),
+    )?;
+    let words = prompter.ask(
+        &format!("Words per passphrase (1-{})", config::MAX_WORDS),
+        DEFAULT_WORDS,
+        |n| (1..=config::MAX_WORDS as usize).contains(n),
+    )?;
+
+    let names = wordlist_names();
+    let wordlist = prompter.ask(
+        &format!("Wordlist ({})", names.join(", ")),
+        names[0].clone(),
+        |name| names.contains(name),
+    )?;
+    let separator = prompter.answer("Passphrase separator", DEFAULT_SEPARATOR)?;
+    let copy = prompter.confirm("Copy results to the clipboard by default?", false)?;
+
+    Ok(Config {
+        defaults: Settings {
+            length: Some(length),
+            words: Some(words),
+            wordlist: Some(wordlist),
+            separator: Some(separator),
+            copy: Some(copy),
+            ..Settings::default()
+        },
+        ..Config::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wizard(input: &str) -> (Config, String) {
+        let mut output = Vec::new();
+        let config = run_wizard(&mut input.as_bytes(), &mut output).unwrap();
+        (config, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_defaults_on_empty_answers() {
+        let (config, _) = wizard("\n\n\n\n\n");
+        assert_eq!(config.defaults.length, Some(DEFAULT_LENGTH));
+        assert_eq!(config.defaults.words, Some(DEFAULT_WORDS));
+        assert_eq!(config.defaults.wordlist.as_deref(), Some("eff-large"));
+        assert_eq!(config.defaults.separator.as_deref(), Some("-"));
+        assert_eq!(config.defaults.copy, Some(false));
+
+        // End of input behaves like pressing Enter
+        assert_eq!(wizard("").0, config);
+    }
+
+    #[test]
+    fn test_answers_are_used() {
+        let (config, _) = wizard("20\n5\neff-short1\n.\ny\n");
+        assert_eq!(config.defaults.length, Some(20));
+        assert_eq!(config.defaults.words, Some(5));
+        assert_eq!(config.defaults.wordlist.as_deref(), Some("eff-short1"));
+        assert_eq!(config.defaults.separator.as_deref(), Some("."));
+        assert_eq!(config.defaults.copy, Some(true));
+    }
+
+    #[test]
+    fn test_invalid_answers_are_asked_again() {
+        let (config, output) = wizard("zero\n0\n16\n\nklingon\neff-short2\n\nmaybe\nno\n");
+        assert_eq!(config.defaults.length, Some(16));
+        assert_eq!(config.defaults.wordlist.as_deref(), Some("eff-short2"));
+        assert_eq!(config.defaults.copy, Some(false));
+        assert!(output.contains("`zero` is not a valid answer"));
+        assert!(output.contains("`0` is not a valid answer"));
+        assert!(output.contains("`klingon` is not a valid answer"));
+        assert!(output.contains("`maybe` is not a valid answer"));
+    }
+
+    #[test]
+    fn test_answers_are_bounded() {
+        let (config, output) = wizard(
+            "100000000
+4096
+65
+64
+
+
+
+",
+        );
+        assert_eq!(config.defaults.length, Some(4096));
+        assert_eq!(config.defaults.words, Some(64));
+        assert!(output.contains("`100000000` is not a valid answer"));
+        assert!(output.contains("`65` is not a valid answer"));
+    }
+
+    #[test]
+    fn test_written_config_validates() {
+        let (config, _) = wizard("20\n5\neff-short1\n.\ny\n");
+        let source = config.to_toml().unwrap();
+        assert!(config::validate(&source, None).is_empty(), "{}", source);
+        assert_eq!(Config::parse(&source).unwrap(), config);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::checker::Classification;
+use crate::passgen::clipboard;
+use crate::passgen::config;
+use crate::passgen::generate::Generator;
+use crate::passgen::passphrase::{self, Transform};
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use rand::CryptoRng;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use std::io;
+
+// Candidates shown at once
+const CANDIDATES: usize = 8;
+const HELP: &str = "↑/↓ select  ←/→ length  a alphabet  w word list  tab password/passphrase  \
+                    r regenerate  enter copy  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Password,
+    Passphrase,
+}
+
+/// State of `passgen interactive`: the settings being tweaked and the candidates drawn
+/// with them.
+pub struct App<R> {
+    mode: Mode,
+    length: usize,
+    words: usize,
+    separator: String,
+    alphabets: Vec<Alphabet>,
+    alphabet: usize,
+    wordlists: Vec<WordList>,
+    wordlist: usize,
+    candidates: Vec<String>,
+    entropy: f64,
+    selected: usize,
+    status: String,
+    rng: R,
+}
+
+impl<R: CryptoRng> App<R> {
+    /// Starts from the given settings. The built-in alphabets and word lists can be cycled
+    /// through, with `alphabet` and `wordlist` first among them if they are custom.
+    pub fn new(
+        length: usize,
+        alphabet: Alphabet,
+        words: usize,
+        wordlist: WordList,
+        separator: String,
+        rng: R,
+    ) -> Self {
+        let (alphabets, alphabet) = choices(alphabet);
+        let (wordlists, wordlist) = choices(wordlist);
+        let mut app = Self {
+            mode: Mode::Password,
+            length,
+            words,
+            separator,
+            alphabets,
+            alphabet,
+            wordlists,
+            wordlist,
+            candidates: Vec::new(),
+            entropy: 0.0,
+            selected: 0,
+            status: String::new(),
+            rng,
+        };
+        app.refresh();
+        app
+    }
+
+    /// Applies a key press, returning false once it asks to quit.
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return key.code != KeyCode::Char('c');
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.candidates.len().saturating_sub(1))
+            }
+            KeyCode::Left | KeyCode::Char('-') => self.resize(-1),
+            KeyCode::Right | KeyCode::Char('+') => self.resize(1),
+            KeyCode::Char('a') if self.mode == Mode::Password => {
+                self.alphabet = (self.alphabet + 1) % self.alphabets.len();
+                self.refresh();
+            }
+            KeyCode::Char('w') if self.mode == Mode::Passphrase => {
+                self.wordlist = (self.wordlist + 1) % self.wordlists.len();
+                self.refresh();
+            }
+            KeyCode::Tab => {
+                self.mode = match self.mode {
+                    Mode::Password => Mode::Passphrase,
+                    Mode::Passphrase => Mode::Password,
+                };
+                self.refresh();
+            }
+            KeyCode::Char('r') | KeyCode::Char(' ') => self.refresh(),
+            KeyCode::Enter | KeyCode::Char('c') => self.copy(),
+            _ => {}
+        }
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [summary, candidates, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(CANDIDATES as u16 + 2),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+        frame.render_widget(
+            Paragraph::new(self.summary()).block(Block::bordered().title(" passgen ")),
+            summary,
+        );
+        let list = List::new(self.candidates.iter().map(String::as_str))
+            .block(Block::bordered().title(" candidates "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, candidates, &mut state);
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(self.status.as_str()), Line::from(HELP)]),
+            help,
+        );
+    }
+
+    // The settings in effect and the entropy of each candidate
+    fn summary(&self) -> String {
+        let settings = match self.mode {
+            Mode::Password => {
+                let alphabet = &self.alphabets[self.alphabet];
+                format!(
+                    "password, {} characters of {} ({})",
+                    self.length,
+                    alphabet.name(),
+                    alphabet.len()
+                )
+            }
+            Mode::Passphrase => {
+                let wordlist = &self.wordlists[self.wordlist];
+                format!(
+                    "passphrase, {} words of {} ({})",
+                    self.words,
+                    wordlist
+                        .to_possible_value()
+                        .map_or("custom".to_string(), |v| v.get_name().to_string()),
+                    wordlist.words().len()
+                )
+            }
+        };
+        format!(
+            "{}: {:.1} bits, {:?}",
+            settings,
+            self.entropy,
+            Classification::from_entropy(self.entropy)
+        )
+    }
+
+    fn resize(&mut self, by: isize) {
+        let (size, max) = match self.mode {
+            Mode::Password => (&mut self.length, config::MAX_LENGTH as usize),
+            Mode::Passphrase => (&mut self.words, config::MAX_WORDS as usize),
+        };
+        *size = size.saturating_add_signed(by).clamp(1, max);
+        self.refresh();
+    }
+
+    // Draws new candidates, or reports why none can be drawn
+    fn refresh(&mut self) {
+        self.status.clear();
+        if let Err(e) = self.regenerate() {
+            self.candidates.clear();
+            self.status = e;
+        }
+        self.selected = self.selected.min(CANDIDATES - 1);
+    }
+
+    fn regenerate(&mut self) -> Result<(), String> {
+        self.candidates = match self.mode {
+            Mode::Password => {
+                let generator = Generator::new(&self.alphabets[self.alphabet])?;
+                self.entropy = generator.entropy(self.length);
+                (0..CANDIDATES)
+                    .map(|_| {
+                        let mut candidate = String::new();
+                        generator.generate_into(self.length, &mut self.rng, &mut candidate);
+                        candidate
+                    })
+                    .collect()
+            }
+            Mode::Passphrase => {
+                let wordlist = &self.wordlists[self.wordlist];
+                let transform = Transform::default();
+                self.entropy = passphrase::entropy(self.words, wordlist, &transform);
+                (0..CANDIDATES)
+                    .map(|_| {
+                        passphrase::generate_transformed(
+                            self.words,
+                            &self.separator,
+                            wordlist,
+                            &transform,
+                            &mut self.rng,
+                        )
+                        .map(|passphrase| passphrase.value.into_owned())
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(())
+    }
+
+    fn copy(&mut self) {
+        let Some(candidate) = self.candidates.get(self.selected) else {
+            return;
+        };
+        self.status = match clipboard::copy(candidate) {
+            Ok(()) => "Copied to the clipboard".to_string(),
+            Err(e) => format!("Cannot copy: {}", e),
+        };
+    }
+}
+
+/// Runs the app on the terminal until it is quit, restoring the terminal afterwards.
+pub fn run<R: CryptoRng>(app: &mut App<R>) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = (|| {
+        loop {
+            terminal.draw(|frame| app.render(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !app.on_key(key)
+            {
+                return Ok(());
+            }
+        }
+    })();
+    ratatui::try_restore()?;
+    result
+}
+
+// The built-in variants and the index of `chosen` among them, adding it first if custom
+fn choices<T: ValueEnum>(chosen: T) -> (Vec<T>, usize) {
+    let mut choices = T::value_variants().to_vec();
+    let name = chosen.to_possible_value().map(|v| v.get_name().to_string());
+    let index = choices
+        .iter()
+        .position(|choice| choice.to_possible_value().map(|v| v.get_name().to_string()) == name);
+    match (index, name) {
+        (Some(index), Some(_)) => (choices, index),
+        _ => {
+            choices.insert(0, chosen);
+            (choices, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn app(alphabet: Alphabet) -> App<ChaCha20Rng> {
+        let rng = ChaCha20Rng::from_seed([6; 32]);
+        App::new(12, alphabet, 3, WordList::default(), "-".into(), rng)
+    }
+
+    fn press(app: &mut App<ChaCha20Rng>, code: KeyCode) -> bool {
+        app.on_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_keys_change_settings() {
+        let mut app = app(Alphabet::default());
+        assert_eq!(app.candidates.len(), CANDIDATES);
+        assert!(app.candidates.iter().all(|c| c.chars().count() == 12));
+
+        press(&mut app, KeyCode::Right);
+        assert!(app.candidates.iter().all(|c| c.chars().count() == 13));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(
+            app.summary()
+                .starts_with("password, 13 characters of lower-case (26)")
+        );
+
+        let before = app.candidates.clone();
+        press(&mut app, KeyCode::Char('r'));
+        assert_ne!(app.candidates, before);
+
+        press(&mut app, KeyCode::Tab);
+        assert!(app.candidates.iter().all(|c| c.split('-').count() == 3));
+        assert!(
+            app.summary()
+                .starts_with("passphrase, 3 words of eff-large (7776)")
+        );
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected, 1);
+        assert!(!press(&mut app, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_custom_alphabet_comes_first() {
+        let mut app = app(Alphabet::Custom(String::new()));
+        assert!(app.candidates.is_empty());
+        assert!(!app.status.is_empty());
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.alphabets[app.alphabet].name(), "full");
+        assert_eq!(app.candidates.len(), CANDIDATES);
+    }
+
+    #[test]
+    fn test_render() {
+        let app = app(Alphabet::Digits);
+        let mut terminal = Terminal::new(TestBackend::new(100, 16)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("12 characters of digits (10): 39.9 bits, Medium"));
+        assert!(screen.contains(&format!("> {}", app.candidates[0])));
+    }
+}
@@ -0,0 +1,51 @@
+use clap::ValueEnum;
+use std::io::{self, IsTerminal};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per event, for log collectors
+    Json,
+}
+
+pub fn level_for_verbosity(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Installs the global subscriber. Diagnostics always go to stderr so they never mix with
+/// results, colored only when it is a terminal and `NO_COLOR` is not set.
+pub fn init(verbosity: u8, format: LogFormat) {
+    let color = io::stderr().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level_for_verbosity(verbosity))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(color)
+        .with_writer(io::stderr);
+
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_for_verbosity() {
+        assert_eq!(level_for_verbosity(0), LevelFilter::WARN);
+        assert_eq!(level_for_verbosity(1), LevelFilter::DEBUG);
+        assert_eq!(level_for_verbosity(2), LevelFilter::TRACE);
+        assert_eq!(level_for_verbosity(5), LevelFilter::TRACE);
+    }
+}
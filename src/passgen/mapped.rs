@@ -0,0 +1,185 @@
+use memmap2::Mmap;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// How the lines of a word file give their words.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileFormat {
+    /// One word per line
+    Plain,
+    /// EFF dice lines such as `11111<TAB>abacus`
+    Dice,
+    /// Some lines of each
+    Mixed,
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FileFormat::Plain => "one word per line",
+            FileFormat::Dice => "EFF dice lines",
+            FileFormat::Mixed => "words and EFF dice lines mixed",
+        })
+    }
+}
+
+/// A word list or breach corpus read from a file, one word per line, that is memory-mapped
+/// rather than read into memory so that lists of millions of entries stay cheap.
+///
+/// Lines are trimmed and blank ones skipped; in lines with a tab, the word is the text after
+/// the first tab, as in the EFF dice lists. Lines that are not valid UTF-8 are skipped.
+pub struct MappedWords {
+    path: PathBuf,
+    map: Mmap,
+    // Byte ranges of the words, built on first indexed access
+    index: OnceLock<Vec<Range<usize>>>,
+}
+
+impl MappedWords {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read, and a file truncated while mapped can at worst
+        // crash this short-lived process, which is the usual trade-off of mapping files
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            path: path.to_path_buf(),
+            map,
+            index: OnceLock::new(),
+        })
+    }
+
+    fn index(&self) -> &[Range<usize>] {
+        self.index.get_or_init(|| {
+            let _span =
+                tracing::debug_span!("index_wordlist", path = %self.path.display()).entered();
+            word_ranges(&self.map).collect()
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index().is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &str {
+        self.word(&self.index()[index])
+    }
+
+    /// A uniformly random word, `None` for a list without words.
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&str> {
+        self.index().choose(rng).map(|range| self.word(range))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the lines with words have dice indices, judged by every one of them; plain
+    /// for a file without words.
+    pub fn format(&self) -> FileFormat {
+        let (mut plain, mut dice) = (false, false);
+        for line in self.map.split(|&b| b == b'\n') {
+            match line.iter().position(|&b| b == b'\t') {
+                Some(tab) if !line[tab + 1..].trim_ascii().is_empty() => dice = true,
+                None if !line.trim_ascii().is_empty() => plain = true,
+                _ => {}
+            }
+        }
+        match (plain, dice) {
+            (true, true) => FileFormat::Mixed,
+            (false, true) => FileFormat::Dice,
+            _ => FileFormat::Plain,
+        }
+    }
+
+    fn word(&self, range: &Range<usize>) -> &str {
+        std::str::from_utf8(&self.map[range.clone()]).expect("validated when indexing")
+    }
+
+    /// The words in file order, read straight from the map without building the index.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        word_ranges(&self.map).map(|range| self.word(&range))
+    }
+}
+
+impl fmt::Debug for MappedWords {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MappedWords")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+fn word_ranges(data: &[u8]) -> impl Iterator<Item = Range<usize>> {
+    let mut start = 0;
+    data.split(|&b| b == b'\n').filter_map(move |line| {
+        let line_start = start;
+        start += line.len() + 1;
+
+        let word_start = match line.iter().position(|&b| b == b'\t') {
+            Some(tab) => tab + 1,
+            None => 0,
+        };
+        let word = line[word_start..].trim_ascii();
+        if word.is_empty() || std::str::from_utf8(word).is_err() {
+            return None;
+        }
+        // `word` is a subslice of `line`, so its offset follows from the pointers
+        let offset = word.as_ptr() as usize - line.as_ptr() as usize;
+        Some(line_start + offset..line_start + offset + word.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_mapped(contents: &[u8], name: &str, test: impl FnOnce(&MappedWords)) {
+        let path = std::env::temp_dir().join(format!("passgen-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        test(&MappedWords::open(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_words() {
+        let contents = b"123456\r\n  password \n\n11111\tabacus\n\xff\xfe\nqwerty";
+        with_mapped(contents, "mapped", |words| {
+            assert_eq!(
+                words.iter().collect::<Vec<_>>(),
+                ["123456", "password", "abacus", "qwerty"]
+            );
+            assert_eq!(words.len(), 4);
+            assert_eq!(words.get(2), "abacus");
+            assert_eq!(words.get(3), "qwerty");
+            assert_eq!(words.format(), FileFormat::Mixed);
+        });
+        with_mapped(
+            b"11111\tabacus\n11112\tabdomen\n\n",
+            "mapped-dice",
+            |words| {
+                assert_eq!(words.format(), FileFormat::Dice);
+            },
+        );
+    }
+
+    #[test]
+    fn test_empty_file() {
+        with_mapped(b"", "mapped-empty", |words| {
+            assert_eq!(words.len(), 0);
+            assert_eq!(words.iter().count(), 0);
+            assert_eq!(words.format(), FileFormat::Plain);
+        });
+    }
+}
@@ -0,0 +1,282 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::serve;
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+use tracing::{debug, warn};
+
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type Tool = fn(Value) -> Result<Value, String>;
+
+fn value_names<T: ValueEnum>(variants: &[T]) -> Vec<String> {
+    variants
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+fn tools() -> Vec<(&'static str, &'static str, Value, Tool)> {
+    let alphabet = json!({
+        "type": "string",
+        "enum": value_names(Alphabet::value_variants()),
+        "description": "Built-in alphabet, defaults to full"
+    });
+    let custom = json!({
+        "type": "string",
+        "description": "Custom characters to use instead of a built-in alphabet"
+    });
+    let count = json!({"type": "integer", "minimum": 1, "maximum": 100});
+
+    vec![
+        (
+            "generate_password",
+            "Generate random passwords locally with a cryptographically secure RNG",
+            json!({
+                "type": "object",
+                "properties": {
+                    "length": {"type": "integer", "minimum": 1, "maximum": 4096},
+                    "alphabet": alphabet,
+                    "custom": custom,
+                    "rules": {
+                        "type": "string",
+                        "description": "Site requirements in passwordrules syntax, e.g. \"minlength: 12; required: upper; required: digit\"; replaces alphabet and custom"
+                    },
+                    "count": count,
+                },
+                "additionalProperties": false
+            }),
+            serve::generate,
+        ),
+        (
+            "generate_passphrase",
+            "Generate diceware-style passphrases from the EFF word lists",
+            json!({
+                "type": "object",
+                "properties": {
+                    "words": {"type": "integer", "minimum": 1, "maximum": 64},
+                    "wordlist": {"type": "string", "enum": value_names(WordList::value_variants())},
+                    "separator": {"type": "string"},
+                    "count": count,
+                },
+                "additionalProperties": false
+            }),
+            serve::passphrase,
+        ),
+        (
+            "check_password",
+            "Check whether a password is a common word and classify its strength",
+            json!({
+                "type": "object",
+                "properties": {
+                    "password": {"type": "string"},
+                    "alphabet": alphabet,
+                    "custom": custom,
+                },
+                "required": ["password"],
+                "additionalProperties": false
+            }),
+            serve::check,
+        ),
+    ]
+}
+
+fn error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message.into()}
+    })
+}
+
+fn call_tool(params: &Value) -> Result<Value, (i64, String)> {
+    let name = params["name"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "missing tool name".to_string()))?;
+    let (_, _, _, tool) = tools()
+        .into_iter()
+        .find(|(tool_name, ..)| *tool_name == name)
+        .ok_or_else(|| (INVALID_PARAMS, format!("unknown tool `{}`", name)))?;
+
+    let arguments = match &params["arguments"] {
+        Value::Null => json!({}),
+        arguments => arguments.clone(),
+    };
+    // Tool failures are reported to the model as results, not protocol errors
+    Ok(match tool(arguments) {
+        Ok(result) => json!({
+            "content": [{"type": "text", "text": result.to_string()}],
+            "structuredContent": result,
+            "isError": false
+        }),
+        Err(message) => json!({
+            "content": [{"type": "text", "text": message}],
+            "isError": true
+        }),
+    })
+}
+
+/// Answers one JSON-RPC message; notifications get no reply.
+pub fn handle_message(line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let Some(method) = message["method"].as_str() else {
+        return Some(error(
+            message["id"].clone(),
+            INVALID_REQUEST,
+            "missing method",
+        ));
+    };
+    let id = message.get("id")?.clone();
+    debug!(method, "mcp request");
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "passgen", "version": env!("CARGO_PKG_VERSION")}
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({
+            "tools": tools()
+                .into_iter()
+                .map(|(name, description, schema, _)| json!({
+                    "name": name,
+                    "description": description,
+                    "inputSchema": schema
+                }))
+                .collect::<Vec<_>>()
+        })),
+        "tools/call" => call_tool(&message["params"]),
+        other => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", other))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => error(id, code, message),
+    })
+}
+
+/// Serves the Model Context Protocol over newline-delimited JSON-RPC until input ends.
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = handle_message(&line) {
+            if reply.get("error").is_some() {
+                warn!(error = %reply["error"], "mcp request failed");
+            }
+            serde_json::to_writer(&mut output, &reply)?;
+            writeln!(output)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, params: Value) -> Value {
+        let message = json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": params});
+        handle_message(&message.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_initialize() {
+        let reply = request("initialize", json!({"protocolVersion": PROTOCOL_VERSION}));
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["result"]["serverInfo"]["name"], "passgen");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+    }
+
+    #[test]
+    fn test_notifications_get_no_reply() {
+        let message = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(handle_message(&message.to_string()).is_none());
+    }
+
+    #[test]
+    fn test_tools_list() {
+        let reply = request("tools/list", json!({}));
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["generate_password", "generate_passphrase", "check_password"]
+        );
+    }
+
+    #[test]
+    fn test_tools_call() {
+        let reply = request(
+            "tools/call",
+            json!({"name": "generate_password", "arguments": {"length": 24}}),
+        );
+        let result = &reply["result"];
+        assert_eq!(result["isError"], false);
+        let password = result["structuredContent"]["passwords"][0]
+            .as_str()
+            .unwrap();
+        assert_eq!(password.len(), 24);
+
+        let reply = request("tools/call", json!({"name": "generate_passphrase"}));
+        assert_eq!(reply["result"]["isError"], false);
+
+        let reply = request(
+            "tools/call",
+            json!({"name": "check_password", "arguments": {"password": "password"}}),
+        );
+        assert_eq!(reply["result"]["structuredContent"]["safe"], false);
+    }
+
+    #[test]
+    fn test_tool_errors() {
+        let reply = request(
+            "tools/call",
+            json!({"name": "generate_password", "arguments": {"length": 0}}),
+        );
+        assert_eq!(reply["result"]["isError"], true);
+
+        let reply = request("tools/call", json!({"name": "nope"}));
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        assert_eq!(
+            request("nope", json!({}))["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(handle_message("{").unwrap()["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_run() {
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n\n\
+                     {\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n";
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n"
+        );
+    }
+}
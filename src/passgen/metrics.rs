@@ -0,0 +1,225 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const METHODS: &[&str] = &["generate", "passphrase", "check"];
+// Upper bounds in seconds of the latency histogram buckets
+const LATENCY_BUCKETS: &[f64] = &[0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    // (interface, method, outcome) -> count
+    requests: BTreeMap<(&'static str, String, &'static str), u64>,
+    // (interface, method) -> latency histogram
+    latencies: BTreeMap<(&'static str, String), Histogram>,
+    // strength class -> count
+    strengths: BTreeMap<String, u64>,
+}
+
+/// Operational counters for the long-running modes, rendered in the Prometheus text format.
+/// Only request shapes and outcomes are recorded, never generated or checked secrets.
+///
+/// Breach-check cache hits and misses are not counted yet: the server modes do no breach
+/// lookups, and [`BreachList`](crate::passgen::breach::BreachList) searches its file
+/// without a cache. Counters belong here once they gain one.
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+impl Metrics {
+    /// Records one request. `result` is the method's JSON result, or `None` when it failed;
+    /// for checks the strength classification feeds the strength distribution.
+    pub fn observe(
+        &self,
+        interface: &'static str,
+        method: &str,
+        result: Option<&Value>,
+        elapsed: Duration,
+    ) {
+        // Method names come from clients, so unknown ones share a label instead of
+        // growing the series without bound
+        let method = if METHODS.contains(&method) {
+            method
+        } else {
+            "unknown"
+        };
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let outcome = if result.is_some() { "ok" } else { "error" };
+        *inner
+            .requests
+            .entry((interface, method.to_string(), outcome))
+            .or_default() += 1;
+        inner
+            .latencies
+            .entry((interface, method.to_string()))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+
+        if method == "check"
+            && let Some(result) = result
+        {
+            let strength = match (&result["safe"], result["classification"].as_str()) {
+                (Value::Bool(false), _) => "unsafe".to_string(),
+                (_, Some(classification)) => snake_case(classification),
+                _ => return,
+            };
+            *inner.strengths.entry(strength).or_default() += 1;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        out.push_str(
+            "# HELP passgen_requests_total Requests handled, by interface, method, and outcome.\n",
+        );
+        out.push_str("# TYPE passgen_requests_total counter\n");
+        for ((interface, method, outcome), count) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "passgen_requests_total{{interface=\"{}\",method=\"{}\",outcome=\"{}\"}} {}",
+                interface, method, outcome, count
+            );
+        }
+
+        out.push_str("# HELP passgen_request_duration_seconds Time spent handling requests.\n");
+        out.push_str("# TYPE passgen_request_duration_seconds histogram\n");
+        for ((interface, method), histogram) in &inner.latencies {
+            let labels = format!("interface=\"{}\",method=\"{}\"", interface, method);
+            for (count, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "passgen_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "passgen_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "passgen_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "passgen_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        out.push_str(
+            "# HELP passgen_checked_passwords_total Checked passwords by strength class.\n",
+        );
+        out.push_str("# TYPE passgen_checked_passwords_total counter\n");
+        for (strength, count) in &inner.strengths {
+            let _ = writeln!(
+                out,
+                "passgen_checked_passwords_total{{strength=\"{}\"}} {}",
+                strength, count
+            );
+        }
+        out
+    }
+}
+
+// Label values follow Prometheus conventions: "VeryStrong" becomes "very_strong"
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        let ms = Duration::from_millis(2);
+        metrics.observe("http", "generate", Some(&json!({"passwords": ["x"]})), ms);
+        metrics.observe("http", "generate", None, ms);
+        metrics.observe(
+            "daemon",
+            "check",
+            Some(&json!({"safe": true, "classification": "VeryStrong"})),
+            ms,
+        );
+        metrics.observe(
+            "daemon",
+            "check",
+            Some(&json!({"safe": false, "finding": "common password"})),
+            ms,
+        );
+
+        let text = metrics.render();
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"http\",method=\"generate\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"http\",method=\"generate\",outcome=\"error\"} 1\n"
+        ));
+        assert!(text.contains(
+            "passgen_request_duration_seconds_bucket{interface=\"http\",method=\"generate\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "passgen_request_duration_seconds_bucket{interface=\"http\",method=\"generate\",le=\"0.005\"} 2\n"
+        ));
+        assert!(text.contains(
+            "passgen_request_duration_seconds_count{interface=\"daemon\",method=\"check\"} 2\n"
+        ));
+        assert!(text.contains("passgen_checked_passwords_total{strength=\"very_strong\"} 1\n"));
+        assert!(text.contains("passgen_checked_passwords_total{strength=\"unsafe\"} 1\n"));
+    }
+
+    #[test]
+    fn test_unknown_methods_share_a_label() {
+        let metrics = Metrics::default();
+        metrics.observe("daemon", "x\"}", None, Duration::ZERO);
+        metrics.observe("daemon", "nope", None, Duration::ZERO);
+        assert!(metrics.render().contains(
+            "passgen_requests_total{interface=\"daemon\",method=\"unknown\",outcome=\"error\"} 2\n"
+        ));
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("Weak"), "weak");
+        assert_eq!(snake_case("VeryStrong"), "very_strong");
+    }
+}
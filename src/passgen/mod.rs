@@ -1,7 +1,99 @@
 pub mod alphabet;
+#[cfg(feature = "cli")]
+pub mod audit;
+#[cfg(feature = "cli")]
+pub mod breach;
+#[cfg(feature = "cli")]
+pub mod bulk;
+#[cfg(feature = "cli")]
+pub mod cache;
 pub mod checker;
+#[cfg(feature = "cli")]
+pub mod clipboard;
+#[cfg(feature = "cli")]
+pub mod collide;
 pub mod commonwords;
+#[cfg(feature = "cli")]
+pub mod config;
+#[cfg(feature = "cli")]
+pub mod confirm;
+#[cfg(all(unix, feature = "cli"))]
+pub mod daemon;
+#[cfg(feature = "cli")]
+pub mod decoy;
+#[cfg(feature = "cli")]
+pub mod derive;
+#[cfg(feature = "cli")]
+pub mod dict;
+#[cfg(feature = "cli")]
+pub mod doctor;
+#[cfg(feature = "cli")]
+pub mod export;
 pub mod generate;
+#[cfg(feature = "cli")]
+pub mod hash;
+#[cfg(feature = "cli")]
+pub mod history;
+#[cfg(feature = "cli")]
+pub mod hook;
+#[cfg(feature = "serve")]
+pub mod http;
+#[cfg(feature = "cli")]
+pub mod id;
+#[cfg(feature = "cli")]
+pub mod init;
+#[cfg(feature = "cli")]
+pub mod interactive;
+#[cfg(feature = "cli")]
+pub mod logging;
+pub mod mapped;
+#[cfg(feature = "cli")]
+pub mod mcp;
+#[cfg(feature = "cli")]
+pub mod metrics;
+#[cfg(feature = "cli")]
+pub mod native_host;
+#[cfg(feature = "cli")]
+pub mod output;
 pub mod passphrase;
 pub mod password;
+pub mod patterns;
+#[cfg(feature = "cli")]
+pub mod policy;
+#[cfg(feature = "cli")]
+pub mod pronounce;
+#[cfg(feature = "cli")]
+pub mod recovery;
+pub mod resources;
+pub mod rng;
+#[cfg(feature = "cli")]
+pub mod rules;
+#[cfg(feature = "cli")]
+pub mod seed;
+#[cfg(feature = "cli")]
+pub mod selftest;
+#[cfg(feature = "cli")]
+pub mod serve;
+#[cfg(feature = "cli")]
+pub mod ssh_key;
+#[cfg(feature = "cli")]
+pub mod store;
+#[cfg(feature = "cli")]
+pub mod strengthen;
+#[cfg(feature = "cli")]
+pub mod systemd_creds;
+#[cfg(feature = "cli")]
+pub mod template;
+#[cfg(feature = "cli")]
+pub mod token;
+#[cfg(feature = "cli")]
+pub mod unique;
+#[cfg(feature = "cli")]
+pub mod update;
+#[cfg(feature = "cli")]
+pub mod variants;
+#[cfg(feature = "cli")]
+pub mod vault;
+#[cfg(feature = "cli")]
+pub mod which;
 pub mod wordlist;
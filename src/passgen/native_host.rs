@@ -0,0 +1,147 @@
+use crate::passgen::serve;
+use serde_json::{Value, json};
+use std::io::{self, Read, Write};
+use tracing::debug;
+
+// Browsers refuse messages from the host larger than 1 MiB
+const MAX_OUTGOING: usize = 1024 * 1024;
+// Requests are small JSON objects; guard against a corrupt length prefix
+const MAX_INCOMING: u32 = 64 * 1024;
+
+/// Reads one message: a native-endian `u32` length followed by UTF-8 JSON.
+/// Returns `None` once the browser closes stdin.
+pub fn read_message(reader: &mut impl Read) -> io::Result<Option<Value>> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_ne_bytes(header);
+    if len > MAX_INCOMING {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "message of {} bytes exceeds the {} byte limit",
+                len, MAX_INCOMING
+            ),
+        ));
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    serde_json::from_slice(&payload)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let payload = message.to_string();
+    if payload.len() > MAX_OUTGOING {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "reply exceeds the browser's 1 MiB message limit",
+        ));
+    }
+    writer.write_all(&(payload.len() as u32).to_ne_bytes())?;
+    writer.write_all(payload.as_bytes())?;
+    writer.flush()
+}
+
+/// Answers a request like `{"id": 1, "method": "generate", "params": {"rules": "minlength: 12"}}`.
+/// The `id`, if any, is echoed so the extension can match replies to requests.
+pub fn handle_message(message: &Value) -> Value {
+    let id = message.get("id").cloned().unwrap_or(Value::Null);
+    let params = match &message["params"] {
+        Value::Null => json!({}),
+        params => params.clone(),
+    };
+    let result = match message["method"].as_str() {
+        Some(method) => serve::call(method, params),
+        None => Err("missing method".to_string()),
+    };
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(message) => json!({ "id": id, "error": message }),
+    }
+}
+
+/// Exchanges messages with the browser over stdin and stdout until the extension disconnects.
+pub fn run(mut input: impl Read, mut output: impl Write) -> io::Result<()> {
+    while let Some(message) = read_message(&mut input)? {
+        let reply = handle_message(&message);
+        debug!(
+            error = reply.get("error").is_some(),
+            "native messaging request"
+        );
+        write_message(&mut output, &reply)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: &Value) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, message).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_message_roundtrip() {
+        let message = json!({"method": "generate"});
+        let buffer = frame(&message);
+        assert_eq!(
+            &buffer[..4],
+            &(message.to_string().len() as u32).to_ne_bytes()
+        );
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let mut reader: &[u8] = &(MAX_INCOMING + 1).to_ne_bytes();
+        assert!(read_message(&mut reader).is_err());
+
+        let mut buffer = 3u32.to_ne_bytes().to_vec();
+        buffer.extend(b"{{{");
+        assert!(read_message(&mut buffer.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_handle_message_echoes_id() {
+        let reply = handle_message(&json!({
+            "id": "req-1",
+            "method": "generate",
+            "params": {"rules": "minlength: 14; maxlength: 14; required: upper; required: digit"}
+        }));
+        assert_eq!(reply["id"], "req-1");
+        let password = reply["result"]["passwords"][0].as_str().unwrap();
+        assert_eq!(password.len(), 14);
+
+        let reply = handle_message(&json!({"id": 2, "method": "nope"}));
+        assert_eq!(reply["id"], 2);
+        assert!(reply["error"].is_string());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut input = frame(&json!({"id": 1, "method": "passphrase"}));
+        input.extend(frame(
+            &json!({"id": 2, "method": "check", "params": {"password": "password"}}),
+        ));
+        let mut output = Vec::new();
+        run(input.as_slice(), &mut output).unwrap();
+
+        let mut reader = output.as_slice();
+        let first = read_message(&mut reader).unwrap().unwrap();
+        assert!(first["result"]["passphrases"].is_array());
+        let second = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(second["result"]["safe"], false);
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+}
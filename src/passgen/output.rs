@@ -0,0 +1,491 @@
+use crate::passgen::clipboard;
+use crate::passgen::confirm::WriteMode;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    #[default]
+    Plain,
+    /// One JSON object per line
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum Destination {
+    #[default]
+    Stdout,
+    File(PathBuf),
+    /// The clipboard, cleared again after the given time if one is set
+    Clipboard(Option<Duration>),
+}
+
+enum Sink {
+    Stdout(BufWriter<io::Stdout>),
+    File(BufWriter<File>),
+    Buffer(Vec<u8>),
+    Discard,
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Stdout(w) => w.write(buf),
+            Sink::File(w) => w.write(buf),
+            Sink::Buffer(w) => w.write(buf),
+            Sink::Discard => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Stdout(w) => w.flush(),
+            Sink::File(w) => w.flush(),
+            Sink::Buffer(_) | Sink::Discard => Ok(()),
+        }
+    }
+}
+
+/// A record holding a secret that people may need to read out, e.g. over the phone.
+pub trait Secret: Serialize + Display {
+    fn secret(&self) -> &str;
+}
+
+/// Single route for command results: renders records in the chosen format and
+/// delivers them to stdout, a file, or the clipboard.
+pub struct Output {
+    format: Format,
+    sink: Sink,
+    terminal: bool,
+    csv_columns: Option<Vec<String>>,
+    clear_after: Option<Duration>,
+    phonetic: bool,
+    color: bool,
+}
+
+/// Colors plain results can be shown in on a terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Yellow,
+    Green,
+}
+
+impl Color {
+    /// `text` in this color when `enabled`, otherwise unchanged.
+    pub fn paint(self, text: impl Display, enabled: bool) -> String {
+        if !enabled {
+            return text.to_string();
+        }
+        let code = match self {
+            Color::Red => 31,
+            Color::Yellow => 33,
+            Color::Green => 32,
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+}
+
+/// A bar of `width` marks filled in proportion to `entropy` out of `full` bits, e.g.
+/// `[#####-----]` for half.
+pub fn entropy_bar(entropy: f64, full: f64, width: usize) -> String {
+    let filled = ((entropy / full).clamp(0.0, 1.0) * width as f64).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+impl Output {
+    pub fn new(format: Format, destination: Destination, mode: &WriteMode) -> io::Result<Self> {
+        let clear_after = match destination {
+            Destination::Clipboard(clear_after) => clear_after,
+            _ => None,
+        };
+        let sink = match destination {
+            Destination::Stdout => Sink::Stdout(BufWriter::new(io::stdout())),
+            Destination::Clipboard(_) => Sink::Buffer(Vec::new()),
+            Destination::File(path) => {
+                // New files are written directly; replacing one goes through confirmation
+                let action = if path.exists() {
+                    format!("overwrite {}", path.display())
+                } else {
+                    format!("write output to {}", path.display())
+                };
+                if (mode.dry_run || path.exists()) && !mode.approve(&action)? {
+                    Sink::Discard
+                } else {
+                    Sink::File(BufWriter::new(create_private(&path)?))
+                }
+            }
+        };
+        let terminal = matches!(sink, Sink::Stdout(_)) && io::stdout().is_terminal();
+
+        Ok(Self {
+            format,
+            sink,
+            terminal,
+            csv_columns: None,
+            clear_after,
+            phonetic: false,
+            color: false,
+        })
+    }
+
+    /// Colors plain results going straight to a terminal, unless `color` is false or the
+    /// `NO_COLOR` environment variable is set.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color
+            && self.terminal
+            && self.format == Format::Plain
+            && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+        self
+    }
+
+    /// Spells out the secrets of plain results with [`phonetic`] on a line after each,
+    /// except in the clipboard, which gets the secrets alone.
+    pub fn with_phonetic(mut self, phonetic: bool) -> Self {
+        self.phonetic = phonetic && !matches!(self.sink, Sink::Buffer(_));
+        self
+    }
+
+    #[cfg(test)]
+    fn buffered(format: Format) -> Self {
+        Self {
+            format,
+            sink: Sink::Buffer(Vec::new()),
+            terminal: false,
+            csv_columns: None,
+            clear_after: None,
+            phonetic: false,
+            color: false,
+        }
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Whether plain results may use [`Color`]s.
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    pub fn emit<R: Serialize + Display>(&mut self, record: &R) -> io::Result<()> {
+        match self.format {
+            Format::Plain => writeln!(self.sink, "{}", record),
+            Format::Json => {
+                serde_json::to_writer(&mut self.sink, record)?;
+                writeln!(self.sink)
+            }
+            Format::Csv => self.write_csv(record),
+        }
+    }
+
+    /// Like [`Output::emit`], also spelling the secret out if asked to.
+    pub fn emit_secret<R: Secret>(&mut self, record: &R) -> io::Result<()> {
+        self.emit(record)?;
+        if self.phonetic {
+            self.note(phonetic(record.secret()))?;
+        }
+        Ok(())
+    }
+
+    /// Extra human-oriented text such as totals; omitted from structured formats.
+    pub fn note(&mut self, text: impl Display) -> io::Result<()> {
+        if self.format == Format::Plain {
+            writeln!(self.sink, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    /// Writes an already-formatted document, such as a vault import file, verbatim.
+    pub fn write_document(&mut self, document: &str) -> io::Result<()> {
+        self.sink.write_all(document.as_bytes())
+    }
+
+    fn write_csv<R: Serialize>(&mut self, record: &R) -> io::Result<()> {
+        let Value::Object(fields) = serde_json::to_value(record)? else {
+            return Err(io::Error::other("CSV output requires records with fields"));
+        };
+
+        if self.csv_columns.is_none() {
+            let columns: Vec<String> = fields.keys().cloned().collect();
+            writeln!(self.sink, "{}", columns.join(","))?;
+            self.csv_columns = Some(columns);
+        }
+
+        let row: Vec<String> = self
+            .csv_columns
+            .iter()
+            .flatten()
+            .map(|column| csv_escape(&csv_cell(fields.get(column).unwrap_or(&Value::Null))))
+            .collect();
+        writeln!(self.sink, "{}", row.join(","))
+    }
+
+    /// Pushes buffered results out now, for long-running commands.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.sink.flush()?;
+        if let Sink::Buffer(buffer) = &self.sink {
+            let text = String::from_utf8_lossy(buffer);
+            let text = text.trim_end_matches('\n');
+            clipboard::copy(text)?;
+            if let Some(delay) = self.clear_after {
+                clipboard::clear_after(text, delay)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reports a failure on stderr, since results may be going to a file or the clipboard:
+/// as `Error: ...` text, or in the structured formats as a JSON `{"error": ...}` object.
+pub fn report_error(format: Format, error: impl Display) {
+    let message = error.to_string();
+    match format {
+        Format::Plain => eprintln!("Error: {}", message),
+        Format::Json | Format::Csv => eprintln!("{}", serde_json::json!({ "error": message })),
+    }
+}
+
+// Files may receive secrets, so new ones are readable only by their owner
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Spells `text` out for reading aloud: letters in the NATO phonetic alphabet, upper case
+/// ones in capitals, and digits and symbols by name, e.g. `K - KILO, 7 - Seven, @ - At sign`.
+pub fn phonetic(text: &str) -> String {
+    text.chars()
+        .map(|c| match spoken(c) {
+            Some(name) if c.is_ascii_uppercase() => format!("{} - {}", c, name.to_uppercase()),
+            Some(name) => format!("{} - {}", c, name),
+            None => format!("{} - U+{:04X}", c, c as u32),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn spoken(c: char) -> Option<&'static str> {
+    const LETTERS: [&str; 26] = [
+        "alfa", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india",
+        "juliett", "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo",
+        "sierra", "tango", "uniform", "victor", "whiskey", "x-ray", "yankee", "zulu",
+    ];
+    const DIGITS: [&str; 10] = [
+        "Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine",
+    ];
+    if c.is_ascii_alphabetic() {
+        return Some(LETTERS[(c.to_ascii_lowercase() as u8 - b'a') as usize]);
+    }
+    if let Some(digit) = c.to_digit(10) {
+        return Some(DIGITS[digit as usize]);
+    }
+    Some(match c {
+        ' ' => "Space",
+        '!' => "Exclamation mark",
+        '"' => "Double quote",
+        '#' => "Hash",
+        '$' => "Dollar sign",
+        '%' => "Percent sign",
+        '&' => "Ampersand",
+        '\'' => "Apostrophe",
+        '(' => "Left parenthesis",
+        ')' => "Right parenthesis",
+        '*' => "Asterisk",
+        '+' => "Plus sign",
+        ',' => "Comma",
+        '-' => "Hyphen",
+        '.' => "Period",
+        '/' => "Slash",
+        ':' => "Colon",
+        ';' => "Semicolon",
+        '<' => "Less-than sign",
+        '=' => "Equals sign",
+        '>' => "Greater-than sign",
+        '?' => "Question mark",
+        '@' => "At sign",
+        '[' => "Left bracket",
+        '\\' => "Backslash",
+        ']' => "Right bracket",
+        '^' => "Caret",
+        '_' => "Underscore",
+        '`' => "Backtick",
+        '{' => "Left brace",
+        '|' => "Vertical bar",
+        '}' => "Right brace",
+        '~' => "Tilde",
+        _ => return None,
+    })
+}
+
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(csv_cell).collect::<Vec<_>>().join(";"),
+        other => other.to_string(),
+    }
+}
+
+pub fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[derive(Serialize)]
+    struct Item {
+        name: &'static str,
+        size: usize,
+        tags: Vec<&'static str>,
+        note: Option<&'static str>,
+    }
+
+    impl fmt::Display for Item {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} ({})", self.name, self.size)
+        }
+    }
+
+    fn render(format: Format, items: &[Item]) -> String {
+        let mut output = Output::buffered(format);
+        for item in items {
+            output.emit(item).unwrap();
+        }
+        output.note("total").unwrap();
+        match output.sink {
+            Sink::Buffer(buffer) => String::from_utf8(buffer).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "a",
+                size: 1,
+                tags: vec!["x", "y"],
+                note: None,
+            },
+            Item {
+                name: "b,c",
+                size: 2,
+                tags: vec![],
+                note: Some("say \"hi\""),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(render(Format::Plain, &items()), "a (1)\nb,c (2)\ntotal\n");
+    }
+
+    #[test]
+    fn test_json_lines() {
+        assert_eq!(
+            render(Format::Json, &items()),
+            "{\"name\":\"a\",\"size\":1,\"tags\":[\"x\",\"y\"],\"note\":null}\n\
+             {\"name\":\"b,c\",\"size\":2,\"tags\":[],\"note\":\"say \\\"hi\\\"\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            render(Format::Csv, &items()),
+            "name,size,tags,note\na,1,x;y,\n\"b,c\",2,,\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_phonetic() {
+        assert_eq!(
+            phonetic("Kk7@ é"),
+            "K - KILO, k - kilo, 7 - Seven, @ - At sign,   - Space, é - U+00E9"
+        );
+        assert_eq!(phonetic("X-"), "X - X-RAY, - - Hyphen");
+
+        struct Code(&'static str);
+        impl Serialize for Code {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_str(self.0)
+            }
+        }
+        impl fmt::Display for Code {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+        impl Secret for Code {
+            fn secret(&self) -> &str {
+                self.0
+            }
+        }
+        let mut output = Output::buffered(Format::Plain);
+        output.phonetic = true;
+        output.emit_secret(&Code("a1")).unwrap();
+        let Sink::Buffer(buffer) = &output.sink else {
+            unreachable!()
+        };
+        assert_eq!(buffer, b"a1\na - alfa, 1 - One\n");
+        assert!(!Output::buffered(Format::Plain).with_phonetic(true).phonetic);
+    }
+
+    #[test]
+    fn test_color() {
+        assert_eq!(Color::Red.paint("weak", true), "\x1b[31mweak\x1b[0m");
+        assert_eq!(Color::Green.paint("strong", false), "strong");
+        assert!(!Output::buffered(Format::Plain).with_color(true).color());
+        assert_eq!(entropy_bar(50.0, 100.0, 10), "[#####-----]");
+        assert_eq!(entropy_bar(0.0, 100.0, 4), "[----]");
+        assert_eq!(entropy_bar(250.0, 100.0, 4), "[####]");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("passgen-output-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mode = WriteMode::new(false, false);
+        let output = Output::new(Format::Plain, Destination::File(path.clone()), &mode).unwrap();
+        output.finish().unwrap();
+        let permissions = std::fs::metadata(&path).unwrap().permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o600);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_csv_requires_fields() {
+        let mut output = Output::buffered(Format::Csv);
+        assert!(output.emit(&"plain string").is_err());
+    }
+}
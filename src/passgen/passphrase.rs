@@ -1,28 +1,374 @@
 // Or for lazy loading:
 
+use crate::passgen::checker;
 use crate::passgen::password::Password;
-use crate::passgen::wordlist::WordList;
-use rand::Rng;
+use crate::passgen::wordlist::{Typing, WordList};
+use clap::ValueEnum;
+use rand::seq::IndexedRandom;
+use rand::{CryptoRng, Rng};
+use std::iter;
 
-pub fn generate_passphrase(
+const DIGITS: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+const SYMBOLS: &[char] = &['!', '@', '#', '$', '%', '^', '&', '*'];
+
+/// Which words of a passphrase start with a capital letter.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Capitalize {
+    /// Each word with even odds, adding a bit of entropy per word
+    Random,
+    /// The first word only
+    First,
+    /// Every word
+    All,
+}
+
+/// Where the padding of a passphrase goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum PadPosition {
+    /// After the last word
+    #[default]
+    End,
+    /// Before the first word
+    Start,
+    /// The whole pad on each side
+    Both,
+    /// One of the others at random, adding log2(3) bits
+    Random,
+}
+
+/// A run of one character repeated around a passphrase, e.g.
+/// `correct-horse-battery!!!!!!!!`. It is easy to remember and adds length and the classes
+/// many policies require, but only its choice of character and position add entropy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Padding {
+    pub length: usize,
+    /// The character repeated; a random digit or symbol if not given
+    pub char: Option<char>,
+    pub position: PadPosition,
+}
+
+impl Padding {
+    fn apply<R: CryptoRng + ?Sized>(&self, passphrase: &mut String, rng: &mut R) {
+        let c = self.char.unwrap_or_else(|| {
+            let choices = DIGITS.len() + SYMBOLS.len();
+            let index = rng.random_range(0..choices);
+            *DIGITS
+                .iter()
+                .chain(SYMBOLS)
+                .nth(index)
+                .expect("index is in range")
+        });
+        let position = match self.position {
+            PadPosition::Random => *[PadPosition::End, PadPosition::Start, PadPosition::Both]
+                .choose(rng)
+                .expect("positions are not empty"),
+            position => position,
+        };
+        let pad: String = iter::repeat_n(c, self.length).collect();
+        if matches!(position, PadPosition::Start | PadPosition::Both) {
+            passphrase.insert_str(0, &pad);
+        }
+        if matches!(position, PadPosition::End | PadPosition::Both) {
+            passphrase.push_str(&pad);
+        }
+    }
+
+    /// Entropy in bits of the random choices: the character unless given, and the
+    /// position for [`PadPosition::Random`]. The length is taken to be known.
+    pub fn entropy(&self) -> f64 {
+        if self.length == 0 {
+            return 0.0;
+        }
+        let mut bits = 0.0;
+        if self.char.is_none() {
+            bits += ((DIGITS.len() + SYMBOLS.len()) as f64).log2();
+        }
+        if self.position == PadPosition::Random {
+            bits += 3f64.log2();
+        }
+        bits
+    }
+}
+
+/// Changes to the words of a passphrase that mix in the character classes many sites
+/// require.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transform {
+    pub capitalize: Option<Capitalize>,
+    /// Append a random digit to a random word
+    pub add_digit: bool,
+    /// Append a random symbol to a random word
+    pub add_symbol: bool,
+    /// Pad the joined words
+    pub pad: Option<Padding>,
+    /// Draw words with double letters, such as `fiddle`, half as often as the others
+    pub easy_type: bool,
+    /// Join each pair of words with a random one of these characters instead of the
+    /// separator, see [`separator_set`]
+    pub separators: Option<Vec<char>>,
+}
+
+impl Transform {
+    fn apply<R: CryptoRng + ?Sized>(&self, words: &mut [String], rng: &mut R) {
+        match self.capitalize {
+            Some(Capitalize::Random) => {
+                for word in words.iter_mut() {
+                    if rng.random() {
+                        capitalize(word);
+                    }
+                }
+            }
+            Some(Capitalize::First) => words.iter_mut().take(1).for_each(capitalize),
+            Some(Capitalize::All) => words.iter_mut().for_each(capitalize),
+            None => {}
+        }
+        for (enabled, chars) in [(self.add_digit, DIGITS), (self.add_symbol, SYMBOLS)] {
+            if enabled && !words.is_empty() {
+                let word = rng.random_range(0..words.len());
+                words[word].push(*chars.choose(rng).expect("character sets are not empty"));
+            }
+        }
+    }
+
+    /// Entropy in bits the changes add to a passphrase of `word_count` words from
+    /// `wordlist`: one bit for each word random capitalization can change, the choice of
+    /// character and word for each one appended, that of each random separator, and that
+    /// of the padding.
+    pub fn entropy(&self, word_count: usize, wordlist: &WordList) -> f64 {
+        let mut bits = 0.0;
+        if self.capitalize == Some(Capitalize::Random) {
+            let words = wordlist.words();
+            let capitalizable = words.iter().filter(|word| has_capital(word)).count();
+            bits += word_count as f64 * capitalizable as f64 / words.len().max(1) as f64;
+        }
+        for (enabled, chars) in [(self.add_digit, DIGITS), (self.add_symbol, SYMBOLS)] {
+            if enabled && word_count > 0 {
+                bits += ((chars.len() * word_count) as f64).log2();
+            }
+        }
+        if let Some(separators) = &self.separators {
+            bits += word_count.saturating_sub(1) as f64 * (separators.len() as f64).log2();
+        }
+        bits + self.pad.map_or(0.0, |pad| pad.entropy())
+    }
+}
+
+fn has_capital(word: &str) -> bool {
+    word.chars()
+        .next()
+        .is_some_and(|first| !first.to_uppercase().eq([first]))
+}
+
+fn capitalize(word: &mut String) {
+    if let Some(first) = word.chars().next() {
+        let upper: String = first.to_uppercase().collect();
+        word.replace_range(..first.len_utf8(), &upper);
+    }
+}
+
+/// The characters a `--separator` of `random-digit`, `random-symbol` or `random:CHARS`,
+/// e.g. `random:-_.,`, picks each separator of a passphrase from, or `None` for a
+/// separator used as given, which includes an unprefixed run of characters such as `-_.,`.
+/// A set needs at least two characters to add entropy.
+pub fn separator_set(separator: &str) -> Result<Option<Vec<char>>, String> {
+    let mut chars: Vec<char> = match separator {
+        "random-digit" => DIGITS.to_vec(),
+        "random-symbol" => SYMBOLS.to_vec(),
+        _ => match separator.strip_prefix("random:") {
+            Some(chars) => chars.chars().collect(),
+            None => return Ok(None),
+        },
+    };
+    chars.sort_unstable();
+    chars.dedup();
+    if chars.len() < 2 {
+        return Err(format!(
+            "`{}` needs at least two different characters to pick separators from",
+            separator
+        ));
+    }
+    Ok(Some(chars))
+}
+
+/// Joins `word_count` words chosen uniformly from `wordlist` with `separator`. Fails for an
+/// empty word list.
+pub fn generate_passphrase<R: CryptoRng + ?Sized>(
+    word_count: usize,
+    separator: &str,
+    wordlist: &WordList,
+    rng: &mut R,
+) -> Result<Password<'static>, String> {
+    generate_transformed(word_count, separator, wordlist, &Transform::default(), rng)
+}
+
+/// Like [`generate_passphrase`], with `transform` applied to the chosen words.
+pub fn generate_transformed<R: CryptoRng + ?Sized>(
     word_count: usize,
     separator: &str,
     wordlist: &WordList,
-) -> Password<'static> {
+    transform: &Transform,
+    rng: &mut R,
+) -> Result<Password<'static>, String> {
+    generate_words(word_count, separator, wordlist, transform, rng)
+        .map(|(passphrase, _)| passphrase)
+}
+
+/// Like [`generate_transformed`], also giving the words as chosen from the list, before
+/// `transform` changed them.
+#[tracing::instrument(level = "debug", skip_all, fields(word_count = word_count))]
+pub fn generate_words<'w, R: CryptoRng + ?Sized>(
+    word_count: usize,
+    separator: &str,
+    wordlist: &'w WordList,
+    transform: &Transform,
+    rng: &mut R,
+) -> Result<(Password<'static>, Vec<&'w str>), String> {
     let words = wordlist.words();
-    if words.is_empty() || word_count == 0 {
-        return Password::new("");
+    if words.is_empty() {
+        return Err("the word list is empty".to_string());
     }
 
-    let mut rng = rand::rng();
-    let passphrase_parts: Vec<&str> = (0..word_count)
+    let chosen: Vec<&str> = (0..word_count)
         .map(|_| {
-            let idx = rng.random_range(0..words.len());
-            words[idx]
+            loop {
+                let word = words.choose(rng).expect("word list is not empty");
+                // Keeping words with double letters half the time halves their weight
+                if !transform.easy_type || !Typing::of(word).double_letters || rng.random() {
+                    break word;
+                }
+            }
         })
         .collect();
+    let mut passphrase_parts: Vec<String> = chosen.iter().map(|word| word.to_string()).collect();
+    transform.apply(&mut passphrase_parts, rng);
+    let mut passphrase = match &transform.separators {
+        Some(separators) => {
+            let mut joined = String::new();
+            for (i, part) in passphrase_parts.iter().enumerate() {
+                if i > 0 {
+                    joined.push(
+                        *separators
+                            .choose(rng)
+                            .expect("separator sets are not empty"),
+                    );
+                }
+                joined.push_str(part);
+            }
+            joined
+        }
+        None => passphrase_parts.join(separator),
+    };
+    if let Some(pad) = &transform.pad {
+        pad.apply(&mut passphrase, rng);
+    }
+
+    Ok((Password::new(passphrase), chosen))
+}
 
-    Password::new(passphrase_parts.join(separator))
+/// Words of a built-in list selected by physical dice, read from whitespace-separated rolls
+/// such as `52434 11356`, one roll per word.
+pub fn dice_words(rolls: &str, wordlist: &WordList) -> Result<Vec<&'static str>, String> {
+    if wordlist.source().is_none() {
+        return Err("only the built-in word lists have dice indices".to_string());
+    }
+    let words = rolls
+        .split_whitespace()
+        .map(|roll| {
+            if !roll.bytes().all(|b| (b'1'..=b'6').contains(&b)) {
+                return Err(format!("`{}` is not a dice roll", roll));
+            }
+            wordlist
+                .dice_word(roll)
+                .ok_or_else(|| format!("the word list has no word for the roll {}", roll))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if words.is_empty() {
+        return Err("no dice rolls were given".to_string());
+    }
+    Ok(words)
+}
+
+/// The fewest words from `wordlist` that, changed by `transform`, reach `bits` of entropy.
+/// Fails for word lists of fewer than two words, which add no entropy.
+pub fn words_for_entropy(
+    bits: f64,
+    wordlist: &WordList,
+    transform: &Transform,
+) -> Result<usize, String> {
+    let per_word = word_entropy(wordlist, transform);
+    if per_word < 1e-9 {
+        return Err("the word list needs at least two words to add entropy".to_string());
+    }
+    // Allow for rounding, as checker::length_for_entropy does
+    let words = ((bits / per_word - 1e-9).ceil() as usize).max(1);
+    // The changes only add entropy, so at most as many words are needed without them
+    Ok((1..words)
+        .find(|&count| entropy(count, wordlist, transform) >= bits - 1e-9)
+        .unwrap_or(words))
+}
+
+/// Most characters a passphrase of `word_count` words has besides the words themselves:
+/// the separators, the digit and symbol `transform` appends, and its padding.
+pub fn extra_chars(word_count: usize, separator: &str, transform: &Transform) -> usize {
+    let separator = match transform.separators {
+        Some(_) => 1,
+        None => separator.chars().count(),
+    };
+    let pad = transform.pad.map_or(0, |pad| match pad.position {
+        PadPosition::End | PadPosition::Start => pad.length,
+        PadPosition::Both | PadPosition::Random => 2 * pad.length,
+    });
+    word_count.saturating_sub(1) * separator
+        + usize::from(transform.add_digit)
+        + usize::from(transform.add_symbol)
+        + pad
+}
+
+/// The words of `wordlist` short enough that any passphrase of `word_count` of them,
+/// joined with `separator` and changed by `transform`, has at most `max_chars` characters.
+/// Fails when none are.
+pub fn fit_chars(
+    max_chars: usize,
+    word_count: usize,
+    separator: &str,
+    wordlist: &WordList,
+    transform: &Transform,
+) -> Result<WordList, String> {
+    let room = max_chars.saturating_sub(extra_chars(word_count, separator, transform));
+    let fitting = wordlist.with_word_lengths(1..=room / word_count.max(1));
+    if fitting.words().is_empty() {
+        let shortest = wordlist.stats().min_length;
+        return Err(format!(
+            "{} words of at least {} characters cannot fit in {} characters",
+            word_count, shortest, max_chars
+        ));
+    }
+    Ok(fitting)
+}
+
+/// Entropy in bits of a passphrase of `word_count` words from `wordlist`, changed by
+/// `transform`.
+pub fn entropy(word_count: usize, wordlist: &WordList, transform: &Transform) -> f64 {
+    word_count as f64 * word_entropy(wordlist, transform) + transform.entropy(word_count, wordlist)
+}
+
+// Entropy of each word: that of a uniform choice from the list, or with `easy_type` that of
+// guessing one of the likelier words without double letters, which weigh twice as much
+fn word_entropy(wordlist: &WordList, transform: &Transform) -> f64 {
+    let words = wordlist.words();
+    if !transform.easy_type {
+        return checker::passphrase_entropy(1, words.len());
+    }
+    let doubled = words
+        .iter()
+        .filter(|word| Typing::of(word).double_letters)
+        .count();
+    let easy = words.len() - doubled;
+    match (easy, doubled) {
+        (0, 0) => 0.0,
+        (0, doubled) => (doubled as f64).log2(),
+        (easy, doubled) => (easy as f64 + doubled as f64 / 2.0).log2(),
+    }
 }
 
 #[cfg(test)]
@@ -39,7 +385,7 @@ mod tests {
         ];
         let wordlist = WordList::from_custom(custom_words);
 
-        let passphrase = generate_passphrase(3, "-", &wordlist);
+        let passphrase = generate_passphrase(3, "-", &wordlist, &mut rand::rng()).unwrap();
 
         assert!(!passphrase.value.is_empty());
         assert_eq!(passphrase.value.matches('-').count(), 2); // 3 words = 2 separators
@@ -57,7 +403,7 @@ mod tests {
         let custom_words = vec!["word1".to_string(), "word2".to_string()];
         let wordlist = WordList::from_custom(custom_words);
 
-        let passphrase = generate_passphrase(2, "_", &wordlist);
+        let passphrase = generate_passphrase(2, "_", &wordlist, &mut rand::rng()).unwrap();
 
         assert!(passphrase.value.contains('_'));
         assert!(!passphrase.value.contains('-'));
@@ -68,7 +414,7 @@ mod tests {
         let custom_words = vec!["single".to_string()];
         let wordlist = WordList::from_custom(custom_words);
 
-        let passphrase = generate_passphrase(1, "-", &wordlist);
+        let passphrase = generate_passphrase(1, "-", &wordlist, &mut rand::rng()).unwrap();
 
         assert_eq!(passphrase.value.as_ref(), "single");
         assert!(!passphrase.value.contains('-'));
@@ -79,9 +425,9 @@ mod tests {
         let empty_words = vec![];
         let wordlist = WordList::from_custom(empty_words);
 
-        let passphrase = generate_passphrase(3, "-", &wordlist);
+        let error = generate_passphrase(3, "-", &wordlist, &mut rand::rng()).unwrap_err();
 
-        assert!(passphrase.value.is_empty());
+        assert_eq!(error, "the word list is empty");
     }
 
     #[test]
@@ -89,7 +435,7 @@ mod tests {
         let custom_words = vec!["test".to_string()];
         let wordlist = WordList::from_custom(custom_words);
 
-        let passphrase = generate_passphrase(0, "-", &wordlist);
+        let passphrase = generate_passphrase(0, "-", &wordlist, &mut rand::rng()).unwrap();
 
         assert!(passphrase.value.is_empty());
     }
@@ -108,7 +454,12 @@ mod tests {
 
         // Generate multiple passphrases and check they're not all identical
         let passphrases: Vec<String> = (0..10)
-            .map(|_| generate_passphrase(3, "-", &wordlist).value.into_owned())
+            .map(|_| {
+                generate_passphrase(3, "-", &wordlist, &mut rand::rng())
+                    .unwrap()
+                    .value
+                    .into_owned()
+            })
             .collect();
 
         // With 6 words choosing 3, we should get some variation
@@ -118,4 +469,264 @@ mod tests {
             "Generated passphrases should show randomness"
         );
     }
+
+    #[test]
+    fn test_generate_passphrase_is_uniform() {
+        use crate::passgen::selftest::{chi_square, chi_square_critical};
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let words: Vec<String> = (0..8).map(|i| format!("word{}", i)).collect();
+        let wordlist = WordList::from_custom(words.clone());
+        let passphrase =
+            generate_passphrase(40_000, " ", &wordlist, &mut ChaCha20Rng::from_seed([5; 32]))
+                .unwrap();
+        let mut counts = vec![0u64; words.len()];
+        for word in passphrase.value.split(' ') {
+            counts[words.iter().position(|w| w == word).unwrap()] += 1;
+        }
+        assert!(chi_square(&counts) < chi_square_critical(words.len() - 1));
+    }
+
+    #[test]
+    fn test_words_for_entropy() {
+        // log2(7776) is about 12.9 bits per word
+        let none = Transform::default();
+        assert_eq!(words_for_entropy(77.0, &WordList::EffLarge, &none), Ok(6));
+        assert!((entropy(6, &WordList::EffLarge, &none) - 77.55).abs() < 0.01);
+        let single = WordList::from_custom(vec!["only".to_string()]);
+        assert!(words_for_entropy(10.0, &single, &none).is_err());
+    }
+
+    #[test]
+    fn test_dice_words() {
+        let words = dice_words("52434 11356\n66666\n", &WordList::EffLarge).unwrap();
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[2], "zoom");
+        assert_eq!(words[0], WordList::EffLarge.dice_word("52434").unwrap());
+
+        assert!(dice_words("1111", &WordList::EffLarge).is_err());
+        assert!(
+            dice_words("11170", &WordList::EffLarge)
+                .unwrap_err()
+                .contains("11170")
+        );
+        assert!(dice_words("  \n", &WordList::EffLarge).is_err());
+        let custom = WordList::from_custom(vec!["a".to_string()]);
+        assert!(dice_words("11111", &custom).is_err());
+    }
+
+    #[test]
+    fn test_transform() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let wordlist = WordList::from_custom(vec!["alpha".to_string(), "beta".to_string()]);
+        let mut rng = ChaCha20Rng::from_seed([9; 32]);
+        let all = Transform {
+            capitalize: Some(Capitalize::All),
+            add_digit: true,
+            add_symbol: true,
+            ..Transform::default()
+        };
+        for _ in 0..50 {
+            let value = generate_transformed(4, " ", &wordlist, &all, &mut rng)
+                .unwrap()
+                .value;
+            let words: Vec<&str> = value.split(' ').collect();
+            assert!(words.iter().all(|w| w.starts_with(['A', 'B'])), "{}", value);
+            assert_eq!(value.chars().filter(char::is_ascii_digit).count(), 1);
+            assert_eq!(value.chars().filter(|c| SYMBOLS.contains(c)).count(), 1);
+        }
+
+        let first = Transform {
+            capitalize: Some(Capitalize::First),
+            ..Transform::default()
+        };
+        let value = generate_transformed(3, "-", &wordlist, &first, &mut rng)
+            .unwrap()
+            .value;
+        assert_eq!(value.chars().filter(char::is_ascii_uppercase).count(), 1);
+        assert!(value.starts_with(['A', 'B']));
+
+        let (passphrase, words) = generate_words(3, "-", &wordlist, &first, &mut rng).unwrap();
+        assert_eq!(passphrase.value.to_lowercase(), words.join("-"));
+    }
+
+    #[test]
+    fn test_transform_entropy() {
+        let wordlist = WordList::from_custom(vec!["alpha".to_string(), "2fa".to_string()]);
+        assert_eq!(Transform::default().entropy(4, &wordlist), 0.0);
+        // Only half the words have a capital form
+        let random = Transform {
+            capitalize: Some(Capitalize::Random),
+            ..Transform::default()
+        };
+        assert!((random.entropy(4, &wordlist) - 2.0).abs() < 1e-9);
+        // A digit on one of 4 words, and a symbol on one of 4 words
+        let appended = Transform {
+            add_digit: true,
+            add_symbol: true,
+            ..Transform::default()
+        };
+        assert!((appended.entropy(4, &wordlist) - (40f64.log2() + 32f64.log2())).abs() < 1e-9);
+        assert!((entropy(4, &wordlist, &appended) - (4.0 + 40f64.log2() + 5.0)).abs() < 1e-9);
+
+        // 5 words of 12.9 bits fall short of 70, but a digit and symbol on them reach it
+        assert_eq!(
+            words_for_entropy(70.0, &WordList::EffLarge, &appended),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn test_padding() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let wordlist = WordList::from_custom(vec!["alpha".to_string()]);
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let padded = |pad: Padding, rng: &mut ChaCha20Rng| {
+            let transform = Transform {
+                pad: Some(pad),
+                ..Transform::default()
+            };
+            generate_transformed(2, "-", &wordlist, &transform, rng)
+                .unwrap()
+                .value
+                .into_owned()
+        };
+        let bang = Padding {
+            length: 4,
+            char: Some('!'),
+            position: PadPosition::End,
+        };
+        assert_eq!(padded(bang, &mut rng), "alpha-alpha!!!!");
+        let both = Padding {
+            position: PadPosition::Both,
+            ..bang
+        };
+        assert_eq!(padded(both, &mut rng), "!!!!alpha-alpha!!!!");
+        assert_eq!(bang.entropy(), 0.0);
+
+        let random = Padding {
+            length: 3,
+            char: None,
+            position: PadPosition::Random,
+        };
+        let mut positions = [false; 3];
+        for _ in 0..50 {
+            let value = padded(random, &mut rng);
+            let pad = value.replace("alpha-alpha", "");
+            assert!(
+                pad.chars().all(|c| c == pad.chars().next().unwrap()),
+                "{}",
+                value
+            );
+            let position = match (value.starts_with('a'), value.ends_with('a')) {
+                (true, false) => 0,
+                (false, true) => 1,
+                _ => 2,
+            };
+            assert_eq!(pad.len(), if position == 2 { 6 } else { 3 }, "{}", value);
+            positions[position] = true;
+        }
+        assert_eq!(positions, [true; 3]);
+        assert!((random.entropy() - (18f64.log2() + 3f64.log2())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_easy_type_weighting() {
+        let words = ["ab", "cd", "ee", "ff"].map(String::from).to_vec();
+        let wordlist = WordList::from_custom(words);
+        let easy = Transform {
+            easy_type: true,
+            ..Transform::default()
+        };
+        // Guessing ab or cd first succeeds 1 time in 3, as from 3 equally likely words
+        assert!((entropy(2, &wordlist, &easy) - 2.0 * 3f64.log2()).abs() < 1e-9);
+        assert_eq!(words_for_entropy(4.0, &wordlist, &easy), Ok(3));
+        assert_eq!(
+            words_for_entropy(4.0, &wordlist, &Transform::default()),
+            Ok(2)
+        );
+
+        let mut rng = rand::rng();
+        let value = generate_transformed(3000, " ", &wordlist, &easy, &mut rng)
+            .unwrap()
+            .value;
+        let doubled = value
+            .split(' ')
+            .filter(|word| *word == "ee" || *word == "ff");
+        let share = doubled.count() as f64 / 3000.0;
+        assert!((share - 1.0 / 3.0).abs() < 0.05, "{}", share);
+
+        let single = WordList::from_custom(vec!["ee".to_string()]);
+        assert!(words_for_entropy(10.0, &single, &easy).is_err());
+    }
+
+    #[test]
+    fn test_random_separators() {
+        assert_eq!(separator_set("-"), Ok(None));
+        assert_eq!(separator_set(" - "), Ok(None));
+        assert_eq!(separator_set("-_.,"), Ok(None));
+        assert_eq!(separator_set("random-digit"), Ok(Some(DIGITS.to_vec())));
+        assert_eq!(separator_set("random:.-_-"), Ok(Some(vec!['-', '.', '_'])));
+        assert!(separator_set("random:--").is_err());
+        assert!(separator_set("random:").is_err());
+
+        let wordlist = WordList::from_custom(vec!["ab".to_string(), "cd".to_string()]);
+        let digits = Transform {
+            separators: separator_set("random-digit").unwrap(),
+            ..Transform::default()
+        };
+        // One word choice per word, and one of 10 digits between each pair
+        let expected = 4.0 + 3.0 * 10f64.log2();
+        assert!((entropy(4, &wordlist, &digits) - expected).abs() < 1e-9);
+        assert_eq!(digits.entropy(1, &wordlist), 0.0);
+
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            let value = generate_transformed(4, "-", &wordlist, &digits, &mut rng)
+                .unwrap()
+                .value;
+            let chars: Vec<char> = value.chars().collect();
+            assert_eq!(chars.len(), 11, "{}", value);
+            assert!(
+                [2, 5, 8].iter().all(|&i| chars[i].is_ascii_digit()),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_fit_chars() {
+        let pad = Transform {
+            add_digit: true,
+            pad: Some(Padding {
+                length: 2,
+                char: Some('!'),
+                position: PadPosition::Both,
+            }),
+            ..Transform::default()
+        };
+        // Two separators, the digit and a pad at each end
+        assert_eq!(extra_chars(3, "-", &pad), 7);
+        assert_eq!(extra_chars(3, " - ", &Transform::default()), 6);
+
+        let fitting = fit_chars(22, 3, "-", &WordList::EffLarge, &pad).unwrap();
+        assert!(fitting.words().iter().all(|word| word.chars().count() <= 5));
+        let mut rng = rand::rng();
+        for _ in 0..50 {
+            let value = generate_transformed(3, "-", &fitting, &pad, &mut rng)
+                .unwrap()
+                .value;
+            assert!(value.chars().count() <= 22, "{}", value);
+        }
+        assert_eq!(
+            fit_chars(9, 3, "-", &WordList::EffLarge, &Transform::default()).map(|_| ()),
+            Err("3 words of at least 3 characters cannot fit in 9 characters".to_string())
+        );
+    }
 }
@@ -1,10 +1,30 @@
 use std::borrow::Cow;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+/// A generated or user-supplied password or passphrase, borrowed where possible. Its
+/// `Debug` output leaves the value out, so logging it at any level never shows it.
+#[derive(PartialEq)]
 pub struct Password<'a> {
     pub value: Cow<'a, str>,
 }
 
+impl fmt::Debug for Password<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Password")
+            .field("value", &Redacted(&self.value))
+            .finish()
+    }
+}
+
+/// Stands in for a secret in the `Debug` output of the types holding one.
+pub struct Redacted<'a>(pub &'a str);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<redacted, {} characters>", self.0.chars().count())
+    }
+}
+
 impl<'a> Password<'a> {
     pub fn new(value: impl Into<Cow<'a, str>>) -> Self {
         Self {
@@ -12,3 +32,14 @@ impl<'a> Password<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let debug = format!("{:?}", Password::new("s3cret!"));
+        assert_eq!(debug, "Password { value: <redacted, 7 characters> }");
+    }
+}
@@ -0,0 +1,593 @@
+use serde::Serialize;
+use std::fmt;
+
+// Rows of a US QWERTY keyboard, unshifted, with how far each row's first key is shifted
+// right of the row above's, in quarter keys
+const KEYBOARD_ROWS: &[(&str, usize)] = &[
+    ("`1234567890-=", 0),
+    ("qwertyuiop[]\\", 6),
+    ("asdfghjkl;'", 1),
+    ("zxcvbnm,./", 2),
+];
+
+// Shorter runs are too common by chance in random passwords to count against them
+const MIN_LENGTH: usize = 3;
+
+// Kinds of personal information accepted in a context, e.g. `name=alice`
+const CONTEXT_KEYS: &[&str] = &["name", "username", "email", "company", "birth"];
+
+// Common substitutions of digits and symbols for letters, each with the letter it stands for
+const LEET: &[(char, char)] = &[
+    ('4', 'a'),
+    ('@', 'a'),
+    ('8', 'b'),
+    ('3', 'e'),
+    ('6', 'g'),
+    ('1', 'i'),
+    ('!', 'i'),
+    ('l', 'i'),
+    ('|', 'i'),
+    ('0', 'o'),
+    ('5', 's'),
+    ('$', 's'),
+    ('7', 't'),
+    ('+', 't'),
+    ('2', 'z'),
+];
+
+// Years a date may be in
+const YEARS: std::ops::RangeInclusive<u32> = 1900..=2099;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+// Parts of a numeric date, in the orders people write them
+#[derive(Clone, Copy)]
+enum Part {
+    Day,
+    Month,
+    Year,
+}
+
+const DATE_ORDERS: [[Part; 3]; 3] = [
+    [Part::Day, Part::Month, Part::Year],
+    [Part::Month, Part::Day, Part::Year],
+    [Part::Year, Part::Month, Part::Day],
+];
+const DATE_SEPARATORS: [Option<char>; 4] = [None, Some('-'), Some('/'), Some('.')];
+
+/// A predictable run of characters that a guesser tries long before random strings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pattern {
+    /// One character repeated, e.g. `aaaa`
+    Repeat,
+    /// A block of characters repeated, e.g. `abcabc`
+    RepeatedBlock,
+    /// Consecutive letters or digits, e.g. `abcd` or `9876`
+    Sequence,
+    /// Neighbouring keys along or across keyboard rows, e.g. `qwerty`, `lkjh` or `1qaz`
+    KeyboardWalk,
+    /// Something about the password's owner from a [`PersonalContext`], possibly reversed
+    /// or in leetspeak, e.g. `3c1l4` for `alice`
+    PersonalInfo,
+    /// A year from 1900 to 2099, a date of one in any order of day, month and year, or a
+    /// month's name with a year, e.g. `2024`, `01011990` or `jan1990`
+    Date,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Pattern::Repeat => "repeated character",
+            Pattern::RepeatedBlock => "repeated block",
+            Pattern::Sequence => "sequence",
+            Pattern::KeyboardWalk => "keyboard walk",
+            Pattern::PersonalInfo => "personal information",
+            Pattern::Date => "date",
+        })
+    }
+}
+
+/// Where a pattern occurs in a password, in characters rather than bytes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternMatch {
+    pub pattern: Pattern,
+    pub start: usize,
+    pub length: usize,
+    // Characters in the repeated unit: 1 for a repeated character, the block's length for
+    // a repeated block, the number of tokens guessed from for personal information, and
+    // the number of dates of its form for a date
+    #[serde(skip)]
+    block: usize,
+}
+
+impl PatternMatch {
+    /// Entropy in bits of the matched characters when each other character carries
+    /// `per_char` bits: the pattern's first character or block, plus its length and, for
+    /// sequences and walks, direction.
+    pub fn entropy(&self, per_char: f64) -> f64 {
+        let length = (self.length as f64).log2();
+        match self.pattern {
+            Pattern::Repeat => per_char + length,
+            Pattern::RepeatedBlock => {
+                self.block as f64 * per_char + ((self.length / self.block) as f64).log2()
+            }
+            Pattern::Sequence | Pattern::KeyboardWalk => per_char + length + 1.0,
+            // Which token, whether reversed, and whether in leetspeak
+            Pattern::PersonalInfo => (self.block as f64).log2() + 2.0,
+            Pattern::Date => (self.block as f64).log2(),
+        }
+    }
+
+    fn overlaps(&self, start: usize, length: usize) -> bool {
+        start < self.start + self.length && self.start < start + length
+    }
+}
+
+/// What is known about a password's owner, which guessers try first: zxcvbn's "user
+/// inputs".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersonalContext {
+    // Case folded and with leetspeak undone
+    tokens: Vec<Vec<char>>,
+}
+
+impl PersonalContext {
+    /// Parses entries such as `name=Alice Smith`, `email=alice@example.com` or
+    /// `birth=1990-05-17`. Names are split into words and emails into their local part's
+    /// words and domain name; pieces shorter than 3 characters are left out.
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Self, String> {
+        let mut context = Self::default();
+        for entry in entries {
+            let entry = entry.as_ref();
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("`{}` is not of the form key=value", entry))?;
+            let key = key.trim().to_lowercase();
+            if !CONTEXT_KEYS.contains(&key.as_str()) {
+                return Err(format!(
+                    "unknown context `{}`; expected one of {}",
+                    key,
+                    CONTEXT_KEYS.join(", ")
+                ));
+            }
+            let value = match (key.as_str(), value.split_once('@')) {
+                // The domain's name without its top-level domain, e.g. `example`
+                ("email", Some((local, domain))) => {
+                    let name = domain.split('.').next().unwrap_or_default();
+                    format!("{} {}", local, name)
+                }
+                _ => value.to_string(),
+            };
+            context.add(&value);
+        }
+        Ok(context)
+    }
+
+    fn add(&mut self, value: &str) {
+        let whole: String = value.chars().filter(|c| c.is_alphanumeric()).collect();
+        let pieces = value.split(|c: char| !c.is_alphanumeric());
+        for piece in pieces.chain([whole.as_str()]) {
+            let token: Vec<char> = piece.chars().map(canonical).collect();
+            if token.len() >= MIN_LENGTH && !self.tokens.contains(&token) {
+                self.tokens.push(token);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+// Lowercase letter a character is or stands in for, so `Alice`, `4l1c3` and `ALICE` match
+fn canonical(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    LEET.iter()
+        .find(|(leet, _)| *leet == c)
+        .map_or(c, |(_, letter)| *letter)
+}
+
+/// Finds the longest non-overlapping occurrences of `context`'s tokens in the password,
+/// forwards or reversed.
+pub fn find_personal(password: &str, context: &PersonalContext) -> Vec<PatternMatch> {
+    let chars: Vec<char> = password.chars().map(canonical).collect();
+    let mut candidates = Vec::new();
+    for token in &context.tokens {
+        let reversed: Vec<char> = token.iter().rev().copied().collect();
+        for needle in [token, &reversed] {
+            for start in 0..chars.len().saturating_sub(needle.len() - 1) {
+                if chars[start..].starts_with(needle) {
+                    candidates.push((start, needle.len()));
+                }
+            }
+        }
+    }
+    candidates.sort_by_key(|&(start, length)| (std::cmp::Reverse(length), start));
+    let mut matches: Vec<PatternMatch> = Vec::new();
+    for (start, length) in candidates {
+        if !matches.iter().any(|found| found.overlaps(start, length)) {
+            matches.push(PatternMatch {
+                pattern: Pattern::PersonalInfo,
+                start,
+                length,
+                block: context.tokens.len(),
+            });
+        }
+    }
+    matches.sort_by_key(|found| found.start);
+    matches
+}
+
+/// Patterns of [`find_patterns`] that do not overlap the personal information found, and
+/// that information, in order.
+pub fn find_all(password: &str, context: &PersonalContext) -> Vec<PatternMatch> {
+    let mut matches = find_personal(password, context);
+    let personal = matches.clone();
+    matches.extend(find_patterns(password).into_iter().filter(|found| {
+        !personal
+            .iter()
+            .any(|info| info.overlaps(found.start, found.length))
+    }));
+    matches.sort_by_key(|found| found.start);
+    matches
+}
+
+/// The dates among [`find_patterns`], e.g. `2024` in `summer2024`.
+pub fn find_dates(password: &str) -> Vec<PatternMatch> {
+    find_patterns(password)
+        .into_iter()
+        .filter(|found| found.pattern == Pattern::Date)
+        .collect()
+}
+
+/// Finds non-overlapping patterns from left to right, taking the longest one at each
+/// position.
+pub fn find_patterns(password: &str) -> Vec<PatternMatch> {
+    let chars: Vec<char> = password.chars().collect();
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        match longest_at(&chars, start) {
+            Some(found) => {
+                start += found.length;
+                matches.push(found);
+            }
+            None => start += 1,
+        }
+    }
+    matches
+}
+
+fn longest_at(chars: &[char], start: usize) -> Option<PatternMatch> {
+    let rest = &chars[start..];
+    let sequence = run(rest, is_step).max(run(rest, |a, b| is_step(b, a)));
+    let candidates = [
+        date(rest),
+        (Pattern::Repeat, run(rest, |a, b| a == b), 1),
+        (Pattern::Sequence, sequence, 1),
+        (Pattern::KeyboardWalk, run(rest, adjacent_keys), 1),
+        repeated_block(rest),
+    ];
+    // The first of equally long candidates wins
+    let (pattern, length, block) = candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|&(_, length, _)| length)?;
+    (length >= MIN_LENGTH).then_some(PatternMatch {
+        pattern,
+        start,
+        length,
+        block,
+    })
+}
+
+// The longest date at the start of `chars`, with the number of dates of its form, as a
+// candidate of `longest_at`
+fn date(chars: &[char]) -> (Pattern, usize, usize) {
+    let years = YEARS.count();
+    let year = number(chars, 0, 4)
+        .filter(|year| YEARS.contains(year))
+        .map(|_| (4, years));
+    let numeric = DATE_SEPARATORS
+        .iter()
+        .flat_map(|&separator| {
+            DATE_ORDERS
+                .iter()
+                .filter_map(move |order| numeric_date(chars, order, separator))
+        })
+        .max()
+        .map(|length| {
+            (
+                length,
+                years * 366 * DATE_ORDERS.len() * DATE_SEPARATORS.len(),
+            )
+        });
+    let (length, dates) = [year, numeric, month_date(chars)]
+        .into_iter()
+        .flatten()
+        .max_by_key(|&(length, _)| length)
+        .unwrap_or((0, 1));
+    (Pattern::Date, length, dates)
+}
+
+// Length of a date of day, month and year in `order` at the start of `chars`, e.g.
+// `17.05.1990`, with two digits for the day and month and four for the year
+fn numeric_date(chars: &[char], order: &[Part; 3], separator: Option<char>) -> Option<usize> {
+    let (mut day, mut month, mut year) = (0, 0, 0);
+    let mut at = 0;
+    for (i, part) in order.iter().enumerate() {
+        if let Some(separator) = separator.filter(|_| i > 0) {
+            if chars.get(at) != Some(&separator) {
+                return None;
+            }
+            at += 1;
+        }
+        let (value, digits) = match part {
+            Part::Day => (&mut day, 2),
+            Part::Month => (&mut month, 2),
+            Part::Year => (&mut year, 4),
+        };
+        *value = number(chars, at, digits)?;
+        at += digits;
+    }
+    is_date(day, month, year).then_some(at)
+}
+
+// Length and number of dates of its form of a month's name or its first three letters at
+// the start of `chars`, followed by a year, optionally after a day, e.g. `Jan1990`,
+// `march171990` or `may90`
+fn month_date(chars: &[char]) -> Option<(usize, usize)> {
+    let lower: String = chars
+        .iter()
+        .take(9)
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    let (month, name) = MONTHS.iter().enumerate().find_map(|(month, name)| {
+        [*name, &name[..3]]
+            .into_iter()
+            .find(|prefix| lower.starts_with(prefix))
+            .map(|prefix| (month as u32 + 1, prefix.chars().count()))
+    })?;
+    let years = YEARS.count();
+    // A day, then a four-digit year; or a four- or two-digit year alone
+    let day_then_year = (1..=2).rev().find_map(|digits| {
+        let day = number(chars, name, digits)?;
+        let year = number(chars, name + digits, 4)?;
+        is_date(day, month, year).then_some((name + digits + 4, years * 31))
+    });
+    let year = number(chars, name, 4)
+        .filter(|year| YEARS.contains(year))
+        .map(|_| (name + 4, years))
+        .or_else(|| number(chars, name, 2).map(|_| (name + 2, 100)));
+    let (length, dates) = day_then_year.or(year)?;
+    Some((length, dates * MONTHS.len() * 2))
+}
+
+// The number written with `digits` ASCII digits at `at` in `chars`, if they are digits
+fn number(chars: &[char], at: usize, digits: usize) -> Option<u32> {
+    chars
+        .get(at..at + digits)?
+        .iter()
+        .try_fold(0, |value, c| Some(value * 10 + c.to_digit(10)?))
+}
+
+fn is_date(day: u32, month: u32, year: u32) -> bool {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    YEARS.contains(&year) && (1..=days).contains(&day)
+}
+
+// Length of the longest prefix of `chars` whose neighbouring characters all satisfy `step`
+fn run(chars: &[char], step: impl Fn(char, char) -> bool) -> usize {
+    if chars.is_empty() {
+        return 0;
+    }
+    let steps = chars.windows(2).take_while(|pair| step(pair[0], pair[1]));
+    1 + steps.count()
+}
+
+fn is_step(a: char, b: char) -> bool {
+    a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() && b as u32 == a as u32 + 1
+}
+
+// Row and horizontal position in quarter keys of a key
+fn key_position(key: char) -> Option<(usize, usize)> {
+    let key = key.to_ascii_lowercase();
+    let mut offset = 0;
+    for (row, (keys, shift)) in KEYBOARD_ROWS.iter().enumerate() {
+        offset += shift;
+        if let Some(column) = keys.find(key) {
+            return Some((row, offset + 4 * column));
+        }
+    }
+    None
+}
+
+// Whether the keys touch: next to each other in a row, or less than a key apart
+// horizontally in neighbouring rows
+fn adjacent_keys(a: char, b: char) -> bool {
+    match (key_position(a), key_position(b)) {
+        (Some((row_a, x_a)), Some((row_b, x_b))) => match row_a.abs_diff(row_b) {
+            0 => x_a.abs_diff(x_b) == 4,
+            1 => x_a.abs_diff(x_b) < 4,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// The longest run of a block of two or more characters repeated back to back, as a
+// candidate of `longest_at`
+fn repeated_block(chars: &[char]) -> (Pattern, usize, usize) {
+    (2..=chars.len() / 2)
+        .map(|block| {
+            let copies = chars
+                .chunks_exact(block)
+                .take_while(|chunk| *chunk == &chars[..block])
+                .count();
+            (
+                Pattern::RepeatedBlock,
+                if copies >= 2 { block * copies } else { 0 },
+                block,
+            )
+        })
+        .rev()
+        .max_by_key(|&(_, length, _)| length)
+        .unwrap_or((Pattern::RepeatedBlock, 0, 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(password: &str) -> Vec<(Pattern, usize, usize)> {
+        find_patterns(password)
+            .into_iter()
+            .map(|m| (m.pattern, m.start, m.length))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_patterns() {
+        assert_eq!(found("aaaaaaaa"), [(Pattern::Repeat, 0, 8)]);
+        assert_eq!(found("xabcdefx"), [(Pattern::Sequence, 1, 6)]);
+        assert_eq!(found("98765"), [(Pattern::Sequence, 0, 5)]);
+        assert_eq!(found("Qwerty!"), [(Pattern::KeyboardWalk, 0, 6)]);
+        assert_eq!(found("7890"), [(Pattern::KeyboardWalk, 0, 4)]);
+        assert_eq!(found("asdfgh"), [(Pattern::KeyboardWalk, 0, 6)]);
+        // Down the columns
+        assert_eq!(
+            found("1qaz2wsx"),
+            [(Pattern::KeyboardWalk, 0, 4), (Pattern::KeyboardWalk, 4, 4)]
+        );
+        assert_eq!(found("zaq1"), [(Pattern::KeyboardWalk, 0, 4)]);
+        assert_eq!(found("987654"), [(Pattern::Sequence, 0, 6)]);
+        assert_eq!(found("xyzxyzxyz"), [(Pattern::RepeatedBlock, 0, 9)]);
+        assert_eq!(
+            found("zz!asdf#1111"),
+            [(Pattern::KeyboardWalk, 3, 4), (Pattern::Repeat, 8, 4)]
+        );
+        // Characters, not bytes
+        assert_eq!(found("ééé"), [(Pattern::Repeat, 0, 3)]);
+        // Pairs happen by chance
+        assert!(found("aa-ab-qw-Xk9#mP2$vL").is_empty());
+        assert!(found("").is_empty());
+    }
+
+    #[test]
+    fn test_adjacent_keys() {
+        for (a, b) in [
+            ('q', 'w'),
+            ('1', 'q'),
+            ('2', 'q'),
+            ('w', 'a'),
+            ('s', 'z'),
+            ('S', 'x'),
+        ] {
+            assert!(adjacent_keys(a, b), "{} {}", a, b);
+            assert!(adjacent_keys(b, a), "{} {}", b, a);
+        }
+        for (a, b) in [
+            ('q', 's'),
+            ('q', 'e'),
+            ('a', 'x'),
+            ('1', 'a'),
+            ('q', 'q'),
+            ('!', 'q'),
+        ] {
+            assert!(!adjacent_keys(a, b), "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(found("summer2024"), [(Pattern::Date, 6, 4)]);
+        assert_eq!(found("Jan1990"), [(Pattern::Date, 0, 7)]);
+        assert_eq!(found("may90"), [(Pattern::Date, 0, 5)]);
+        assert_eq!(found("september171990x"), [(Pattern::Date, 0, 15)]);
+        // Day and month either way round, or year first
+        assert_eq!(found("x01021990"), [(Pattern::Date, 1, 8)]);
+        assert_eq!(found("12311999"), [(Pattern::Date, 0, 8)]);
+        assert_eq!(found("1990-05-17"), [(Pattern::Date, 0, 10)]);
+        assert_eq!(found("17.05.1990"), [(Pattern::Date, 0, 10)]);
+        // Not a leap year, so only the year is a date
+        assert_eq!(found("29021900"), [(Pattern::Date, 4, 4)]);
+        assert!(found("1899").is_empty());
+        assert!(found("2150").is_empty());
+        assert!(found("marble").is_empty());
+
+        let year = &find_patterns("2024")[0];
+        assert!((year.entropy(6.0) - 200f64.log2()).abs() < 1e-9);
+        assert_eq!(
+            find_dates("a2024b01011990"),
+            find_patterns("a2024b01011990")
+        );
+    }
+
+    #[test]
+    fn test_personal_context() {
+        let context = PersonalContext::parse(&[
+            "name=Alice Smith",
+            "email=a.smith@example.com",
+            "birth=1990-05-17",
+        ])
+        .unwrap();
+        let found = |password| -> Vec<(usize, usize)> {
+            find_personal(password, &context)
+                .into_iter()
+                .map(|m| (m.start, m.length))
+                .collect()
+        };
+        assert_eq!(found("xALICEx"), [(1, 5)]);
+        assert_eq!(found("4l1c3!"), [(0, 5)]);
+        // Reversed
+        assert_eq!(found("ecila"), [(0, 5)]);
+        assert_eq!(found("Smith1990"), [(0, 5), (5, 4)]);
+        assert_eq!(found("example"), [(0, 7)]);
+        assert_eq!(found("alicesmith"), [(0, 10)]);
+        assert!(found("kq7#Zm2!pX").is_empty());
+
+        assert!(PersonalContext::parse(&["nickname=al"]).is_err());
+        assert!(PersonalContext::parse(&["alice"]).is_err());
+        assert!(PersonalContext::parse(&["name=Al"]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_all() {
+        let context = PersonalContext::parse(&["username=bob123"]).unwrap();
+        let found: Vec<Pattern> = find_all("bob123qwerty", &context)
+            .into_iter()
+            .map(|m| m.pattern)
+            .collect();
+        assert_eq!(found, [Pattern::PersonalInfo, Pattern::KeyboardWalk]);
+    }
+
+    #[test]
+    fn test_entropy() {
+        let repeat = &find_patterns("aaaa")[0];
+        assert!((repeat.entropy(4.7) - 6.7).abs() < 1e-9);
+        let block = &find_patterns("abab")[0];
+        assert_eq!(block.pattern, Pattern::RepeatedBlock);
+        assert!((block.entropy(4.7) - 10.4).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,626 @@
+use crate::passgen::generate::{CharClass, Generator};
+use crate::passgen::password::Password;
+use crate::passgen::variants;
+use anyhow::Context;
+use clap::ValueEnum;
+use rand::CryptoRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Overrides the platform policy location, like PASSGEN_CONFIG does for the config file
+const POLICY_FILE_ENV: &str = "PASSGEN_POLICY";
+
+// Rejection sampling gives up after this many candidates
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Character categories as Active Directory counts them for its complexity rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Upper,
+    Lower,
+    Digit,
+    Symbol,
+    // Letters without case, e.g. from Asian scripts
+    OtherLetter,
+}
+
+const CATEGORIES: usize = 5;
+
+fn category(c: char) -> Category {
+    if c.is_uppercase() {
+        Category::Upper
+    } else if c.is_lowercase() {
+        Category::Lower
+    } else if c.is_numeric() {
+        Category::Digit
+    } else if c.is_alphabetic() {
+        Category::OtherLetter
+    } else {
+        Category::Symbol
+    }
+}
+
+fn category_count(chars: impl Iterator<Item = char>) -> usize {
+    let mut categories: Vec<Category> = chars.map(category).collect();
+    categories.sort();
+    categories.dedup();
+    categories.len()
+}
+
+/// Password requirements that generation and checks must both honour.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// How many of the character categories (upper, lower, digit, symbol, other letters)
+    /// a password must mix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_categories: Option<usize>,
+    /// Previous passwords the directory remembers; informational, PassGen keeps no history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<usize>,
+    /// Days until the directory expires a password; informational
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Character classes (upper, lower, digit, special) a password must each contain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_classes: Vec<CharClass>,
+    /// Words a password must not contain, ignoring case, e.g. the company name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub banned_words: Vec<String>,
+    /// Least entropy in bits, as `passgen check` measures it over the character classes
+    /// used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_entropy: Option<f64>,
+    /// Most times the same character may appear in a row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_repeated: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum PolicyFormat {
+    /// Active Directory domain policy from `secedit /export`, `Get-ADDefaultDomainPasswordPolicy`,
+    /// or `net accounts`
+    Ad,
+}
+
+/// Location of the policy file, e.g. `~/.config/passgen/policy.toml` on Linux.
+pub fn policy_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(POLICY_FILE_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join("passgen").join("policy.toml"))
+}
+
+impl Policy {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Writes the policy file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        std::fs::write(path, self.to_toml()?)
+            .with_context(|| format!("Cannot write policy file {}", path.display()))
+    }
+
+    /// Loads the policy file if one exists.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let Some(path) = policy_path() else {
+            return Ok(None);
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::from_file(&path).map(Some)
+    }
+
+    /// Reads a policy from a TOML file, or a JSON one if its name ends in `.json`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read policy file {}", path.display()))?;
+        let policy = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&source).map_err(anyhow::Error::from)
+        } else {
+            Self::parse(&source)
+        };
+        policy.with_context(|| format!("Invalid policy file {}", path.display()))
+    }
+
+    /// Converts an exported policy in the given format.
+    pub fn import(format: PolicyFormat, source: &str) -> Result<Self, String> {
+        match format {
+            PolicyFormat::Ad => import_ad(source),
+        }
+    }
+
+    /// The requested length raised to the policy minimum.
+    pub fn length(&self, requested: usize) -> usize {
+        requested.max(self.min_length.unwrap_or(0))
+    }
+
+    /// Ways the password falls short of the policy; empty when it complies.
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(min) = self.min_length
+            && password.chars().count() < min
+        {
+            violations.push(format!("shorter than {} characters", min));
+        }
+        if let Some(min) = self.min_categories {
+            let found = category_count(password.chars());
+            if found < min {
+                violations.push(format!(
+                    "mixes {} character categories, {} required",
+                    found, min
+                ));
+            }
+        }
+        for class in &self.required_classes {
+            if !password.chars().any(|c| CharClass::of(c) == *class) {
+                violations.push(format!("has no {} character", class.name()));
+            }
+        }
+        let folded = password.to_lowercase();
+        for word in &self.banned_words {
+            if !word.is_empty() && folded.contains(&word.to_lowercase()) {
+                violations.push(format!("contains the banned word `{}`", word));
+            }
+        }
+        if let Some(max) = self.max_repeated {
+            let longest = longest_run(password);
+            if longest > max {
+                violations.push(format!(
+                    "repeats a character {} times in a row, at most {} allowed",
+                    longest, max
+                ));
+            }
+        }
+        if let Some(min) = self.min_entropy {
+            let alphabet = variants::search_space(password);
+            let entropy = Password::new(password).strength(alphabet).entropy;
+            if entropy < min {
+                violations.push(format!(
+                    "has {:.1} bits of entropy, {} required",
+                    entropy, min
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Generates a compliant password into `buf` by sampling from the generator's alphabet
+    /// and rejecting candidates that miss the policy, so the distribution stays uniform
+    /// over them.
+    pub fn generate<R: CryptoRng + ?Sized>(
+        &self,
+        length: usize,
+        generator: &Generator,
+        rng: &mut R,
+        buf: &mut String,
+    ) -> Result<(), String> {
+        let alphabet = generator.alphabet();
+        let required = self.min_categories.unwrap_or(0);
+        let available = category_count(alphabet.as_str().chars());
+        if available < required {
+            return Err(format!(
+                "the {} alphabet has {} character categories, the policy requires {}",
+                alphabet.name(),
+                available,
+                required
+            ));
+        }
+        if length < required {
+            return Err(format!(
+                "{} character categories cannot fit in {} characters",
+                required, length
+            ));
+        }
+        if let Some(class) = self
+            .required_classes
+            .iter()
+            .find(|&&class| !alphabet.as_str().chars().any(|c| CharClass::of(c) == class))
+        {
+            return Err(format!(
+                "the {} alphabet has no {} characters, the policy requires them",
+                alphabet.name(),
+                class.name()
+            ));
+        }
+        if length < self.required_classes.len() {
+            return Err(format!(
+                "{} required character classes cannot fit in {} characters",
+                self.required_classes.len(),
+                length
+            ));
+        }
+
+        self.sample(rng, buf, |rng, buf| {
+            generator.generate_into(length, rng, buf)
+        })
+    }
+
+    /// Draws passwords into `buf` with `draw` until one complies, for generators other than
+    /// an alphabet, e.g. patterns.
+    pub fn sample<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+        buf: &mut String,
+        mut draw: impl FnMut(&mut R, &mut String),
+    ) -> Result<(), String> {
+        let mut violations = Vec::new();
+        for _ in 0..MAX_ATTEMPTS {
+            draw(rng, buf);
+            violations = self.violations(buf);
+            if violations.is_empty() {
+                return Ok(());
+            }
+        }
+        Err(format!(
+            "could not generate a password satisfying the policy: {}",
+            violations.join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let toml = self.to_toml().map_err(|_| fmt::Error)?;
+        f.write_str(toml.trim_end())
+    }
+}
+
+// Length of the longest run of one character
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+/// Decodes an exported file; `secedit /export` writes UTF-16 with a byte order mark.
+pub fn decode_text(bytes: &[u8]) -> Result<String, String> {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|e| e.to_string())
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|e| e.to_string())
+        }
+        _ => String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string()),
+    }
+}
+
+// Lowercase letters and digits only, so `MinPasswordLength`, `MinimumPasswordLength`, and
+// `Minimum password length` compare by their words
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn parse_count(key: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{}` must be a number, got `{}`", key.trim(), value))
+}
+
+// Ages are plain days in secedit and net accounts, or a TimeSpan such as `42.00:00:00` from
+// PowerShell; zero, -1, and "Unlimited" mean passwords never expire
+fn parse_age(key: &str, value: &str) -> Result<Option<u32>, String> {
+    if value.eq_ignore_ascii_case("unlimited") {
+        return Ok(None);
+    }
+    let days = match value.split_once(':') {
+        Some((days_and_hours, _)) => days_and_hours.split_once('.').map_or("0", |(days, _)| days),
+        None => value,
+    };
+    match days.parse::<i64>() {
+        Ok(days) if days > 0 => Ok(u32::try_from(days).ok()),
+        Ok(_) => Ok(None),
+        Err(_) => Err(format!(
+            "`{}` must be a number of days, got `{}`",
+            key.trim(),
+            value
+        )),
+    }
+}
+
+/// Reads `key = value` lines from `secedit /export`, or `key : value` lines from
+/// `Get-ADDefaultDomainPasswordPolicy` and `net accounts`.
+fn import_ad(source: &str) -> Result<Policy, String> {
+    let mut policy = Policy::default();
+    let mut found = false;
+    for line in source.lines() {
+        let separator = line.find(['=', ':']);
+        let Some((key, value)) = separator.map(|i| (&line[..i], line[i + 1..].trim())) else {
+            continue;
+        };
+        match normalize_key(key).as_str() {
+            "minimumpasswordlength" | "minpasswordlength" => {
+                policy.min_length = Some(parse_count(key, value)?).filter(|&min| min > 0);
+            }
+            "passwordcomplexity" | "complexityenabled" => {
+                // Complexity means three of the five categories; its other half, not
+                // containing the account name, cannot be known here
+                let enabled = matches!(value.to_lowercase().as_str(), "1" | "true");
+                policy.min_categories = enabled.then_some(3);
+            }
+            "passwordhistorysize"
+            | "passwordhistorycount"
+            | "lengthofpasswordhistorymaintained" => {
+                policy.history = match value.to_lowercase().as_str() {
+                    "none" => None,
+                    _ => Some(parse_count(key, value)?).filter(|&count| count > 0),
+                };
+            }
+            "maximumpasswordage" | "maxpasswordage" | "maximumpasswordagedays" => {
+                policy.max_age_days = parse_age(key, value)?;
+            }
+            _ => continue,
+        }
+        found = true;
+    }
+    if !found {
+        return Err("no password policy settings found".to_string());
+    }
+    if policy.min_categories.is_some_and(|min| min > CATEGORIES) {
+        return Err("the policy requires more character categories than exist".to_string());
+    }
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passgen::alphabet::Alphabet;
+    use crate::passgen::template::Template;
+
+    const SECEDIT: &str = "[Unicode]\r\nUnicode=yes\r\n[System Access]\r\nMinimumPasswordAge = 1\r\n\
+                           MaximumPasswordAge = 42\r\nMinimumPasswordLength = 14\r\n\
+                           PasswordComplexity = 1\r\nPasswordHistorySize = 24\r\n";
+
+    const POWERSHELL: &str = "\
+ComplexityEnabled           : True
+DistinguishedName           : DC=example,DC=com
+LockoutDuration             : 00:30:00
+MaxPasswordAge              : 90.00:00:00
+MinPasswordAge              : 1.00:00:00
+MinPasswordLength           : 12
+PasswordHistoryCount        : 10
+ReversibleEncryptionEnabled : False
+";
+
+    const NET_ACCOUNTS: &str = "\
+Force user logoff how long after time expires?:       Never
+Minimum password age (days):                          0
+Maximum password age (days):                          Unlimited
+Minimum password length:                              8
+Length of password history maintained:                None
+The command completed successfully.
+";
+
+    #[test]
+    fn test_import_secedit() {
+        let policy = Policy::import(PolicyFormat::Ad, SECEDIT).unwrap();
+        assert_eq!(
+            policy,
+            Policy {
+                min_length: Some(14),
+                min_categories: Some(3),
+                history: Some(24),
+                max_age_days: Some(42),
+                ..Policy::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_powershell() {
+        let policy = Policy::import(PolicyFormat::Ad, POWERSHELL).unwrap();
+        assert_eq!(policy.min_length, Some(12));
+        assert_eq!(policy.min_categories, Some(3));
+        assert_eq!(policy.history, Some(10));
+        assert_eq!(policy.max_age_days, Some(90));
+    }
+
+    #[test]
+    fn test_import_net_accounts() {
+        let policy = Policy::import(PolicyFormat::Ad, NET_ACCOUNTS).unwrap();
+        assert_eq!(
+            policy,
+            Policy {
+                min_length: Some(8),
+                ..Policy::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_errors() {
+        assert!(Policy::import(PolicyFormat::Ad, "hello").is_err());
+        assert!(Policy::import(PolicyFormat::Ad, "MinimumPasswordLength = many").is_err());
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(
+            "MinimumPasswordLength = 9"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        assert_eq!(decode_text(&bytes).unwrap(), "MinimumPasswordLength = 9");
+        assert_eq!(decode_text(b"plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let policy = Policy::import(PolicyFormat::Ad, SECEDIT).unwrap();
+        let toml = policy.to_toml().unwrap();
+        assert!(toml.contains("min_length = 14"));
+        assert_eq!(Policy::parse(&toml).unwrap(), policy);
+        assert!(Policy::parse("min_lenght = 3").is_err());
+    }
+
+    #[test]
+    fn test_violations() {
+        let policy = Policy {
+            min_length: Some(10),
+            min_categories: Some(3),
+            ..Policy::default()
+        };
+        assert_eq!(policy.violations("Tr0ub4dor&3"), Vec::<String>::new());
+        assert_eq!(
+            policy.violations("short"),
+            vec![
+                "shorter than 10 characters",
+                "mixes 1 character categories, 3 required"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let policy = Policy::parse(
+            "required_classes = [\"upper\", \"digit\"]\n\
+             banned_words = [\"Acme\"]\n\
+             min_entropy = 40.0\n\
+             max_repeated = 2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.required_classes,
+            [CharClass::Upper, CharClass::Digit]
+        );
+        assert_eq!(policy.violations("Kq7#zm2!pXw"), Vec::<String>::new());
+        assert_eq!(
+            policy.violations("myacmepassword"),
+            vec![
+                "has no upper case character",
+                "has no digit character",
+                "contains the banned word `Acme`",
+            ]
+        );
+        let violations = policy.violations("Zaaa1");
+        assert_eq!(
+            violations[0],
+            "repeats a character 3 times in a row, at most 2 allowed"
+        );
+        assert!(
+            violations[1].ends_with("bits of entropy, 40 required"),
+            "{}",
+            violations[1]
+        );
+        assert!(Policy::parse("required_classes = [\"emoji\"]").is_err());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = std::env::temp_dir();
+        let json = dir.join(format!("passgen-policy-{}.json", std::process::id()));
+        std::fs::write(&json, r#"{"min_length": 12, "banned_words": ["acme"]}"#).unwrap();
+        let policy = Policy::from_file(&json).unwrap();
+        assert_eq!(policy.min_length, Some(12));
+        assert_eq!(policy.banned_words, ["acme"]);
+        std::fs::remove_file(&json).unwrap();
+
+        let toml = dir.join(format!("passgen-policy-{}.toml", std::process::id()));
+        std::fs::write(&toml, policy.to_toml().unwrap()).unwrap();
+        assert_eq!(Policy::from_file(&toml).unwrap(), policy);
+        std::fs::remove_file(&toml).unwrap();
+        assert!(Policy::from_file(&toml).is_err());
+    }
+
+    #[test]
+    fn test_generate_complies() {
+        let policy = Policy {
+            min_length: Some(14),
+            min_categories: Some(4),
+            ..Policy::default()
+        };
+        let mut rng = rand::rng();
+        let length = policy.length(8);
+        assert_eq!(length, 14);
+        let full = Generator::new(&Alphabet::Full).unwrap();
+        let mut password = String::new();
+        for _ in 0..50 {
+            policy
+                .generate(length, &full, &mut rng, &mut password)
+                .unwrap();
+            assert!(policy.violations(&password).is_empty());
+        }
+        let digits = Generator::new(&Alphabet::Digits).unwrap();
+        assert!(
+            policy
+                .generate(14, &digits, &mut rng, &mut password)
+                .is_err()
+        );
+        assert!(policy.generate(3, &full, &mut rng, &mut password).is_err());
+
+        let strict = Policy {
+            required_classes: vec![CharClass::Special],
+            max_repeated: Some(1),
+            banned_words: vec!["a".to_string()],
+            ..Policy::default()
+        };
+        for _ in 0..50 {
+            strict.generate(16, &full, &mut rng, &mut password).unwrap();
+            assert!(strict.violations(&password).is_empty(), "{}", password);
+        }
+        let lower = Generator::new(&Alphabet::LowerCase).unwrap();
+        assert!(
+            strict
+                .generate(16, &lower, &mut rng, &mut password)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sample_template() {
+        let policy = Policy {
+            min_length: Some(8),
+            min_categories: Some(4),
+            ..Policy::default()
+        };
+        let mut rng = rand::rng();
+        let mut password = String::new();
+        // Only the `a` can supply the missing categories
+        let template = Template::parse("Cvcvnnaa").unwrap();
+        policy
+            .sample(&mut rng, &mut password, |rng, buf| {
+                template.generate_into(rng, buf)
+            })
+            .unwrap();
+        assert!(policy.violations(&password).is_empty());
+
+        let short = Template::parse("Cvnn!").unwrap();
+        let error = policy
+            .sample(&mut rng, &mut password, |rng, buf| {
+                short.generate_into(rng, buf)
+            })
+            .unwrap_err();
+        assert!(error.ends_with("shorter than 8 characters"), "{}", error);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::passgen::generate::ln_sum;
+use rand::seq::IndexedRandom;
+use rand::{CryptoRng, Rng};
+
+// Letters that read naturally before a vowel and after one; `q`, `x` and `y` are left out
+// so every letter is unambiguously a consonant or a vowel
+const ONSETS: &str = "bcdfghjklmnprstvwz";
+const VOWELS: &str = "aeiou";
+const CODAS: &str = "bdgklmnprst";
+
+/// Generates lowercase passwords built from syllables of a consonant, a vowel and an
+/// optional final consonant, e.g. `bakotinuzeb`.
+///
+/// The letters of a password determine its syllables, so every syllable sequence gives a
+/// different password. Counting them gives the exact entropy, and passwords are chosen
+/// uniformly among all of a length.
+pub struct Pronounceable {
+    onsets: Vec<char>,
+    vowels: Vec<char>,
+    codas: Vec<char>,
+    // `ways[n]`: natural log of the number of passwords of `n` characters
+    ways: Vec<f64>,
+}
+
+impl Pronounceable {
+    /// Fails for lengths shorter than a syllable.
+    pub fn new(length: usize) -> Result<Self, String> {
+        if length < 2 {
+            return Err(format!(
+                "pronounceable passwords need at least 2 characters, not {}",
+                length
+            ));
+        }
+        let mut generator = Self {
+            onsets: ONSETS.chars().collect(),
+            vowels: VOWELS.chars().collect(),
+            codas: CODAS.chars().collect(),
+            ways: vec![f64::NEG_INFINITY; length + 1],
+        };
+        generator.ways[0] = 0.0;
+        for n in 2..=length {
+            let terms: Vec<f64> = [2, 3]
+                .into_iter()
+                .filter(|&syllable| syllable <= n)
+                .map(|syllable| generator.ln_syllables(syllable) + generator.ways[n - syllable])
+                .collect();
+            generator.ways[n] = ln_sum(&terms);
+        }
+        Ok(generator)
+    }
+
+    // Natural log of the number of syllables of `length` characters
+    fn ln_syllables(&self, length: usize) -> f64 {
+        let open = ((self.onsets.len() * self.vowels.len()) as f64).ln();
+        match length {
+            2 => open,
+            _ => open + (self.codas.len() as f64).ln(),
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.ways.len() - 1
+    }
+
+    /// Entropy in bits of a generated password.
+    pub fn entropy(&self) -> f64 {
+        self.ways[self.length()] / std::f64::consts::LN_2
+    }
+
+    /// Replaces the contents of `buf` with a password, choosing each syllable's length in
+    /// proportion to the number of passwords that continue it.
+    pub fn generate_into<R: CryptoRng + ?Sized>(&self, rng: &mut R, buf: &mut String) {
+        buf.clear();
+        let mut remaining = self.length();
+        while remaining > 0 {
+            let open =
+                (self.ln_syllables(2) + self.ways[remaining - 2] - self.ways[remaining]).exp();
+            let closed = remaining >= 3 && rng.random::<f64>() >= open;
+            buf.push(*self.onsets.choose(rng).expect("onsets are not empty"));
+            buf.push(*self.vowels.choose(rng).expect("vowels are not empty"));
+            if closed {
+                buf.push(*self.codas.choose(rng).expect("codas are not empty"));
+                remaining -= 3;
+            } else {
+                remaining -= 2;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passgen::selftest::{chi_square, chi_square_critical};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_syllables() {
+        let generator = Pronounceable::new(11).unwrap();
+        let mut rng = ChaCha20Rng::from_seed([8; 32]);
+        let mut password = String::new();
+        for _ in 0..200 {
+            generator.generate_into(&mut rng, &mut password);
+            assert_eq!(password.len(), 11);
+            let letters: Vec<char> = password.chars().collect();
+            assert!(ONSETS.contains(letters[0]), "{}", password);
+            // Never two vowels or three consonants in a row
+            for pair in letters.windows(2) {
+                assert!(!pair.iter().all(|&c| VOWELS.contains(c)), "{}", password);
+            }
+            for triple in letters.windows(3) {
+                assert!(triple.iter().any(|&c| VOWELS.contains(c)), "{}", password);
+            }
+        }
+    }
+
+    #[test]
+    fn test_entropy() {
+        // 90 open syllables, or 990 closed ones
+        assert!((Pronounceable::new(2).unwrap().entropy() - 90f64.log2()).abs() < 1e-9);
+        assert!((Pronounceable::new(3).unwrap().entropy() - 990f64.log2()).abs() < 1e-9);
+        // Only two open syllables make four letters
+        assert!((Pronounceable::new(4).unwrap().entropy() - 8100f64.log2()).abs() < 1e-9);
+        // An open and a closed syllable in either order
+        let five = 2.0 * 90.0 * 990.0;
+        assert!((Pronounceable::new(5).unwrap().entropy() - f64::log2(five)).abs() < 1e-9);
+        assert!(Pronounceable::new(1).is_err());
+    }
+
+    #[test]
+    fn test_shapes_are_uniform() {
+        // Open then closed, or closed then open, each half of the length-5 passwords
+        let generator = Pronounceable::new(5).unwrap();
+        let mut rng = ChaCha20Rng::from_seed([8; 32]);
+        let mut password = String::new();
+        let mut counts = [0u64; 2];
+        for _ in 0..10_000 {
+            generator.generate_into(&mut rng, &mut password);
+            let fourth_is_vowel = VOWELS.contains(&password[3..4]);
+            counts[usize::from(fourth_is_vowel)] += 1;
+        }
+        assert!(chi_square(&counts) < chi_square_critical(counts.len() - 1));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::passgen::template::Template;
+use crate::passgen::unique::Batch;
+use clap::ValueEnum;
+use rand::CryptoRng;
+use std::fmt::Write;
+
+// Letters and digits without the look-alikes `0`, `1`, `i`, `l` and `o`, for codes copied
+// by hand
+const CODE_CHARS: &str = "23456789abcdefghjkmnpqrstuvwxyz";
+const DIGITS: &str = "0123456789";
+
+/// Placeholders of a recovery-code pattern such as `XXXX-XXXX`; other characters are kept.
+pub const PLACEHOLDERS: &[(char, &str)] = &[('X', CODE_CHARS), ('#', DIGITS)];
+
+/// Layout of a printable sheet of recovery codes.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Sheet {
+    /// Plain text with a box to tick off each code
+    Text,
+    /// A Markdown task list
+    Markdown,
+}
+
+/// Parses a pattern of [`PLACEHOLDERS`], failing if it has none.
+pub fn template(pattern: &str) -> Result<Template, String> {
+    let template = Template::parse_with(pattern, PLACEHOLDERS)?;
+    if template.entropy() == 0.0 {
+        return Err(format!(
+            "the pattern `{}` has no `X` or `#` to fill in",
+            pattern
+        ));
+    }
+    Ok(template)
+}
+
+/// Draws `count` codes of `template`, none repeated.
+pub fn generate<R: CryptoRng + ?Sized>(
+    template: &Template,
+    count: usize,
+    rng: &mut R,
+) -> Result<Vec<String>, String> {
+    let mut batch = Batch::new(count, template.entropy())?;
+    let mut codes = Vec::with_capacity(count);
+    let mut code = String::new();
+    while codes.len() < count {
+        template.generate_into(rng, &mut code);
+        if batch.insert(&code)? {
+            codes.push(code.clone());
+        }
+    }
+    Ok(codes)
+}
+
+/// Lays `codes` out for printing under `title`, with a reminder that each works once.
+pub fn sheet(codes: &[String], title: &str, layout: Sheet) -> String {
+    let mut sheet = String::new();
+    let note = "Each code can be used once. Keep this sheet somewhere safe.";
+    match layout {
+        Sheet::Text => {
+            let rule = "=".repeat(title.chars().count());
+            let _ = write!(sheet, "{}\n{}\n\n{}\n\n", title, rule, note);
+            let width = codes.len().to_string().len();
+            for (i, code) in codes.iter().enumerate() {
+                let _ = writeln!(sheet, "[ ] {:>width$}. {}", i + 1, code);
+            }
+        }
+        Sheet::Markdown => {
+            let _ = write!(sheet, "# {}\n\n{}\n\n", title, note);
+            for code in codes {
+                let _ = writeln!(sheet, "- [ ] `{}`", code);
+            }
+        }
+    }
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = ChaCha20Rng::from_seed([2; 32]);
+        let template = template("XXXX-XXXX").unwrap();
+        let codes = generate(&template, 10, &mut rng).unwrap();
+        assert_eq!(codes.len(), 10);
+        for code in &codes {
+            let (left, right) = code.split_once('-').unwrap();
+            assert_eq!((left.len(), right.len()), (4, 4), "{}", code);
+            assert!(code.chars().all(|c| c == '-' || CODE_CHARS.contains(c)));
+        }
+
+        // All hundred two-digit codes, each once
+        let mut codes = generate(&super::template("##").unwrap(), 100, &mut rng).unwrap();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), 100);
+        assert!(generate(&super::template("##").unwrap(), 101, &mut rng).is_err());
+        assert!(super::template("----").is_err());
+    }
+
+    #[test]
+    fn test_sheet() {
+        let codes: Vec<String> = (1..=10).map(|i| format!("code-{}", i)).collect();
+        let text = sheet(&codes, "Recovery codes", Sheet::Text);
+        assert!(text.starts_with("Recovery codes\n==============\n\n"));
+        assert!(text.contains("\n[ ]  1. code-1\n"));
+        assert!(text.ends_with("[ ] 10. code-10\n"));
+        let markdown = sheet(&codes[..2], "Codes", Sheet::Markdown);
+        assert!(markdown.starts_with("# Codes\n\n"));
+        assert!(markdown.ends_with("- [ ] `code-1`\n- [ ] `code-2`\n"));
+    }
+}
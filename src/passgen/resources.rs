@@ -0,0 +1,63 @@
+use std::sync::OnceLock;
+
+/// A text resource compressed by `build.rs` and inflated on first use.
+pub struct Embedded {
+    compressed: &'static [u8],
+    text: OnceLock<String>,
+}
+
+impl Embedded {
+    pub const fn new(compressed: &'static [u8]) -> Self {
+        Self {
+            compressed,
+            text: OnceLock::new(),
+        }
+    }
+
+    /// The inflated text, empty for resources left out by the `embedded` feature.
+    pub fn text(&'static self) -> &'static str {
+        self.text.get_or_init(|| {
+            if self.compressed.is_empty() {
+                return String::new();
+            }
+            let data = miniz_oxide::inflate::decompress_to_vec(self.compressed)
+                .expect("embedded resources are compressed at build time");
+            String::from_utf8(data).expect("embedded resources are UTF-8")
+        })
+    }
+}
+
+/// Embeds `resources/<path>` in its compressed form, e.g. `embedded!("common/english.txt")`.
+#[cfg(feature = "embedded")]
+macro_rules! embedded {
+    ($path:literal) => {
+        $crate::passgen::resources::Embedded::new(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/",
+            $path,
+            ".deflate"
+        )))
+    };
+}
+/// Stands in for a resource without the `embedded` feature, so the lists are empty.
+#[cfg(not(feature = "embedded"))]
+macro_rules! embedded {
+    ($path:literal) => {
+        $crate::passgen::resources::Embedded::new(&[])
+    };
+}
+pub(crate) use embedded;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PASSWORDS: Embedded = embedded!("common/passwords.txt");
+
+    #[test]
+    fn test_round_trip() {
+        let original = include_str!("../../resources/common/passwords.txt");
+        assert!(PASSWORDS.compressed.len() < original.len());
+        assert_eq!(PASSWORDS.text(), original);
+    }
+}
@@ -0,0 +1,74 @@
+use clap::ValueEnum;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, TryRngCore};
+
+/// Where generated secrets draw their randomness from.
+///
+/// Every generation path takes its generator as a [`CryptoRng`], so a non-cryptographic
+/// generator cannot reach them; besides these sources, only the ChaCha20 generator derived
+/// from a `--seed-file` keyfile is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum RngSource {
+    /// ChaCha12 seeded from the operating system and reseeded periodically, per thread
+    #[default]
+    Thread,
+    /// The operating system's generator for every draw, e.g. getrandom(2) on Linux
+    Os,
+}
+
+impl RngSource {
+    pub fn rng(self) -> Box<dyn CryptoRng> {
+        match self {
+            RngSource::Thread => Box::new(rand::rng()),
+            // Like `rand::rng`, panics should the operating system fail to provide randomness
+            RngSource::Os => Box::new(OsRng.unwrap_err()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passgen::alphabet::Alphabet;
+    use crate::passgen::generate::Generator;
+    use crate::passgen::passphrase::generate_passphrase;
+    use crate::passgen::selftest::{chi_square, chi_square_critical};
+    use crate::passgen::wordlist::WordList;
+
+    #[test]
+    fn test_characters_are_uniform() {
+        let alphabet = Alphabet::Custom("abcdefghij".to_string());
+        let generator = Generator::new(&alphabet).unwrap();
+        for source in RngSource::value_variants() {
+            let password = generator.generate(50_000, &mut *source.rng());
+            let mut counts = [0u64; 10];
+            for c in password.value.chars() {
+                counts[(c as u8 - b'a') as usize] += 1;
+            }
+            assert!(
+                chi_square(&counts) < chi_square_critical(counts.len() - 1),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_words_are_uniform() {
+        let words: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let wordlist = WordList::from_custom(words);
+        for source in RngSource::value_variants() {
+            let passphrase = generate_passphrase(20_000, " ", &wordlist, &mut *source.rng())
+                .expect("word list is not empty");
+            let mut counts = [0u64; 8];
+            for word in passphrase.value.split(' ') {
+                counts[word.parse::<usize>().unwrap()] += 1;
+            }
+            assert!(
+                chi_square(&counts) < chi_square_critical(counts.len() - 1),
+                "{:?}",
+                source
+            );
+        }
+    }
+}
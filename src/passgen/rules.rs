@@ -0,0 +1,277 @@
+use rand::CryptoRng;
+use rand::seq::IndexedRandom;
+use std::collections::BTreeSet;
+
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGIT: &str = "0123456789";
+const SPECIAL: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?]";
+
+const DEFAULT_LENGTH: usize = 16;
+// Rejection sampling gives up after this many candidates
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Site password requirements in the `passwordrules` attribute syntax, e.g.
+/// `minlength: 12; maxlength: 20; required: lower; required: upper, digit; allowed: [-_]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordRules {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// Each set must contribute at least one character
+    pub required: Vec<BTreeSet<char>>,
+    pub allowed: BTreeSet<char>,
+    pub max_consecutive: Option<usize>,
+}
+
+fn class_chars(class: &str) -> Result<BTreeSet<char>, String> {
+    let class = class.trim();
+    if let Some(custom) = class.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+        if custom.is_empty() {
+            return Err("empty character class `[]`".to_string());
+        }
+        return Ok(custom.chars().collect());
+    }
+    let chars = match class.to_lowercase().as_str() {
+        "upper" => UPPER.to_string(),
+        "lower" => LOWER.to_string(),
+        "digit" => DIGIT.to_string(),
+        "special" => SPECIAL.to_string(),
+        // Generated passwords stay within printable ASCII even when a site allows more
+        "ascii-printable" | "unicode" => format!("{}{}{}{}", UPPER, LOWER, DIGIT, SPECIAL),
+        other => return Err(format!("unknown character class `{}`", other)),
+    };
+    Ok(chars.chars().collect())
+}
+
+// Splits on commas outside of `[...]` custom classes, which may contain commas themselves
+fn split_classes(value: &str) -> Vec<&str> {
+    let mut classes = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth > 0 => depth -= 1,
+            ',' if depth == 0 => {
+                classes.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    classes.push(&value[start..]);
+    classes
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{}` must be a number, got `{}`", name, value.trim()))
+}
+
+impl PasswordRules {
+    pub fn parse(rules: &str) -> Result<Self, String> {
+        let mut parsed = PasswordRules::default();
+        for property in rules.split(';') {
+            if property.trim().is_empty() {
+                continue;
+            }
+            let (name, value) = property
+                .split_once(':')
+                .ok_or_else(|| format!("expected `name: value`, got `{}`", property.trim()))?;
+            match name.trim().to_lowercase().as_str() {
+                "minlength" => parsed.min_length = Some(parse_number("minlength", value)?),
+                "maxlength" => parsed.max_length = Some(parse_number("maxlength", value)?),
+                "max-consecutive" => {
+                    parsed.max_consecutive = Some(parse_number("max-consecutive", value)?)
+                }
+                "required" => {
+                    let mut set = BTreeSet::new();
+                    for class in split_classes(value) {
+                        set.extend(class_chars(class)?);
+                    }
+                    parsed.required.push(set);
+                }
+                "allowed" => {
+                    for class in split_classes(value) {
+                        parsed.allowed.extend(class_chars(class)?);
+                    }
+                }
+                other => return Err(format!("unknown rule `{}`", other)),
+            }
+        }
+
+        if let (Some(min), Some(max)) = (parsed.min_length, parsed.max_length)
+            && min > max
+        {
+            return Err(format!("minlength {} is above maxlength {}", min, max));
+        }
+        if parsed.max_consecutive == Some(0) {
+            return Err("max-consecutive must be at least 1".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// Every character a password may use; without any classes, all of printable ASCII.
+    pub fn alphabet(&self) -> Vec<char> {
+        let mut chars: BTreeSet<char> = self.allowed.clone();
+        for set in &self.required {
+            chars.extend(set);
+        }
+        if chars.is_empty() {
+            chars = class_chars("ascii-printable").expect("built-in class");
+        }
+        chars.into_iter().collect()
+    }
+
+    /// The requested length clamped into the site's bounds, or a default that fits them.
+    pub fn length(&self, requested: Option<usize>) -> usize {
+        let length = requested.unwrap_or(DEFAULT_LENGTH.max(self.min_length.unwrap_or(0)));
+        let length = length.max(self.min_length.unwrap_or(1)).max(1);
+        match self.max_length {
+            Some(max) => length.min(max),
+            None => length,
+        }
+    }
+
+    pub fn is_satisfied_by(&self, password: &str) -> bool {
+        let length = password.chars().count();
+        if self.min_length.is_some_and(|min| length < min)
+            || self.max_length.is_some_and(|max| length > max)
+        {
+            return false;
+        }
+        if !self
+            .required
+            .iter()
+            .all(|set| password.chars().any(|c| set.contains(&c)))
+        {
+            return false;
+        }
+        match self.max_consecutive {
+            Some(max) => longest_run(password) <= max,
+            None => true,
+        }
+    }
+
+    /// Generates a password meeting the rules by sampling uniformly from the allowed
+    /// characters and rejecting candidates that miss a requirement.
+    pub fn generate<R: CryptoRng + ?Sized>(
+        &self,
+        length: Option<usize>,
+        rng: &mut R,
+    ) -> Result<String, String> {
+        let alphabet = self.alphabet();
+        let length = self.length(length);
+        if self.required.len() > length {
+            return Err(format!(
+                "{} required character classes cannot fit in {} characters",
+                self.required.len(),
+                length
+            ));
+        }
+
+        for _ in 0..MAX_ATTEMPTS {
+            let candidate: String = (0..length)
+                .map(|_| *alphabet.choose(rng).expect("alphabet is never empty"))
+                .collect();
+            if self.is_satisfied_by(&candidate) {
+                return Ok(candidate);
+            }
+        }
+        Err("could not generate a password satisfying these rules".to_string())
+    }
+}
+
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let rules = PasswordRules::parse(
+            "minlength: 8; maxlength: 20; required: lower; required: upper, digit; allowed: [-_,]; max-consecutive: 2",
+        )
+        .unwrap();
+        assert_eq!(rules.min_length, Some(8));
+        assert_eq!(rules.max_length, Some(20));
+        assert_eq!(rules.required.len(), 2);
+        assert_eq!(rules.required[1].len(), 36);
+        assert_eq!(rules.allowed, ['-', '_', ','].into_iter().collect());
+        assert_eq!(rules.max_consecutive, Some(2));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(PasswordRules::parse("minlength: eight").is_err());
+        assert!(PasswordRules::parse("required: emoji").is_err());
+        assert!(PasswordRules::parse("colour: red").is_err());
+        assert!(PasswordRules::parse("minlength 8").is_err());
+        assert!(PasswordRules::parse("minlength: 20; maxlength: 8").is_err());
+        assert!(PasswordRules::parse("allowed: []").is_err());
+    }
+
+    #[test]
+    fn test_empty_rules_allow_printable_ascii() {
+        let rules = PasswordRules::parse("").unwrap();
+        assert_eq!(rules.alphabet().len(), 26 + 26 + 10 + SPECIAL.len());
+        assert_eq!(rules.length(None), DEFAULT_LENGTH);
+    }
+
+    #[test]
+    fn test_length_is_clamped() {
+        let rules = PasswordRules::parse("minlength: 20; maxlength: 24").unwrap();
+        assert_eq!(rules.length(None), 20);
+        assert_eq!(rules.length(Some(8)), 20);
+        assert_eq!(rules.length(Some(40)), 24);
+
+        let rules = PasswordRules::parse("maxlength: 10").unwrap();
+        assert_eq!(rules.length(None), 10);
+    }
+
+    #[test]
+    fn test_generate_meets_rules() {
+        let rules = PasswordRules::parse(
+            "minlength: 10; maxlength: 12; required: upper; required: digit; required: [!]; allowed: lower; max-consecutive: 1",
+        )
+        .unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..50 {
+            let password = rules.generate(None, &mut rng).unwrap();
+            assert!(rules.is_satisfied_by(&password), "{}", password);
+            assert!(
+                password
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '!')
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsatisfiable_rules() {
+        let rules =
+            PasswordRules::parse("maxlength: 2; required: upper; required: lower; required: digit")
+                .unwrap();
+        assert!(rules.generate(None, &mut rand::rng()).is_err());
+    }
+
+    #[test]
+    fn test_longest_run() {
+        assert_eq!(longest_run(""), 0);
+        assert_eq!(longest_run("abc"), 1);
+        assert_eq!(longest_run("abbbc"), 3);
+    }
+}
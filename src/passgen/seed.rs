@@ -0,0 +1,112 @@
+use anyhow::Context;
+use hkdf::Hkdf;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Sha256;
+use std::path::Path;
+
+// Fixed salt so the same keyfile and label always derive the same seed
+const HKDF_SALT: &[u8] = b"passgen-seed-file-v1";
+const LABEL_DOMAIN: &[u8] = b"label";
+const MIN_KEY_LEN: usize = 16;
+
+/// Expands keyfile contents and an optional label into a 256-bit RNG seed.
+///
+/// A label is length-prefixed in its own domain, so no label (not even an
+/// empty one) derives the unlabeled seed.
+pub fn derive_seed(key: &[u8], label: Option<&str>) -> [u8; 32] {
+    let hkdf = Hkdf::<Sha256>::new(Some(HKDF_SALT), key);
+    let mut seed = [0u8; 32];
+    let info = match label {
+        None => Vec::new(),
+        Some(label) => [
+            LABEL_DOMAIN,
+            &(label.len() as u64).to_be_bytes(),
+            label.as_bytes(),
+        ]
+        .concat(),
+    };
+    hkdf.expand(&info, &mut seed)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    seed
+}
+
+/// Parses up to 64 hex digits into an RNG seed used as is, zero-padded at the end.
+pub fn parse_hex_seed(hex: &str) -> Result<[u8; 32], String> {
+    let mut seed = [0u8; 32];
+    if hex.is_empty() || hex.len() > 2 * seed.len() || !hex.len().is_multiple_of(2) {
+        return Err(format!(
+            "expected an even number of hex digits, up to {}",
+            2 * seed.len()
+        ));
+    }
+    if let Some(c) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("`{}` is not a hex digit", c));
+    }
+    for (byte, i) in seed.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *byte = u8::from_str_radix(&hex[i..i + 2], 16).expect("digits are hex");
+    }
+    Ok(seed)
+}
+
+pub fn rng_from_key(key: &[u8], label: Option<&str>) -> anyhow::Result<ChaCha20Rng> {
+    if key.len() < MIN_KEY_LEN {
+        return Err(anyhow::anyhow!(
+            "Keyfile must contain at least {} bytes, found {}",
+            MIN_KEY_LEN,
+            key.len()
+        ));
+    }
+    Ok(ChaCha20Rng::from_seed(derive_seed(key, label)))
+}
+
+pub fn rng_from_seed_file(path: &Path, label: Option<&str>) -> anyhow::Result<ChaCha20Rng> {
+    let key =
+        std::fs::read(path).with_context(|| format!("Cannot read keyfile {}", path.display()))?;
+    rng_from_key(&key, label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_derive_seed_is_deterministic() {
+        assert_eq!(derive_seed(KEY, None), derive_seed(KEY, None));
+        assert_eq!(derive_seed(KEY, Some("web")), derive_seed(KEY, Some("web")));
+    }
+
+    #[test]
+    fn test_label_changes_seed() {
+        assert_ne!(derive_seed(KEY, Some("web")), derive_seed(KEY, Some("db")));
+        assert_ne!(derive_seed(KEY, None), derive_seed(KEY, Some("web")));
+        assert_ne!(derive_seed(KEY, None), derive_seed(KEY, Some("")));
+    }
+
+    #[test]
+    fn test_rng_from_key_reproducible() {
+        let mut first = rng_from_key(KEY, Some("label")).unwrap();
+        let mut second = rng_from_key(KEY, Some("label")).unwrap();
+        assert_eq!(first.next_u64(), second.next_u64());
+    }
+
+    #[test]
+    fn test_parse_hex_seed() {
+        let mut expected = [0u8; 32];
+        expected[..2].copy_from_slice(&[0x2a, 0xff]);
+        assert_eq!(parse_hex_seed("2aFF").unwrap(), expected);
+        assert_eq!(parse_hex_seed(&"01".repeat(32)).unwrap(), [1; 32]);
+        for invalid in ["", "abc", "zz", "+1", &"00".repeat(33)] {
+            assert!(parse_hex_seed(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_short_key_rejected() {
+        assert!(rng_from_key(b"short", None).is_err());
+        assert!(rng_from_key(b"", None).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::passphrase::generate_passphrase;
+use crate::passgen::password::Password;
+use crate::passgen::wordlist::WordList;
+use rand::CryptoRng;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// Standard normal quantile for a 0.1% false-failure rate
+const Z_CRITICAL: f64 = 3.09;
+// Most repeats expected among the secrets of the duplicate test, which keeps them few
+// enough to count as a Poisson variable yet enough to show excess collisions
+const MAX_EXPECTED_DUPLICATES: f64 = 2000.0;
+
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub statistic: f64,
+    pub threshold: f64,
+    pub passed: bool,
+}
+
+impl TestResult {
+    fn new(name: String, statistic: f64, threshold: f64) -> Self {
+        Self {
+            name,
+            statistic,
+            threshold,
+            passed: statistic.abs() < threshold,
+        }
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<5} {}: {:.4} (threshold {:.4})",
+            if self.passed { "pass" } else { "FAIL" },
+            self.name,
+            self.statistic,
+            self.threshold
+        )
+    }
+}
+
+/// Pearson's chi-square statistic of observed counts against a uniform distribution.
+pub fn chi_square(counts: &[u64]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    if counts.is_empty() || total == 0 {
+        return 0.0;
+    }
+    let expected = total as f64 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|&observed| {
+            let diff = observed as f64 - expected;
+            diff * diff / expected
+        })
+        .sum()
+}
+
+/// Upper critical value of the chi-square distribution (Wilson-Hilferty approximation).
+pub fn chi_square_critical(degrees_of_freedom: usize) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let h = 2.0 / (9.0 * k);
+    k * (1.0 - h + Z_CRITICAL * h.sqrt()).powi(3)
+}
+
+/// Knuth's circular serial correlation coefficient; near zero for independent samples.
+pub fn serial_correlation(samples: &[usize]) -> f64 {
+    let n = samples.len() as f64;
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let (mut sum, mut sum_sq, mut sum_lag) = (0.0, 0.0, 0.0);
+    for (i, &value) in samples.iter().enumerate() {
+        let u = value as f64;
+        let next = samples[(i + 1) % samples.len()] as f64;
+        sum += u;
+        sum_sq += u * u;
+        sum_lag += u * next;
+    }
+    let denominator = n * sum_sq - sum * sum;
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    (n * sum_lag - sum * sum) / denominator
+}
+
+// Under independence the coefficient is roughly normal with standard deviation 1/sqrt(n)
+fn serial_correlation_threshold(sample_size: usize) -> f64 {
+    Z_CRITICAL / (sample_size as f64).sqrt()
+}
+
+/// Secrets of `group` samples each that repeat an earlier one, compared with the number
+/// expected of `categories` uniform choices, as a z-score. Biased sampling makes some
+/// secrets likelier and so repeats more often.
+pub fn duplicates(samples: &[usize], categories: usize, group: usize) -> f64 {
+    let secrets = samples.len() / group;
+    let distinct: HashSet<&[usize]> = samples.chunks_exact(group).collect();
+    let observed = (secrets - distinct.len()) as f64;
+    // Of `secrets` draws from `space`, `space * (1 - (1 - 1 / space)^secrets)` are distinct
+    let space = (categories as f64).powi(group as i32);
+    let expected = secrets as f64 + space * (secrets as f64 * (-1.0 / space).ln_1p()).exp_m1();
+    if expected <= 0.0 {
+        return observed;
+    }
+    (observed - expected) / expected.sqrt()
+}
+
+// Samples per secret for the duplicate test: the fewest that keep the expected repeats
+// among the secrets they make at most `MAX_EXPECTED_DUPLICATES`
+fn duplicate_group(samples: usize, categories: usize) -> usize {
+    (1..)
+        .find(|&group| {
+            let secrets = (samples / group) as f64;
+            let space = (categories as f64).powi(group as i32);
+            secrets * secrets / (2.0 * space) <= MAX_EXPECTED_DUPLICATES
+        })
+        .expect("repeats fall with longer secrets")
+}
+
+fn check_samples(label: &str, samples: &[usize], categories: usize) -> Vec<TestResult> {
+    let mut counts = vec![0u64; categories];
+    for &sample in samples {
+        counts[sample] += 1;
+    }
+    let group = duplicate_group(samples.len(), categories);
+    vec![
+        TestResult::new(
+            format!("chi-square {}", label),
+            chi_square(&counts),
+            chi_square_critical(categories - 1),
+        ),
+        TestResult::new(
+            format!("serial correlation {}", label),
+            serial_correlation(samples),
+            serial_correlation_threshold(samples.len()),
+        ),
+        TestResult::new(
+            format!("duplicate rate {}, {} at a time", label, group),
+            duplicates(samples, categories, group),
+            Z_CRITICAL,
+        ),
+    ]
+}
+
+fn sample_characters<R: CryptoRng + ?Sized>(
+    alphabet: &Alphabet,
+    sample_size: usize,
+    rng: &mut R,
+) -> Vec<usize> {
+    let index: HashMap<char, usize> = alphabet
+        .chars()
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| (c, i))
+        .collect();
+    Password::generate(sample_size, alphabet, rng)
+        .expect("the alphabet is not empty")
+        .value
+        .chars()
+        .map(|c| index[&c])
+        .collect()
+}
+
+fn sample_words<R: CryptoRng + ?Sized>(
+    wordlist: &WordList,
+    sample_size: usize,
+    rng: &mut R,
+) -> Vec<usize> {
+    let words = wordlist.words();
+    let index: HashMap<&str, usize> = words.iter().enumerate().map(|(i, w)| (w, i)).collect();
+    // Words are joined with a character none of them has
+    let separator = "\n";
+    generate_passphrase(sample_size, separator, wordlist, rng)
+        .expect("the word list is not empty")
+        .value
+        .split(separator)
+        .map(|w| index[w])
+        .collect()
+}
+
+/// Samples characters of `alphabet` and words of `wordlist` through the regular generation
+/// paths and tests them for bias. Fails for an alphabet or word list of fewer than two
+/// choices, which leaves nothing to compare.
+pub fn run<R: CryptoRng + ?Sized>(
+    alphabet: &Alphabet,
+    wordlist: &WordList,
+    sample_size: usize,
+    rng: &mut R,
+) -> Result<Vec<TestResult>, String> {
+    if alphabet.len() < 2 {
+        return Err(format!(
+            "the {} alphabet needs at least two characters to test",
+            alphabet.name()
+        ));
+    }
+    let words = wordlist.words().iter().collect::<HashSet<_>>().len();
+    if words < 2 {
+        return Err("the word list needs at least two distinct words to test".to_string());
+    }
+
+    let characters = sample_characters(alphabet, sample_size, rng);
+    let mut results = check_samples(
+        &format!("characters ({})", alphabet.name()),
+        &characters,
+        alphabet.len(),
+    );
+    let samples = sample_words(wordlist, sample_size, rng);
+    results.extend(check_samples(
+        &format!("words ({})", wordlist.name()),
+        &samples,
+        wordlist.words().len(),
+    ));
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_chi_square_uniform() {
+        assert_eq!(chi_square(&[10, 10, 10, 10]), 0.0);
+        assert_eq!(chi_square(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_chi_square_biased() {
+        // Expected 10 per bucket: (10^2 + 10^2) / 10
+        assert_eq!(chi_square(&[20, 0, 10, 10]), 20.0);
+    }
+
+    #[test]
+    fn test_chi_square_critical() {
+        // Exact 99.9% quantiles: df=10 -> 29.59, df=71 -> 112.7
+        assert!((chi_square_critical(10) - 29.59).abs() < 0.5);
+        assert!((chi_square_critical(71) - 112.7).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_serial_correlation() {
+        let alternating: Vec<usize> = (0..1000).map(|i| i % 2).collect();
+        assert!((serial_correlation(&alternating) + 1.0).abs() < 1e-9);
+
+        let constant = vec![3; 100];
+        assert_eq!(serial_correlation(&constant), 0.0);
+    }
+
+    #[test]
+    fn test_run_passes_with_csprng() {
+        let mut rng = ChaCha20Rng::from_seed([42; 32]);
+        let results = run(&Alphabet::Full, &WordList::EffLarge, 200_000, &mut rng).unwrap();
+        assert_eq!(results.len(), 6);
+        for result in results {
+            assert!(
+                result.passed,
+                "{}: {} >= {}",
+                result.name, result.statistic, result.threshold
+            );
+        }
+    }
+
+    #[test]
+    fn test_biased_sampler_fails() {
+        use rand::Rng;
+
+        // Modulo-reducing a byte onto 72 characters over-weights the first 40
+        let mut rng = ChaCha20Rng::from_seed([1; 32]);
+        let samples: Vec<usize> = (0..200_000)
+            .map(|_| rng.random::<u8>() as usize % 72)
+            .collect();
+        let results = check_samples("biased", &samples, 72);
+        assert!(!results[0].passed);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        use rand::Rng;
+
+        // Pairs of 100 values: 10,000 secrets, about 50 expected repeats among 10,000 pairs
+        let mut rng = ChaCha20Rng::from_seed([2; 32]);
+        let samples: Vec<usize> = (0..20_000).map(|_| rng.random_range(0..100)).collect();
+        assert!(duplicates(&samples, 100, 2).abs() < Z_CRITICAL);
+        // The same values sampled from only half of them repeat about twice as often
+        let halved: Vec<usize> = samples.iter().map(|&s| s / 2).collect();
+        assert!(duplicates(&halved, 100, 2) > Z_CRITICAL);
+
+        assert_eq!(duplicate_group(1_000_000, 72), 4);
+        assert_eq!(duplicate_group(1000, 7776), 1);
+    }
+
+    #[test]
+    fn test_run_needs_choices() {
+        let mut rng = ChaCha20Rng::from_seed([42; 32]);
+        let custom = Alphabet::Custom("a".to_string());
+        assert!(run(&custom, &WordList::EffLarge, 100, &mut rng).is_err());
+        let words = WordList::from_custom(vec!["same".to_string(), "same".to_string()]);
+        assert!(run(&Alphabet::Full, &words, 100, &mut rng).is_err());
+    }
+}
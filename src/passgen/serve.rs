@@ -0,0 +1,530 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::checker::{self, Classification, Screen};
+use crate::passgen::generate::Generator;
+use crate::passgen::metrics::Metrics;
+use crate::passgen::passphrase;
+use crate::passgen::password::Password;
+use crate::passgen::patterns::PersonalContext;
+use crate::passgen::rules::PasswordRules;
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+const DEFAULT_LENGTH: usize = 12;
+const MAX_LENGTH: usize = 4096;
+const MAX_WORDS: usize = 64;
+const MAX_COUNT: usize = 100;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Fixed-window request counter per client address.
+pub struct RateLimiter {
+    limit: u32,
+    clients: HashMap<IpAddr, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Records a request and returns whether it is within the limit.
+    pub fn allow(&mut self, client: IpAddr, now: Instant) -> bool {
+        self.clients
+            .retain(|_, (start, _)| now.duration_since(*start) < RATE_WINDOW);
+        let (_, count) = self.clients.entry(client).or_insert((now, 0));
+        *count += 1;
+        *count <= self.limit
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GenerateRequest {
+    length: Option<usize>,
+    alphabet: Option<String>,
+    custom: Option<String>,
+    /// Site requirements in `passwordrules` syntax, instead of an alphabet
+    rules: Option<String>,
+    #[serde(default = "default_count")]
+    count: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PassphraseRequest {
+    #[serde(default = "default_words")]
+    words: usize,
+    wordlist: Option<String>,
+    #[serde(default = "default_separator")]
+    separator: String,
+    #[serde(default = "default_count")]
+    count: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckRequest {
+    password: String,
+    alphabet: Option<String>,
+    custom: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckResponse {
+    safe: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finding: Option<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classification: Option<Classification>,
+}
+
+fn default_words() -> usize {
+    3
+}
+
+fn default_separator() -> String {
+    "-".to_string()
+}
+
+fn default_count() -> usize {
+    1
+}
+
+/// An HTTP response as a status code and JSON body.
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Routes API requests; independent of the HTTP transport so it can be tested directly.
+pub struct Api {
+    token: String,
+    limiter: RateLimiter,
+    metrics: Metrics,
+}
+
+impl Api {
+    pub fn new(token: String, rate_limit: u32) -> Self {
+        Self {
+            token,
+            limiter: RateLimiter::new(rate_limit),
+            metrics: Metrics::default(),
+        }
+    }
+
+    pub fn handle(
+        &mut self,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+        client: IpAddr,
+        body: &str,
+        now: Instant,
+    ) -> Reply {
+        // Counted before the token is checked, so guessing it is throttled too
+        if !self.limiter.allow(client, now) {
+            return Reply::error(429, "rate limit exceeded");
+        }
+        let authorized = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), self.token.as_bytes()));
+        if !authorized {
+            return Reply::error(401, "missing or invalid bearer token");
+        }
+
+        let name = match path {
+            "/generate" => "generate",
+            "/passphrase" => "passphrase",
+            "/check" => "check",
+            _ => return Reply::error(404, format!("no endpoint {}", path)),
+        };
+        if method != "POST" {
+            return Reply::error(405, "use POST with a JSON body");
+        }
+
+        // An empty body means "all defaults"
+        let body = if body.trim().is_empty() { "{}" } else { body };
+        let started = Instant::now();
+        let result = serde_json::from_str(body)
+            .map_err(|e| format!("invalid request: {}", e))
+            .and_then(|request| call(name, request));
+        self.metrics
+            .observe("http", name, result.as_ref().ok(), started.elapsed());
+        result.map_or_else(|message| Reply::error(400, message), Reply::ok)
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+}
+
+fn parse<T: DeserializeOwned>(request: Value) -> Result<T, String> {
+    serde_json::from_value(request).map_err(|e| format!("invalid request: {}", e))
+}
+
+fn in_range(name: &str, value: usize, max: usize) -> Result<(), String> {
+    if value == 0 || value > max {
+        return Err(format!("`{}` must be between 1 and {}", name, max));
+    }
+    Ok(())
+}
+
+fn alphabet(name: Option<String>, custom: Option<String>) -> Result<Alphabet, String> {
+    match (name, custom) {
+        (Some(_), Some(_)) => Err("`alphabet` and `custom` cannot both be set".to_string()),
+        (_, Some(custom)) if custom.is_empty() => Err("`custom` alphabet is empty".to_string()),
+        (_, Some(custom)) => Ok(Alphabet::Custom(custom)),
+        (Some(name), None) => {
+            Alphabet::from_str(&name, true).map_err(|_| format!("unknown alphabet `{}`", name))
+        }
+        (None, None) => Ok(Alphabet::default()),
+    }
+}
+
+/// Generates passwords for a request like `{"length": 20, "alphabet": "digits", "count": 3}`
+/// or `{"rules": "minlength: 8; required: upper; required: digit"}`.
+pub fn generate(request: Value) -> Result<Value, String> {
+    let request: GenerateRequest = parse(request)?;
+    if let Some(length) = request.length {
+        in_range("length", length, MAX_LENGTH)?;
+    }
+    in_range("count", request.count, MAX_COUNT)?;
+    let mut rng = rand::rng();
+
+    let passwords = match request.rules {
+        Some(_) if request.alphabet.is_some() || request.custom.is_some() => {
+            return Err("`rules` cannot be combined with `alphabet` or `custom`".to_string());
+        }
+        Some(rules) => {
+            let rules = PasswordRules::parse(&rules)?;
+            if rules.length(request.length) > MAX_LENGTH {
+                return Err(format!("`length` must be between 1 and {}", MAX_LENGTH));
+            }
+            (0..request.count)
+                .map(|_| rules.generate(request.length, &mut rng))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        None => {
+            let alphabet = alphabet(request.alphabet, request.custom)?;
+            let length = request.length.unwrap_or(DEFAULT_LENGTH);
+            let generator = Generator::new(&alphabet)?;
+            (0..request.count)
+                .map(|_| generator.generate(length, &mut rng).value.into_owned())
+                .collect()
+        }
+    };
+    Ok(json!({ "passwords": passwords }))
+}
+
+/// Generates passphrases for a request like `{"words": 5, "wordlist": "eff-short1"}`.
+pub fn passphrase(request: Value) -> Result<Value, String> {
+    let request: PassphraseRequest = parse(request)?;
+    in_range("words", request.words, MAX_WORDS)?;
+    in_range("count", request.count, MAX_COUNT)?;
+    let wordlist = match request.wordlist {
+        Some(name) => {
+            WordList::from_str(&name, true).map_err(|_| format!("unknown wordlist `{}`", name))?
+        }
+        None => WordList::default(),
+    };
+
+    let mut rng = rand::rng();
+    let passphrases = (0..request.count)
+        .map(|_| {
+            passphrase::generate_passphrase(request.words, &request.separator, &wordlist, &mut rng)
+                .map(|passphrase| passphrase.value.into_owned())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(json!({ "passphrases": passphrases }))
+}
+
+/// Dispatches a method name shared by the local protocols to its handler.
+pub fn call(method: &str, params: Value) -> Result<Value, String> {
+    match method {
+        "generate" => generate(params),
+        "passphrase" => passphrase(params),
+        "check" => check(params),
+        other => Err(format!("unknown method `{}`", other)),
+    }
+}
+
+/// Checks a password for a request like `{"password": "hunter2"}` as `passgen check` does,
+/// inferring the alphabet from the password unless `alphabet` or `custom` is given.
+pub fn check(request: Value) -> Result<Value, String> {
+    let request: CheckRequest = parse(request)?;
+    let alphabet = match (request.alphabet, request.custom) {
+        (None, None) => None,
+        (name, custom) => Some(alphabet(name, custom)?),
+    };
+    let password = Password::new(&request.password);
+
+    let lists = checker::SAFETY_CHECKS;
+    let assessment = Screen::new(&lists)
+        .assess(&password, alphabet.as_ref(), &PersonalContext::default())
+        .map_err(|e| e.to_string())?;
+    let response = CheckResponse {
+        safe: assessment.safety.is_safe(),
+        finding: assessment.safety.finding,
+        classification: assessment.classification,
+    };
+    Ok(serde_json::to_value(response).expect("response serializes"))
+}
+
+// Avoids leaking how much of the token matched through response timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn random_token() -> String {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "secret-token";
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn call(api: &mut Api, path: &str, body: &str) -> Reply {
+        api.handle(
+            "POST",
+            path,
+            Some("Bearer secret-token"),
+            CLIENT,
+            body,
+            Instant::now(),
+        )
+    }
+
+    fn api() -> Api {
+        Api::new(TOKEN.to_string(), 100)
+    }
+
+    #[test]
+    fn test_requires_token() {
+        let mut api = api();
+        let now = Instant::now();
+        assert_eq!(
+            api.handle("POST", "/generate", None, CLIENT, "", now)
+                .status,
+            401
+        );
+        assert_eq!(
+            api.handle("POST", "/generate", Some("Bearer wrong"), CLIENT, "", now)
+                .status,
+            401
+        );
+        assert_eq!(
+            api.handle("POST", "/generate", Some(TOKEN), CLIENT, "", now)
+                .status,
+            401
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let reply = call(
+            &mut api(),
+            "/generate",
+            r#"{"length": 20, "alphabet": "digits", "count": 3}"#,
+        );
+        assert_eq!(reply.status, 200);
+        let passwords = reply.body["passwords"].as_array().unwrap();
+        assert_eq!(passwords.len(), 3);
+        for password in passwords {
+            let password = password.as_str().unwrap();
+            assert_eq!(password.len(), 20);
+            assert!(password.chars().all(|c| c.is_ascii_digit()));
+        }
+
+        let reply = call(&mut api(), "/generate", "");
+        assert_eq!(reply.body["passwords"][0].as_str().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn test_generate_with_rules() {
+        let reply = call(
+            &mut api(),
+            "/generate",
+            r#"{"rules": "minlength: 20; maxlength: 20; required: digit; allowed: lower", "count": 5}"#,
+        );
+        assert_eq!(reply.status, 200);
+        for password in reply.body["passwords"].as_array().unwrap() {
+            let password = password.as_str().unwrap();
+            assert_eq!(password.len(), 20);
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(
+                password
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+            );
+        }
+
+        let mut api = api();
+        assert_eq!(
+            call(&mut api, "/generate", r#"{"rules": "required: emoji"}"#).status,
+            400
+        );
+        assert_eq!(
+            call(
+                &mut api,
+                "/generate",
+                r#"{"rules": "", "alphabet": "digits"}"#
+            )
+            .status,
+            400
+        );
+    }
+
+    #[test]
+    fn test_passphrase() {
+        let reply = call(
+            &mut api(),
+            "/passphrase",
+            r#"{"words": 5, "separator": " ", "wordlist": "eff-short1"}"#,
+        );
+        assert_eq!(reply.status, 200);
+        let passphrase = reply.body["passphrases"][0].as_str().unwrap();
+        assert_eq!(passphrase.split(' ').count(), 5);
+    }
+
+    #[test]
+    fn test_check() {
+        let reply = call(&mut api(), "/check", r#"{"password": "password"}"#);
+        assert_eq!(
+            reply.body,
+            json!({"safe": false, "finding": "common password"})
+        );
+
+        let reply = call(&mut api(), "/check", r#"{"password": "xQ9#vL2!pZ7@kR4$"}"#);
+        assert_eq!(
+            reply.body,
+            json!({"safe": true, "classification": "VeryStrong"})
+        );
+
+        // Spaces widen the inferred alphabet instead of failing the check
+        let reply = call(&mut api(), "/check", r#"{"password": "tangy orbit mulch"}"#);
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body["safe"], true);
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let mut api = api();
+        assert_eq!(call(&mut api, "/generate", "{").status, 400);
+        assert_eq!(call(&mut api, "/generate", r#"{"length": 0}"#).status, 400);
+        assert_eq!(
+            call(&mut api, "/generate", r#"{"count": 1000}"#).status,
+            400
+        );
+        assert_eq!(call(&mut api, "/generate", r#"{"lenght": 8}"#).status, 400);
+        assert_eq!(
+            call(&mut api, "/generate", r#"{"alphabet": "greek"}"#).status,
+            400
+        );
+        assert_eq!(
+            call(
+                &mut api,
+                "/generate",
+                r#"{"alphabet": "digits", "custom": "ab"}"#
+            )
+            .status,
+            400
+        );
+        assert_eq!(call(&mut api, "/check", "{}").status, 400);
+        assert_eq!(call(&mut api, "/nope", "").status, 404);
+        assert_eq!(
+            api.handle(
+                "GET",
+                "/generate",
+                Some("Bearer secret-token"),
+                CLIENT,
+                "",
+                Instant::now()
+            )
+            .status,
+            405
+        );
+    }
+
+    #[test]
+    fn test_requests_are_counted() {
+        let mut api = api();
+        call(&mut api, "/check", r#"{"password": "password"}"#);
+        call(&mut api, "/generate", r#"{"length": 0}"#);
+        let text = api.metrics.render();
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"http\",method=\"check\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains(
+            "passgen_requests_total{interface=\"http\",method=\"generate\",outcome=\"error\"} 1\n"
+        ));
+        assert!(text.contains("passgen_checked_passwords_total{strength=\"unsafe\"} 1\n"));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        let other = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+        assert!(limiter.allow(CLIENT, start));
+        assert!(limiter.allow(CLIENT, start));
+        assert!(!limiter.allow(CLIENT, start));
+        assert!(limiter.allow(other, start));
+        assert!(limiter.allow(CLIENT, start + RATE_WINDOW));
+    }
+
+    #[test]
+    fn test_rate_limited_requests_are_rejected() {
+        let mut api = Api::new(TOKEN.to_string(), 1);
+        assert_eq!(call(&mut api, "/generate", "").status, 200);
+        assert_eq!(call(&mut api, "/generate", "").status, 429);
+
+        let mut api = Api::new(TOKEN.to_string(), 1);
+        let now = Instant::now();
+        let guess = |api: &mut Api| {
+            api.handle("POST", "/generate", Some("Bearer guess"), CLIENT, "", now)
+                .status
+        };
+        assert_eq!(guess(&mut api), 401);
+        assert_eq!(guess(&mut api), 429);
+    }
+
+    #[test]
+    fn test_random_token() {
+        let token = random_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, random_token());
+    }
+}
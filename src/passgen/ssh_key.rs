@@ -0,0 +1,175 @@
+use clap::ValueEnum;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Environment variable through which [`generate`] hands the passphrase to passgen running
+/// as `ssh-keygen`'s askpass helper.
+pub const ASKPASS_ENV: &str = "PASSGEN_SSH_PASSPHRASE";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum KeyType {
+    #[default]
+    Ed25519,
+    Ecdsa,
+    /// 4096-bit RSA, for servers that accept nothing newer
+    Rsa,
+}
+
+impl KeyType {
+    fn name(self) -> &'static str {
+        match self {
+            KeyType::Ed25519 => "ed25519",
+            KeyType::Ecdsa => "ecdsa",
+            KeyType::Rsa => "rsa",
+        }
+    }
+}
+
+/// Where `ssh-keygen` would put the key by default, e.g. `~/.ssh/id_ed25519`.
+pub fn default_path(key_type: KeyType) -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join(format!("id_{}", key_type.name())))
+}
+
+pub fn public_key_path(path: &Path) -> PathBuf {
+    let mut public = path.as_os_str().to_owned();
+    public.push(".pub");
+    PathBuf::from(public)
+}
+
+// Next to the key, so the final rename stays on one filesystem
+fn staged_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or(OsStr::new("key")));
+    name.push(".new");
+    path.with_file_name(name)
+}
+
+fn keygen_args(key_type: KeyType, path: &Path, comment: Option<&str>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["-q".into(), "-t".into(), key_type.name().into()];
+    if key_type == KeyType::Rsa {
+        args.extend(["-b".into(), "4096".into()]);
+    }
+    args.extend(["-f".into(), path.into()]);
+    if let Some(comment) = comment {
+        args.extend(["-C".into(), comment.into()]);
+    }
+    args
+}
+
+/// The passphrase to answer `ssh-keygen`'s prompts with, when passgen runs as its askpass
+/// helper.
+pub fn askpass_passphrase() -> Option<OsString> {
+    std::env::var_os(ASKPASS_ENV)
+}
+
+/// Creates a key pair protected by `passphrase`, replacing any key already at `path` only
+/// once the new one exists.
+///
+/// Rather than on the command line, where other users could see it in the process list,
+/// `ssh-keygen` gets the passphrase from this program run as its askpass helper, with the
+/// passphrase in its environment. This needs OpenSSH 8.4 or later.
+pub fn generate(
+    key_type: KeyType,
+    path: &Path,
+    passphrase: &str,
+    comment: Option<&str>,
+) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(dir)?;
+    }
+    let staged = staged_path(path);
+    let staged_public = public_key_path(&staged);
+    let remove_staged = || {
+        for file in [&staged, &staged_public] {
+            if file.exists() {
+                let _ = fs::remove_file(file);
+            }
+        }
+    };
+    // ssh-keygen would otherwise stop to ask before overwriting a leftover
+    remove_staged();
+
+    let status = Command::new("ssh-keygen")
+        .args(keygen_args(key_type, &staged, comment))
+        .env("SSH_ASKPASS", std::env::current_exe()?)
+        .env("SSH_ASKPASS_REQUIRE", "force")
+        .env(ASKPASS_ENV, passphrase)
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(e.kind(), "ssh-keygen not found; install OpenSSH")
+            }
+            _ => e,
+        });
+    let result = match status {
+        Ok(status) if status.success() => fs::rename(&staged, path)
+            .and_then(|()| fs::rename(&staged_public, public_key_path(path))),
+        Ok(status) => Err(io::Error::other(format!(
+            "ssh-keygen exited with {}",
+            status
+        ))),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        remove_staged();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keygen_args() {
+        let args = keygen_args(
+            KeyType::Ed25519,
+            Path::new("/home/me/.ssh/id_ed25519"),
+            Some("me@laptop"),
+        );
+        assert_eq!(
+            args,
+            [
+                "-q",
+                "-t",
+                "ed25519",
+                "-f",
+                "/home/me/.ssh/id_ed25519",
+                "-C",
+                "me@laptop"
+            ]
+            .map(OsString::from)
+        );
+
+        let args = keygen_args(KeyType::Rsa, Path::new("key"), None);
+        assert_eq!(
+            args,
+            ["-q", "-t", "rsa", "-b", "4096", "-f", "key"].map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let path = default_path(KeyType::Ecdsa).unwrap();
+        assert!(path.ends_with(".ssh/id_ecdsa"));
+        assert_eq!(
+            public_key_path(Path::new("/keys/id_ed25519")),
+            PathBuf::from("/keys/id_ed25519.pub")
+        );
+        assert_eq!(
+            staged_path(Path::new("/keys/id_ed25519")),
+            PathBuf::from("/keys/.id_ed25519.new")
+        );
+    }
+}
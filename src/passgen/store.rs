@@ -0,0 +1,421 @@
+use crate::passgen::systemd_creds::Credential;
+use crate::passgen::vault::{self, VaultPath};
+use crate::passgen::which::on_path;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// password-store compatible tools, in order of preference
+const PASS_BACKENDS: &[&str] = &["pass", "gopass"];
+// Container engines with a `secret` command, in order of preference
+const SECRET_BACKENDS: &[&str] = &["docker", "podman"];
+const KEEPASSXC_CLI: &[&str] = &["keepassxc-cli"];
+
+/// Where a generated secret is saved instead of being printed, e.g. `pass:web/github.com`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Store {
+    Pass(String),
+    /// Platform keychain entry: service and account
+    Keyring(String, String),
+    Vault(VaultPath),
+    SystemdCred(Credential),
+    DockerSecret(String),
+    /// KeePassXC database and the entry in it, with `/` between groups
+    Keepassxc(PathBuf, String),
+}
+
+impl FromStr for Store {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected STORE:NAME, e.g. pass:web/github.com, got `{}`", s))?;
+        match kind {
+            "pass" => {
+                validate_entry(name)?;
+                Ok(Store::Pass(name.to_string()))
+            }
+            "keyring" => match name.rsplit_once('/') {
+                Some((service, account)) if !service.is_empty() && !account.is_empty() => {
+                    Ok(Store::Keyring(service.to_string(), account.to_string()))
+                }
+                _ => Err(format!(
+                    "expected keyring:SERVICE/ACCOUNT, e.g. keyring:github.com/octocat, got `{}`",
+                    s
+                )),
+            },
+            "vault" => VaultPath::parse(name).map(Store::Vault),
+            "systemd-cred" => Credential::parse(name).map(Store::SystemdCred),
+            "docker-secret" => {
+                validate_secret_name(name)?;
+                Ok(Store::DockerSecret(name.to_string()))
+            }
+            // The database path may have colons of its own, e.g. a Windows drive
+            "keepassxc" => match name.rsplit_once(':') {
+                Some((database, entry))
+                    if !database.is_empty() && !entry.is_empty() && !entry.ends_with('/') =>
+                {
+                    Ok(Store::Keepassxc(PathBuf::from(database), entry.to_string()))
+                }
+                _ => Err(format!(
+                    "expected keepassxc:DATABASE:ENTRY, e.g. keepassxc:vault.kdbx:web/github.com, \
+                     got `{}`",
+                    s
+                )),
+            },
+            other => Err(format!(
+                "unknown store `{}`, expected pass, keyring, vault, systemd-cred, docker-secret, \
+                 or keepassxc",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Store {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Store::Pass(entry) => write!(f, "pass:{}", entry),
+            Store::Keyring(service, account) => write!(f, "keyring:{}/{}", service, account),
+            Store::Vault(secret) => write!(f, "vault:{}", secret),
+            Store::SystemdCred(credential) => write!(f, "systemd-cred:{}", credential),
+            Store::DockerSecret(name) => write!(f, "docker-secret:{}", name),
+            Store::Keepassxc(database, entry) => {
+                write!(f, "keepassxc:{}:{}", database.display(), entry)
+            }
+        }
+    }
+}
+
+// Entries are paths inside the store; refuse anything that could escape it
+fn validate_entry(name: &str) -> Result<(), String> {
+    let path = Path::new(name);
+    if name.is_empty() || name.ends_with('/') {
+        return Err(format!("invalid pass entry `{}`", name));
+    }
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("pass entry `{}` must be a relative path", name));
+    }
+    Ok(())
+}
+
+// Docker accepts up to 64 letters, digits, `.`, `_`, and `-`, starting with a letter or digit
+fn validate_secret_name(name: &str) -> Result<(), String> {
+    let valid = name.len() <= 64
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        return Err(format!("invalid Docker secret name `{}`", name));
+    }
+    Ok(())
+}
+
+// A valid secret name to stage the replacement of `name` under
+fn staged_secret_name(name: &str) -> String {
+    const SUFFIX: &str = ".passgen-new";
+    format!("{}{}", &name[..name.len().min(64 - SUFFIX.len())], SUFFIX)
+}
+
+/// The password store directory, honouring `PASSWORD_STORE_DIR` like `pass` does.
+fn pass_store_dir() -> Option<PathBuf> {
+    match std::env::var_os("PASSWORD_STORE_DIR") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::home_dir().map(|home| home.join(".password-store")),
+    }
+}
+
+fn pass_entry_path(dir: &Path, entry: &str) -> PathBuf {
+    dir.join(format!("{}.gpg", entry))
+}
+
+fn backend(candidates: &[&'static str], kind: &str) -> io::Result<&'static str> {
+    candidates
+        .iter()
+        .copied()
+        .find(|program| on_path(program))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No {} found; install one of: {}",
+                    kind,
+                    candidates.join(", ")
+                ),
+            )
+        })
+}
+
+// Secrets are handed over on stdin, never as arguments other users could see
+fn run_with_input(program: &str, args: &[&str], input: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} {} exited with {}",
+            program, args[0], status
+        )));
+    }
+    Ok(())
+}
+
+fn succeeds(program: &str, args: &[&str]) -> io::Result<bool> {
+    let status = Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    Ok(status.success())
+}
+
+// The password unlocking a KeePassXC database, asked for once however often the database
+// is opened
+fn keepassxc_password(database: &Path) -> io::Result<&'static str> {
+    static PASSWORD: OnceLock<String> = OnceLock::new();
+    if let Some(password) = PASSWORD.get() {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password(format!("Password for {}: ", database.display()))?;
+    Ok(PASSWORD.get_or_init(|| password))
+}
+
+// Runs `keepassxc-cli` quietly on the database, which reads its password as the first
+// line of stdin and any entry password after it
+fn keepassxc(command: &str, database: &Path, entry: &str, secret: Option<&str>) -> io::Result<()> {
+    let program = backend(KEEPASSXC_CLI, "KeePassXC command-line tool")?;
+    let database_arg = database.to_string_lossy();
+    let mut args = vec![command, "--quiet"];
+    if secret.is_some() {
+        args.push("--password-prompt");
+    }
+    args.extend([database_arg.as_ref(), entry]);
+    let mut input = format!("{}\n", keepassxc_password(database)?);
+    if let Some(secret) = secret {
+        input.push_str(secret);
+        input.push('\n');
+    }
+    run_with_input(program, &args, &input)
+}
+
+impl Store {
+    /// Whether saving would replace an existing secret.
+    pub fn exists(&self) -> io::Result<bool> {
+        match self {
+            Store::Pass(entry) => {
+                Ok(pass_store_dir().is_some_and(|dir| pass_entry_path(&dir, entry).exists()))
+            }
+            Store::Keyring(service, account) => {
+                match keyring_entry(service, account)?.get_password() {
+                    Ok(_) => Ok(true),
+                    Err(keyring::Error::NoEntry) => Ok(false),
+                    Err(e) => Err(keyring_error(e)),
+                }
+            }
+            Store::Vault(secret) => vault::exists(secret),
+            Store::SystemdCred(credential) => Ok(credential.exists()),
+            Store::DockerSecret(name) => {
+                let program = backend(SECRET_BACKENDS, "container engine")?;
+                succeeds(program, &["secret", "inspect", name])
+            }
+            // A wrong database password fails the same way, and then saving fails too
+            Store::Keepassxc(database, entry) => {
+                Ok(keepassxc("show", database, entry, None).is_ok())
+            }
+        }
+    }
+
+    pub fn save(&self, secret: &str) -> io::Result<()> {
+        match self {
+            Store::Pass(entry) => {
+                let program = backend(PASS_BACKENDS, "password store")?;
+                run_with_input(
+                    program,
+                    &["insert", "--multiline", "--force", entry],
+                    &format!("{}\n", secret),
+                )
+            }
+            Store::Keyring(service, account) => keyring_entry(service, account)?
+                .set_password(secret)
+                .map_err(keyring_error),
+            Store::Vault(path) => vault::save(path, secret),
+            Store::SystemdCred(credential) => {
+                let path = credential.save(secret)?;
+                tracing::debug!("Wrote credential {} to {}", credential.name, path.display());
+                Ok(())
+            }
+            Store::DockerSecret(name) => {
+                let program = backend(SECRET_BACKENDS, "container engine")?;
+                if !succeeds(program, &["secret", "inspect", name])? {
+                    return run_with_input(program, &["secret", "create", name, "-"], secret);
+                }
+                // Secrets are immutable, so replacing one means removing it first; the new
+                // secret is created under a temporary name beforehand, so a failure never
+                // loses both the old and the new one
+                let staged = staged_secret_name(name);
+                run_with_input(program, &["secret", "create", &staged, "-"], secret)?;
+                if !succeeds(program, &["secret", "rm", name])? {
+                    let _ = succeeds(program, &["secret", "rm", &staged]);
+                    return Err(io::Error::other(format!(
+                        "{} secret rm {} failed; is a service still using it?",
+                        program, name
+                    )));
+                }
+                run_with_input(program, &["secret", "create", name, "-"], secret).map_err(|e| {
+                    io::Error::other(format!("{}; the new secret is kept as {}", e, staged))
+                })?;
+                let _ = succeeds(program, &["secret", "rm", &staged]);
+                Ok(())
+            }
+            Store::Keepassxc(database, entry) => {
+                let command = match self.exists()? {
+                    true => "edit",
+                    false => "add",
+                };
+                keepassxc(command, database, entry, Some(secret))
+            }
+        }
+    }
+}
+
+fn keyring_entry(service: &str, account: &str) -> io::Result<keyring::Entry> {
+    keyring::Entry::new(service, account).map_err(keyring_error)
+}
+
+// Keyring errors already describe their cause, so keep only the message
+fn keyring_error(e: keyring::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pass() {
+        let store: Store = "pass:web/github.com".parse().unwrap();
+        assert_eq!(store, Store::Pass("web/github.com".to_string()));
+        assert_eq!(store.to_string(), "pass:web/github.com");
+    }
+
+    #[test]
+    fn test_parse_keyring() {
+        let store: Store = "keyring:github.com/octocat".parse().unwrap();
+        assert_eq!(
+            store,
+            Store::Keyring("github.com".to_string(), "octocat".to_string())
+        );
+        assert_eq!(store.to_string(), "keyring:github.com/octocat");
+
+        let store: Store = "keyring:corp/vpn/alice".parse().unwrap();
+        assert_eq!(
+            store,
+            Store::Keyring("corp/vpn".to_string(), "alice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_vault() {
+        let store: Store = "vault:secret/data/app/db".parse().unwrap();
+        assert_eq!(
+            store,
+            Store::Vault(VaultPath::parse("secret/data/app/db").unwrap())
+        );
+        assert_eq!(store.to_string(), "vault:secret/data/app/db");
+        assert!("vault:secret".parse::<Store>().is_err());
+    }
+
+    #[test]
+    fn test_parse_systemd_cred() {
+        let store: Store = "systemd-cred:db-password".parse().unwrap();
+        assert_eq!(
+            store,
+            Store::SystemdCred(Credential::parse("db-password").unwrap())
+        );
+        assert_eq!(store.to_string(), "systemd-cred:db-password");
+        assert!("systemd-cred:app/db".parse::<Store>().is_err());
+    }
+
+    #[test]
+    fn test_staged_secret_name() {
+        assert_eq!(staged_secret_name("db"), "db.passgen-new");
+        let staged = staged_secret_name(&"a".repeat(64));
+        assert!(validate_secret_name(&staged).is_ok());
+        assert!(staged.ends_with(".passgen-new"));
+    }
+
+    #[test]
+    fn test_parse_docker_secret() {
+        let store: Store = "docker-secret:db_password.v2".parse().unwrap();
+        assert_eq!(store, Store::DockerSecret("db_password.v2".to_string()));
+        assert_eq!(store.to_string(), "docker-secret:db_password.v2");
+        assert!("docker-secret:".parse::<Store>().is_err());
+        assert!("docker-secret:-db".parse::<Store>().is_err());
+        assert!("docker-secret:app/db".parse::<Store>().is_err());
+        assert!(
+            format!("docker-secret:{}", "a".repeat(65))
+                .parse::<Store>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_keepassxc() {
+        let store: Store = "keepassxc:vault.kdbx:web/github.com".parse().unwrap();
+        assert_eq!(
+            store,
+            Store::Keepassxc(PathBuf::from("vault.kdbx"), "web/github.com".to_string())
+        );
+        assert_eq!(store.to_string(), "keepassxc:vault.kdbx:web/github.com");
+
+        let store: Store = r"keepassxc:C:\Users\alice\vault.kdbx:github.com"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            store,
+            Store::Keepassxc(
+                PathBuf::from(r"C:\Users\alice\vault.kdbx"),
+                "github.com".to_string()
+            )
+        );
+        assert!("keepassxc:vault.kdbx".parse::<Store>().is_err());
+        assert!("keepassxc::github.com".parse::<Store>().is_err());
+        assert!("keepassxc:vault.kdbx:web/".parse::<Store>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!("keyring:github.com".parse::<Store>().is_err());
+        assert!("keyring:/octocat".parse::<Store>().is_err());
+        assert!("keyring:github.com/".parse::<Store>().is_err());
+        assert!("web/github.com".parse::<Store>().is_err());
+        assert!("vault:web".parse::<Store>().is_err());
+        assert!("pass:".parse::<Store>().is_err());
+        assert!("pass:web/".parse::<Store>().is_err());
+        assert!("pass:/etc/passwd".parse::<Store>().is_err());
+        assert!("pass:../outside".parse::<Store>().is_err());
+        assert!("pass:web/../../outside".parse::<Store>().is_err());
+    }
+
+    #[test]
+    fn test_pass_entry_path() {
+        assert_eq!(
+            pass_entry_path(Path::new("/store"), "web/github.com"),
+            PathBuf::from("/store/web/github.com.gpg")
+        );
+    }
+}
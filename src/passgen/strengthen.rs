@@ -0,0 +1,215 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::checker::{self, Classification, Screen};
+use crate::passgen::generate::Generator;
+use crate::passgen::passphrase;
+use crate::passgen::password::{Password, Redacted};
+use crate::passgen::variants;
+use crate::passgen::wordlist::WordList;
+use rand::{CryptoRng, Rng};
+use serde::Serialize;
+use std::fmt;
+
+// Characters appended as a tail
+const TAIL: &str = "0123456789!@#$%^&*";
+
+/// How a variant hardens the password it keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    /// Random words appended after a separator
+    AppendWords,
+    /// Random digits and symbols appended
+    AppendSymbols,
+    /// Random characters inserted at random positions
+    InsertCharacters,
+}
+
+impl Method {
+    pub const ALL: [Method; 3] = [
+        Method::AppendWords,
+        Method::AppendSymbols,
+        Method::InsertCharacters,
+    ];
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Method::AppendWords => "appended words",
+            Method::AppendSymbols => "appended symbols",
+            Method::InsertCharacters => "inserted characters",
+        })
+    }
+}
+
+/// A hardened variant of a password, with its entropy: the base's plus that of the random
+/// additions, which alone is [`Hardened::gain`].
+#[derive(Clone, PartialEq)]
+pub struct Hardened {
+    pub value: String,
+    pub method: Method,
+    pub entropy: f64,
+    pub gain: f64,
+}
+
+impl fmt::Debug for Hardened {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hardened")
+            .field("value", &Redacted(&self.value))
+            .field("method", &self.method)
+            .field("entropy", &self.entropy)
+            .field("gain", &self.gain)
+            .finish()
+    }
+}
+
+/// Entropy an attacker guessing by character class credits `base` with, after its
+/// patterns; none for a common password, which is tried first.
+pub fn base_entropy(base: &str) -> f64 {
+    let password = Password::new(base);
+    let checks = checker::SAFETY_CHECKS;
+    if Screen::new(&checks).finding(&password).is_some() {
+        return 0.0;
+    }
+    password.strength(variants::search_space(base)).entropy
+}
+
+/// Hardens `base` with `method` just enough to reach `target`, drawing words from
+/// `wordlist` and joining them with `separator`. `None` if `base` is already there.
+pub fn strengthen<R: CryptoRng + ?Sized>(
+    base: &str,
+    method: Method,
+    target: Classification,
+    wordlist: &WordList,
+    separator: &str,
+    rng: &mut R,
+) -> Result<Option<Hardened>, String> {
+    let entropy = base_entropy(base);
+    let missing = target.min_entropy() - entropy;
+    if missing <= 0.0 {
+        return Ok(None);
+    }
+    let (value, gain) = match method {
+        Method::AppendWords => {
+            let size = wordlist.words().len();
+            if size < 2 {
+                return Err("the word list needs at least two words".to_string());
+            }
+            let count = (missing / (size as f64).log2()).ceil() as usize;
+            let words = passphrase::generate_passphrase(count, separator, wordlist, rng)?;
+            let value = format!("{}{}{}", base, separator, words.value);
+            (value, checker::passphrase_entropy(count, size))
+        }
+        Method::AppendSymbols => {
+            let alphabet = Alphabet::Custom(TAIL.to_string());
+            let count = (missing / alphabet.bits_per_char()).ceil() as usize;
+            let tail = Generator::new(&alphabet)?.generate(count, rng);
+            let value = format!("{}{}", base, tail.value);
+            (value, count as f64 * alphabet.bits_per_char())
+        }
+        Method::InsertCharacters => {
+            let alphabet = Alphabet::default();
+            let length = base.chars().count();
+            let count = (1..)
+                .find(|&count| insertion_entropy(length, count, &alphabet) >= missing)
+                .expect("each insertion adds entropy");
+            let generator = Generator::new(&alphabet)?;
+            let mut chars: Vec<char> = base.chars().collect();
+            for c in generator.generate(count, rng).value.chars() {
+                let at = rng.random_range(0..=chars.len());
+                chars.insert(at, c);
+            }
+            let value = chars.into_iter().collect();
+            (value, insertion_entropy(length, count, &alphabet))
+        }
+    };
+    Ok(Some(Hardened {
+        value,
+        method,
+        entropy: entropy + gain,
+        gain,
+    }))
+}
+
+// Entropy of `count` characters of `alphabet` inserted into `length` others: the
+// characters, and which of the final positions they take
+fn insertion_entropy(length: usize, count: usize, alphabet: &Alphabet) -> f64 {
+    let positions: f64 = (1..=count)
+        .map(|i| ((length + i) as f64 / i as f64).log2())
+        .sum();
+    count as f64 * alphabet.bits_per_char() + positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_base_entropy() {
+        assert_eq!(base_entropy("dragon"), 0.0);
+        // Lower-case letters only, whatever the default alphabet
+        assert!((base_entropy("kqzmvt") - 6.0 * 26f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strengthen_reaches_target() {
+        let mut rng = ChaCha20Rng::from_seed([8; 32]);
+        let wordlist = WordList::default();
+        for method in Method::ALL {
+            let hardened = strengthen(
+                "dragon",
+                method,
+                Classification::Strong,
+                &wordlist,
+                "-",
+                &mut rng,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(hardened.method, method);
+            assert!(hardened.entropy >= 40.0, "{:?}", hardened);
+            assert_eq!(hardened.entropy, hardened.gain);
+            assert!(hardened.value.len() > "dragon".len());
+            let kept = hardened.value.chars().filter(|c| "dragon".contains(*c));
+            assert!(kept.count() >= 6, "{:?}", hardened);
+        }
+
+        let words = strengthen(
+            "dragon",
+            Method::AppendWords,
+            Classification::Strong,
+            &wordlist,
+            " ",
+            &mut rng,
+        )
+        .unwrap()
+        .unwrap();
+        // 12.9 bits a word
+        assert!(words.value.starts_with("dragon "));
+        assert_eq!(words.value.split(' ').count(), 5);
+    }
+
+    #[test]
+    fn test_strong_enough_already() {
+        let hardened = strengthen(
+            "kq7#Zm2!pXr4Tw9&",
+            Method::AppendSymbols,
+            Classification::Strong,
+            &WordList::default(),
+            "-",
+            &mut rand::rng(),
+        );
+        assert_eq!(hardened, Ok(None));
+    }
+
+    #[test]
+    fn test_insertion_entropy() {
+        let digits = Alphabet::Digits;
+        // One digit into four characters: ten digits at five positions
+        assert!((insertion_entropy(4, 1, &digits) - 50f64.log2()).abs() < 1e-9);
+        // Two: C(6, 2) = 15 position pairs
+        assert!((insertion_entropy(4, 2, &digits) - 1500f64.log2()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::passgen::which::on_path;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// Directories systemd searches for LoadCredentialEncrypted= and LoadCredential= names
+const ENCRYPTED_STORE: &str = "/etc/credstore.encrypted";
+const PLAIN_STORE: &str = "/etc/credstore";
+
+/// A systemd service credential, either a bare name placed in the system credential
+/// store or an absolute path whose file name is the credential name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credential {
+    pub name: String,
+    path: Option<PathBuf>,
+}
+
+impl Credential {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, path) = if spec.starts_with('/') {
+            let path = PathBuf::from(spec);
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string();
+            (name, Some(path))
+        } else {
+            (spec.to_string(), None)
+        };
+        if name.is_empty()
+            || name == "."
+            || name == ".."
+            || name.contains('/')
+            || spec.ends_with('/')
+        {
+            return Err(format!(
+                "expected systemd-cred:NAME or systemd-cred:/ABSOLUTE/PATH, got `systemd-cred:{}`",
+                spec
+            ));
+        }
+        Ok(Self { name, path })
+    }
+
+    fn encrypted_path(&self) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| Path::new(ENCRYPTED_STORE).join(&self.name))
+    }
+
+    fn plain_path(&self) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| Path::new(PLAIN_STORE).join(&self.name))
+    }
+
+    pub fn exists(&self) -> bool {
+        self.encrypted_path().exists() || self.plain_path().exists()
+    }
+
+    /// Encrypts the secret with `systemd-creds` when it is installed, otherwise writes it
+    /// as a plain credential file only root can read. Returns the file written.
+    pub fn save(&self, secret: &str) -> io::Result<PathBuf> {
+        if on_path("systemd-creds") {
+            let path = self.encrypted_path();
+            create_parent(&path)?;
+            encrypt(&self.name, secret, &path)?;
+            restrict(&path)?;
+            Ok(path)
+        } else {
+            let path = self.plain_path();
+            create_parent(&path)?;
+            write_private(&path, secret)?;
+            Ok(path)
+        }
+    }
+}
+
+impl std::fmt::Display for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}", path.display()),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) => private_dir_builder().create(dir),
+        None => Ok(()),
+    }
+}
+
+fn private_dir_builder() -> fs::DirBuilder {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+}
+
+// The secret goes through stdin, never the command line, where other users could see it
+fn encrypt(name: &str, secret: &str, path: &Path) -> io::Result<()> {
+    let mut child = Command::new("systemd-creds")
+        .arg("encrypt")
+        .arg(format!("--name={}", name))
+        .arg("-")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(secret.as_bytes())?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "systemd-creds encrypt exited with {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+// Credentials are read verbatim, so no trailing newline is added
+fn write_private(path: &Path, secret: &str) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // An existing file keeps its old mode when opened, so tighten it explicitly
+    restrict(path)?;
+    file.write_all(secret.as_bytes())?;
+    file.sync_all()
+}
+
+fn restrict(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name() {
+        let credential = Credential::parse("db-password").unwrap();
+        assert_eq!(credential.name, "db-password");
+        assert_eq!(
+            credential.encrypted_path(),
+            PathBuf::from("/etc/credstore.encrypted/db-password")
+        );
+        assert_eq!(
+            credential.plain_path(),
+            PathBuf::from("/etc/credstore/db-password")
+        );
+        assert_eq!(credential.to_string(), "db-password");
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let credential = Credential::parse("/srv/app/creds/db").unwrap();
+        assert_eq!(credential.name, "db");
+        assert_eq!(
+            credential.encrypted_path(),
+            PathBuf::from("/srv/app/creds/db")
+        );
+        assert_eq!(credential.to_string(), "/srv/app/creds/db");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!(Credential::parse("").is_err());
+        assert!(Credential::parse("..").is_err());
+        assert!(Credential::parse("app/db").is_err());
+        assert!(Credential::parse("/srv/app/").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plain_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("passgen-creds-{}", std::process::id()));
+        let path = dir.join("db");
+        create_parent(&path).unwrap();
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, "s3cret").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "s3cret");
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
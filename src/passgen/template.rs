@@ -0,0 +1,147 @@
+use rand::CryptoRng;
+use rand::seq::IndexedRandom;
+
+const CONSONANTS: &str = "bcdfghjklmnpqrstvwxyz";
+const VOWELS: &str = "aeiou";
+const UPPER_CONSONANTS: &str = "BCDFGHJKLMNPQRSTVWXYZ";
+const UPPER_VOWELS: &str = "AEIOU";
+const DIGITS: &str = "0123456789";
+const SPECIAL_CHARS: &str = "!@#$%^&*";
+const ANY: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*()";
+
+/// Placeholders of a pattern, with the characters each one stands for.
+pub const PLACEHOLDERS: &[(char, &str)] = &[
+    ('c', CONSONANTS),
+    ('C', UPPER_CONSONANTS),
+    ('v', VOWELS),
+    ('V', UPPER_VOWELS),
+    ('n', DIGITS),
+    ('s', SPECIAL_CHARS),
+    ('a', ANY),
+];
+
+#[derive(Debug, PartialEq)]
+enum Slot {
+    Literal(char),
+    Choice(Vec<char>),
+}
+
+/// A password format such as `Cvcvnn-Cvcvnn!`, in the style of pwgen and KeePass: each
+/// placeholder of [`PLACEHOLDERS`] is replaced by a uniformly chosen character of its class,
+/// and every other character is kept as is. A backslash keeps the character after it, e.g.
+/// `\n` for a literal `n`.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    slots: Vec<Slot>,
+}
+
+impl Template {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        Self::parse_with(pattern, PLACEHOLDERS)
+    }
+
+    /// Like [`Template::parse`], with other placeholders than [`PLACEHOLDERS`].
+    pub fn parse_with(pattern: &str, placeholders: &[(char, &str)]) -> Result<Self, String> {
+        let class_of = |placeholder| {
+            placeholders
+                .iter()
+                .find(|(c, _)| *c == placeholder)
+                .map(|(_, class)| *class)
+        };
+        let mut slots = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let slot = if c == '\\' {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| format!("the pattern `{}` ends with a backslash", pattern))?;
+                Slot::Literal(escaped)
+            } else {
+                match class_of(c) {
+                    Some(class) => Slot::Choice(class.chars().collect()),
+                    None => Slot::Literal(c),
+                }
+            };
+            slots.push(slot);
+        }
+        if slots.is_empty() {
+            return Err("the pattern is empty".to_string());
+        }
+        Ok(Self { slots })
+    }
+
+    /// Number of characters in generated passwords.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Entropy in bits of a generated password; literals add none.
+    pub fn entropy(&self) -> f64 {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Literal(_) => 0.0,
+                Slot::Choice(chars) => (chars.len() as f64).log2(),
+            })
+            .sum()
+    }
+
+    /// Replaces the contents of `buf` with a password of this format.
+    pub fn generate_into<R: CryptoRng + ?Sized>(&self, rng: &mut R, buf: &mut String) {
+        buf.clear();
+        buf.extend(self.slots.iter().map(|slot| match slot {
+            Slot::Literal(c) => *c,
+            Slot::Choice(chars) => *chars.choose(rng).expect("classes are not empty"),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_generate_follows_pattern() {
+        let template = Template::parse("Cvcvnn-Cvcvnn!").unwrap();
+        assert_eq!(template.len(), 14);
+        let mut rng = ChaCha20Rng::from_seed([6; 32]);
+        let mut buf = String::new();
+        for _ in 0..100 {
+            template.generate_into(&mut rng, &mut buf);
+            let chars: Vec<char> = buf.chars().collect();
+            assert_eq!(chars.len(), 14, "{}", buf);
+            for i in [0, 7] {
+                assert!(UPPER_CONSONANTS.contains(chars[i]), "{}", buf);
+                assert!(VOWELS.contains(chars[i + 1]), "{}", buf);
+                assert!(CONSONANTS.contains(chars[i + 2]), "{}", buf);
+                assert!(VOWELS.contains(chars[i + 3]), "{}", buf);
+                assert!(chars[i + 4].is_ascii_digit(), "{}", buf);
+                assert!(chars[i + 5].is_ascii_digit(), "{}", buf);
+            }
+            assert_eq!(chars[6], '-');
+            assert_eq!(chars[13], '!');
+        }
+    }
+
+    #[test]
+    fn test_escapes_and_entropy() {
+        let template = Template::parse(r"\n\\n").unwrap();
+        let mut buf = String::new();
+        template.generate_into(&mut rand::rng(), &mut buf);
+        assert!(buf.starts_with(r"n\"), "{}", buf);
+        assert!((template.entropy() - 10f64.log2()).abs() < 1e-9);
+        assert_eq!(Template::parse("x-y").unwrap().entropy(), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert_eq!(Template::parse("").unwrap_err(), "the pattern is empty");
+        assert!(Template::parse(r"nn\").unwrap_err().contains("backslash"));
+    }
+}
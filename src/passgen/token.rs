@@ -0,0 +1,139 @@
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use clap::ValueEnum;
+use rand::CryptoRng;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+// Bitcoin's alphabet, without 0, O, I and l, which are easily confused
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Text encoding of a token's random bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Encoding {
+    /// Lower case hexadecimal
+    #[default]
+    Hex,
+    /// RFC 4648 base32 without padding, e.g. for TOTP secrets
+    Base32,
+    /// Standard base64 with padding
+    Base64,
+    /// URL- and filename-safe base64 without padding
+    Base64url,
+    /// Bitcoin-style base58, free of look-alike characters
+    Base58,
+}
+
+impl Encoding {
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Hex => crate::passgen::hash::hex(bytes),
+            Encoding::Base32 => base32(bytes),
+            Encoding::Base64 => STANDARD.encode(bytes),
+            Encoding::Base64url => URL_SAFE_NO_PAD.encode(bytes),
+            Encoding::Base58 => base58(bytes),
+        }
+    }
+}
+
+/// Draws `bytes` random bytes and encodes them. The entropy is exactly 8 bits a byte,
+/// whatever the encoding.
+pub fn generate<R: CryptoRng + ?Sized>(bytes: usize, encoding: Encoding, rng: &mut R) -> String {
+    let mut raw = vec![0u8; bytes];
+    rng.fill_bytes(&mut raw);
+    encoding.encode(&raw)
+}
+
+fn base32(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for chunk in bytes.chunks(5) {
+        let mut block = [0u8; 5];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let bits = block.iter().fold(0u64, |acc, &b| acc << 8 | u64::from(b));
+        let digits = (chunk.len() * 8).div_ceil(5);
+        for i in 0..digits {
+            let index = (bits >> (35 - 5 * i)) & 0x1f;
+            encoded.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    encoded
+}
+
+// Treats the bytes as one big-endian number, with a leading `1` for each leading zero byte
+fn base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // Base-58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&d| BASE58_ALPHABET[d as usize] as char),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_base32_vectors() {
+        // RFC 4648, section 10, without padding
+        for (input, expected) in [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ] {
+            assert_eq!(Encoding::Base32.encode(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn test_base58_vectors() {
+        assert_eq!(Encoding::Base58.encode(b""), "");
+        assert_eq!(Encoding::Base58.encode(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(
+            Encoding::Base58.encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]),
+            "11233QC4"
+        );
+    }
+
+    #[test]
+    fn test_encodings() {
+        let bytes = [0xfb, 0xff, 0x00];
+        assert_eq!(Encoding::Hex.encode(&bytes), "fbff00");
+        assert_eq!(Encoding::Base64.encode(&bytes), "+/8A");
+        assert_eq!(Encoding::Base64url.encode(&bytes), "-_8A");
+    }
+
+    #[test]
+    fn test_generate_length() {
+        let mut rng = ChaCha20Rng::from_seed([9; 32]);
+        assert_eq!(generate(32, Encoding::Hex, &mut rng).len(), 64);
+        assert_eq!(generate(32, Encoding::Base64, &mut rng).len(), 44);
+        assert_eq!(generate(20, Encoding::Base32, &mut rng).len(), 32);
+        assert_ne!(
+            generate(16, Encoding::Hex, &mut rng),
+            generate(16, Encoding::Hex, &mut rng)
+        );
+    }
+}
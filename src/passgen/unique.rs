@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+// Repeats in a row allowed before giving up, however empty the space still is
+const MIN_MISSES: u64 = 1000;
+// Allowed repeats as a multiple of the draws a new secret takes on average, which makes
+// giving up on a space that still has room a one in e^32 event
+const MISS_FACTOR: f64 = 32.0;
+
+/// The secrets of one batch, for generating it without duplicates, e.g. recovery or
+/// voucher codes. A policy or requirements can leave fewer secrets than the entropy
+/// suggests, so drawing gives up after far more repeats than the space explains.
+pub struct Batch {
+    seen: HashSet<String>,
+    space: f64,
+    misses: u64,
+}
+
+impl Batch {
+    /// A batch of up to `count` secrets with `entropy` bits each, or an error if there are
+    /// not that many.
+    pub fn new(count: usize, entropy: f64) -> Result<Self, String> {
+        let space = entropy.exp2().round();
+        if count as f64 > space {
+            return Err(format!(
+                "cannot generate {} unique secrets; there are only {} possible",
+                count, space
+            ));
+        }
+        Ok(Self {
+            seen: HashSet::with_capacity(count),
+            space,
+            misses: 0,
+        })
+    }
+
+    /// Whether `secret` is new to the batch, adding it if so, or an error once too many
+    /// draws in a row repeated an earlier secret.
+    pub fn insert(&mut self, secret: &str) -> Result<bool, String> {
+        if !self.seen.contains(secret) {
+            self.seen.insert(secret.to_string());
+            self.misses = 0;
+            return Ok(true);
+        }
+        self.misses += 1;
+        let left = self.space - self.seen.len() as f64;
+        let allowed = (MISS_FACTOR * self.space / left).max(MIN_MISSES as f64);
+        if self.misses as f64 > allowed {
+            return Err(format!(
+                "{} draws in a row repeated a secret of the batch after {} unique ones; \
+                 the policy or requirements leave too few possible secrets",
+                self.misses,
+                self.seen.len()
+            ));
+        }
+        Ok(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_whole_space() {
+        // Every two-digit code, drawn at random until none is missing
+        let mut rng = ChaCha20Rng::from_seed([4; 32]);
+        let mut batch = Batch::new(100, 100f64.log2()).unwrap();
+        while batch.len() < 100 {
+            let code = format!("{:02}", rng.random_range(0..100));
+            batch.insert(&code).unwrap();
+        }
+        assert!(!batch.insert("42").unwrap());
+        assert!(Batch::new(101, 100f64.log2()).is_err());
+    }
+
+    #[test]
+    fn test_gives_up_on_smaller_space() {
+        // Claims 8 bits, but only ever draws one secret
+        let mut batch = Batch::new(2, 8.0).unwrap();
+        assert!(batch.insert("a").unwrap());
+        let misses = (0..).find(|_| batch.insert("a").is_err()).unwrap();
+        assert_eq!(misses, MIN_MISSES);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::passgen::confirm::WriteMode;
+use crate::passgen::hash::sha256_hex;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/dewaka/PassGen/releases/latest";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+// Release binaries are a few MB; anything far larger is not ours
+const MAX_BINARY_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "status", content = "version", rename_all = "snake_case")]
+pub enum UpdateOutcome {
+    UpToDate(String),
+    Available(String),
+    Updated(String),
+    Declined(String),
+}
+
+impl fmt::Display for UpdateOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdateOutcome::UpToDate(version) => write!(f, "passgen {} is up to date", version),
+            UpdateOutcome::Available(version) => write!(f, "passgen {} is available", version),
+            UpdateOutcome::Updated(version) => write!(f, "Updated passgen to {}", version),
+            UpdateOutcome::Declined(version) => write!(f, "Skipped update to {}", version),
+        }
+    }
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> anyhow::Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Release {} has no asset {}", self.tag_name, name))
+    }
+}
+
+/// Name of the release asset built for the running platform, e.g. `passgen-x86_64-linux`.
+pub fn asset_name() -> String {
+    format!(
+        "passgen-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    parse_version(latest) > parse_version(current)
+}
+
+/// Looks up the digest for `asset` in a `sha256sum`-style listing.
+pub fn find_checksum<'a>(sums: &'a str, asset: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == asset).then_some(digest)
+    })
+}
+
+pub fn verify_checksum(data: &[u8], expected: &str) -> anyhow::Result<()> {
+    let actual = sha256_hex(data);
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Checksum mismatch: expected {}, got {}",
+            expected,
+            actual
+        ))
+    }
+}
+
+fn get(url: &str) -> anyhow::Result<ureq::http::Response<ureq::Body>> {
+    ureq::get(url)
+        .header("User-Agent", concat!("passgen/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .with_context(|| format!("Request to {} failed", url))
+}
+
+pub fn latest_release() -> anyhow::Result<Release> {
+    let release = get(RELEASES_URL)?.body_mut().read_json::<Release>()?;
+    Ok(release)
+}
+
+fn download(asset: &Asset) -> anyhow::Result<Vec<u8>> {
+    let data = get(&asset.browser_download_url)?
+        .body_mut()
+        .with_config()
+        .limit(MAX_BINARY_SIZE)
+        .read_to_vec()?;
+    Ok(data)
+}
+
+// Stage the new binary next to the current one so the final rename stays on one filesystem
+fn replace_current_exe(current: &Path, binary: &[u8]) -> anyhow::Result<()> {
+    let dir = current
+        .parent()
+        .context("Cannot determine the directory of the running binary")?;
+    let staged = dir.join(format!(".{}.new", asset_name()));
+
+    fs::write(&staged, binary).with_context(|| format!("Cannot write to {}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows refuses to overwrite a running executable but allows renaming it
+    #[cfg(windows)]
+    {
+        let old = current.with_extension("old");
+        fs::rename(current, &old)?;
+        if let Err(e) = fs::rename(&staged, current) {
+            // Put the original back so the install path never ends up empty
+            fs::rename(&old, current).with_context(|| {
+                format!(
+                    "Cannot restore {} from {}",
+                    current.display(),
+                    old.display()
+                )
+            })?;
+            return Err(e.into());
+        }
+    }
+
+    #[cfg(not(windows))]
+    fs::rename(&staged, current)?;
+    Ok(())
+}
+
+pub fn self_update(check_only: bool, mode: &WriteMode) -> anyhow::Result<UpdateOutcome> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = latest_release()?;
+
+    if !is_newer(&release.tag_name, current) {
+        return Ok(UpdateOutcome::UpToDate(current.to_string()));
+    }
+    if check_only {
+        return Ok(UpdateOutcome::Available(release.tag_name));
+    }
+
+    let name = asset_name();
+    let sums = get(&release.asset(CHECKSUMS_ASSET)?.browser_download_url)?
+        .body_mut()
+        .read_to_string()?;
+    let expected = find_checksum(&sums, &name)
+        .ok_or_else(|| anyhow::anyhow!("{} does not list {}", CHECKSUMS_ASSET, name))?;
+
+    let binary = download(release.asset(&name)?)?;
+    verify_checksum(&binary, expected)?;
+
+    let current_exe = std::env::current_exe()?;
+    let action = format!(
+        "replace {} with passgen {}",
+        current_exe.display(),
+        release.tag_name
+    );
+    if !mode.approve(&action)? {
+        return Ok(UpdateOutcome::Declined(release.tag_name));
+    }
+    replace_current_exe(&current_exe, &binary)?;
+
+    Ok(UpdateOutcome::Updated(release.tag_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("0.1.1", "0.1.0"));
+        assert!(is_newer("v1.0.0", "0.9.9"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("v0.0.9", "0.1.0"));
+        assert!(is_newer("v0.10.0", "0.9.0"));
+    }
+
+    #[test]
+    fn test_find_checksum() {
+        let sums = "aaaa  passgen-x86_64-linux\nbbbb *passgen-aarch64-macos\n";
+        assert_eq!(find_checksum(sums, "passgen-x86_64-linux"), Some("aaaa"));
+        assert_eq!(find_checksum(sums, "passgen-aarch64-macos"), Some("bbbb"));
+        assert_eq!(find_checksum(sums, "passgen-x86_64-windows.exe"), None);
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let digest = sha256_hex(b"binary");
+        assert!(verify_checksum(b"binary", &digest).is_ok());
+        assert!(verify_checksum(b"binary", &digest.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"tampered", &digest).is_err());
+    }
+
+    #[test]
+    fn test_release_asset_lookup() {
+        let release: Release = serde_json::from_str(
+            r#"{"tag_name": "v0.2.0", "assets": [
+                {"name": "SHA256SUMS", "browser_download_url": "https://example.com/sums"}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(release.asset(CHECKSUMS_ASSET).is_ok());
+        assert!(release.asset("missing").is_err());
+    }
+}
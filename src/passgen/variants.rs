@@ -0,0 +1,120 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::checker;
+use crate::passgen::generate::Generator;
+use rand::CryptoRng;
+
+// Candidates that fail the comparison are rare unless the base is very short
+const MAX_ATTEMPTS: usize = 1000;
+
+/// An alphabet of the character classes `base` uses, plus any other characters in it, so
+/// variants keep the kind of secret the base is.
+pub fn alphabet_for(base: &str) -> Alphabet {
+    let mut chars = classes_for(base).as_str().to_string();
+    for c in base.chars() {
+        if !chars.contains(c) {
+            chars.push(c);
+        }
+    }
+    Alphabet::Custom(chars)
+}
+
+/// Characters an attacker guessing by character class would search for `base`: the
+/// classes it uses, and the scripts of any characters outside them.
+pub fn search_space(base: &str) -> usize {
+    Alphabet::detect(base).search_space(base)
+}
+
+// The built-in classes `base` has characters of
+fn classes_for(base: &str) -> Alphabet {
+    let classes = [
+        Alphabet::LowerCase,
+        Alphabet::UpperCase,
+        Alphabet::Digits,
+        Alphabet::SpecialChars,
+    ];
+    let mut chars = String::new();
+    for class in &classes {
+        if base.chars().any(|c| class.contains(c)) {
+            chars.push_str(class.as_str());
+        }
+    }
+    Alphabet::Custom(chars)
+}
+
+/// Generates `count` secrets as long as `base` and drawn from its character classes, each
+/// of which [`checker::compare`] finds to be a meaningful change from the base and from
+/// the variants before it.
+pub fn generate<R: CryptoRng + ?Sized>(
+    base: &str,
+    count: usize,
+    min_distance: usize,
+    rng: &mut R,
+) -> Result<Vec<String>, String> {
+    let length = base.chars().count();
+    if min_distance > length {
+        return Err(format!(
+            "a minimum distance of {} cannot be reached from a {}-character base",
+            min_distance, length
+        ));
+    }
+
+    let alphabet = alphabet_for(base);
+    let generator = Generator::new(&alphabet)?;
+    let mut variants: Vec<String> = Vec::with_capacity(count);
+    while variants.len() < count {
+        let variant = (0..MAX_ATTEMPTS)
+            .map(|_| generator.generate(length, rng).value.into_owned())
+            .find(|candidate| {
+                std::iter::once(base)
+                    .chain(variants.iter().map(String::as_str))
+                    .all(|previous| checker::compare(previous, candidate, min_distance).is_empty())
+            })
+            .ok_or_else(|| {
+                format!(
+                    "found no variant at least {} edits from the base and the other variants",
+                    min_distance
+                )
+            })?;
+        variants.push(variant);
+    }
+    Ok(variants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_alphabet_for() {
+        assert_eq!(
+            alphabet_for("abc123").as_str(),
+            "abcdefghijklmnopqrstuvwxyz0123456789"
+        );
+        assert_eq!(alphabet_for("AB-C").as_str(), "ABCDEFGHIJKLMNOPQRSTUVWXYZ-");
+    }
+
+    #[test]
+    fn test_variants_differ_meaningfully() {
+        let base = "Summer2024!xy";
+        let mut rng = ChaCha20Rng::from_seed([9; 32]);
+        let variants = generate(base, 5, 6, &mut rng).unwrap();
+        assert_eq!(variants.len(), 5);
+        for (i, variant) in variants.iter().enumerate() {
+            assert_eq!(variant.chars().count(), base.chars().count());
+            assert!(checker::compare(base, variant, 6).is_empty());
+            for other in &variants[..i] {
+                assert!(checker::edit_distance(other, variant) >= 6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable_distance() {
+        let error = generate("abc", 1, 4, &mut rand::rng()).unwrap_err();
+        assert!(error.contains("cannot be reached"));
+        // Two-letter secrets that differ in both letters from each other run out at 26
+        assert!(generate("ab", 30, 2, &mut rand::rng()).is_err());
+    }
+}
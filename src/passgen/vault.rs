@@ -0,0 +1,225 @@
+use serde_json::{Map, Value, json};
+use std::io;
+
+const FIELD_DEFAULT: &str = "password";
+
+/// A secret in Vault's KV engine, e.g. `secret/data/app/db` with the value under `password`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultPath {
+    pub path: String,
+    pub field: String,
+}
+
+impl VaultPath {
+    /// Parses `mount/path[#field]`; the field defaults to `password`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (path, field) = match spec.split_once('#') {
+            Some((path, field)) => (path, field),
+            None => (spec, FIELD_DEFAULT),
+        };
+        let path = path.trim_matches('/');
+        if path.is_empty() || !path.contains('/') || field.is_empty() {
+            return Err(format!(
+                "expected vault:MOUNT/PATH[#FIELD], e.g. vault:secret/data/app/db, got `vault:{}`",
+                spec
+            ));
+        }
+        if path
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "..")
+        {
+            return Err(format!("invalid Vault path `{}`", path));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            field: field.to_string(),
+        })
+    }
+
+    // KV version 2 paths read and write through the mount's `data/` prefix
+    fn is_kv2(&self) -> bool {
+        self.path.split('/').nth(1) == Some("data")
+    }
+
+    /// The secret's fields as Vault returned them, unwrapping the KV v2 envelope.
+    fn fields(&self, response: &Value) -> Map<String, Value> {
+        let data = if self.is_kv2() {
+            &response["data"]["data"]
+        } else {
+            &response["data"]
+        };
+        data.as_object().cloned().unwrap_or_default()
+    }
+
+    fn write_body(&self, fields: Map<String, Value>) -> Value {
+        if self.is_kv2() {
+            json!({ "data": fields })
+        } else {
+            Value::Object(fields)
+        }
+    }
+}
+
+impl std::fmt::Display for VaultPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.field == FIELD_DEFAULT {
+            write!(f, "{}", self.path)
+        } else {
+            write!(f, "{}#{}", self.path, self.field)
+        }
+    }
+}
+
+struct Client {
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    fn from_env() -> io::Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| io::Error::other(format!("{} is not set", name)))
+        };
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .new_agent();
+        Ok(Self {
+            addr: var("VAULT_ADDR")?.trim_end_matches('/').to_string(),
+            token: var("VAULT_TOKEN")?,
+            namespace: std::env::var("VAULT_NAMESPACE").ok(),
+            agent,
+        })
+    }
+
+    fn url(&self, secret: &VaultPath) -> String {
+        format!("{}/v1/{}", self.addr, secret.path)
+    }
+
+    fn request<B>(&self, request: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        let request = request
+            .header("X-Vault-Token", &self.token)
+            .header("User-Agent", concat!("passgen/", env!("CARGO_PKG_VERSION")));
+        match &self.namespace {
+            Some(namespace) => request.header("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
+
+    fn read(&self, secret: &VaultPath) -> io::Result<Option<Map<String, Value>>> {
+        let mut response = self
+            .request(self.agent.get(&self.url(secret)))
+            .call()
+            .map_err(io::Error::other)?;
+        match response.status().as_u16() {
+            200 => {
+                let body: Value = response.body_mut().read_json().map_err(io::Error::other)?;
+                Ok(Some(secret.fields(&body)))
+            }
+            404 => Ok(None),
+            status => Err(vault_error(
+                status,
+                response.body_mut().read_to_string().ok(),
+            )),
+        }
+    }
+
+    fn write(&self, secret: &VaultPath, fields: Map<String, Value>) -> io::Result<()> {
+        let mut response = self
+            .request(self.agent.post(&self.url(secret)))
+            .send_json(secret.write_body(fields))
+            .map_err(io::Error::other)?;
+        match response.status().as_u16() {
+            200 | 204 => Ok(()),
+            status => Err(vault_error(
+                status,
+                response.body_mut().read_to_string().ok(),
+            )),
+        }
+    }
+}
+
+fn vault_error(status: u16, body: Option<String>) -> io::Error {
+    let errors = body
+        .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+        .and_then(|body| {
+            body["errors"].as_array().map(|errors| {
+                errors
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+        })
+        .filter(|errors| !errors.is_empty());
+    match errors {
+        Some(errors) => io::Error::other(format!("Vault returned {}: {}", status, errors)),
+        None => io::Error::other(format!("Vault returned {}", status)),
+    }
+}
+
+/// Whether the secret already holds a value in the target field.
+pub fn exists(secret: &VaultPath) -> io::Result<bool> {
+    let fields = Client::from_env()?.read(secret)?;
+    Ok(fields.is_some_and(|fields| fields.contains_key(&secret.field)))
+}
+
+/// Writes the value into the secret's field, keeping any other fields it already has.
+pub fn save(secret: &VaultPath, value: &str) -> io::Result<()> {
+    let client = Client::from_env()?;
+    let mut fields = client.read(secret)?.unwrap_or_default();
+    fields.insert(secret.field.clone(), Value::String(value.to_string()));
+    client.write(secret, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let secret = VaultPath::parse("secret/data/app/db").unwrap();
+        assert_eq!(secret.path, "secret/data/app/db");
+        assert_eq!(secret.field, "password");
+        assert_eq!(secret.to_string(), "secret/data/app/db");
+
+        let secret = VaultPath::parse("/kv/app/db/#token").unwrap();
+        assert_eq!(secret.path, "kv/app/db");
+        assert_eq!(secret.field, "token");
+        assert_eq!(secret.to_string(), "kv/app/db#token");
+
+        assert!(VaultPath::parse("secret").is_err());
+        assert!(VaultPath::parse("secret/data/app#").is_err());
+        assert!(VaultPath::parse("secret//app").is_err());
+        assert!(VaultPath::parse("secret/../sys/policy").is_err());
+    }
+
+    #[test]
+    fn test_kv2_envelope() {
+        let secret = VaultPath::parse("secret/data/app/db").unwrap();
+        let response = json!({"data": {"data": {"user": "app"}, "metadata": {"version": 3}}});
+        let fields = secret.fields(&response);
+        assert_eq!(Value::Object(fields.clone()), json!({"user": "app"}));
+        assert_eq!(secret.write_body(fields), json!({"data": {"user": "app"}}));
+    }
+
+    #[test]
+    fn test_kv1_body() {
+        let secret = VaultPath::parse("kv/app/db").unwrap();
+        let response = json!({"data": {"user": "app"}});
+        let fields = secret.fields(&response);
+        assert_eq!(secret.write_body(fields), json!({"user": "app"}));
+    }
+
+    #[test]
+    fn test_vault_error_message() {
+        let error = vault_error(
+            403,
+            Some(r#"{"errors": ["permission denied"]}"#.to_string()),
+        );
+        assert_eq!(error.to_string(), "Vault returned 403: permission denied");
+        assert_eq!(vault_error(500, None).to_string(), "Vault returned 500");
+    }
+}
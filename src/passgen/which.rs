@@ -0,0 +1,11 @@
+/// Whether `program` is an executable file in one of the `PATH` directories, also trying
+/// the platform's executable suffix, e.g. `docker.exe` on Windows.
+pub fn on_path(program: &str) -> bool {
+    let suffixed = format!("{}{}", program, std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .any(|dir| dir.join(program).is_file() || dir.join(&suffixed).is_file())
+        })
+        .unwrap_or(false)
+}
@@ -1,80 +1,373 @@
 // Or for lazy loading:
+use crate::passgen::mapped::{FileFormat, MappedWords};
+use crate::passgen::resources::{Embedded, embedded};
 use clap::ValueEnum;
-use std::sync::OnceLock;
-
-#[derive(Debug, Clone, ValueEnum)]
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::ops::{Index, RangeInclusive};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+/// Words passphrases are drawn from: one of the EFF or language dice lists built into the
+/// binary, a custom list, or a file.
+#[derive(Debug, Clone, Default, ValueEnum)]
 pub enum WordList {
+    #[default]
     EffLarge,
     EffShort1,
     EffShort2,
+    /// German words for four dice
+    DeDiceware,
+    /// Spanish words for four dice
+    EsDiceware,
+    /// French words for four dice
+    FrDiceware,
+    /// Italian words for four dice
+    ItDiceware,
+    /// The given words
     #[clap(skip)]
     Custom(Vec<String>),
+    /// A list read from a file, e.g. with `--wordlist-file`
+    #[clap(skip)]
+    File(Arc<MappedWords>),
+    /// Some of the words of another list
+    #[clap(skip)]
+    Filtered(Arc<Filtered>),
 }
 
-// Wordlist file contents
-const EFF_LARGE_WORDLIST: &str = include_str!("../../resources/wordlist/eff_large_wordlist.txt");
-const EFF_SHORT_WORDLIST_1: &str =
-    include_str!("../../resources/wordlist/eff_short_wordlist_1.txt");
-const EFF_SHORT_WORDLIST_2_0: &str =
-    include_str!("../../resources/wordlist/eff_short_wordlist_2_0.txt");
-
-// Static caches for lazy loading
-static EFF_LARGE_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static EFF_SHORT1_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-static EFF_SHORT2_CACHE: OnceLock<Vec<&'static str>> = OnceLock::new();
-
-fn get_eff_large_wordlist() -> &'static [&'static str] {
-    EFF_LARGE_CACHE.get_or_init(|| {
-        EFF_LARGE_WORDLIST
-            .lines()
-            .filter_map(|line| parse_eff_line(line))
-            .collect()
-    })
+/// The words of a list that passed a filter, kept as their positions in the list so none
+/// are copied.
+#[derive(Debug)]
+pub struct Filtered {
+    list: WordList,
+    indices: Vec<usize>,
 }
 
-fn get_eff_short1_wordlist() -> &'static [&'static str] {
-    EFF_SHORT1_CACHE.get_or_init(|| {
-        EFF_SHORT_WORDLIST_1
-            .lines()
-            .filter_map(|line| parse_eff_line(line))
-            .collect()
-    })
+/// Size and word lengths of a list, in characters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub words: usize,
+    /// Entropy each word of a passphrase adds
+    pub bits_per_word: f64,
+    pub average_length: f64,
+    pub min_length: usize,
+    pub max_length: usize,
 }
 
-fn get_eff_short2_wordlist() -> &'static [&'static str] {
-    EFF_SHORT2_CACHE.get_or_init(|| {
-        EFF_SHORT_WORDLIST_2_0
-            .lines()
-            .filter_map(|line| parse_eff_line(line))
-            .collect()
-    })
+/// Most syllables a word of an easily typed passphrase has.
+pub const EASY_SYLLABLES: usize = 3;
+
+/// What makes a word awkward to type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Typing {
+    /// Estimated from the groups of vowels, so `table` has 2 and `cake` 1
+    pub syllables: usize,
+    /// Has characters other than letters, such as the hyphen of `t-shirt`
+    pub punctuation: bool,
+    /// Has a letter twice in a row, as in `fiddle`
+    pub double_letters: bool,
 }
 
-fn parse_eff_line(line: &str) -> Option<&str> {
-    // EFF format: "11111\tabacus"
-    // Split by tab and take the second part (the word)
-    line.split('\t').nth(1)
+impl Typing {
+    pub fn of(word: &str) -> Self {
+        let letters: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+        let vowel = |c: &char| "aeiouy".contains(*c);
+        let mut syllables = letters
+            .iter()
+            .enumerate()
+            .filter(|&(i, c)| vowel(c) && (i == 0 || !vowel(&letters[i - 1])))
+            .count();
+        // A final e is usually silent, except after a consonant and l as in `table`
+        if let [.., before, last, 'e'] = letters[..]
+            && !vowel(&last)
+            && (last != 'l' || vowel(&before))
+            && syllables > 1
+        {
+            syllables -= 1;
+        }
+        Typing {
+            syllables: syllables.max(1),
+            punctuation: !letters.iter().all(|c| c.is_alphabetic()),
+            double_letters: letters.windows(2).any(|pair| pair[0] == pair[1]),
+        }
+    }
+}
+
+/// The words of a list, borrowed from the cached built-in lists, a custom list, a
+/// memory-mapped file, or a filtered list rather than copied out of them.
+#[derive(Debug, Clone, Copy)]
+pub enum Words<'a> {
+    Embedded(&'static [&'static str]),
+    Custom(&'a [String]),
+    Mapped(&'a MappedWords),
+    Filtered(&'a Filtered),
+}
+
+impl<'a> Words<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            Words::Embedded(words) => words.len(),
+            Words::Custom(words) => words.len(),
+            Words::Mapped(words) => words.len(),
+            Words::Filtered(filtered) => filtered.indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> &'a str {
+        match *self {
+            Words::Embedded(words) => words[index],
+            Words::Custom(words) => &words[index],
+            Words::Mapped(words) => words.get(index),
+            Words::Filtered(filtered) => filtered.list.words().get(filtered.indices[index]),
+        }
+    }
+
+    /// A uniformly random word, `None` for an empty list.
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&'a str> {
+        match *self {
+            Words::Embedded(words) => words.choose(rng).copied(),
+            Words::Custom(words) => words.choose(rng).map(String::as_str),
+            Words::Mapped(words) => words.choose(rng),
+            Words::Filtered(filtered) => filtered
+                .indices
+                .choose(rng)
+                .map(|&index| filtered.list.words().get(index)),
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match *self {
+            Words::Embedded(words) => Box::new(words.iter().copied()),
+            Words::Custom(words) => Box::new(words.iter().map(String::as_str)),
+            // Streams the file without building its index
+            Words::Mapped(words) => Box::new(words.iter()),
+            Words::Filtered(filtered) => {
+                let words = filtered.list.words();
+                Box::new(filtered.indices.iter().map(move |&index| words.get(index)))
+            }
+        }
+    }
 }
 
-// For EFF_Short1 and EFF_Short2, we'll use subsets of the large list for now
-// In a real implementation, you'd include the actual short wordlist files
-impl Default for WordList {
-    fn default() -> Self {
-        WordList::EffLarge
+impl Index<usize> for Words<'_> {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        self.get(index)
     }
 }
 
+/// A wordlist built into the binary, compressed at build time and split into words on
+/// first use. Adding a list takes its file under `resources/wordlist`, a static here and a
+/// `WordList` variant.
+struct Builtin {
+    name: &'static str,
+    source: Embedded,
+    words: OnceLock<Vec<&'static str>>,
+    // The dice index of each word
+    rolls: OnceLock<HashMap<&'static str, &'static str>>,
+}
+
+macro_rules! builtin {
+    ($name:literal, $path:literal) => {
+        Builtin {
+            name: $name,
+            source: embedded!($path),
+            words: OnceLock::new(),
+            rolls: OnceLock::new(),
+        }
+    };
+}
+
+static EFF_LARGE: Builtin = builtin!("eff-large", "wordlist/eff_large_wordlist.txt");
+static EFF_SHORT1: Builtin = builtin!("eff-short1", "wordlist/eff_short_wordlist_1.txt");
+static EFF_SHORT2: Builtin = builtin!("eff-short2", "wordlist/eff_short_wordlist_2_0.txt");
+static DE_DICEWARE: Builtin = builtin!("de-diceware", "wordlist/de_diceware.txt");
+static ES_DICEWARE: Builtin = builtin!("es-diceware", "wordlist/es_diceware.txt");
+static FR_DICEWARE: Builtin = builtin!("fr-diceware", "wordlist/fr_diceware.txt");
+static IT_DICEWARE: Builtin = builtin!("it-diceware", "wordlist/it_diceware.txt");
+
+impl Builtin {
+    fn text(&'static self) -> &'static str {
+        self.source.text()
+    }
+
+    fn words(&'static self) -> &'static [&'static str] {
+        self.words.get_or_init(|| {
+            let _span = tracing::debug_span!("load_wordlist", list = self.name).entered();
+            self.text().lines().filter_map(parse_eff_line).collect()
+        })
+    }
+
+    fn rolls(&'static self) -> &'static HashMap<&'static str, &'static str> {
+        self.rolls.get_or_init(|| {
+            self.text()
+                .lines()
+                .filter_map(parse_eff_entry)
+                .map(|(index, word)| (word, index))
+                .collect()
+        })
+    }
+}
+
+fn parse_eff_entry(line: &str) -> Option<(&str, &str)> {
+    // EFF format: "11111\tabacus"
+    // Split by tab into the dice index and the word
+    let mut parts = line.split('\t');
+    let index = parts.next()?;
+    let word = parts.next()?;
+    Some((index, word))
+}
+
+fn parse_eff_line(line: &str) -> Option<&str> {
+    parse_eff_entry(line).map(|(_, word)| word)
+}
+
 impl WordList {
     pub fn from_custom(custom: Vec<String>) -> Self {
         WordList::Custom(custom)
     }
 
-    pub fn words(&self) -> Vec<&str> {
+    /// A list memory-mapped from a file of one word per line, or of EFF dice lines such as
+    /// `11111<TAB>abacus`.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Ok(WordList::File(Arc::new(MappedWords::open(path)?)))
+    }
+
+    // The embedded list behind a built-in variant
+    fn builtin(&self) -> Option<&'static Builtin> {
+        match self {
+            WordList::EffLarge => Some(&EFF_LARGE),
+            WordList::EffShort1 => Some(&EFF_SHORT1),
+            WordList::EffShort2 => Some(&EFF_SHORT2),
+            WordList::DeDiceware => Some(&DE_DICEWARE),
+            WordList::EsDiceware => Some(&ES_DICEWARE),
+            WordList::FrDiceware => Some(&FR_DICEWARE),
+            WordList::ItDiceware => Some(&IT_DICEWARE),
+            WordList::Custom(_) | WordList::File(_) | WordList::Filtered(_) => None,
+        }
+    }
+
+    /// The words of this list of `lengths` characters, e.g. `3..=5` for short, easily typed
+    /// passphrases.
+    pub fn with_word_lengths(&self, lengths: RangeInclusive<usize>) -> Self {
+        self.filter(|word| lengths.contains(&word.chars().count()))
+    }
+
+    /// The words of this list made only of letters, of at most `max_syllables` syllables.
+    pub fn easy_to_type(&self, max_syllables: usize) -> Self {
+        self.filter(|word| {
+            let typing = Typing::of(word);
+            !typing.punctuation && typing.syllables <= max_syllables
+        })
+    }
+
+    fn filter(&self, keep: impl Fn(&str) -> bool) -> Self {
+        let _span = tracing::debug_span!("filter_wordlist", list = %self.name()).entered();
+        let indices = self
+            .words()
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| keep(word))
+            .map(|(index, _)| index)
+            .collect();
+        WordList::Filtered(Arc::new(Filtered {
+            list: self.clone(),
+            indices,
+        }))
+    }
+
+    /// Raw embedded text of a built-in wordlist, `None` for custom lists.
+    pub fn source(&self) -> Option<&'static str> {
+        self.builtin().map(Builtin::text)
+    }
+
+    /// Dice index and word pairs of a built-in list, `None` for custom lists.
+    pub fn dice_entries(&self) -> Option<Vec<(&'static str, &'static str)>> {
+        self.source()
+            .map(|source| source.lines().filter_map(parse_eff_entry).collect())
+    }
+
+    /// The word a roll of dice such as `52434` selects in a built-in list, `None` for custom
+    /// lists and rolls without an entry.
+    pub fn dice_word(&self, roll: &str) -> Option<&'static str> {
+        self.source()?
+            .lines()
+            .filter_map(parse_eff_entry)
+            .find(|(index, _)| *index == roll)
+            .map(|(_, word)| word)
+    }
+
+    /// The name of a built-in list as on the command line, the path of a file, or `custom`.
+    pub fn name(&self) -> String {
         match self {
-            WordList::EffLarge => get_eff_large_wordlist().to_vec(),
-            WordList::EffShort1 => get_eff_short1_wordlist().to_vec(),
-            WordList::EffShort2 => get_eff_short2_wordlist().to_vec(),
-            WordList::Custom(custom) => custom.iter().map(|s| s.as_str()).collect(),
+            WordList::Custom(_) => "custom".to_string(),
+            WordList::File(words) => words.path().display().to_string(),
+            WordList::Filtered(filtered) => format!("{} (filtered)", filtered.list.name()),
+            _ => self
+                .builtin()
+                .expect("other lists are built in")
+                .name
+                .to_string(),
+        }
+    }
+
+    /// How the file behind the list writes its words, `None` for custom and filtered lists.
+    pub fn format(&self) -> Option<FileFormat> {
+        match self {
+            WordList::Custom(_) | WordList::Filtered(_) => None,
+            WordList::File(words) => Some(words.format()),
+            _ => Some(FileFormat::Dice),
+        }
+    }
+
+    /// The dice index of each word of a built-in list, or of one some of whose words were
+    /// kept, e.g. `52434` for `royal`; `None` for custom lists.
+    pub fn rolls(&self) -> Option<&'static HashMap<&'static str, &'static str>> {
+        match self {
+            WordList::Filtered(filtered) => filtered.list.rolls(),
+            _ => self.builtin().map(Builtin::rolls),
+        }
+    }
+
+    /// Dice rolled for each word of a built-in list, `None` for custom lists.
+    pub fn dice(&self) -> Option<usize> {
+        let (index, _) = self.source()?.lines().find_map(parse_eff_entry)?;
+        Some(index.len())
+    }
+
+    /// Counts the words and their lengths.
+    pub fn stats(&self) -> Stats {
+        let words = self.words();
+        let (mut total, mut min_length, mut max_length) = (0, usize::MAX, 0);
+        for word in words.iter() {
+            let length = word.chars().count();
+            total += length;
+            min_length = min_length.min(length);
+            max_length = max_length.max(length);
+        }
+        Stats {
+            words: words.len(),
+            bits_per_word: (words.len().max(1) as f64).log2(),
+            average_length: total as f64 / words.len().max(1) as f64,
+            min_length: min_length.min(max_length),
+            max_length,
+        }
+    }
+
+    pub fn words(&self) -> Words<'_> {
+        match self {
+            WordList::Custom(custom) => Words::Custom(custom),
+            WordList::File(words) => Words::Mapped(words),
+            WordList::Filtered(filtered) => Words::Filtered(filtered),
+            _ => Words::Embedded(self.builtin().expect("other lists are built in").words()),
         }
     }
 }
@@ -105,9 +398,42 @@ mod tests {
         assert_eq!(parse_eff_line("11111\t abacus "), Some(" abacus "));
     }
 
+    #[test]
+    fn test_eff_entry_parsing() {
+        assert_eq!(parse_eff_entry("11111\tabacus"), Some(("11111", "abacus")));
+        assert_eq!(parse_eff_entry("11111abacus"), None);
+    }
+
+    #[test]
+    fn test_dice_entries() {
+        let entries = WordList::EffLarge.dice_entries().unwrap();
+        assert_eq!(entries.len(), 7776);
+        assert_eq!(entries[0], ("11111", "abacus"));
+        assert_eq!(entries.last().unwrap().0, "66666");
+
+        let short = WordList::EffShort1.dice_entries().unwrap();
+        assert_eq!(short[0].0, "1111");
+
+        assert!(
+            WordList::from_custom(vec!["a".to_string()])
+                .dice_entries()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_dice_word() {
+        assert_eq!(WordList::EffLarge.dice_word("11111"), Some("abacus"));
+        assert_eq!(WordList::EffLarge.dice_word("66666"), Some("zoom"));
+        assert_eq!(WordList::EffShort1.dice_word("1111"), Some("acid"));
+        assert_eq!(WordList::EffLarge.dice_word("1111"), None);
+        assert_eq!(WordList::EffLarge.dice_word("11117"), None);
+        assert_eq!(WordList::from_custom(vec![]).dice_word("11111"), None);
+    }
+
     #[test]
     fn test_eff_large_wordlist() {
-        let words = get_eff_large_wordlist();
+        let words = EFF_LARGE.words();
         assert!(!words.is_empty());
         assert!(words.contains(&"abacus"));
         assert!(words.contains(&"abdomen"));
@@ -115,9 +441,9 @@ mod tests {
 
     #[test]
     fn test_eff_short_lists_are_different() {
-        let large = get_eff_large_wordlist();
-        let short1 = get_eff_short1_wordlist();
-        let short2 = get_eff_short2_wordlist();
+        let large = EFF_LARGE.words();
+        let short1 = EFF_SHORT1.words();
+        let short2 = EFF_SHORT2.words();
 
         assert!(short1.len() <= large.len());
         assert!(short2.len() <= large.len());
@@ -127,7 +453,7 @@ mod tests {
 
     #[test]
     fn test_eff_short1_wordlist() {
-        let words = get_eff_short1_wordlist();
+        let words = EFF_SHORT1.words();
         assert!(!words.is_empty());
         // EFF short wordlist 1 should have 1296 words (6^4)
         assert_eq!(words.len(), 1296);
@@ -135,7 +461,7 @@ mod tests {
 
     #[test]
     fn test_eff_short2_wordlist() {
-        let words = get_eff_short2_wordlist();
+        let words = EFF_SHORT2.words();
         assert!(!words.is_empty());
         // EFF short wordlist 2.0 should have 1296 words (6^4)
         assert_eq!(words.len(), 1296);
@@ -177,7 +503,7 @@ mod tests {
         let wordlist = WordList::from_custom(single_word);
         let words = wordlist.words();
         assert_eq!(words.len(), 1);
-        assert_eq!(words[0], "hello");
+        assert_eq!(&words[0], "hello");
     }
 
     #[test]
@@ -191,10 +517,10 @@ mod tests {
         let wordlist = WordList::from_custom(special_words.clone());
         let words = wordlist.words();
         assert_eq!(words.len(), 4);
-        assert_eq!(words[0], "hello-world");
-        assert_eq!(words[1], "test@email.com");
-        assert_eq!(words[2], "unicode_測試");
-        assert_eq!(words[3], "emoji🎉");
+        assert_eq!(&words[0], "hello-world");
+        assert_eq!(&words[1], "test@email.com");
+        assert_eq!(&words[2], "unicode_測試");
+        assert_eq!(&words[3], "emoji🎉");
     }
 
     #[test]
@@ -214,11 +540,22 @@ mod tests {
         let short2_words = eff_short2.words();
         assert_eq!(short2_words.len(), 1296);
 
+        // Test the language lists
+        for list in [
+            WordList::DeDiceware,
+            WordList::EsDiceware,
+            WordList::FrDiceware,
+            WordList::ItDiceware,
+        ] {
+            assert_eq!(list.words().len(), 1296);
+            assert_eq!(list.dice(), Some(4));
+        }
+
         // Test Custom
         let custom = WordList::from_custom(vec!["test".to_string()]);
         let custom_words = custom.words();
         assert_eq!(custom_words.len(), 1);
-        assert_eq!(custom_words[0], "test");
+        assert_eq!(&custom_words[0], "test");
     }
 
     #[test]
@@ -252,52 +589,159 @@ mod tests {
     #[test]
     fn test_eff_wordlists_consistency() {
         // Ensure all wordlists return consistent results on multiple calls
-        let words1 = get_eff_large_wordlist();
-        let words2 = get_eff_large_wordlist();
+        let words1 = EFF_LARGE.words();
+        let words2 = EFF_LARGE.words();
         assert_eq!(words1.len(), words2.len());
         assert_eq!(words1, words2);
 
-        let short1_words1 = get_eff_short1_wordlist();
-        let short1_words2 = get_eff_short1_wordlist();
+        let short1_words1 = EFF_SHORT1.words();
+        let short1_words2 = EFF_SHORT1.words();
         assert_eq!(short1_words1, short1_words2);
 
-        let short2_words1 = get_eff_short2_wordlist();
-        let short2_words2 = get_eff_short2_wordlist();
+        let short2_words1 = EFF_SHORT2.words();
+        let short2_words2 = EFF_SHORT2.words();
         assert_eq!(short2_words1, short2_words2);
     }
 
     #[test]
     fn test_eff_wordlists_no_empty_words() {
         // Ensure no wordlist contains empty strings
-        let large_words = get_eff_large_wordlist();
+        let large_words = EFF_LARGE.words();
         assert!(!large_words.iter().any(|word| word.is_empty()));
 
-        let short1_words = get_eff_short1_wordlist();
+        let short1_words = EFF_SHORT1.words();
         assert!(!short1_words.iter().any(|word| word.is_empty()));
 
-        let short2_words = get_eff_short2_wordlist();
+        let short2_words = EFF_SHORT2.words();
         assert!(!short2_words.iter().any(|word| word.is_empty()));
     }
 
     #[test]
     fn test_eff_wordlists_unique_words() {
         // Ensure all words in each wordlist are unique
-        let large_words = get_eff_large_wordlist();
+        let large_words = EFF_LARGE.words();
         let mut large_unique: Vec<&str> = large_words.to_vec();
         large_unique.sort();
         large_unique.dedup();
         assert_eq!(large_words.len(), large_unique.len());
 
-        let short1_words = get_eff_short1_wordlist();
+        let short1_words = EFF_SHORT1.words();
         let mut short1_unique: Vec<&str> = short1_words.to_vec();
         short1_unique.sort();
         short1_unique.dedup();
         assert_eq!(short1_words.len(), short1_unique.len());
 
-        let short2_words = get_eff_short2_wordlist();
+        let short2_words = EFF_SHORT2.words();
         let mut short2_unique: Vec<&str> = short2_words.to_vec();
         short2_unique.sort();
         short2_unique.dedup();
         assert_eq!(short2_words.len(), short2_unique.len());
     }
+
+    #[test]
+    fn test_words_borrow_the_cached_list() {
+        let first = WordList::EffLarge.words();
+        let second = WordList::EffLarge.words();
+        let (Words::Embedded(first), Words::Embedded(second)) = (first, second) else {
+            panic!("Expected embedded words");
+        };
+        assert!(std::ptr::eq(first, second));
+
+        let custom = WordList::from_custom(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(custom.words().iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(&WordList::EffShort1.words()[0], "acid");
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("passgen-wordlist-{}", std::process::id()));
+        std::fs::write(&path, "11111\tabacus\n11112\tabdomen\nplain\n").unwrap();
+        let wordlist = WordList::from_file(&path).unwrap();
+        assert_eq!(
+            wordlist.words().iter().collect::<Vec<_>>(),
+            ["abacus", "abdomen", "plain"]
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(WordList::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_builtin_names_match_variants() {
+        for wordlist in WordList::value_variants() {
+            let builtin = wordlist.builtin().unwrap();
+            let value = wordlist.to_possible_value().unwrap();
+            assert_eq!(builtin.name, value.get_name());
+            assert!(!builtin.words().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = WordList::EffLarge.stats();
+        assert_eq!(stats.words, 7776);
+        assert!((stats.bits_per_word - 12.925).abs() < 0.001);
+        assert_eq!((stats.min_length, stats.max_length), (3, 9));
+        assert!(stats.average_length > 6.0 && stats.average_length < 8.0);
+        assert_eq!(WordList::EffLarge.dice(), Some(5));
+        assert_eq!(WordList::EffShort1.dice(), Some(4));
+        assert_eq!(WordList::EffShort2.name(), "eff-short2");
+        assert_eq!(WordList::EffLarge.rolls().unwrap()["zoom"], "66666");
+        let short = WordList::EffLarge.with_word_lengths(3..=4);
+        assert_eq!(short.rolls().unwrap().len(), 7776);
+
+        let custom = WordList::from_custom(vec!["ab".to_string(), "abcd".to_string()]);
+        let stats = custom.stats();
+        assert_eq!((stats.words, stats.bits_per_word), (2, 1.0));
+        assert_eq!(stats.average_length, 3.0);
+        assert_eq!((custom.dice(), custom.format()), (None, None));
+        assert!(custom.rolls().is_none());
+        let empty = WordList::from_custom(Vec::new()).stats();
+        assert_eq!(
+            (empty.min_length, empty.max_length, empty.bits_per_word),
+            (0, 0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_with_word_lengths() {
+        let short = WordList::EffLarge.with_word_lengths(3..=5);
+        let words = short.words();
+        assert!(words.len() > 1000 && words.len() < 7776, "{}", words.len());
+        assert!(words.iter().all(|word| (3..=5).contains(&word.len())));
+        assert_eq!(words.iter().count(), words.len());
+        assert!((3..=5).contains(&words[words.len() - 1].len()));
+        let word = words.choose(&mut rand::rng()).unwrap();
+        assert!((3..=5).contains(&word.len()));
+        assert!(!short.words().iter().any(|word| word == "abacus"));
+
+        let custom = WordList::from_custom(vec!["ab".to_string(), "émigré".to_string()]);
+        let six = custom.with_word_lengths(6..=6);
+        assert_eq!(six.words().iter().collect::<Vec<_>>(), ["émigré"]);
+        assert!(custom.with_word_lengths(10..=20).words().is_empty());
+    }
+
+    #[test]
+    fn test_typing() {
+        let syllables = |word| Typing::of(word).syllables;
+        assert_eq!(syllables("cake"), 1);
+        assert_eq!(syllables("table"), 2);
+        assert_eq!(syllables("abacus"), 3);
+        assert_eq!(syllables("ability"), 4);
+        assert_eq!(syllables("strength"), 1);
+        assert_eq!(syllables("the"), 1);
+        assert!(Typing::of("t-shirt").punctuation);
+        assert!(Typing::of("fiddle").double_letters);
+        assert!(!Typing::of("Émigré").punctuation);
+        assert!(!Typing::of("royal").double_letters);
+
+        let easy = WordList::EffLarge.easy_to_type(EASY_SYLLABLES);
+        let words = easy.words();
+        assert!(words.len() > 5000 && words.len() < 7776, "{}", words.len());
+        assert!(
+            !words
+                .iter()
+                .any(|word| word == "t-shirt" || word == "ability")
+        );
+        assert!(words.iter().any(|word| word == "abacus"));
+    }
 }
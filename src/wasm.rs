@@ -0,0 +1,99 @@
+use crate::passgen::alphabet::Alphabet;
+use crate::passgen::checker::{self, Classification, Screen};
+use crate::passgen::commonwords::CommonWords;
+use crate::passgen::generate::Generator;
+use crate::passgen::passphrase;
+use crate::passgen::password::Password;
+use crate::passgen::patterns::PersonalContext;
+use crate::passgen::wordlist::WordList;
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+
+/// Generates a password of `length` characters from an alphabet named as on the command
+/// line, e.g. `alphanumeric`, or from the characters of `custom`; the full alphabet if
+/// neither is given.
+#[wasm_bindgen]
+pub fn generate(
+    length: usize,
+    alphabet: Option<String>,
+    custom: Option<String>,
+) -> Result<String, JsError> {
+    let alphabet = match (alphabet, custom) {
+        (Some(_), Some(_)) => return Err(JsError::new("give an alphabet or custom characters")),
+        (_, Some(custom)) => Alphabet::Custom(custom),
+        (Some(name), None) => Alphabet::from_str(&name, true)
+            .map_err(|_| JsError::new(&format!("unknown alphabet `{}`", name)))?,
+        (None, None) => Alphabet::default(),
+    };
+    let generator = Generator::new(&alphabet).map_err(|e| JsError::new(&e))?;
+    Ok(generator
+        .generate(length, &mut rand::rng())
+        .value
+        .into_owned())
+}
+
+/// Joins `words` words drawn from `wordlist` with `separator`, `-` if not given. Without a
+/// word list, the EFF large list is used, which builds without the `embedded` feature leave
+/// empty.
+#[wasm_bindgen(js_name = generatePassphrase)]
+pub fn generate_passphrase(
+    words: usize,
+    separator: Option<String>,
+    wordlist: Option<Vec<String>>,
+) -> Result<String, JsError> {
+    let wordlist = wordlist.map_or_else(WordList::default, WordList::from_custom);
+    let separator = separator.as_deref().unwrap_or("-");
+    passphrase::generate_passphrase(words, separator, &wordlist, &mut rand::rng())
+        .map(|passphrase| passphrase.value.into_owned())
+        .map_err(|e| JsError::new(&e))
+}
+
+/// Classifies a password as `Weak`, `Medium`, `Strong` or `VeryStrong` like `passgen
+/// check`: repeats, sequences and keyboard walks count as the few guesses they take, and
+/// common passwords, which `passgen check` leaves unclassified, are weak. Those are looked up in `common` if given, else in the
+/// built-in lists.
+#[wasm_bindgen]
+pub fn classify(password: &str, common: Option<Vec<String>>) -> String {
+    let lists: Vec<(CommonWords, &str)> = match common {
+        Some(words) => vec![(CommonWords::Custom(words), "common password")],
+        None => checker::SAFETY_CHECKS.into(),
+    };
+    let assessment = Screen::new(&lists)
+        .assess(&Password::new(password), None, &PersonalContext::default())
+        .expect("an inferred alphabet has every character of the password");
+    format!(
+        "{:?}",
+        assessment.classification.unwrap_or(Classification::Weak)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let password = generate(20, Some("digits".to_string()), None).unwrap();
+        assert_eq!(password.len(), 20);
+        assert!(password.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(generate(5, None, Some("ab".to_string())).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let words = vec!["alpha".to_string(), "beta".to_string()];
+        let passphrase = generate_passphrase(4, Some(" ".to_string()), Some(words)).unwrap();
+        assert_eq!(passphrase.split(' ').count(), 4);
+        assert!(passphrase.split(' ').all(|w| w == "alpha" || w == "beta"));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("xQ9#vL2!pZ7@kR4$", None), "VeryStrong");
+        assert_eq!(classify("password", None), "Weak");
+        assert_eq!(classify("qwerty", None), "Weak");
+        assert_eq!(classify("correct horse battery staple", None), "VeryStrong");
+        let common = vec!["xQ9#vL2!pZ7@kR4$".to_string()];
+        assert_eq!(classify("xQ9#vL2!pZ7@kR4$", Some(common)), "Weak");
+    }
+}